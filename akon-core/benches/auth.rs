@@ -0,0 +1,71 @@
+//! Benchmarks for the OTP/password generation path
+//!
+//! Run with: cargo bench -p akon-core --bench auth
+//!
+//! Credentials are passed in directly so the keyring is never touched.
+
+use akon_core::auth::base32::decode_base32;
+use akon_core::auth::hmac::hmac_sha1;
+use akon_core::auth::password::generate_password_from_credentials;
+use akon_core::auth::totp::generate_otp;
+use akon_core::types::{OtpSecret, Pin};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const SECRET: &str = "JBSWY3DPEHPK3PXP";
+const TIMESTAMP: u64 = 1609459200; // 2021-01-01 00:00:00 UTC
+
+fn bench_generate_otp(c: &mut Criterion) {
+    let secret = OtpSecret::new(SECRET.to_string());
+
+    c.bench_function("generate_otp", |b| {
+        b.iter(|| generate_otp(black_box(&secret), black_box(Some(TIMESTAMP))).unwrap())
+    });
+}
+
+fn bench_generate_password(c: &mut Criterion) {
+    let pin = Pin::new("1234".to_string()).unwrap();
+    let secret = OtpSecret::new(SECRET.to_string());
+
+    c.bench_function("generate_password_from_credentials", |b| {
+        b.iter(|| {
+            generate_password_from_credentials(
+                black_box(&pin),
+                black_box(&secret),
+                black_box(Some(TIMESTAMP)),
+            )
+            .unwrap()
+        })
+    });
+}
+
+fn bench_hmac_sha1(c: &mut Criterion) {
+    let key = decode_base32(SECRET).unwrap();
+    let counter = (TIMESTAMP / 30).to_be_bytes();
+
+    c.bench_function("hmac_sha1", |b| {
+        b.iter(|| hmac_sha1(black_box(&key), black_box(&counter)))
+    });
+}
+
+fn bench_decode_base32(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_base32");
+
+    for len in [16, 32, 64, 256] {
+        let input: String = SECRET.chars().cycle().take(len).collect();
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(len), &input, |b, input| {
+            b.iter(|| decode_base32(black_box(input)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_generate_otp,
+    bench_generate_password,
+    bench_hmac_sha1,
+    bench_decode_base32
+);
+criterion_main!(benches);
@@ -46,6 +46,24 @@ pub fn decode_base32(input: &str) -> Result<Vec<u8>, OtpError> {
         .map_err(|_| OtpError::InvalidBase32)
 }
 
+/// Normalize a Base32 secret to canonical form: uppercase, unpadded, no whitespace
+///
+/// The secret must decode; the result decodes to the same bytes as the input.
+pub fn normalize(input: &str) -> Result<String, OtpError> {
+    let canonical: String = input
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .trim_end_matches('=')
+        .to_uppercase();
+    if canonical.is_empty() {
+        return Err(OtpError::EmptySecret);
+    }
+
+    decode_base32(&canonical)?;
+    Ok(canonical)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -122,4 +140,31 @@ mod tests {
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), OtpError::InvalidBase32);
     }
+
+    #[test]
+    fn test_normalize_messy_secrets_to_one_canonical_form() {
+        for messy in [
+            "JBSWY3DPEHPK3PXP",
+            "jbswy3dpehpk3pxp",
+            "JBSW Y3DP EHPK 3PXP",
+            " jbsw y3dp\tehpk 3pxp\n",
+            "JBSWY3DPEHPK3PXP========",
+        ] {
+            assert_eq!(normalize(messy).unwrap(), "JBSWY3DPEHPK3PXP", "{:?}", messy);
+        }
+
+        // Padding is dropped rather than completed
+        assert_eq!(normalize("gezdgnbvgy======").unwrap(), "GEZDGNBVGY");
+        assert_eq!(
+            decode_base32(&normalize("gezdgnbvgy======").unwrap()).unwrap(),
+            decode_base32("GEZDGNBVGY======").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_normalize_rejects_undecodable_secrets() {
+        assert_eq!(normalize("  == "), Err(OtpError::EmptySecret));
+        assert_eq!(normalize("JBSW1DP8"), Err(OtpError::InvalidBase32));
+        assert_eq!(normalize("INVALID@CHARS!"), Err(OtpError::InvalidBase32));
+    }
 }
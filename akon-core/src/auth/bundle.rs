@@ -0,0 +1,337 @@
+//! Encrypted export of the configuration and credentials
+//!
+//! `akon export` packs config.toml together with the PIN and OTP secret from
+//! the keyring into one file for moving to another machine; `akon import`
+//! restores them. The bundle is encrypted with ChaCha20-Poly1305 under a key
+//! derived from a passphrase with scrypt. Secrets only exist in plaintext in
+//! memory.
+
+use crate::auth::keyring;
+use crate::config::toml_config::load_config_from_path;
+use crate::error::{AkonError, ConfigError};
+use crate::types::{OtpSecret, Pin};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use data_encoding::BASE64;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Identifies a bundle file
+const FORMAT: &str = "akon-bundle";
+
+/// Current bundle format version
+const VERSION: u32 = 1;
+
+/// scrypt cost (2^15 iterations), as recommended for interactive use
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+/// Highest scrypt cost accepted from a bundle (2 GiB of memory at the
+/// maximum), so a crafted file can't make import exhaust the machine
+const MAX_SCRYPT_LOG_N: u8 = 20;
+const MAX_SCRYPT_R: u32 = 16;
+const MAX_SCRYPT_P: u32 = 4;
+
+/// Passphrases shorter than this are refused
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// What a bundle holds, decrypted
+#[derive(Debug)]
+pub struct BundleContents {
+    /// config.toml as written, `${VAR}` references included
+    pub config_toml: String,
+    /// Keyring account the credentials are stored under
+    pub username: String,
+    pub pin: Pin,
+    pub otp_secret: OtpSecret,
+}
+
+/// The encrypted payload, serialized before sealing
+#[derive(Serialize, Deserialize)]
+struct Payload {
+    config_toml: String,
+    username: String,
+    pin: String,
+    otp_secret: String,
+}
+
+/// The bundle file: key derivation parameters and the sealed payload
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    format: String,
+    version: u32,
+    scrypt_log_n: u8,
+    scrypt_r: u32,
+    scrypt_p: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn bundle_error(message: impl Into<String>) -> AkonError {
+    AkonError::Config(ConfigError::ValidationError {
+        message: message.into(),
+    })
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N], AkonError> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| bundle_error(format!("Failed to generate random bytes: {}", e)))?;
+    Ok(bytes)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], log_n: u8, r: u32, p: u32) -> Result<Key, AkonError> {
+    let params = scrypt::Params::new(log_n, r, p, 32)
+        .map_err(|_| bundle_error("Bundle has invalid key derivation parameters"))?;
+    let mut key = Key::default();
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut key)
+        .map_err(|_| bundle_error("Failed to derive the bundle key"))?;
+    Ok(key)
+}
+
+impl BundleContents {
+    /// Encrypt the contents under `passphrase`
+    pub fn seal(&self, passphrase: &str) -> Result<Vec<u8>, AkonError> {
+        if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+            return Err(bundle_error(format!(
+                "Passphrase must be at least {} characters",
+                MIN_PASSPHRASE_LEN
+            )));
+        }
+
+        let payload = serde_json::to_vec(&Payload {
+            config_toml: self.config_toml.clone(),
+            username: self.username.clone(),
+            pin: self.pin.expose().to_string(),
+            otp_secret: self.otp_secret.expose().to_string(),
+        })
+        .map_err(|e| bundle_error(format!("Failed to serialize bundle: {}", e)))?;
+
+        let salt = random_bytes::<16>()?;
+        let nonce = random_bytes::<12>()?;
+        let key = derive_key(passphrase, &salt, SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P)?;
+        let ciphertext = ChaCha20Poly1305::new(&key)
+            .encrypt(Nonce::from_slice(&nonce), payload.as_slice())
+            .map_err(|_| bundle_error("Failed to encrypt bundle"))?;
+
+        let envelope = Envelope {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            scrypt_log_n: SCRYPT_LOG_N,
+            scrypt_r: SCRYPT_R,
+            scrypt_p: SCRYPT_P,
+            salt: BASE64.encode(&salt),
+            nonce: BASE64.encode(&nonce),
+            ciphertext: BASE64.encode(&ciphertext),
+        };
+        serde_json::to_vec_pretty(&envelope)
+            .map_err(|e| bundle_error(format!("Failed to serialize bundle: {}", e)))
+    }
+
+    /// Decrypt a bundle written by [`BundleContents::seal`]
+    pub fn open(bundle: &[u8], passphrase: &str) -> Result<Self, AkonError> {
+        let envelope: Envelope = serde_json::from_slice(bundle)
+            .ok()
+            .filter(|envelope: &Envelope| envelope.format == FORMAT)
+            .ok_or_else(|| bundle_error("Not an akon bundle"))?;
+        if envelope.version != VERSION {
+            return Err(bundle_error(format!(
+                "Unsupported bundle version {}",
+                envelope.version
+            )));
+        }
+
+        let decode = |field: &str| {
+            BASE64
+                .decode(field.as_bytes())
+                .map_err(|_| bundle_error("Bundle is corrupted"))
+        };
+        let salt = decode(&envelope.salt)?;
+        let nonce = decode(&envelope.nonce)?;
+        let ciphertext = decode(&envelope.ciphertext)?;
+        if nonce.len() != 12 {
+            return Err(bundle_error("Bundle is corrupted"));
+        }
+        if envelope.scrypt_log_n > MAX_SCRYPT_LOG_N
+            || envelope.scrypt_r > MAX_SCRYPT_R
+            || envelope.scrypt_p > MAX_SCRYPT_P
+        {
+            return Err(bundle_error(
+                "Bundle asks for an unreasonably expensive key derivation",
+            ));
+        }
+
+        let key = derive_key(
+            passphrase,
+            &salt,
+            envelope.scrypt_log_n,
+            envelope.scrypt_r,
+            envelope.scrypt_p,
+        )?;
+        let payload = ChaCha20Poly1305::new(&key)
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| bundle_error("Wrong passphrase or corrupted bundle"))?;
+        let payload: Payload =
+            serde_json::from_slice(&payload).map_err(|_| bundle_error("Bundle is corrupted"))?;
+
+        Ok(Self {
+            config_toml: payload.config_toml,
+            username: payload.username,
+            pin: Pin::from_unchecked(payload.pin),
+            otp_secret: OtpSecret::new(payload.otp_secret),
+        })
+    }
+}
+
+/// Collect config.toml at `config_path` and the credentials stored for the
+/// user of `profile`
+pub fn collect_bundle(
+    config_path: &Path,
+    profile: Option<&str>,
+) -> Result<BundleContents, AkonError> {
+    let config_toml = std::fs::read_to_string(config_path).map_err(|e| {
+        AkonError::Config(ConfigError::IoError {
+            message: format!("Failed to read {}: {}", config_path.display(), e),
+        })
+    })?;
+    let username = load_config_from_path(config_path, profile)?.keyring_account();
+
+    Ok(BundleContents {
+        pin: keyring::retrieve_pin(&username)?,
+        otp_secret: OtpSecret::new(keyring::retrieve_otp_secret(&username)?),
+        config_toml,
+        username,
+    })
+}
+
+/// Write the bundle's config.toml to `config_path` and store its credentials
+///
+/// An existing config file is only replaced with `overwrite`.
+pub fn restore_bundle(
+    contents: &BundleContents,
+    config_path: &Path,
+    overwrite: bool,
+) -> Result<(), AkonError> {
+    if config_path.exists() && !overwrite {
+        return Err(bundle_error(format!(
+            "{} already exists; use --force to replace it",
+            config_path.display()
+        )));
+    }
+    // Fail on an unusable config before touching anything
+    toml::from_str::<toml::Value>(&contents.config_toml)
+        .map_err(|e| bundle_error(format!("Bundle holds an invalid config: {}", e)))?;
+
+    if let Some(dir) = config_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(config_path, &contents.config_toml).map_err(|e| {
+        AkonError::Config(ConfigError::IoError {
+            message: format!("Failed to write {}: {}", config_path.display(), e),
+        })
+    })?;
+
+    keyring::store_pin(&contents.username, &contents.pin)?;
+    keyring::store_otp_secret(&contents.username, contents.otp_secret.expose())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"[vpn]
+server = "vpn.example.com"
+username = "bundle_user"
+protocol = "anyconnect"
+"#;
+
+    fn contents() -> BundleContents {
+        BundleContents {
+            config_toml: CONFIG.to_string(),
+            username: "bundle_user".to_string(),
+            pin: Pin::from_unchecked("4321".to_string()),
+            otp_secret: OtpSecret::new("JBSWY3DPEHPK3PXP".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let source = tempfile::tempdir().unwrap();
+        let source_config = source.path().join("config.toml");
+        std::fs::write(&source_config, CONFIG).unwrap();
+        keyring::store_pin("bundle_user", &Pin::from_unchecked("4321".to_string())).unwrap();
+        keyring::store_otp_secret("bundle_user", "JBSWY3DPEHPK3PXP").unwrap();
+
+        let bundle = collect_bundle(&source_config, None)
+            .unwrap()
+            .seal("correct horse battery")
+            .unwrap();
+
+        // Nothing secret is readable in the file
+        let text = String::from_utf8_lossy(&bundle);
+        assert!(!text.contains("JBSWY3DPEHPK3PXP"));
+        assert!(!text.contains("vpn.example.com"));
+
+        // Import on a "new machine": empty keyring entries, fresh config dir
+        keyring::delete_pin("bundle_user").unwrap();
+        keyring::delete_otp_secret("bundle_user").unwrap();
+        let target = tempfile::tempdir().unwrap();
+        let target_config = target.path().join("akon").join("config.toml");
+
+        let opened = BundleContents::open(&bundle, "correct horse battery").unwrap();
+        restore_bundle(&opened, &target_config, false).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&target_config).unwrap(), CONFIG);
+        assert_eq!(
+            keyring::retrieve_pin("bundle_user").unwrap().expose(),
+            "4321"
+        );
+        assert_eq!(
+            keyring::retrieve_otp_secret("bundle_user").unwrap(),
+            "JBSWY3DPEHPK3PXP"
+        );
+
+        // A second import needs --force
+        assert!(restore_bundle(&opened, &target_config, false).is_err());
+        assert!(restore_bundle(&opened, &target_config, true).is_ok());
+    }
+
+    #[test]
+    fn test_wrong_passphrase_is_rejected() {
+        let bundle = contents().seal("correct horse battery").unwrap();
+
+        let error = BundleContents::open(&bundle, "wrong horse battery").unwrap_err();
+        assert!(error.to_string().contains("Wrong passphrase"));
+        assert!(BundleContents::open(b"{}", "correct horse battery").is_err());
+    }
+
+    #[test]
+    fn test_excessive_key_derivation_cost_is_rejected() {
+        let bundle = contents().seal("correct horse battery").unwrap();
+        let envelope: serde_json::Value = serde_json::from_slice(&bundle).unwrap();
+
+        for (field, value) in [("scrypt_log_n", 40), ("scrypt_r", 64), ("scrypt_p", 16)] {
+            let mut tampered = envelope.clone();
+            tampered[field] = value.into();
+            let error = BundleContents::open(
+                &serde_json::to_vec(&tampered).unwrap(),
+                "correct horse battery",
+            )
+            .unwrap_err();
+            assert!(
+                error.to_string().contains("unreasonably expensive"),
+                "{}: {}",
+                field,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn test_short_passphrase_is_refused() {
+        assert!(contents().seal("short").is_err());
+    }
+}
@@ -0,0 +1,123 @@
+//! Detecting a skewed system clock
+//!
+//! A TOTP code is only accepted within its 30s time step, so a clock that is
+//! off by more than half a step is the most common cause of rejected logins.
+//! With `check_clock_skew = true`, `akon vpn on` compares the local clock
+//! with the `Date` header the VPN server sends and warns about a large gap.
+
+use crate::auth::totp::TIME_STEP_SECS;
+use chrono::{DateTime, Duration, Utc};
+
+/// Skew beyond this many seconds is likely to get codes rejected
+pub const SKEW_WARN_SECS: i64 = TIME_STEP_SECS as i64 / 2;
+
+/// Estimate how far the local clock is off from `reference`
+///
+/// `sent` and `received` are the local times around the request that
+/// returned `reference`, which is taken to be from halfway between them.
+/// HTTP dates are truncated to the second, so half a second is added back.
+/// Positive when the local clock is behind.
+pub fn estimate_offset(
+    reference: DateTime<Utc>,
+    sent: DateTime<Utc>,
+    received: DateTime<Utc>,
+) -> Duration {
+    let midpoint = sent + (received - sent) / 2;
+    reference + Duration::milliseconds(500) - midpoint
+}
+
+/// Whether a clock `offset` is large enough to get codes rejected
+pub fn skew_warrants_warning(offset: Duration) -> bool {
+    offset.num_milliseconds().abs() > SKEW_WARN_SECS * 1000
+}
+
+/// Estimate the local clock's offset from the `Date` sent by the server at `url`
+pub async fn query_clock_offset(
+    url: &str,
+    accept_invalid_certs: bool,
+    timeout: std::time::Duration,
+) -> Result<Duration, String> {
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .use_rustls_tls()
+        .danger_accept_invalid_certs(accept_invalid_certs)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let sent = Utc::now();
+    let response = client
+        .head(url)
+        .send()
+        .await
+        .map_err(|e| format!("Request to {} failed: {}", url, e))?;
+    let received = Utc::now();
+
+    let date = response
+        .headers()
+        .get(reqwest::header::DATE)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| format!("{} sent no Date header", url))?;
+    let reference = DateTime::parse_from_rfc2822(date)
+        .map_err(|_| format!("Unrecognized Date header from {}: {}", url, date))?;
+    Ok(estimate_offset(
+        reference.with_timezone(&Utc),
+        sent,
+        received,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_offset_is_measured_from_the_request_midpoint() {
+        // The server's second began 10s after the local clock's midpoint
+        let offset = estimate_offset(
+            at("2024-03-01T12:00:10Z"),
+            at("2024-03-01T11:59:59.800Z"),
+            at("2024-03-01T12:00:00.200Z"),
+        );
+        assert_eq!(offset, Duration::milliseconds(10_500));
+
+        // A local clock ahead of the server gives a negative offset
+        let offset = estimate_offset(
+            at("2024-03-01T12:00:00Z"),
+            at("2024-03-01T12:00:40Z"),
+            at("2024-03-01T12:00:41Z"),
+        );
+        assert_eq!(offset, Duration::seconds(-40));
+    }
+
+    #[test]
+    fn test_warning_starts_past_half_a_time_step() {
+        assert_eq!(SKEW_WARN_SECS, 15);
+        assert!(!skew_warrants_warning(Duration::zero()));
+        assert!(!skew_warrants_warning(Duration::seconds(15)));
+        assert!(!skew_warrants_warning(Duration::seconds(-15)));
+        assert!(skew_warrants_warning(Duration::milliseconds(15_001)));
+        assert!(skew_warrants_warning(Duration::seconds(-16)));
+    }
+
+    #[tokio::test]
+    async fn test_server_in_sync_has_no_significant_offset() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let date = Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(200).insert_header("Date", date.as_str()))
+            .mount(&server)
+            .await;
+
+        let offset = query_clock_offset(&server.uri(), false, std::time::Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert!(!skew_warrants_warning(offset), "offset {:?}", offset);
+    }
+}
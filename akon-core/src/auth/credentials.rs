@@ -0,0 +1,210 @@
+//! Storing the credentials collected by `akon setup`
+//!
+//! Re-running setup to change a non-secret setting shouldn't wipe the
+//! stored credentials, so a credential that isn't re-entered is kept.
+//! Credentials are keyed by username; [`migrate_credentials`] moves them
+//! when the username changes.
+
+use crate::auth::keyring;
+use crate::error::{AkonError, KeyringError};
+use crate::types::{OtpSecret, Pin};
+
+/// Store the PIN and OTP secret for `username`
+///
+/// `None` keeps the credential already in the keyring; it is an error if
+/// there is none to keep.
+pub fn store_credentials(
+    username: &str,
+    pin: Option<&Pin>,
+    otp_secret: Option<&OtpSecret>,
+) -> Result<(), AkonError> {
+    if pin.is_none() && !keyring::has_pin(username)? {
+        return Err(AkonError::Keyring(KeyringError::PinMissing));
+    }
+    if otp_secret.is_none() && !keyring::has_otp_secret(username)? {
+        return Err(AkonError::Keyring(KeyringError::OtpSecretMissing));
+    }
+
+    if let Some(pin) = pin {
+        keyring::store_pin(username, pin)?;
+    }
+    if let Some(otp_secret) = otp_secret {
+        keyring::store_otp_secret(username, otp_secret.expose())?;
+    }
+    Ok(())
+}
+
+/// Move the PIN and OTP secret stored for `from` to `to`
+///
+/// `update_config` runs once the credentials are stored under `to`; if it
+/// fails, the keyring is put back as it was. The entries for `from` are only
+/// deleted after it succeeds. Credentials already stored for `to` are
+/// replaced only with `overwrite`.
+pub fn migrate_credentials<F>(
+    from: &str,
+    to: &str,
+    overwrite: bool,
+    update_config: F,
+) -> Result<(), AkonError>
+where
+    F: FnOnce() -> Result<(), AkonError>,
+{
+    let pin = keyring::retrieve_pin(from)?;
+    let otp_secret = OtpSecret::new(
+        keyring::retrieve_otp_secret(from)
+            .map_err(|_| AkonError::Keyring(KeyringError::OtpSecretMissing))?,
+    );
+    if from == to {
+        return update_config();
+    }
+
+    let previous_pin = keyring::retrieve_pin(to).ok();
+    let previous_secret = keyring::retrieve_otp_secret(to).ok().map(OtpSecret::new);
+    if (previous_pin.is_some() || previous_secret.is_some()) && !overwrite {
+        return Err(AkonError::Keyring(KeyringError::CredentialsExist {
+            username: to.to_string(),
+        }));
+    }
+
+    let result =
+        store_credentials(to, Some(&pin), Some(&otp_secret)).and_then(|()| update_config());
+    if let Err(e) = result {
+        restore(to, previous_pin.as_ref(), previous_secret.as_ref());
+        return Err(e);
+    }
+
+    keyring::delete_pin(from)?;
+    keyring::delete_otp_secret(from)
+}
+
+/// Put back the credentials `username` had before a failed migration
+fn restore(username: &str, pin: Option<&Pin>, otp_secret: Option<&OtpSecret>) {
+    let restored = match pin {
+        Some(pin) => keyring::store_pin(username, pin),
+        None => keyring::delete_pin(username),
+    }
+    .and_then(|()| match otp_secret {
+        Some(secret) => keyring::store_otp_secret(username, secret.expose()),
+        None => keyring::delete_otp_secret(username),
+    });
+    if let Err(e) = restored {
+        tracing::warn!("Failed to restore credentials for {}: {}", username, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resetup_without_secret_keeps_stored_secret() {
+        let username = "resetup_keeps_secret_user";
+        store_credentials(
+            username,
+            Some(&Pin::from_unchecked("1234".to_string())),
+            Some(&OtpSecret::new("JBSWY3DPEHPK3PXP".to_string())),
+        )
+        .unwrap();
+
+        // Setup re-run with a new PIN, secret left blank
+        store_credentials(
+            username,
+            Some(&Pin::from_unchecked("9876".to_string())),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            keyring::retrieve_otp_secret(username).unwrap(),
+            "JBSWY3DPEHPK3PXP"
+        );
+        assert_eq!(keyring::retrieve_pin(username).unwrap().expose(), "9876");
+    }
+
+    #[test]
+    fn test_keeping_a_missing_secret_fails() {
+        let username = "resetup_missing_secret_user";
+
+        let error = store_credentials(
+            username,
+            Some(&Pin::from_unchecked("1234".to_string())),
+            None,
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            error,
+            AkonError::Keyring(KeyringError::OtpSecretMissing)
+        ));
+        // Nothing is stored when the request can't be completed
+        assert!(!keyring::has_pin(username).unwrap());
+    }
+
+    fn store(username: &str, pin: &str, secret: &str) {
+        store_credentials(
+            username,
+            Some(&Pin::from_unchecked(pin.to_string())),
+            Some(&OtpSecret::new(secret.to_string())),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_migrate_moves_credentials_to_new_username() {
+        let (from, to) = ("migrate_old_user", "migrate_new_user");
+        store(from, "1234", "JBSWY3DPEHPK3PXP");
+        let mut config_updated = false;
+
+        migrate_credentials(from, to, false, || {
+            config_updated = true;
+            Ok(())
+        })
+        .unwrap();
+
+        assert!(config_updated);
+        assert_eq!(keyring::retrieve_pin(to).unwrap().expose(), "1234");
+        assert_eq!(
+            keyring::retrieve_otp_secret(to).unwrap(),
+            "JBSWY3DPEHPK3PXP"
+        );
+        assert!(!keyring::has_pin(from).unwrap());
+        assert!(!keyring::has_otp_secret(from).unwrap());
+    }
+
+    #[test]
+    fn test_migrate_requires_overwrite_for_existing_destination() {
+        let (from, to) = ("migrate_guard_old_user", "migrate_guard_new_user");
+        store(from, "1234", "JBSWY3DPEHPK3PXP");
+        store(to, "5555", "GEZDGNBVGY3TQOJQ");
+
+        let error = migrate_credentials(from, to, false, || Ok(())).unwrap_err();
+        assert!(matches!(
+            error,
+            AkonError::Keyring(KeyringError::CredentialsExist { .. })
+        ));
+        assert_eq!(keyring::retrieve_pin(to).unwrap().expose(), "5555");
+        assert!(keyring::has_pin(from).unwrap());
+
+        migrate_credentials(from, to, true, || Ok(())).unwrap();
+        assert_eq!(keyring::retrieve_pin(to).unwrap().expose(), "1234");
+        assert!(!keyring::has_pin(from).unwrap());
+    }
+
+    #[test]
+    fn test_failed_config_update_leaves_keyring_unchanged() {
+        let (from, to) = ("migrate_rollback_old_user", "migrate_rollback_new_user");
+        store(from, "1234", "JBSWY3DPEHPK3PXP");
+
+        let result = migrate_credentials(from, to, false, || {
+            Err(AkonError::Config(crate::error::ConfigError::SaveFailed {
+                path: "config.toml".to_string(),
+            }))
+        });
+
+        assert!(result.is_err());
+        assert!(keyring::has_pin(from).unwrap());
+        assert!(keyring::has_otp_secret(from).unwrap());
+        assert!(!keyring::has_pin(to).unwrap());
+        assert!(!keyring::has_otp_secret(to).unwrap());
+    }
+}
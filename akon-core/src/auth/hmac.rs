@@ -1,20 +1,22 @@
-//! Custom HMAC-SHA1 implementation matching auto-openconnect
+//! Custom HMAC implementation matching auto-openconnect
 //!
 //! This module implements HMAC-SHA1 following RFC 2104 exactly as
 //! auto-openconnect's `lib.py` does, to ensure cross-compatibility.
+//! The same construction over SHA-256 and SHA-512 serves TOTP secrets
+//! provisioned with those algorithms.
 //!
 //! Reference: https://www.ietf.org/rfc/rfc2104.txt
-//! Block size: 64 bytes for SHA-1
+//! Block size: 64 bytes for SHA-1 and SHA-256, 128 bytes for SHA-512
 //! Inner pad (ipad): 0x36
 //! Outer pad (opad): 0x5C
 
 use sha1::{Digest, Sha1};
+use sha2::{Sha256, Sha512};
 
-const BLOCK_SIZE: usize = 64;
 const IPAD: u8 = 0x36;
 const OPAD: u8 = 0x5C;
 
-/// Compute HMAC-SHA1 following RFC 2104
+/// Compute HMAC over hash `D`, whose blocks are `block_size` bytes
 ///
 /// This implementation matches auto-openconnect's `hmac()` function:
 /// 1. Create translation tables for ipad and opad
@@ -22,16 +24,14 @@ const OPAD: u8 = 0x5C;
 /// 3. Pad key to block size
 /// 4. XOR key with ipad and opad
 /// 5. Compute inner and outer hashes
-pub fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
+fn hmac<D: Digest>(block_size: usize, key: &[u8], message: &[u8]) -> Vec<u8> {
     // Step 1: Process key
-    let mut key_block = [0u8; BLOCK_SIZE];
+    let mut key_block = vec![0u8; block_size];
 
-    if key.len() > BLOCK_SIZE {
+    if key.len() > block_size {
         // If key is longer than block size, hash it first
-        let mut hasher = Sha1::new();
-        hasher.update(key);
-        let hashed = hasher.finalize();
-        key_block[..20].copy_from_slice(&hashed);
+        let hashed = D::digest(key);
+        key_block[..hashed.len()].copy_from_slice(&hashed);
     } else {
         // Otherwise use key directly
         key_block[..key.len()].copy_from_slice(key);
@@ -39,29 +39,40 @@ pub fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
     // Remaining bytes are already 0x00 (padding)
 
     // Step 2: Create ipad and opad keys
-    let mut ipad_key = [0u8; BLOCK_SIZE];
-    let mut opad_key = [0u8; BLOCK_SIZE];
-
-    for i in 0..BLOCK_SIZE {
-        ipad_key[i] = key_block[i] ^ IPAD;
-        opad_key[i] = key_block[i] ^ OPAD;
-    }
+    let ipad_key: Vec<u8> = key_block.iter().map(|byte| byte ^ IPAD).collect();
+    let opad_key: Vec<u8> = key_block.iter().map(|byte| byte ^ OPAD).collect();
 
     // Step 3: Compute inner hash
-    let mut inner = Sha1::new();
+    let mut inner = D::new();
     inner.update(ipad_key);
     inner.update(message);
     let inner_hash = inner.finalize();
 
     // Step 4: Compute outer hash
-    let mut outer = Sha1::new();
+    let mut outer = D::new();
     outer.update(opad_key);
     outer.update(inner_hash);
-    let outer_hash = outer.finalize();
+    outer.finalize().to_vec()
+}
 
-    // Convert to fixed-size array
+/// Compute HMAC-SHA1 following RFC 2104
+pub fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
     let mut result = [0u8; 20];
-    result.copy_from_slice(&outer_hash);
+    result.copy_from_slice(&hmac::<Sha1>(64, key, message));
+    result
+}
+
+/// Compute HMAC-SHA256 following RFC 2104
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut result = [0u8; 32];
+    result.copy_from_slice(&hmac::<Sha256>(64, key, message));
+    result
+}
+
+/// Compute HMAC-SHA512 following RFC 2104
+pub fn hmac_sha512(key: &[u8], message: &[u8]) -> [u8; 64] {
+    let mut result = [0u8; 64];
+    result.copy_from_slice(&hmac::<Sha512>(128, key, message));
     result
 }
 
@@ -138,6 +149,40 @@ mod tests {
         assert_eq!(result.len(), 20);
     }
 
+    #[test]
+    fn test_hmac_sha2_rfc4231_test_case_2() {
+        // RFC 4231 Test Case 2
+        let key = b"Jefe";
+        let data = b"what do ya want for nothing?";
+
+        assert_eq!(
+            hex::encode(hmac_sha256(key, data)),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hex::encode(hmac_sha512(key, data)),
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+             9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+        );
+    }
+
+    #[test]
+    fn test_hmac_sha2_rfc4231_test_case_6() {
+        // RFC 4231 Test Case 6: a key longer than either block size
+        let key = [0xaa; 131];
+        let data = b"Test Using Larger Than Block-Size Key - Hash Key First";
+
+        assert_eq!(
+            hex::encode(hmac_sha256(&key, data)),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+        assert_eq!(
+            hex::encode(hmac_sha512(&key, data)),
+            "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f352\
+             6b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598"
+        );
+    }
+
     #[test]
     fn test_hmac_sha1_empty_message() {
         let key = b"key";
@@ -41,12 +41,8 @@ pub fn has_otp_secret(username: &str) -> Result<bool, AkonError> {
 }
 
 /// Delete an OTP secret from the keyring
-pub fn delete_otp_secret(_username: &str) -> Result<(), AkonError> {
-    // Note: The keyring crate doesn't provide a reliable delete API
-    // For now, we just return success to avoid blocking operations
-    // In a real implementation, this would need platform-specific code
-    // or a different keyring library that supports deletion
-    Ok(())
+pub fn delete_otp_secret(username: &str) -> Result<(), AkonError> {
+    delete_entry(KEYRING_SERVICE_OTP, username)
 }
 
 /// Store a PIN in the system keyring
@@ -100,8 +96,19 @@ pub fn has_pin(username: &str) -> Result<bool, AkonError> {
 }
 
 /// Delete a PIN from the keyring
-pub fn delete_pin(_username: &str) -> Result<(), AkonError> {
-    // Note: The keyring crate doesn't provide a reliable delete API
-    // For now, we just return success to avoid blocking operations
+pub fn delete_pin(username: &str) -> Result<(), AkonError> {
+    delete_entry(KEYRING_SERVICE_PIN, username)
+}
+
+/// Delete a keyring entry, best effort
+///
+/// A failure is logged rather than returned, so cleanup never blocks the
+/// operation around it.
+fn delete_entry(service: &str, username: &str) -> Result<(), AkonError> {
+    let result = Entry::new(service, username).and_then(|entry| entry.delete_credential());
+    match result {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => tracing::warn!("Failed to delete {} entry for {}: {}", service, username, e),
+    }
     Ok(())
 }
@@ -0,0 +1,212 @@
+//! Async wrappers around keyring operations
+//!
+//! The Secret Service backend performs blocking D-Bus I/O. These wrappers run
+//! the synchronous keyring calls on tokio's blocking pool so async callers
+//! (like the reconnection daemon) don't stall the executor.
+
+use crate::auth::bundle::{self, BundleContents};
+use crate::auth::{credentials, keyring};
+use crate::error::{AkonError, KeyringError};
+use crate::types::{OtpSecret, Pin};
+use std::path::{Path, PathBuf};
+
+/// Run a keyring operation on the blocking thread pool
+async fn run_blocking<T, F>(operation: F, on_panic: KeyringError) -> Result<T, AkonError>
+where
+    F: FnOnce() -> Result<T, AkonError> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(operation)
+        .await
+        .map_err(|_| AkonError::Keyring(on_panic))?
+}
+
+/// Store an OTP secret in the keyring without blocking the runtime
+pub async fn store_otp_secret(username: &str, secret: &str) -> Result<(), AkonError> {
+    let username = username.to_string();
+    let secret = secret.to_string();
+    run_blocking(
+        move || keyring::store_otp_secret(&username, &secret),
+        KeyringError::StoreFailed,
+    )
+    .await
+}
+
+/// Retrieve an OTP secret from the keyring without blocking the runtime
+pub async fn retrieve_otp_secret(username: &str) -> Result<String, AkonError> {
+    let username = username.to_string();
+    run_blocking(
+        move || keyring::retrieve_otp_secret(&username),
+        KeyringError::RetrieveFailed,
+    )
+    .await
+}
+
+/// Check if an OTP secret exists without blocking the runtime
+pub async fn has_otp_secret(username: &str) -> Result<bool, AkonError> {
+    let username = username.to_string();
+    run_blocking(
+        move || keyring::has_otp_secret(&username),
+        KeyringError::RetrieveFailed,
+    )
+    .await
+}
+
+/// Delete an OTP secret without blocking the runtime
+pub async fn delete_otp_secret(username: &str) -> Result<(), AkonError> {
+    let username = username.to_string();
+    run_blocking(
+        move || keyring::delete_otp_secret(&username),
+        KeyringError::StoreFailed,
+    )
+    .await
+}
+
+/// Store a PIN in the keyring without blocking the runtime
+pub async fn store_pin(username: &str, pin: &Pin) -> Result<(), AkonError> {
+    let username = username.to_string();
+    let pin = pin.clone();
+    run_blocking(
+        move || keyring::store_pin(&username, &pin),
+        KeyringError::StoreFailed,
+    )
+    .await
+}
+
+/// Retrieve a PIN from the keyring without blocking the runtime
+pub async fn retrieve_pin(username: &str) -> Result<Pin, AkonError> {
+    let username = username.to_string();
+    run_blocking(
+        move || keyring::retrieve_pin(&username),
+        KeyringError::RetrieveFailed,
+    )
+    .await
+}
+
+/// Check if a PIN exists without blocking the runtime
+pub async fn has_pin(username: &str) -> Result<bool, AkonError> {
+    let username = username.to_string();
+    run_blocking(
+        move || keyring::has_pin(&username),
+        KeyringError::RetrieveFailed,
+    )
+    .await
+}
+
+/// Store setup's credentials without blocking the runtime
+///
+/// See [`credentials::store_credentials`].
+pub async fn store_credentials(
+    username: &str,
+    pin: Option<Pin>,
+    otp_secret: Option<OtpSecret>,
+) -> Result<(), AkonError> {
+    let username = username.to_string();
+    run_blocking(
+        move || credentials::store_credentials(&username, pin.as_ref(), otp_secret.as_ref()),
+        KeyringError::StoreFailed,
+    )
+    .await
+}
+
+/// Move credentials to a new username without blocking the runtime
+///
+/// See [`credentials::migrate_credentials`].
+pub async fn migrate_credentials<F>(
+    from: &str,
+    to: &str,
+    overwrite: bool,
+    update_config: F,
+) -> Result<(), AkonError>
+where
+    F: FnOnce() -> Result<(), AkonError> + Send + 'static,
+{
+    let from = from.to_string();
+    let to = to.to_string();
+    run_blocking(
+        move || credentials::migrate_credentials(&from, &to, overwrite, update_config),
+        KeyringError::StoreFailed,
+    )
+    .await
+}
+
+/// Collect an export bundle without blocking the runtime
+///
+/// See [`bundle::collect_bundle`].
+pub async fn collect_bundle(
+    config_path: &Path,
+    profile: Option<&str>,
+) -> Result<BundleContents, AkonError> {
+    let config_path = config_path.to_path_buf();
+    let profile = profile.map(str::to_string);
+    run_blocking(
+        move || bundle::collect_bundle(&config_path, profile.as_deref()),
+        KeyringError::RetrieveFailed,
+    )
+    .await
+}
+
+/// Restore an export bundle without blocking the runtime
+///
+/// See [`bundle::restore_bundle`].
+pub async fn restore_bundle(
+    contents: BundleContents,
+    config_path: PathBuf,
+    overwrite: bool,
+) -> Result<(), AkonError> {
+    run_blocking(
+        move || bundle::restore_bundle(&contents, &config_path, overwrite),
+        KeyringError::StoreFailed,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_async_retrieval_matches_sync() {
+        let username = "async_keyring_test_user";
+        let pin = Pin::new("4321".to_string()).unwrap();
+        keyring::store_pin(username, &pin).unwrap();
+        keyring::store_otp_secret(username, "JBSWY3DPEHPK3PXP").unwrap();
+
+        assert_eq!(
+            retrieve_pin(username).await.unwrap().expose(),
+            keyring::retrieve_pin(username).unwrap().expose()
+        );
+        assert_eq!(
+            retrieve_otp_secret(username).await.unwrap(),
+            keyring::retrieve_otp_secret(username).unwrap()
+        );
+        assert!(has_pin(username).await.unwrap());
+        assert!(has_otp_secret(username).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_async_store_is_visible_to_sync_calls() {
+        let username = "async_keyring_store_user";
+        let pin = Pin::new("8765".to_string()).unwrap();
+        store_pin(username, &pin).await.unwrap();
+        store_otp_secret(username, "GEZDGNBVGY3TQOJQ")
+            .await
+            .unwrap();
+
+        assert_eq!(keyring::retrieve_pin(username).unwrap().expose(), "8765");
+        assert_eq!(
+            keyring::retrieve_otp_secret(username).unwrap(),
+            "GEZDGNBVGY3TQOJQ"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_async_missing_credential_errors_like_sync() {
+        let username = "async_keyring_missing_user";
+        assert!(matches!(
+            retrieve_pin(username).await,
+            Err(AkonError::Keyring(KeyringError::PinNotFound))
+        ));
+        assert!(!has_otp_secret(username).await.unwrap());
+    }
+}
@@ -3,7 +3,11 @@
 //! Handles PIN storage, OTP secret storage, TOTP generation, and keyring operations.
 
 pub mod base32;
+pub mod bundle;
+pub mod clock_skew;
+pub mod credentials;
 pub mod hmac;
+pub mod otpauth;
 
 // Use mock keyring in test mode or CI environment
 #[cfg(any(test, feature = "mock-keyring"))]
@@ -14,5 +18,9 @@ pub mod keyring;
 #[cfg(not(any(test, feature = "mock-keyring")))]
 pub mod keyring;
 
+// Non-blocking wrappers for use from async code
+#[cfg(feature = "async-keyring")]
+pub mod keyring_async;
+
 pub mod password;
 pub mod totp;
@@ -0,0 +1,160 @@
+//! Parsing `otpauth://` enrollment URIs
+//!
+//! MFA enrollment usually comes as a QR code holding a URI such as
+//! `otpauth://totp/Corp:alice?secret=JBSWY3DPEHPK3PXP&algorithm=SHA256&digits=8&period=60`.
+//! Setup accepts one in place of the bare secret and takes the code settings
+//! from it. Parameters left out get the usual defaults (SHA-1, 6 digits, 30s),
+//! and unknown ones such as `issuer` are ignored.
+//!
+//! Reference: https://github.com/google/google-authenticator/wiki/Key-Uri-Format
+
+use crate::auth::base32;
+use crate::auth::totp::{OtpAlgorithm, TotpSettings};
+use crate::error::OtpError;
+use crate::types::OtpSecret;
+use url::Url;
+
+/// Scheme of enrollment URIs
+pub const OTPAUTH_SCHEME: &str = "otpauth";
+
+/// What an enrollment URI says about a TOTP token
+#[derive(Debug, Clone)]
+pub struct OtpParams {
+    /// The secret, in canonical Base32
+    pub secret: OtpSecret,
+    /// Algorithm, digits and period
+    pub settings: TotpSettings,
+}
+
+/// Whether `input` looks like an enrollment URI rather than a bare secret
+pub fn is_uri(input: &str) -> bool {
+    input
+        .trim()
+        .get(..OTPAUTH_SCHEME.len() + 1)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(&format!("{}:", OTPAUTH_SCHEME)))
+}
+
+/// Parse an `otpauth://totp/...` URI
+pub fn parse_uri(uri: &str) -> Result<OtpParams, OtpError> {
+    let invalid = |reason: &str| OtpError::InvalidUri {
+        reason: reason.to_string(),
+    };
+
+    let url = Url::parse(uri.trim()).map_err(|_| invalid("not a valid URI"))?;
+    if url.scheme() != OTPAUTH_SCHEME {
+        return Err(invalid("the scheme must be otpauth://"));
+    }
+    match url.host_str() {
+        Some(kind) if kind.eq_ignore_ascii_case("totp") => {}
+        Some(kind) if kind.eq_ignore_ascii_case("hotp") => {
+            return Err(invalid("counter-based (hotp) tokens are not supported"))
+        }
+        _ => return Err(invalid("the token type must be totp")),
+    }
+
+    let mut secret = None;
+    let mut settings = TotpSettings::default();
+    for (key, value) in url.query_pairs() {
+        match key.to_ascii_lowercase().as_str() {
+            "secret" => secret = Some(base32::normalize(&value)?),
+            "algorithm" => {
+                settings.algorithm = match value.to_ascii_uppercase().as_str() {
+                    "SHA1" => OtpAlgorithm::Sha1,
+                    "SHA256" => OtpAlgorithm::Sha256,
+                    "SHA512" => OtpAlgorithm::Sha512,
+                    _ => return Err(invalid("algorithm must be SHA1, SHA256 or SHA512")),
+                }
+            }
+            "digits" => {
+                let digits: u32 = value
+                    .parse()
+                    .map_err(|_| invalid("digits must be a number"))?;
+                if !(6..=8).contains(&digits) {
+                    return Err(OtpError::InvalidDigits { digits });
+                }
+                settings.digits = digits as u8;
+            }
+            "period" => {
+                settings.period_secs = value
+                    .parse()
+                    .map_err(|_| invalid("period must be a number of seconds"))?;
+                if settings.period_secs == 0 {
+                    return Err(OtpError::InvalidPeriod);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let secret = secret.ok_or_else(|| invalid("it has no secret parameter"))?;
+    Ok(OtpParams {
+        secret: OtpSecret::new(secret),
+        settings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_full_uri() {
+        let params = parse_uri(
+            "otpauth://totp/Corp:alice?secret=jbsw%20y3dp%20ehpk3pxp&issuer=Corp&algorithm=SHA256&digits=8&period=60",
+        )
+        .unwrap();
+
+        assert_eq!(params.secret.expose(), "JBSWY3DPEHPK3PXP");
+        assert_eq!(
+            params.settings,
+            TotpSettings {
+                algorithm: OtpAlgorithm::Sha256,
+                digits: 8,
+                period_secs: 60,
+            }
+        );
+    }
+
+    #[test]
+    fn test_missing_parameters_get_defaults() {
+        let params = parse_uri("otpauth://totp/alice?secret=JBSWY3DPEHPK3PXP").unwrap();
+        assert_eq!(params.settings, TotpSettings::default());
+    }
+
+    #[test]
+    fn test_invalid_uris_are_rejected() {
+        let reason = |uri: &str| match parse_uri(uri).unwrap_err() {
+            OtpError::InvalidUri { reason } => reason,
+            other => panic!("unexpected error: {:?}", other),
+        };
+
+        assert!(reason("https://totp/alice?secret=JBSWY3DPEHPK3PXP").contains("otpauth://"));
+        assert!(reason("otpauth://hotp/alice?secret=JBSWY3DPEHPK3PXP&counter=1").contains("hotp"));
+        assert!(reason("otpauth://totp/alice?issuer=Corp").contains("no secret"));
+        assert!(
+            reason("otpauth://totp/alice?secret=JBSWY3DPEHPK3PXP&algorithm=MD5")
+                .contains("algorithm")
+        );
+
+        assert_eq!(
+            parse_uri("otpauth://totp/alice?secret=JBSWY3DPEHPK3PXP&digits=10").unwrap_err(),
+            OtpError::InvalidDigits { digits: 10 }
+        );
+        assert_eq!(
+            parse_uri("otpauth://totp/alice?secret=JBSWY3DPEHPK3PXP&period=0").unwrap_err(),
+            OtpError::InvalidPeriod
+        );
+        assert_eq!(
+            parse_uri("otpauth://totp/alice?secret=not!base32").unwrap_err(),
+            OtpError::InvalidBase32
+        );
+    }
+
+    #[test]
+    fn test_is_uri() {
+        assert!(is_uri("otpauth://totp/alice?secret=JBSWY3DPEHPK3PXP"));
+        assert!(is_uri("  OTPAUTH://totp/alice"));
+        assert!(!is_uri("JBSWY3DPEHPK3PXP"));
+        assert!(!is_uri("otp"));
+    }
+}
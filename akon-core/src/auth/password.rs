@@ -2,10 +2,146 @@
 //!
 //! This module provides complete VPN password generation by combining
 //! the 4-digit PIN with the 6-digit TOTP token.
+//!
+//! For CI pipelines without a keyring daemon, setting `AKON_NO_KEYRING=1`
+//! takes the PIN and TOTP secret from `AKON_PIN` and `AKON_TOTP_SECRET`
+//! instead. This is a testing mode: environment variables are readable by
+//! other processes of the same user, so never use it with real credentials.
+
+use crate::auth::keyring;
+use crate::auth::totp::{self, Clock, TotpSettings};
+use crate::config::VpnConfig;
+use crate::error::{AkonError, ConfigError, KeyringError};
+use crate::types::{OtpSecret, Pin, VpnPassword};
+
+/// Set to `1` to read credentials from the environment (testing only)
+pub const NO_KEYRING_ENV: &str = "AKON_NO_KEYRING";
+
+/// PIN used when [`NO_KEYRING_ENV`] is set
+pub const PIN_ENV: &str = "AKON_PIN";
+
+/// TOTP secret used when [`NO_KEYRING_ENV`] is set
+pub const TOTP_SECRET_ENV: &str = "AKON_TOTP_SECRET";
+
+/// Read the PIN and TOTP secret from the environment in no-keyring mode
+///
+/// Returns `None` unless `var(NO_KEYRING_ENV)` is `1`.
+fn credentials_from_env<F>(var: F) -> Result<Option<(Pin, OtpSecret)>, AkonError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if var(NO_KEYRING_ENV).as_deref() != Some("1") {
+        return Ok(None);
+    }
+    tracing::warn!(
+        "{} is set: using credentials from the environment (testing only)",
+        NO_KEYRING_ENV
+    );
+
+    let required = |name: &str| {
+        var(name)
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| {
+                AkonError::Config(ConfigError::MissingField {
+                    field: name.to_string(),
+                })
+            })
+    };
+    let pin = Pin::from_unchecked(required(PIN_ENV)?);
+    let otp_secret = OtpSecret::new(required(TOTP_SECRET_ENV)?);
+    Ok(Some((pin, otp_secret)))
+}
+
+/// Generate the password from environment credentials in no-keyring mode
+///
+/// Returns `None` unless `var(NO_KEYRING_ENV)` is `1`.
+#[cfg(any(feature = "async-keyring", test))]
+fn password_from_env<F>(
+    var: F,
+    clock: &dyn Clock,
+    settings: TotpSettings,
+) -> Result<Option<VpnPassword>, AkonError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let Some((pin, otp_secret)) = credentials_from_env(var)? else {
+        return Ok(None);
+    };
+
+    let otp_token = settings.generate(&otp_secret, Some(clock.unix_time()?))?;
+    Ok(Some(VpnPassword::from_components(&pin, &otp_token)))
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+/// Report a keyring holding only one of the PIN and OTP secret
+///
+/// An interrupted setup can leave just one credential behind; naming the
+/// missing one is clearer than the generic retrieval error. When both are
+/// missing, retrieval reports it as before.
+fn check_partial_credentials(has_pin: bool, has_otp_secret: bool) -> Result<(), AkonError> {
+    match (has_pin, has_otp_secret) {
+        (true, false) => Err(AkonError::Keyring(KeyringError::OtpSecretMissing)),
+        (false, true) => Err(AkonError::Keyring(KeyringError::PinMissing)),
+        _ => Ok(()),
+    }
+}
+
+/// Which credentials are kept in the keyring; the others are prompted for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoredCredentials {
+    pub pin: bool,
+    pub otp_secret: bool,
+}
+
+impl Default for StoredCredentials {
+    fn default() -> Self {
+        Self {
+            pin: true,
+            otp_secret: true,
+        }
+    }
+}
+
+impl StoredCredentials {
+    /// The `store_pin` and `store_secret` settings
+    pub fn for_config(config: &VpnConfig) -> Self {
+        Self {
+            pin: config.store_pin,
+            otp_secret: config.store_secret,
+        }
+    }
+
+    fn all(&self) -> bool {
+        self.pin && self.otp_secret
+    }
+}
+
+/// Asks the user for credentials that aren't stored in the keyring
+pub trait CredentialPrompt {
+    fn pin(&mut self) -> Result<Pin, AkonError>;
+    fn otp_secret(&mut self) -> Result<OtpSecret, AkonError>;
+}
 
-use crate::auth::{keyring, totp};
-use crate::error::AkonError;
-use crate::types::{OtpSecret, VpnPassword};
+/// Prompt for contexts with nobody to ask; every request fails
+pub struct NoPrompt;
+
+impl CredentialPrompt for NoPrompt {
+    fn pin(&mut self) -> Result<Pin, AkonError> {
+        Err(AkonError::Keyring(KeyringError::PromptUnavailable {
+            credential: "PIN".to_string(),
+        }))
+    }
+
+    fn otp_secret(&mut self) -> Result<OtpSecret, AkonError> {
+        Err(AkonError::Keyring(KeyringError::PromptUnavailable {
+            credential: "TOTP secret".to_string(),
+        }))
+    }
+}
 
 /// Generate the complete VPN password (PIN + OTP)
 ///
@@ -19,23 +155,174 @@ use crate::types::{OtpSecret, VpnPassword};
 /// - OTP secret is not found in keyring
 /// - OTP generation fails
 pub fn generate_password(username: &str) -> Result<VpnPassword, AkonError> {
-    // Retrieve PIN from keyring
-    let pin = keyring::retrieve_pin(username)?;
+    generate_password_with(
+        username,
+        StoredCredentials::default(),
+        &mut NoPrompt,
+        &totp::SystemClock,
+        TotpSettings::default(),
+    )
+}
 
-    // Retrieve OTP secret from keyring
-    let otp_secret_str = keyring::retrieve_otp_secret(username)?;
-    let otp_secret = OtpSecret::new(otp_secret_str);
+/// Generate the complete VPN password, prompting for credentials not in the keyring
+///
+/// The OTP is for the time `clock` reports, generated with `settings`.
+pub fn generate_password_with<P: CredentialPrompt>(
+    username: &str,
+    stored: StoredCredentials,
+    prompt: &mut P,
+    clock: &dyn Clock,
+    settings: TotpSettings,
+) -> Result<VpnPassword, AkonError> {
+    let (pin, otp_secret) = credentials_with(username, stored, prompt)?;
 
     // Generate OTP token
-    let otp_token = totp::generate_otp(&otp_secret, None)?;
+    let otp_token = settings.generate(&otp_secret, Some(clock.unix_time()?))?;
 
     // Combine PIN + OTP
     Ok(VpnPassword::from_components(&pin, &otp_token))
 }
 
+/// Generate the passwords for the current time step and `skew_windows` steps
+/// on either side of it
+///
+/// Lets authentication be retried when the clock is a step off the
+/// gateway's. The current step's password comes first, then the others
+/// nearest first, earlier before later: for one window, the passwords for
+/// the current, previous and next step. Credentials are only read once.
+pub fn generate_candidate_passwords<P: CredentialPrompt>(
+    username: &str,
+    stored: StoredCredentials,
+    prompt: &mut P,
+    clock: &dyn Clock,
+    settings: TotpSettings,
+    skew_windows: u8,
+) -> Result<Vec<VpnPassword>, AkonError> {
+    let (pin, otp_secret) = credentials_with(username, stored, prompt)?;
+    let now = clock.unix_time()?;
+
+    let offsets = std::iter::once(0)
+        .chain((1..=i64::from(skew_windows)).flat_map(|distance| [-distance, distance]));
+    let mut passwords = Vec::new();
+    for offset in offsets {
+        // Steps before the epoch have no code
+        let Some(timestamp) = now.checked_add_signed(offset * settings.period_secs as i64) else {
+            continue;
+        };
+        let otp_token = settings.generate(&otp_secret, Some(timestamp))?;
+        passwords.push(VpnPassword::from_components(&pin, &otp_token));
+    }
+    Ok(passwords)
+}
+
+/// Take the PIN and OTP secret from the environment in no-keyring mode,
+/// else from the keyring or the prompt
+fn credentials_with<P: CredentialPrompt>(
+    username: &str,
+    stored: StoredCredentials,
+    prompt: &mut P,
+) -> Result<(Pin, OtpSecret), AkonError> {
+    if let Some(credentials) = credentials_from_env(env_var)? {
+        return Ok(credentials);
+    }
+
+    if stored.all() {
+        check_partial_credentials(
+            keyring::has_pin(username)?,
+            keyring::has_otp_secret(username)?,
+        )?;
+    }
+
+    // Retrieve PIN from keyring, or ask for it
+    let pin = if stored.pin {
+        keyring::retrieve_pin(username)?
+    } else {
+        prompt.pin()?
+    };
+
+    // Retrieve OTP secret from keyring, or ask for it
+    let otp_secret = if stored.otp_secret {
+        OtpSecret::new(keyring::retrieve_otp_secret(username)?)
+    } else {
+        prompt.otp_secret()?
+    };
+
+    Ok((pin, otp_secret))
+}
+
+/// Generate the complete VPN password without blocking the async runtime
+///
+/// Same as [`generate_password`], but keyring access runs on tokio's
+/// blocking pool.
+#[cfg(feature = "async-keyring")]
+pub async fn generate_password_async(username: &str) -> Result<VpnPassword, AkonError> {
+    generate_password_async_with(
+        username,
+        StoredCredentials::default(),
+        NoPrompt,
+        &totp::SystemClock,
+        TotpSettings::default(),
+    )
+    .await
+}
+
+/// Async [`generate_password_with`]; keyring access and prompts run on
+/// tokio's blocking pool
+#[cfg(feature = "async-keyring")]
+pub async fn generate_password_async_with<P>(
+    username: &str,
+    stored: StoredCredentials,
+    mut prompt: P,
+    clock: &dyn Clock,
+    settings: TotpSettings,
+) -> Result<VpnPassword, AkonError>
+where
+    P: CredentialPrompt + Send + 'static,
+{
+    use crate::auth::keyring_async;
+
+    if let Some(password) = password_from_env(env_var, clock, settings)? {
+        return Ok(password);
+    }
+
+    if stored.all() {
+        check_partial_credentials(
+            keyring_async::has_pin(username).await?,
+            keyring_async::has_otp_secret(username).await?,
+        )?;
+    }
+
+    // Ask for what the keyring doesn't hold before touching the keyring
+    let (prompted_pin, prompted_secret) = if stored.all() {
+        (None, None)
+    } else {
+        tokio::task::spawn_blocking(move || {
+            let pin = (!stored.pin).then(|| prompt.pin()).transpose()?;
+            let secret = (!stored.otp_secret)
+                .then(|| prompt.otp_secret())
+                .transpose()?;
+            Ok::<_, AkonError>((pin, secret))
+        })
+        .await
+        .map_err(|_| AkonError::Keyring(KeyringError::RetrieveFailed))??
+    };
+
+    let pin = match prompted_pin {
+        Some(pin) => pin,
+        None => keyring_async::retrieve_pin(username).await?,
+    };
+    let otp_secret = match prompted_secret {
+        Some(secret) => secret,
+        None => OtpSecret::new(keyring_async::retrieve_otp_secret(username).await?),
+    };
+    let otp_token = settings.generate(&otp_secret, Some(clock.unix_time()?))?;
+
+    Ok(VpnPassword::from_components(&pin, &otp_token))
+}
+
 /// Generate password with explicit credentials (for testing)
 pub fn generate_password_from_credentials(
-    pin: &crate::types::Pin,
+    pin: &Pin,
     otp_secret: &OtpSecret,
     timestamp: Option<u64>,
 ) -> Result<VpnPassword, AkonError> {
@@ -46,7 +333,7 @@ pub fn generate_password_from_credentials(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::Pin;
+    use crate::error::OtpError;
 
     #[test]
     fn test_generate_password_from_credentials() {
@@ -86,4 +373,303 @@ mod tests {
         assert!(pwd_str.starts_with("9999"));
         assert!(pwd_str.chars().all(|c| c.is_ascii_digit()));
     }
+
+    /// Always reports the same time
+    struct FixedClock(u64);
+
+    impl Clock for FixedClock {
+        fn unix_time(&self) -> Result<u64, OtpError> {
+            Ok(self.0)
+        }
+    }
+
+    #[test]
+    fn test_password_uses_the_corrected_clock() {
+        let username = "clock_offset_user";
+        keyring::store_pin(username, &Pin::new("1234".to_string()).unwrap()).unwrap();
+        keyring::store_otp_secret(username, "JBSWY3DPEHPK3PXP").unwrap();
+        let otp_secret = OtpSecret::new("JBSWY3DPEHPK3PXP".to_string());
+        let code_at = |ts: u64| totp::generate_otp(&otp_secret, Some(ts)).unwrap();
+
+        // The last second of a time step
+        let system_time = 1609459199;
+        let password = |clock: &dyn Clock| {
+            generate_password_with(
+                username,
+                StoredCredentials::default(),
+                &mut NoPrompt,
+                clock,
+                TotpSettings::default(),
+            )
+            .unwrap()
+        };
+
+        let uncorrected = password(&FixedClock(system_time));
+        assert_eq!(
+            uncorrected.expose(),
+            format!("1234{}", code_at(system_time).expose())
+        );
+
+        // A clock running 1s behind is corrected into the next step
+        let corrected = password(&totp::OffsetClock::new(FixedClock(system_time), 1));
+        assert_eq!(
+            corrected.expose(),
+            format!("1234{}", code_at(system_time + 1).expose())
+        );
+        assert_ne!(corrected.expose(), uncorrected.expose());
+
+        // And one running ahead back into the previous one
+        let corrected = password(&totp::OffsetClock::new(FixedClock(system_time), -30));
+        assert_eq!(
+            corrected.expose(),
+            format!("1234{}", code_at(system_time - 30).expose())
+        );
+    }
+
+    /// Answers prompts with fixed credentials, counting the questions
+    #[derive(Default)]
+    struct FixedPrompt {
+        pins_asked: usize,
+        secrets_asked: usize,
+    }
+
+    impl CredentialPrompt for FixedPrompt {
+        fn pin(&mut self) -> Result<Pin, AkonError> {
+            self.pins_asked += 1;
+            Ok(Pin::from_unchecked("9876".to_string()))
+        }
+
+        fn otp_secret(&mut self) -> Result<OtpSecret, AkonError> {
+            self.secrets_asked += 1;
+            Ok(OtpSecret::new("JBSWY3DPEHPK3PXP".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_stored_credentials_are_used_without_prompting() {
+        let username = "stored_both_user";
+        keyring::store_pin(username, &Pin::new("1234".to_string()).unwrap()).unwrap();
+        keyring::store_otp_secret(username, "JBSWY3DPEHPK3PXP").unwrap();
+
+        let mut prompt = FixedPrompt::default();
+        let password = generate_password_with(
+            username,
+            StoredCredentials::default(),
+            &mut prompt,
+            &totp::SystemClock,
+            TotpSettings::default(),
+        )
+        .unwrap();
+
+        assert!(password.expose().starts_with("1234"));
+        assert_eq!(password.expose().len(), 10);
+        assert_eq!((prompt.pins_asked, prompt.secrets_asked), (0, 0));
+    }
+
+    #[test]
+    fn test_unstored_pin_is_prompted_for() {
+        let username = "stored_secret_only_user";
+        keyring::store_otp_secret(username, "JBSWY3DPEHPK3PXP").unwrap();
+        let stored = StoredCredentials {
+            pin: false,
+            otp_secret: true,
+        };
+
+        let mut prompt = FixedPrompt::default();
+        let password = generate_password_with(
+            username,
+            stored,
+            &mut prompt,
+            &totp::SystemClock,
+            TotpSettings::default(),
+        )
+        .unwrap();
+
+        assert!(password.expose().starts_with("9876"));
+        assert_eq!((prompt.pins_asked, prompt.secrets_asked), (1, 0));
+
+        // Without anyone to ask, the missing PIN is an error
+        let error = generate_password_with(
+            username,
+            stored,
+            &mut NoPrompt,
+            &totp::SystemClock,
+            TotpSettings::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            AkonError::Keyring(KeyringError::PromptUnavailable { .. })
+        ));
+    }
+
+    #[test]
+    fn test_password_uses_the_configured_algorithm() {
+        let username = "sha256_secret_user";
+        keyring::store_pin(username, &Pin::new("1234".to_string()).unwrap()).unwrap();
+        keyring::store_otp_secret(username, "JBSWY3DPEHPK3PXP").unwrap();
+        let settings = TotpSettings {
+            algorithm: totp::OtpAlgorithm::Sha256,
+            ..TotpSettings::default()
+        };
+        let timestamp = 1609459200;
+
+        let password = generate_password_with(
+            username,
+            StoredCredentials::default(),
+            &mut NoPrompt,
+            &FixedClock(timestamp),
+            settings,
+        )
+        .unwrap();
+        let otp_secret = OtpSecret::new("JBSWY3DPEHPK3PXP".to_string());
+        assert_eq!(
+            password.expose(),
+            format!(
+                "1234{}",
+                settings
+                    .generate(&otp_secret, Some(timestamp))
+                    .unwrap()
+                    .expose()
+            )
+        );
+    }
+
+    #[test]
+    fn test_candidate_passwords_cover_the_skew_windows() {
+        let username = "skew_windows_user";
+        keyring::store_pin(username, &Pin::new("1234".to_string()).unwrap()).unwrap();
+        keyring::store_otp_secret(username, "JBSWY3DPEHPK3PXP").unwrap();
+        let otp_secret = OtpSecret::new("JBSWY3DPEHPK3PXP".to_string());
+        let password_at = |ts: u64| {
+            format!(
+                "1234{}",
+                totp::generate_otp(&otp_secret, Some(ts)).unwrap().expose()
+            )
+        };
+        let candidates = |ts: u64, skew_windows: u8| {
+            generate_candidate_passwords(
+                username,
+                StoredCredentials::default(),
+                &mut NoPrompt,
+                &FixedClock(ts),
+                TotpSettings::default(),
+                skew_windows,
+            )
+            .unwrap()
+            .iter()
+            .map(|password| password.expose().to_string())
+            .collect::<Vec<_>>()
+        };
+
+        // No windows: just the current password
+        let last_second = 1609459229;
+        assert_eq!(candidates(last_second, 0), [password_at(last_second)]);
+
+        // The last second of a step: its neighbors are the steps just around it
+        assert_eq!(
+            candidates(last_second, 1),
+            [
+                password_at(last_second),
+                password_at(1609459199),
+                password_at(1609459230),
+            ]
+        );
+
+        // The first second of the next step moves all windows along
+        let first_second = 1609459230;
+        assert_eq!(
+            candidates(first_second, 2),
+            [
+                password_at(first_second),
+                password_at(1609459200),
+                password_at(1609459260),
+                password_at(1609459170),
+                password_at(1609459290),
+            ]
+        );
+
+        // Steps before the epoch are left out
+        assert_eq!(candidates(10, 1), [password_at(10), password_at(40)]);
+    }
+
+    #[test]
+    fn test_pin_without_otp_secret_is_reported() {
+        let username = "partial_pin_only_user";
+        keyring::store_pin(username, &Pin::new("1234".to_string()).unwrap()).unwrap();
+
+        let error = generate_password(username).unwrap_err();
+        assert!(matches!(
+            error,
+            AkonError::Keyring(KeyringError::OtpSecretMissing)
+        ));
+        assert!(error
+            .to_string()
+            .contains("PIN found but TOTP secret missing"));
+    }
+
+    #[test]
+    fn test_otp_secret_without_pin_is_reported() {
+        let username = "partial_otp_only_user";
+        keyring::store_otp_secret(username, "JBSWY3DPEHPK3PXP").unwrap();
+
+        let error = generate_password(username).unwrap_err();
+        assert!(matches!(
+            error,
+            AkonError::Keyring(KeyringError::PinMissing)
+        ));
+        assert!(error
+            .to_string()
+            .contains("TOTP secret found but PIN missing"));
+    }
+
+    #[test]
+    fn test_no_credentials_reports_missing_pin() {
+        let error = generate_password("partial_no_credentials_user").unwrap_err();
+        assert!(matches!(
+            error,
+            AkonError::Keyring(KeyringError::PinNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_no_keyring_mode_uses_env_credentials() {
+        let env = |name: &str| match name {
+            NO_KEYRING_ENV => Some("1".to_string()),
+            PIN_ENV => Some("4321".to_string()),
+            TOTP_SECRET_ENV => Some("JBSWY3DPEHPK3PXP".to_string()),
+            _ => None,
+        };
+        let timestamp = 1609459200;
+
+        let password = password_from_env(env, &FixedClock(timestamp), TotpSettings::default())
+            .unwrap()
+            .unwrap();
+        let expected = generate_password_from_credentials(
+            &Pin::from_unchecked("4321".to_string()),
+            &OtpSecret::new("JBSWY3DPEHPK3PXP".to_string()),
+            Some(timestamp),
+        )
+        .unwrap();
+        assert_eq!(password.expose(), expected.expose());
+    }
+
+    #[test]
+    fn test_no_keyring_mode_requires_opt_in_and_both_credentials() {
+        let unset = |name: &str| (name == PIN_ENV).then(|| "4321".to_string());
+        assert!(
+            password_from_env(unset, &totp::SystemClock, TotpSettings::default())
+                .unwrap()
+                .is_none()
+        );
+
+        let missing_secret = |name: &str| match name {
+            NO_KEYRING_ENV => Some("1".to_string()),
+            PIN_ENV => Some("4321".to_string()),
+            _ => None,
+        };
+        let error = password_from_env(missing_secret, &totp::SystemClock, TotpSettings::default())
+            .unwrap_err();
+        assert!(error.to_string().contains(TOTP_SECRET_ENV), "{}", error);
+    }
 }
@@ -2,62 +2,374 @@
 //!
 //! Implements RFC 6238 TOTP with custom HMAC-SHA1 and Base32 decoding
 //! to match auto-openconnect's algorithm exactly for cross-compatibility.
+//! Secrets provisioned for HMAC-SHA256 or HMAC-SHA512, 7- or 8-digit
+//! tokens and time steps other than 30s are supported too.
 
 use crate::auth::{base32, hmac};
-use crate::error::AkonError;
+use crate::config::VpnConfig;
+use crate::error::{AkonError, OtpError};
 use crate::types::{OtpSecret, TotpToken};
+use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-/// Get HOTP counter from timestamp
+/// Length of a TOTP time step in seconds, unless configured otherwise
+pub const TIME_STEP_SECS: u64 = 30;
+
+/// Number of digits in generated tokens, unless configured otherwise
+pub const TOTP_DIGITS: u8 = 6;
+
+/// HMAC hash a TOTP secret was provisioned for
 ///
-/// Matches auto-openconnect's logic: `int(time.time() / 30)`
-/// Uses integer division to match Python's behavior
-fn get_hotp_counter(timestamp: Option<u64>) -> Result<u64, AkonError> {
-    let ts = timestamp.unwrap_or_else(|| {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("System time before Unix epoch")
-            .as_secs()
-    });
+/// Authenticator apps and otpauth:// URIs call this the `algorithm`; nearly
+/// all secrets use SHA-1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OtpAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl OtpAlgorithm {
+    /// Compute the HMAC of `message` with this algorithm
+    fn hmac(self, key: &[u8], message: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => hmac::hmac_sha1(key, message).to_vec(),
+            Self::Sha256 => hmac::hmac_sha256(key, message).to_vec(),
+            Self::Sha512 => hmac::hmac_sha512(key, message).to_vec(),
+        }
+    }
+}
+
+/// How codes are derived from a secret, besides the secret itself
+///
+/// Production code takes them from the configuration with
+/// [`TotpSettings::for_config`]; the default (SHA-1, 6 digits, 30s steps)
+/// suits nearly every secret.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TotpSettings {
+    pub algorithm: OtpAlgorithm,
+    /// Digits in a token, 6 to 8
+    pub digits: u8,
+    /// Length of a time step in seconds
+    pub period_secs: u64,
+}
 
-    Ok(ts / 30) // Integer division, matching Python
+impl Default for TotpSettings {
+    fn default() -> Self {
+        Self {
+            algorithm: OtpAlgorithm::default(),
+            digits: TOTP_DIGITS,
+            period_secs: TIME_STEP_SECS,
+        }
+    }
+}
+
+impl TotpSettings {
+    /// The `otp_algorithm`, `otp_digits` and `otp_period_secs` settings
+    pub fn for_config(config: &VpnConfig) -> Self {
+        Self {
+            algorithm: config.otp_algorithm,
+            digits: config.otp_digits,
+            period_secs: config.otp_period_secs,
+        }
+    }
+
+    /// Time step `timestamp` (default: now) falls in
+    ///
+    /// Matches auto-openconnect's logic, `int(time.time() / 30)`, for the
+    /// default period; integer division matches Python's behavior.
+    fn counter(&self, timestamp: Option<u64>) -> Result<u64, AkonError> {
+        if self.period_secs == 0 {
+            return Err(AkonError::Otp(OtpError::InvalidPeriod));
+        }
+        Ok(resolve_timestamp(timestamp)? / self.period_secs)
+    }
+
+    /// Generate the token for `secret` at `timestamp` (default: now)
+    ///
+    /// This implements the exact same logic as auto-openconnect's
+    /// `lib.py::generate_otp()` function, with the hash, digits and period
+    /// as configured:
+    /// 1. Calculate HOTP counter from timestamp
+    /// 2. Decode Base32 secret (with custom padding and whitespace handling)
+    /// 3. Compute the HMAC
+    /// 4. Apply dynamic truncation (RFC 6238)
+    /// 5. Return the zero-padded OTP
+    pub fn generate(
+        &self,
+        secret: &OtpSecret,
+        timestamp: Option<u64>,
+    ) -> Result<TotpToken, AkonError> {
+        // An empty secret would decode to an empty key and yield a useless code
+        if secret.expose().trim().is_empty() {
+            return Err(AkonError::Otp(OtpError::EmptySecret));
+        }
+
+        // Step 1: Get HOTP counter (timestamp / period)
+        let counter = self.counter(timestamp)?;
+
+        // Step 2: Decode Base32 secret with custom logic
+        let key_bytes = base32::decode_base32(secret.expose()).map_err(AkonError::Otp)?;
+
+        // Step 3: Convert counter to big-endian bytes
+        let counter_bytes = counter.to_be_bytes();
+
+        // Step 4: Compute HMAC (SHA-1 unless provisioned otherwise)
+        let hmac_result = self.algorithm.hmac(&key_bytes, &counter_bytes);
+
+        // Step 5: Dynamic truncation (RFC 6238)
+        let offset = (hmac_result[hmac_result.len() - 1] & 0x0f) as usize;
+        let code = u32::from_be_bytes([
+            hmac_result[offset],
+            hmac_result[offset + 1],
+            hmac_result[offset + 2],
+            hmac_result[offset + 3],
+        ]);
+
+        // Step 6: Generate the OTP of the configured length
+        Ok(TotpToken::new(format_token(code, self.digits.into())?))
+    }
+}
+
+/// Source of the current time for TOTP codes
+///
+/// Production code uses [`SystemClock`], corrected by `time_offset_secs`
+/// through [`OffsetClock`]; tests substitute a fixed time.
+pub trait Clock: Send + Sync {
+    /// Seconds since the Unix epoch
+    fn unix_time(&self) -> Result<u64, OtpError>;
+}
+
+/// The system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn unix_time(&self) -> Result<u64, OtpError> {
+        unix_timestamp(SystemTime::now())
+    }
+}
+
+/// A clock shifted by a fixed number of seconds
+///
+/// Compensates for a system clock known to be off: a positive offset for
+/// one that runs behind, a negative one for one that runs ahead.
+#[derive(Debug, Clone, Copy)]
+pub struct OffsetClock<C> {
+    clock: C,
+    offset_secs: i64,
+}
+
+impl<C: Clock> OffsetClock<C> {
+    pub fn new(clock: C, offset_secs: i64) -> Self {
+        Self { clock, offset_secs }
+    }
+}
+
+impl OffsetClock<SystemClock> {
+    /// The system clock corrected by the `time_offset_secs` setting
+    pub fn for_config(config: &VpnConfig) -> Self {
+        Self::new(SystemClock, config.time_offset_secs)
+    }
+}
+
+impl<C: Clock> Clock for OffsetClock<C> {
+    fn unix_time(&self) -> Result<u64, OtpError> {
+        self.clock
+            .unix_time()?
+            .checked_add_signed(self.offset_secs)
+            .ok_or(OtpError::ClockError)
+    }
+}
+
+/// Resolve the timestamp to use: the given one, or the current time
+fn resolve_timestamp(timestamp: Option<u64>) -> Result<u64, OtpError> {
+    match timestamp {
+        Some(ts) => Ok(ts),
+        None => unix_timestamp(SystemTime::now()),
+    }
+}
+
+/// Seconds since the Unix epoch, failing for a clock set before it
+fn unix_timestamp(time: SystemTime) -> Result<u64, OtpError> {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .map_err(|_| OtpError::ClockError)
+}
+
+/// Get the current time step, failing if a code from it was already used
+///
+/// Gateways reject a TOTP code that was already accepted, so a second login
+/// within the same step is bound to fail; the error says when the next code
+/// is available. Steps are `period_secs` long.
+pub fn ensure_unused_step(
+    last_used_step: Option<u64>,
+    timestamp: Option<u64>,
+    period_secs: u64,
+) -> Result<u64, AkonError> {
+    if period_secs == 0 {
+        return Err(AkonError::Otp(OtpError::InvalidPeriod));
+    }
+    let ts = resolve_timestamp(timestamp)?;
+    let step = ts / period_secs;
+
+    if last_used_step == Some(step) {
+        return Err(AkonError::Otp(OtpError::RateLimited {
+            retry_after_secs: period_secs - ts % period_secs,
+        }));
+    }
+    Ok(step)
+}
+
+/// Format a truncated HMAC value as a zero-padded token of `digits` digits
+fn format_token(code: u32, digits: u32) -> Result<String, OtpError> {
+    if !(6..=8).contains(&digits) {
+        return Err(OtpError::InvalidDigits { digits });
+    }
+
+    let otp = (code & 0x7fffffff) % 10u32.pow(digits);
+    Ok(format!("{:0width$}", otp, width = digits as usize))
 }
 
 /// Generate OTP token from secret, matching auto-openconnect's algorithm
 ///
-/// This function implements the exact same logic as auto-openconnect's
-/// `lib.py::generate_otp()` function:
-/// 1. Calculate HOTP counter from timestamp
-/// 2. Decode Base32 secret (with custom padding and whitespace handling)
-/// 3. Compute HMAC-SHA1
-/// 4. Apply dynamic truncation (RFC 6238)
-/// 5. Return 6-digit OTP
+/// A 6-digit HMAC-SHA1 code over 30s steps; see [`TotpSettings::generate`].
 pub fn generate_otp(secret: &OtpSecret, timestamp: Option<u64>) -> Result<TotpToken, AkonError> {
-    // Step 1: Get HOTP counter (timestamp / 30)
-    let counter = get_hotp_counter(timestamp)?;
+    TotpSettings::default().generate(secret, timestamp)
+}
 
-    // Step 2: Decode Base32 secret with custom logic
-    let key_bytes = base32::decode_base32(secret.expose()).map_err(AkonError::Otp)?;
+/// Generate OTP token from a secret provisioned for `algorithm`
+///
+/// Same as [`generate_otp`] with the HMAC computed by `algorithm`; the
+/// dynamic truncation is identical for all of them.
+pub fn generate_otp_with_algorithm(
+    secret: &OtpSecret,
+    timestamp: Option<u64>,
+    algorithm: OtpAlgorithm,
+) -> Result<TotpToken, AkonError> {
+    TotpSettings {
+        algorithm,
+        ..TotpSettings::default()
+    }
+    .generate(secret, timestamp)
+}
+
+/// Time steps checked on either side of the expected one by [`check_code`]
+pub const SKEW_CHECK_STEPS: i64 = 2;
+
+/// Outcome of comparing a code against the stored secret
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeCheck {
+    /// The code is the one for the requested time
+    Match,
+    /// The code belongs to a neighboring time step, `offset` steps away
+    ///
+    /// A positive offset means the code is from the future, i.e. whoever
+    /// generated it has a clock ahead of ours.
+    Skewed { offset: i64 },
+    /// The code matches none of the checked time steps
+    NoMatch,
+}
+
+/// Check whether `code` is what `secret` produces at `timestamp` (default: now)
+///
+/// Neighboring time steps are checked too, so clock skew can be told apart
+/// from a wrong secret.
+pub fn check_code(
+    secret: &OtpSecret,
+    code: &str,
+    timestamp: Option<u64>,
+    settings: TotpSettings,
+) -> Result<CodeCheck, AkonError> {
+    check_code_within(secret, code, timestamp, settings, SKEW_CHECK_STEPS)
+}
+
+/// [`check_code`], checking `steps` time steps on either side
+pub fn check_code_within(
+    secret: &OtpSecret,
+    code: &str,
+    timestamp: Option<u64>,
+    settings: TotpSettings,
+    steps: i64,
+) -> Result<CodeCheck, AkonError> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    let ts = resolve_timestamp(timestamp)?;
 
-    // Step 3: Convert counter to big-endian bytes
-    let counter_bytes = counter.to_be_bytes();
+    if settings.generate(secret, Some(ts))?.expose() == code {
+        return Ok(CodeCheck::Match);
+    }
 
-    // Step 4: Compute HMAC-SHA1
-    let hmac_result = hmac::hmac_sha1(&key_bytes, &counter_bytes);
+    for distance in 1..=steps {
+        for offset in [-distance, distance] {
+            let Some(shifted) = ts.checked_add_signed(offset * settings.period_secs as i64) else {
+                continue;
+            };
+            if settings.generate(secret, Some(shifted))?.expose() == code {
+                return Ok(CodeCheck::Skewed { offset });
+            }
+        }
+    }
 
-    // Step 5: Dynamic truncation (RFC 6238)
-    let offset = (hmac_result[19] & 0x0f) as usize;
-    let code = u32::from_be_bytes([
-        hmac_result[offset],
-        hmac_result[offset + 1],
-        hmac_result[offset + 2],
-        hmac_result[offset + 3],
-    ]);
+    Ok(CodeCheck::NoMatch)
+}
+
+/// Shortest plausible decoded secret, in bytes
+///
+/// RFC 4226 requires at least 128 bits; shorter secrets usually come from a
+/// paste that lost its end.
+pub const MIN_SECRET_BYTES: usize = 16;
+
+/// Something suspicious about a stored secret that still yields codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretAnomaly {
+    /// The secret decodes to fewer than [`MIN_SECRET_BYTES`] bytes
+    TooShort { bytes: usize },
+    /// A generated code is not `digits` decimal digits
+    MalformedCode { digits: u8 },
+}
+
+impl SecretAnomaly {
+    /// Explanation for `akon doctor`
+    pub fn message(&self) -> String {
+        match self {
+            SecretAnomaly::TooShort { bytes } => format!(
+                "OTP secret decodes to only {} bytes (expected at least {}); it may have been truncated when pasted",
+                bytes, MIN_SECRET_BYTES
+            ),
+            SecretAnomaly::MalformedCode { digits } => {
+                format!("OTP secret does not produce a {}-digit code", digits)
+            }
+        }
+    }
+}
 
-    // Step 6: Generate 6-digit OTP
-    let otp = (code & 0x7fffffff) % 1_000_000;
+/// Check that `secret` decodes to a plausible key and yields well-formed codes
+///
+/// Fails if no code can be generated at all, e.g. for invalid Base32; returns
+/// the anomalies found otherwise, none for a healthy secret.
+pub fn inspect_secret(
+    secret: &OtpSecret,
+    settings: TotpSettings,
+    timestamp: Option<u64>,
+) -> Result<Vec<SecretAnomaly>, AkonError> {
+    let token = settings.generate(secret, timestamp)?;
+    let key_bytes = base32::decode_base32(secret.expose()).map_err(AkonError::Otp)?;
 
-    Ok(TotpToken::new(format!("{:06}", otp)))
+    let mut anomalies = Vec::new();
+    if key_bytes.len() < MIN_SECRET_BYTES {
+        anomalies.push(SecretAnomaly::TooShort {
+            bytes: key_bytes.len(),
+        });
+    }
+    let code = token.expose();
+    if code.len() != usize::from(settings.digits) || !code.chars().all(|c| c.is_ascii_digit()) {
+        anomalies.push(SecretAnomaly::MalformedCode {
+            digits: settings.digits,
+        });
+    }
+    Ok(anomalies)
 }
 
 /// Generate a TOTP token with default settings (for backward compatibility)
@@ -87,7 +399,161 @@ mod tests {
     fn test_invalid_base32() {
         let otp_secret = OtpSecret::new("INVALID!@#$".to_string());
         let result = generate_otp(&otp_secret, None);
-        assert!(result.is_err());
+        assert!(matches!(
+            result,
+            Err(AkonError::Otp(OtpError::InvalidBase32))
+        ));
+    }
+
+    #[test]
+    fn test_empty_secret() {
+        for secret in ["", "   "] {
+            let result = generate_otp(&OtpSecret::new(secret.to_string()), Some(0));
+            assert!(matches!(result, Err(AkonError::Otp(OtpError::EmptySecret))));
+        }
+    }
+
+    #[test]
+    fn test_clock_before_epoch() {
+        let before_epoch = UNIX_EPOCH - std::time::Duration::from_secs(1);
+        assert_eq!(unix_timestamp(before_epoch), Err(OtpError::ClockError));
+        assert_eq!(unix_timestamp(UNIX_EPOCH), Ok(0));
+    }
+
+    #[test]
+    fn test_offset_clock() {
+        struct FixedClock(u64);
+        impl Clock for FixedClock {
+            fn unix_time(&self) -> Result<u64, OtpError> {
+                Ok(self.0)
+            }
+        }
+
+        assert_eq!(OffsetClock::new(FixedClock(100), 30).unix_time(), Ok(130));
+        assert_eq!(OffsetClock::new(FixedClock(100), -30).unix_time(), Ok(70));
+        assert_eq!(
+            OffsetClock::new(FixedClock(10), -30).unix_time(),
+            Err(OtpError::ClockError)
+        );
+    }
+
+    #[test]
+    fn test_format_token_digits() {
+        assert_eq!(format_token(123, 6).unwrap(), "000123");
+        assert_eq!(format_token(123_456_789, 8).unwrap(), "23456789");
+        // Leading zeros are padded to every length
+        assert_eq!(format_token(5, 7).unwrap(), "0000005");
+        assert_eq!(format_token(5, 8).unwrap(), "00000005");
+        assert_eq!(format_token(10_000_000, 7).unwrap(), "0000000");
+        assert_eq!(
+            format_token(123, 9),
+            Err(OtpError::InvalidDigits { digits: 9 })
+        );
+        assert_eq!(
+            format_token(123, 5),
+            Err(OtpError::InvalidDigits { digits: 5 })
+        );
+    }
+
+    #[test]
+    fn test_check_code() {
+        let secret = OtpSecret::new("JBSWY3DPEHPK3PXP".to_string());
+        let settings = TotpSettings::default();
+        let ts = 1609459200;
+        let code_at = |ts: u64| {
+            generate_otp(&secret, Some(ts))
+                .unwrap()
+                .expose()
+                .to_string()
+        };
+
+        assert_eq!(
+            check_code(&secret, &code_at(ts), Some(ts), settings).unwrap(),
+            CodeCheck::Match
+        );
+        // Codes are often shown grouped as "123 456"
+        let spaced = format!("{} {}", &code_at(ts)[..3], &code_at(ts)[3..]);
+        assert_eq!(
+            check_code(&secret, &spaced, Some(ts), settings).unwrap(),
+            CodeCheck::Match
+        );
+
+        assert_eq!(
+            check_code(&secret, &code_at(ts + 30), Some(ts), settings).unwrap(),
+            CodeCheck::Skewed { offset: 1 }
+        );
+        assert_eq!(
+            check_code(&secret, &code_at(ts - 60), Some(ts), settings).unwrap(),
+            CodeCheck::Skewed { offset: -2 }
+        );
+
+        assert_eq!(
+            check_code(&secret, &code_at(ts + 3600), Some(ts), settings).unwrap(),
+            CodeCheck::NoMatch
+        );
+    }
+
+    #[test]
+    fn test_check_code_within_a_narrower_window() {
+        let secret = OtpSecret::new("JBSWY3DPEHPK3PXP".to_string());
+        let settings = TotpSettings::default();
+        let ts = 1609459200;
+        let code_at = |ts: u64| generate_otp(&secret, Some(ts)).unwrap();
+
+        assert_eq!(
+            check_code_within(&secret, code_at(ts - 30).expose(), Some(ts), settings, 1).unwrap(),
+            CodeCheck::Skewed { offset: -1 }
+        );
+        assert_eq!(
+            check_code_within(&secret, code_at(ts + 60).expose(), Some(ts), settings, 1).unwrap(),
+            CodeCheck::NoMatch
+        );
+    }
+
+    #[test]
+    fn test_check_code_uses_the_configured_algorithm() {
+        let secret = OtpSecret::new("JBSWY3DPEHPK3PXP".to_string());
+        let sha256 = TotpSettings {
+            algorithm: OtpAlgorithm::Sha256,
+            ..TotpSettings::default()
+        };
+        let ts = 1609459200;
+        let code = sha256.generate(&secret, Some(ts)).unwrap();
+
+        assert_eq!(
+            check_code(&secret, code.expose(), Some(ts), sha256).unwrap(),
+            CodeCheck::Match
+        );
+        assert_ne!(
+            check_code(&secret, code.expose(), Some(ts), TotpSettings::default()).unwrap(),
+            CodeCheck::Match
+        );
+    }
+
+    #[test]
+    fn test_reused_time_step_is_rate_limited() {
+        let step = ensure_unused_step(None, Some(1609459200), 30).unwrap();
+        assert_eq!(step, 1609459200 / 30);
+
+        let result = ensure_unused_step(Some(step), Some(1609459210), 30);
+        assert!(matches!(
+            result,
+            Err(AkonError::Otp(OtpError::RateLimited {
+                retry_after_secs: 20
+            }))
+        ));
+
+        // The next step has a fresh code
+        assert!(ensure_unused_step(Some(step), Some(1609459230), 30).is_ok());
+
+        // With 60s steps, the same wait is longer
+        let step = ensure_unused_step(None, Some(1609459200), 60).unwrap();
+        assert!(matches!(
+            ensure_unused_step(Some(step), Some(1609459230), 60),
+            Err(AkonError::Otp(OtpError::RateLimited {
+                retry_after_secs: 30
+            }))
+        ));
     }
 
     #[test]
@@ -106,6 +572,8 @@ mod tests {
 
     #[test]
     fn test_hotp_counter_calculation() {
+        let get_hotp_counter = |ts| TotpSettings::default().counter(ts);
+
         // Test that counter calculation matches Python's int(time / 30)
         let result = get_hotp_counter(Some(1609459200));
         assert!(result.is_ok());
@@ -117,6 +585,100 @@ mod tests {
         assert_eq!(get_hotp_counter(Some(60)).unwrap(), 2);
         assert_eq!(get_hotp_counter(Some(89)).unwrap(), 2);
         assert_eq!(get_hotp_counter(Some(90)).unwrap(), 3);
+
+        let no_period = TotpSettings {
+            period_secs: 0,
+            ..TotpSettings::default()
+        };
+        assert!(matches!(
+            no_period.counter(Some(90)),
+            Err(AkonError::Otp(OtpError::InvalidPeriod))
+        ));
+
+        // The counter depends on the period
+        let counter = |period_secs, ts| {
+            TotpSettings {
+                period_secs,
+                ..TotpSettings::default()
+            }
+            .counter(Some(ts))
+            .unwrap()
+        };
+
+        // (timestamp, counter with 30s steps, counter with 60s steps)
+        let table = [
+            (0, 0, 0),
+            (29, 0, 0),
+            (30, 1, 0),
+            (59, 1, 0),
+            (60, 2, 1),
+            (89, 2, 1),
+            (90, 3, 1),
+            (120, 4, 2),
+            (1609459200, 53648640, 26824320),
+        ];
+        for (ts, thirty, sixty) in table {
+            assert_eq!(counter(30, ts), thirty, "30s steps at {}", ts);
+            assert_eq!(counter(60, ts), sixty, "60s steps at {}", ts);
+        }
+
+        // The two sequences part ways as soon as the first 30s step ends
+        let sequence = |period_secs| [0, 30, 60, 90].map(|ts| counter(period_secs, ts));
+        assert_eq!(sequence(30), [0, 1, 2, 3]);
+        assert_eq!(sequence(60), [0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_eight_digit_tokens() {
+        // RFC 6238 Appendix B lists 8-digit SHA-1 codes
+        let secret = OtpSecret::new("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_string());
+        let settings = TotpSettings {
+            digits: 8,
+            ..TotpSettings::default()
+        };
+
+        assert_eq!(
+            settings.generate(&secret, Some(59)).unwrap().expose(),
+            "94287082"
+        );
+        // Leading zeros are kept
+        assert_eq!(
+            settings
+                .generate(&secret, Some(1111111109))
+                .unwrap()
+                .expose(),
+            "07081804"
+        );
+
+        for digits in [5, 9] {
+            let settings = TotpSettings {
+                digits,
+                ..TotpSettings::default()
+            };
+            assert!(matches!(
+                settings.generate(&secret, Some(59)),
+                Err(AkonError::Otp(OtpError::InvalidDigits { .. }))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_sixty_second_period() {
+        let secret = OtpSecret::new("JBSWY3DPEHPK3PXP".to_string());
+        let settings = TotpSettings {
+            period_secs: 60,
+            ..TotpSettings::default()
+        };
+        let ts = 1609459259;
+
+        // A 60s step is the 30s step with half the counter
+        let code = settings.generate(&secret, Some(ts)).unwrap();
+        let same_counter = generate_otp(&secret, Some(ts / 60 * 30)).unwrap();
+        assert_eq!(code.expose(), same_counter.expose());
+
+        // The whole minute shares one code
+        let start = settings.generate(&secret, Some(1609459200)).unwrap();
+        assert_eq!(code.expose(), start.expose());
     }
 
     #[test]
@@ -131,4 +693,24 @@ mod tests {
         assert_eq!(token.expose().len(), 6);
         assert!(token.expose().chars().all(|c| c.is_ascii_digit()));
     }
+
+    #[test]
+    fn test_inspect_secret_accepts_a_normal_length_secret() {
+        // 20 bytes, the usual length for SHA-1 secrets
+        let secret = OtpSecret::new("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_string());
+        let anomalies = inspect_secret(&secret, TotpSettings::default(), Some(59)).unwrap();
+        assert!(anomalies.is_empty(), "{:?}", anomalies);
+    }
+
+    #[test]
+    fn test_inspect_secret_flags_a_truncated_secret() {
+        // The first 16 characters of the secret above: 10 bytes
+        let secret = OtpSecret::new("GEZDGNBVGY3TQOJQ".to_string());
+        let anomalies = inspect_secret(&secret, TotpSettings::default(), Some(59)).unwrap();
+        assert_eq!(anomalies, vec![SecretAnomaly::TooShort { bytes: 10 }]);
+        assert!(anomalies[0].message().contains("truncated"));
+
+        let invalid = OtpSecret::new("not base32!".to_string());
+        assert!(inspect_secret(&invalid, TotpSettings::default(), Some(59)).is_err());
+    }
 }
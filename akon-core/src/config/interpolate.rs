@@ -0,0 +1,135 @@
+//! Environment variable interpolation for configuration values
+//!
+//! String values may reference environment variables as `${NAME}`, which are
+//! resolved when the configuration is loaded. `$$` produces a literal `$`.
+//! Reconnection hook commands are left as written: they run through a shell,
+//! which expands their variables (e.g. `${AKON_SERVER}`) when the hook fires.
+
+use crate::error::ConfigError;
+
+/// Keys holding shell commands, which are never interpolated
+const HOOK_KEYS: &[&str] = &["on_reconnect_attempt", "on_reconnect_success"];
+
+/// Resolve `${NAME}` references in a string using the process environment
+pub fn interpolate_env(input: &str) -> Result<String, ConfigError> {
+    interpolate_with(input, |name| std::env::var(name).ok())
+}
+
+/// Resolve `${NAME}` references in a string using a custom lookup
+pub fn interpolate_with<F>(input: &str, lookup: F) -> Result<String, ConfigError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(pos) = rest.find('$') {
+        output.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+
+        if let Some(escaped) = after.strip_prefix('$') {
+            // "$$" is an escaped literal dollar sign
+            output.push('$');
+            rest = escaped;
+        } else if let Some(reference) = after.strip_prefix('{') {
+            let end = reference
+                .find('}')
+                .ok_or_else(|| ConfigError::ValidationError {
+                    message: format!("Unterminated variable reference in '{}'", input),
+                })?;
+            let name = &reference[..end];
+
+            if !is_valid_name(name) {
+                return Err(ConfigError::ValidationError {
+                    message: format!("Invalid environment variable name '{}'", name),
+                });
+            }
+
+            let value = lookup(name).ok_or_else(|| ConfigError::UnsetEnvVar {
+                name: name.to_string(),
+            })?;
+            output.push_str(&value);
+            rest = &reference[end + 1..];
+        } else {
+            // A lone '$' is kept as-is
+            output.push('$');
+            rest = after;
+        }
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Resolve references in every string of a parsed TOML document
+///
+/// Values of the hook keys are skipped, wherever they appear.
+pub fn interpolate_toml(value: &mut toml::Value) -> Result<(), ConfigError> {
+    match value {
+        toml::Value::String(s) => *s = interpolate_env(s)?,
+        toml::Value::Array(items) => {
+            for item in items {
+                interpolate_toml(item)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (key, item) in table.iter_mut() {
+                if HOOK_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+                interpolate_toml(item)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "VPN_HOST" => Some("vpn.example.com".to_string()),
+            "USER" => Some("alice".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_resolves_set_variables() {
+        assert_eq!(
+            interpolate_with("${USER}@${VPN_HOST}", lookup).unwrap(),
+            "alice@vpn.example.com"
+        );
+        assert_eq!(interpolate_with("plain", lookup).unwrap(), "plain");
+    }
+
+    #[test]
+    fn test_unset_variable_is_an_error() {
+        let err = interpolate_with("${MISSING_VAR}", lookup).unwrap_err();
+        assert!(matches!(err, ConfigError::UnsetEnvVar { ref name } if name == "MISSING_VAR"));
+        assert!(err.to_string().contains("MISSING_VAR"));
+    }
+
+    #[test]
+    fn test_escaped_dollar_is_literal() {
+        assert_eq!(interpolate_with("$${USER}", lookup).unwrap(), "${USER}");
+        assert_eq!(interpolate_with("cost $$5", lookup).unwrap(), "cost $5");
+        assert_eq!(interpolate_with("a$b", lookup).unwrap(), "a$b");
+    }
+
+    #[test]
+    fn test_malformed_references_are_errors() {
+        assert!(interpolate_with("${USER", lookup).is_err());
+        assert!(interpolate_with("${1BAD}", lookup).is_err());
+        assert!(interpolate_with("${}", lookup).is_err());
+    }
+}
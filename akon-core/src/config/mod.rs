@@ -2,10 +2,80 @@
 //!
 //! Handles loading and saving VPN configuration from TOML files.
 
+use crate::auth::totp::{OtpAlgorithm, TIME_STEP_SECS, TOTP_DIGITS};
+use crate::vpn::jump::{parse_jump_host, DEFAULT_JUMP_LOCAL_PORT};
+use crate::vpn::route_check::{parse_route_target, RouteCheck};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
 
+pub mod interpolate;
+pub mod template;
 pub mod toml_config;
 
+/// Times a connection failing on a temporary DNS error is retried by default
+pub const DEFAULT_DNS_RETRIES: u32 = 3;
+
+/// Seconds before the first DNS retry by default; later ones wait longer
+pub const DEFAULT_DNS_RETRY_INTERVAL_SECS: u64 = 2;
+
+/// Most TOTP steps either side of the current one `skew_windows` may cover
+pub const MAX_SKEW_WINDOWS: u8 = 3;
+
+/// Profile the `[vpn]` section loads as
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Environment variable selecting a profile, like `--profile`
+///
+/// Also tells the reconnection daemon which profile it works with.
+pub const PROFILE_ENV: &str = "AKON_PROFILE";
+
+/// Profile selected through `AKON_PROFILE`, if any
+pub fn selected_profile() -> Option<String> {
+    std::env::var(PROFILE_ENV)
+        .ok()
+        .filter(|name| !name.is_empty())
+}
+
+/// Check that `name` can name a profile
+///
+/// Profile names end up in file names, so they are kept to letters, digits,
+/// `-` and `_`.
+pub fn validate_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid profile name '{}': use letters, digits, '-' and '_'",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Check that a configured `openconnect_path` is an executable file
+fn validate_openconnect_path(path: &std::path::Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| format!("openconnect_path {} cannot be used: {}", path.display(), e))?;
+    if !metadata.is_file() {
+        return Err(format!("openconnect_path {} is not a file", path.display()));
+    }
+    if metadata.permissions().mode() & 0o111 == 0 {
+        return Err(format!(
+            "openconnect_path {} is not executable",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
 /// VPN protocol type
 ///
 /// Supported VPN protocols for OpenConnect
@@ -42,6 +112,104 @@ impl VpnProtocol {
             Self::Array => "array",
         }
     }
+
+    /// User-Agent to send when none is configured
+    ///
+    /// Only set for protocols whose gateways commonly reject openconnect's
+    /// own User-Agent; `None` keeps openconnect's default.
+    pub fn default_user_agent(&self) -> Option<&'static str> {
+        match self {
+            Self::AnyConnect => Some("AnyConnect Linux_64 4.10.07061"),
+            Self::GlobalProtect => Some("PAN GlobalProtect"),
+            _ => None,
+        }
+    }
+}
+
+/// How akon gets the root privileges openconnect and `kill` need
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrivilegeEscalation {
+    /// `sudo` (default); needs a terminal or a passwordless sudoers rule
+    #[default]
+    Sudo,
+    /// `pkexec`, which asks through the desktop's polkit agent
+    Pkexec,
+    /// Run commands directly, for akon running as root or with CAP_NET_ADMIN
+    None,
+}
+
+impl PrivilegeEscalation {
+    /// Get the setting as written in the config file
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sudo => "sudo",
+            Self::Pkexec => "pkexec",
+            Self::None => "none",
+        }
+    }
+
+    /// Program that runs commands as root, if any
+    pub fn tool(&self) -> Option<&'static str> {
+        match self {
+            Self::Sudo => Some("sudo"),
+            Self::Pkexec => Some("pkexec"),
+            Self::None => None,
+        }
+    }
+
+    /// Command running `program` with root privileges
+    pub fn command(&self, program: impl AsRef<OsStr>) -> std::process::Command {
+        match self.tool() {
+            Some(tool) => {
+                let mut command = std::process::Command::new(tool);
+                command.arg(program);
+                command
+            }
+            None => std::process::Command::new(program),
+        }
+    }
+}
+
+/// What running `akon` without arguments does when lazy mode is enabled
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LazyAction {
+    /// Connect (default); reports the connection if one is already up
+    #[default]
+    Connect,
+    /// Show the connection status
+    Status,
+    /// Connect if disconnected, show the status if connected
+    Toggle,
+}
+
+impl LazyAction {
+    /// Get the action name as written in the config file
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Connect => "connect",
+            Self::Status => "status",
+            Self::Toggle => "toggle",
+        }
+    }
+
+    /// Whether to connect rather than show the status
+    pub fn connects(&self, connected: bool) -> bool {
+        match self {
+            Self::Connect => true,
+            Self::Status => false,
+            Self::Toggle => !connected,
+        }
+    }
+}
+
+/// Settings of the `[behavior]` section
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BehaviorConfig {
+    /// What `akon` without arguments does in lazy mode
+    #[serde(default)]
+    pub lazy_action: LazyAction,
 }
 
 /// VPN configuration structure
@@ -50,6 +218,19 @@ impl VpnProtocol {
 /// Sensitive data like OTP secrets are stored separately in the keyring.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VpnConfig {
+    /// Label identifying this connection in status output and logs (default: server)
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Profile this configuration was loaded as; not stored in the file
+    #[serde(skip)]
+    pub profile: Option<String>,
+
+    /// Address of `server` to connect to, when it resolves to several
+    /// gateways; chosen per attempt and not stored in the file
+    #[serde(skip)]
+    pub server_address: Option<IpAddr>,
+
     /// VPN server hostname or IP address
     pub server: String,
 
@@ -60,28 +241,211 @@ pub struct VpnConfig {
     #[serde(default)]
     pub protocol: VpnProtocol,
 
+    /// openconnect binary to run instead of the one found on PATH
+    #[serde(default)]
+    pub openconnect_path: Option<PathBuf>,
+
     /// Connection timeout in seconds
+    ///
+    /// Bounds the whole connection attempt.
     pub timeout: Option<u32>,
 
+    /// How long openconnect keeps retrying a lost gateway, in seconds
+    ///
+    /// Passed as `--reconnect-timeout`; openconnect's own default (300s)
+    /// applies when unset.
+    #[serde(default)]
+    pub reconnect_timeout: Option<u32>,
+
+    /// Abort if a connection phase produces no output for this many seconds
+    #[serde(default)]
+    pub phase_timeout_secs: Option<u32>,
+
     /// Disable DTLS (Datagram TLS) and use only TCP/TLS
     #[serde(default)]
     pub no_dtls: bool,
 
+    /// Authentication group (realm) to select on gateways that offer several
+    #[serde(default)]
+    pub auth_group: Option<String>,
+
+    /// Network interface the VPN's outer connection must leave through
+    #[serde(default)]
+    pub egress_interface: Option<String>,
+
+    /// SSH host (`[user@]host[:port]`) to reach the VPN server through
+    #[serde(default)]
+    pub jump_host: Option<String>,
+
+    /// Local port of the SSH forward used with `jump_host` (default: 10443)
+    #[serde(default)]
+    pub jump_local_port: Option<u16>,
+
+    /// Seconds orphaned openconnect processes get to exit after SIGTERM (default: 5)
+    #[serde(default)]
+    pub cleanup_grace_secs: Option<u64>,
+
+    /// Leave orphaned openconnect processes that ignore SIGTERM running
+    /// instead of sending SIGKILL
+    #[serde(default)]
+    pub no_kill_escalation: bool,
+
+    /// How openconnect is run as root and signalled: sudo, pkexec or none
+    #[serde(default)]
+    pub privilege_escalation: PrivilegeEscalation,
+
+    /// How many times to look for the daemonized openconnect process (default: 15)
+    #[serde(default)]
+    pub pid_lookup_attempts: Option<u32>,
+
+    /// Retries when the server name fails to resolve temporarily (default: 3)
+    #[serde(default)]
+    pub dns_retries: Option<u32>,
+
+    /// Seconds before the first of those retries, growing with each (default: 2)
+    #[serde(default)]
+    pub dns_retry_interval_secs: Option<u64>,
+
+    /// Authentication attempts allowed per hour, shared by connects and reconnections
+    #[serde(default)]
+    pub max_auth_attempts_per_hour: Option<u32>,
+
+    /// Check after connecting that the tunnel carries the default route
+    #[serde(default)]
+    pub verify_default_route: RouteCheck,
+
+    /// Addresses or CIDRs that must be routed through the tunnel after connecting
+    #[serde(default)]
+    pub verify_routes: Vec<String>,
+
+    /// Block traffic outside the tunnel from connecting until `vpn off`
+    #[serde(default)]
+    pub kill_switch: bool,
+
+    /// Take the PIN from the keyring (otherwise it is asked for on connect)
+    #[serde(default = "default_store_credential")]
+    pub store_pin: bool,
+
+    /// Take the TOTP secret from the keyring (otherwise it is asked for on connect)
+    #[serde(default = "default_store_credential")]
+    pub store_secret: bool,
+
+    /// Seconds added to the system clock when generating OTP codes
+    ///
+    /// Compensates for a clock known to be off: positive when it runs behind.
+    #[serde(default)]
+    pub time_offset_secs: i64,
+
+    /// HMAC hash the TOTP secret was provisioned for (default: SHA-1)
+    #[serde(default)]
+    pub otp_algorithm: OtpAlgorithm,
+
+    /// Digits in a TOTP code, 6 to 8 (default: 6)
+    #[serde(default = "default_otp_digits")]
+    pub otp_digits: u8,
+
+    /// Length of a TOTP time step in seconds (default: 30)
+    #[serde(default = "default_otp_period_secs")]
+    pub otp_period_secs: u64,
+
+    /// TOTP time steps on either side of the current one to make candidate
+    /// passwords for, in case the clock is off from the gateway's (default: 0)
+    #[serde(default)]
+    pub skew_windows: u8,
+
+    /// Compare the clock with the VPN server's before connecting, warning on skew
+    #[serde(default)]
+    pub check_clock_skew: bool,
+
+    /// Check the stored OTP secret for truncation before connecting, as
+    /// `akon doctor` does, warning when it looks damaged
+    #[serde(default)]
+    pub check_otp_secret: bool,
+
+    /// User-Agent sent to the gateway (defaults per protocol)
+    #[serde(default)]
+    pub user_agent: Option<String>,
+
     /// Enable lazy mode - running akon without arguments connects to VPN
     #[serde(default)]
     pub lazy_mode: bool,
+
+    /// Remember recently assigned IPs and flag addresses from an unusual range
+    #[serde(default)]
+    pub track_ip_history: bool,
+
+    /// Skip TLS certificate validation for the VPN server and health checks
+    ///
+    /// Dangerous: only meant for lab/test gateways with broken certificates.
+    /// Requires `insecure_tls_acknowledged` to also be set.
+    #[serde(default)]
+    pub insecure_skip_tls_verify: bool,
+
+    /// Acknowledge the risk of `insecure_skip_tls_verify`
+    #[serde(default)]
+    pub insecure_tls_acknowledged: bool,
+
+    /// Answers to extra gateway prompts, keyed by part of the prompt text
+    #[serde(default)]
+    pub prompt_responses: BTreeMap<String, String>,
+}
+
+fn default_store_credential() -> bool {
+    true
+}
+
+fn default_otp_digits() -> u8 {
+    TOTP_DIGITS
+}
+
+fn default_otp_period_secs() -> u64 {
+    TIME_STEP_SECS
 }
 
 impl VpnConfig {
     /// Create a new VPN configuration
     pub fn new(server: String, username: String) -> Self {
         Self {
+            name: None,
+            profile: None,
+            server_address: None,
             server,
             username,
             protocol: VpnProtocol::default(),
+            openconnect_path: None,
             timeout: None,
+            reconnect_timeout: None,
+            phase_timeout_secs: None,
             no_dtls: false,
+            auth_group: None,
+            egress_interface: None,
+            jump_host: None,
+            jump_local_port: None,
+            cleanup_grace_secs: None,
+            no_kill_escalation: false,
+            privilege_escalation: PrivilegeEscalation::default(),
+            pid_lookup_attempts: None,
+            dns_retries: None,
+            dns_retry_interval_secs: None,
+            max_auth_attempts_per_hour: None,
+            verify_default_route: RouteCheck::default(),
+            verify_routes: Vec::new(),
+            kill_switch: false,
+            store_pin: true,
+            store_secret: true,
+            time_offset_secs: 0,
+            otp_algorithm: OtpAlgorithm::default(),
+            otp_digits: TOTP_DIGITS,
+            otp_period_secs: TIME_STEP_SECS,
+            skew_windows: 0,
+            check_clock_skew: false,
+            check_otp_secret: false,
+            user_agent: None,
             lazy_mode: false,
+            track_ip_history: false,
+            insecure_skip_tls_verify: false,
+            insecure_tls_acknowledged: false,
+            prompt_responses: BTreeMap::new(),
         }
     }
 
@@ -113,19 +477,243 @@ impl VpnConfig {
             }
         }
 
+        if self
+            .name
+            .as_ref()
+            .is_some_and(|name| name.trim().is_empty())
+        {
+            return Err("Name cannot be empty".to_string());
+        }
+
+        if let Some(path) = &self.openconnect_path {
+            validate_openconnect_path(path)?;
+        }
+
+        if self
+            .auth_group
+            .as_ref()
+            .is_some_and(|group| group.trim().is_empty())
+        {
+            return Err("Auth group cannot be empty".to_string());
+        }
+
+        if self
+            .egress_interface
+            .as_ref()
+            .is_some_and(|interface| interface.trim().is_empty())
+        {
+            return Err("Egress interface cannot be empty".to_string());
+        }
+
+        if let Some(jump_host) = &self.jump_host {
+            parse_jump_host(jump_host)?;
+            // The host route would pin the server, but akon only talks to the
+            // jump host
+            if self.egress_interface.is_some() {
+                return Err("egress_interface cannot be combined with jump_host".to_string());
+            }
+            // The kill switch only lets the VPN server through, not the jump host
+            if self.kill_switch {
+                return Err("kill_switch cannot be combined with jump_host".to_string());
+            }
+        }
+
+        match self.jump_local_port {
+            Some(_) if self.jump_host.is_none() => {
+                return Err("jump_local_port requires jump_host".to_string());
+            }
+            Some(port) if port < 1024 => {
+                return Err("Jump local port must be 1024 or higher".to_string());
+            }
+            _ => {}
+        }
+
+        if self
+            .user_agent
+            .as_ref()
+            .is_some_and(|ua| ua.trim().is_empty())
+        {
+            return Err("User agent cannot be empty".to_string());
+        }
+
+        if self.phase_timeout_secs == Some(0) {
+            return Err("Phase timeout cannot be zero".to_string());
+        }
+
+        if self.reconnect_timeout == Some(0) {
+            return Err("Reconnect timeout cannot be zero".to_string());
+        }
+
+        if self.cleanup_grace_secs == Some(0) {
+            return Err("Cleanup grace period cannot be zero".to_string());
+        }
+
+        if self.pid_lookup_attempts == Some(0) {
+            return Err("PID lookup attempts cannot be zero".to_string());
+        }
+
+        if self.dns_retry_interval_secs == Some(0) {
+            return Err("DNS retry interval cannot be zero".to_string());
+        }
+
+        if self.max_auth_attempts_per_hour == Some(0) {
+            return Err("Max auth attempts per hour cannot be zero".to_string());
+        }
+
+        // Beyond this the clock needs fixing, not compensating
+        if self.time_offset_secs.abs() > 3600 {
+            return Err("Time offset must be between -3600 and 3600 seconds".to_string());
+        }
+
+        if !(6..=8).contains(&self.otp_digits) {
+            return Err("OTP digits must be 6, 7 or 8".to_string());
+        }
+
+        if self.otp_period_secs == 0 {
+            return Err("OTP period cannot be zero".to_string());
+        }
+
+        // Each window is another password the gateway may count as a failure
+        if self.skew_windows > MAX_SKEW_WINDOWS {
+            return Err(format!("Skew windows must be at most {}", MAX_SKEW_WINDOWS));
+        }
+
+        for route in &self.verify_routes {
+            parse_route_target(route)?;
+        }
+
+        if self
+            .prompt_responses
+            .keys()
+            .any(|prompt| prompt.trim().is_empty())
+        {
+            return Err("Prompt response keys cannot be empty".to_string());
+        }
+
+        // Disabling certificate validation must be explicitly acknowledged
+        if self.insecure_skip_tls_verify && !self.insecure_tls_acknowledged {
+            return Err(
+                "insecure_skip_tls_verify requires insecure_tls_acknowledged = true".to_string(),
+            );
+        }
+
         Ok(())
     }
+
+    /// Keyring account holding this configuration's credentials
+    ///
+    /// The username, prefixed with the profile name (`work:alice`) for
+    /// profiles other than the default one, so credentials don't collide.
+    pub fn keyring_account(&self) -> String {
+        match self.profile.as_deref() {
+            Some(profile) if profile != DEFAULT_PROFILE => {
+                format!("{}:{}", profile, self.username)
+            }
+            _ => self.username.clone(),
+        }
+    }
+
+    /// Label for this connection: the configured name, or the server
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.server)
+    }
+
+    /// Local port openconnect connects to when going through `jump_host`
+    pub fn jump_forward_port(&self) -> Option<u16> {
+        self.jump_host
+            .as_ref()
+            .map(|_| self.jump_local_port.unwrap_or(DEFAULT_JUMP_LOCAL_PORT))
+    }
+
+    /// How long to wait before DNS retry number `retry` (from 1), or `None`
+    /// once the retries are used up
+    pub fn dns_retry_delay(&self, retry: u32) -> Option<Duration> {
+        if retry == 0 || retry > self.dns_retries.unwrap_or(DEFAULT_DNS_RETRIES) {
+            return None;
+        }
+        let interval = self
+            .dns_retry_interval_secs
+            .unwrap_or(DEFAULT_DNS_RETRY_INTERVAL_SECS);
+        Some(Duration::from_secs(
+            interval.saturating_mul(u64::from(retry)),
+        ))
+    }
+
+    /// Configured answer to `prompt`, if any
+    ///
+    /// When several keys occur in the prompt, the longest (most specific) wins.
+    pub fn prompt_response(&self, prompt: &str) -> Option<&str> {
+        self.prompt_responses
+            .iter()
+            .filter(|(key, _)| prompt.contains(key.as_str()))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, response)| response.as_str())
+    }
+
+    /// User-Agent to send: the configured one, or the protocol default
+    pub fn effective_user_agent(&self) -> Option<&str> {
+        self.user_agent
+            .as_deref()
+            .or_else(|| self.protocol.default_user_agent())
+    }
+
+    /// Warning to display on every connect when TLS validation is disabled
+    pub fn insecure_tls_warning(&self) -> Option<String> {
+        if !self.insecure_skip_tls_verify {
+            return None;
+        }
+
+        Some(format!(
+            "TLS certificate validation is DISABLED for {}. \
+             The connection can be intercepted; never use this outside a lab.",
+            self.server
+        ))
+    }
 }
 
 impl Default for VpnConfig {
     fn default() -> Self {
         Self {
+            name: None,
+            profile: None,
+            server_address: None,
             server: String::new(),
             username: String::new(),
             protocol: VpnProtocol::default(),
+            openconnect_path: None,
             timeout: Some(30),
+            reconnect_timeout: None,
+            phase_timeout_secs: None,
             no_dtls: false,
+            auth_group: None,
+            egress_interface: None,
+            jump_host: None,
+            jump_local_port: None,
+            cleanup_grace_secs: None,
+            no_kill_escalation: false,
+            privilege_escalation: PrivilegeEscalation::default(),
+            pid_lookup_attempts: None,
+            dns_retries: None,
+            dns_retry_interval_secs: None,
+            max_auth_attempts_per_hour: None,
+            verify_default_route: RouteCheck::default(),
+            verify_routes: Vec::new(),
+            kill_switch: false,
+            store_pin: true,
+            store_secret: true,
+            time_offset_secs: 0,
+            otp_algorithm: OtpAlgorithm::default(),
+            otp_digits: TOTP_DIGITS,
+            otp_period_secs: TIME_STEP_SECS,
+            skew_windows: 0,
+            check_clock_skew: false,
+            check_otp_secret: false,
+            user_agent: None,
             lazy_mode: false,
+            track_ip_history: false,
+            insecure_skip_tls_verify: false,
+            insecure_tls_acknowledged: false,
+            prompt_responses: BTreeMap::new(),
         }
     }
 }
@@ -0,0 +1,272 @@
+//! Commented example configuration
+//!
+//! `toml` cannot emit comments, so the template is written by hand. Default
+//! values are taken from the same defaults the deserializer uses, and a test
+//! checks that every `VpnConfig`, `BehaviorConfig` and `ReconnectionPolicy`
+//! field is documented.
+
+use crate::config::{LazyAction, VpnConfig};
+use crate::vpn::reconnection::{ReconnectionPolicy, SERVER_ENDPOINT_ALIAS};
+use std::fmt::Write;
+
+/// Build an example `config.toml`, optionally including a `[reconnection]` section
+pub fn config_template(with_reconnection: bool) -> String {
+    let defaults = VpnConfig::default();
+    let mut template = String::new();
+
+    // Writing to a String cannot fail
+    let _ = write!(
+        template,
+        r#"# akon configuration
+#
+# Save as ~/.config/akon/config.toml. Values may reference environment
+# variables as ${{NAME}}; use $$ for a literal dollar sign.
+# The PIN and OTP secret are not stored here: run `akon setup` to save them
+# in the keyring.
+
+[vpn]
+# Label shown by `akon vpn status` and in logs (defaults to the server)
+# name = "work"
+
+# VPN server hostname or IP address (required)
+server = "vpn.example.com"
+
+# Username for VPN authentication (required)
+username = "your.username"
+
+# VPN protocol: anyconnect, gp, nc, pulse, f5, fortinet or array
+protocol = "{protocol}"
+
+# openconnect binary to run, for one outside PATH or under another name
+# (found on PATH by default)
+# openconnect_path = "/run/current-system/sw/bin/openconnect"
+
+# Connection timeout in seconds
+timeout = {timeout}
+
+# How long openconnect keeps retrying a lost gateway before giving up, in
+# seconds (passed as --reconnect-timeout; openconnect's default is 300)
+# reconnect_timeout = 300
+
+# Abort if a connection phase produces no output for this many seconds
+# (disabled by default)
+# phase_timeout_secs = 60
+
+# Disable DTLS and use only TCP/TLS
+no_dtls = {no_dtls}
+
+# Authentication group (realm) for gateways that offer several. If unset and
+# the gateway asks for one, `akon vpn on` lets you pick from a menu.
+# auth_group = "Employees"
+
+# Send the VPN's own (encrypted) traffic out of this interface, e.g. the wired
+# NIC on a host that also has WiFi. akon adds a host route to the server via
+# this interface before connecting and removes it on disconnect (uses sudo).
+# egress_interface = "eth0"
+
+# Reach the VPN server through an SSH jump host ([user@]host[:port]). akon
+# opens `ssh -L` to the server's port 443 before connecting and closes it on
+# disconnect; the ssh login must not prompt (use keys or an agent).
+# jump_host = "alice@bastion.example.com"
+
+# Local port of that SSH forward (default: 10443)
+# jump_local_port = 10443
+
+# Seconds orphaned openconnect processes get to exit after SIGTERM during
+# cleanup before they are sent SIGKILL (default: 5)
+# cleanup_grace_secs = 5
+
+# Leave processes that ignore SIGTERM running instead of sending SIGKILL
+no_kill_escalation = {no_kill_escalation}
+
+# How openconnect is started as root and stopped: "sudo", "pkexec" (asks through
+# the desktop's polkit agent, for sessions without a terminal) or "none" (akon
+# runs as root or openconnect has CAP_NET_ADMIN)
+privilege_escalation = "{privilege_escalation}"
+
+# How many times to look for the backgrounded openconnect process, 100ms
+# apart, before the connection is reported as failed (default: 15)
+# pid_lookup_attempts = 15
+
+# When the server name fails to resolve temporarily (e.g. the network is still
+# coming up after resume), retry this many times, waiting dns_retry_interval_secs
+# before the first retry and longer before each one after. A name that doesn't
+# exist is never retried. (defaults: 3 and 2)
+# dns_retries = 3
+# dns_retry_interval_secs = 2
+
+# Authentication attempts allowed per hour, counting both `akon vpn on` and
+# automatic reconnections. Further attempts fail with "rate limited by akon"
+# until the budget refills (disabled by default).
+# max_auth_attempts_per_hour = 10
+
+# After connecting, check that traffic to the internet goes through the tunnel
+# device: off, warn, or enforce (disconnect). Leave off for split tunnels.
+verify_default_route = "{verify_default_route}"
+
+# Addresses, CIDRs or hostnames that must be routed through the tunnel once
+# connected; the connection fails if any of them is not
+# verify_routes = ["10.20.0.0/16", "intranet.corp.example"]
+
+# Kill switch: once connected, block all traffic except through the tunnel
+# (plus DNS to the system resolvers and the VPN server, for reconnecting).
+# The block stays while the connection is down and is only lifted by
+# `akon vpn off`. Uses sudo and nft.
+kill_switch = {kill_switch}
+
+# Which credentials are taken from the keyring. One that isn't stored is
+# asked for on the terminal at connect time (the PIN can instead be read from
+# the file descriptor in AKON_PIN_FD). Reconnections without a terminal then
+# fail, so keep both stored if you rely on automatic reconnection.
+store_pin = {store_pin}
+store_secret = {store_secret}
+
+# Seconds added to the system clock when generating OTP codes, for a clock
+# known to be off (positive when it runs behind; -3600 to 3600). Prefer
+# fixing the clock: timedatectl set-ntp true
+# time_offset_secs = 0
+
+# HMAC hash the TOTP secret was provisioned for: "sha1", "sha256" or
+# "sha512". Nearly all secrets use SHA-1; the wrong one gives rejected codes.
+# otp_algorithm = "sha1"
+
+# Digits per TOTP code (6 to 8) and seconds per code, for tokens other than
+# the usual 6 digits every 30 seconds
+# otp_digits = 6
+# otp_period_secs = 30
+
+# Also make passwords for this many TOTP steps before and after the current
+# one, for retrying authentication when the clock is off (0 to 3)
+# skew_windows = 0
+
+# Before connecting, compare the clock with the VPN server's and warn when
+# they differ by more than 15s (half a TOTP step), enough to get codes rejected
+check_clock_skew = {check_clock_skew}
+
+# Before connecting, check the stored TOTP secret for truncation as
+# 'akon doctor' does, and warn when it looks damaged
+check_otp_secret = {check_otp_secret}
+
+# User-Agent sent to the gateway. Defaults to an AnyConnect or GlobalProtect
+# client string for those protocols, and to openconnect's own otherwise.
+# user_agent = "AnyConnect Linux_64 4.10.07061"
+
+# Connect when `akon` is run without arguments
+lazy_mode = {lazy_mode}
+
+# Remember recently assigned IPs and flag addresses from an unusual range
+track_ip_history = {track_ip_history}
+
+# DANGEROUS: skip TLS certificate validation (lab gateways only).
+# Requires insecure_tls_acknowledged = true as well.
+insecure_skip_tls_verify = {insecure_skip_tls_verify}
+insecure_tls_acknowledged = {insecure_tls_acknowledged}
+
+# Answers for extra questions some gateways ask while connecting (accepting a
+# usage policy, picking a realm), keyed by part of the question's text. The
+# answer is typed on openconnect's stdin when a matching prompt appears.
+# prompt_responses = {{ "acceptable use policy" = "yes" }}
+
+[behavior]
+# What `akon` without arguments does in lazy mode: connect, status, or toggle
+# (connect when disconnected, show the status when connected)
+lazy_action = "{lazy_action}"
+"#,
+        protocol = defaults.protocol.as_str(),
+        timeout = defaults.timeout.unwrap_or_default(),
+        no_dtls = defaults.no_dtls,
+        no_kill_escalation = defaults.no_kill_escalation,
+        privilege_escalation = defaults.privilege_escalation.as_str(),
+        verify_default_route = defaults.verify_default_route.as_str(),
+        kill_switch = defaults.kill_switch,
+        store_pin = defaults.store_pin,
+        store_secret = defaults.store_secret,
+        check_clock_skew = defaults.check_clock_skew,
+        check_otp_secret = defaults.check_otp_secret,
+        lazy_mode = defaults.lazy_mode,
+        track_ip_history = defaults.track_ip_history,
+        insecure_skip_tls_verify = defaults.insecure_skip_tls_verify,
+        insecure_tls_acknowledged = defaults.insecure_tls_acknowledged,
+        lazy_action = LazyAction::default().as_str(),
+    );
+
+    if with_reconnection {
+        let policy = ReconnectionPolicy::default();
+        let _ = write!(
+            template,
+            r#"
+[reconnection]
+# HTTP/HTTPS endpoint used to check connectivity (required).
+# "tcp://host:port" checks a TCP connect and "icmp://host" a ping instead;
+# "iface://" only checks that the tunnel device still holds its address.
+# "{alias}" probes https://<vpn server>/.
+health_check_endpoint = "https://your-internal-server.example.com/"
+
+# How often to check health, in seconds (10-3600)
+health_check_interval_secs = {health_check_interval_secs}
+
+# Address family for health checks: auto, v4 or v6
+health_check_address_family = "auto"
+
+# Consecutive health check failures before reconnecting (1-10)
+consecutive_failures_threshold = {consecutive_failures_threshold}
+
+# Maximum reconnection attempts (1-20)
+max_attempts = {max_attempts}
+
+# Initial retry delay in seconds (1-300)
+base_interval_secs = {base_interval_secs}
+
+# Exponential backoff multiplier (1-10)
+backoff_multiplier = {backoff_multiplier}
+
+# Maximum delay between attempts in seconds (at least base_interval_secs)
+max_interval_secs = {max_interval_secs}
+
+# Pause retries after this many runs exhaust max_attempts (1-20, disabled by default)
+# circuit_breaker_threshold = 3
+
+# Cool-down before retries resume, in seconds (60-86400)
+circuit_breaker_cooldown_secs = {circuit_breaker_cooldown_secs}
+
+# Disconnect reasons that trigger reconnection: server_disconnect,
+# process_terminated, timeout and network_down (failing health checks).
+# A disconnect with 'akon vpn off' is never reconnected.
+reconnect_on = [{reconnect_on}]
+
+# Disconnect at this local time (HH:MM) every day and stay disconnected
+# until the next 'akon vpn on'
+# disconnect_at = "18:00"
+
+# Don't reconnect while the desktop session is locked or idle (so nothing
+# prompts for an OTP approval while you're away); resumes once unlocked
+# pause_while_locked = true
+
+# Append the reconnection daemon's stderr to this file, to diagnose a daemon
+# that dies early (discarded by default; AKON_DAEMON_LOG overrides it)
+# daemon_log = "/home/you/.cache/akon/daemon.log"
+
+# Shell commands run by the reconnection daemon. They receive
+# AKON_HOOK_EVENT, AKON_RECONNECT_ATTEMPT and AKON_SERVER.
+# on_reconnect_attempt = "logger 'akon: reconnecting'"
+# on_reconnect_success = "systemctl --user restart ssh-tunnel"
+"#,
+            alias = SERVER_ENDPOINT_ALIAS,
+            health_check_interval_secs = policy.health_check_interval_secs,
+            consecutive_failures_threshold = policy.consecutive_failures_threshold,
+            max_attempts = policy.max_attempts,
+            base_interval_secs = policy.base_interval_secs,
+            backoff_multiplier = policy.backoff_multiplier,
+            max_interval_secs = policy.max_interval_secs,
+            circuit_breaker_cooldown_secs = policy.circuit_breaker_cooldown_secs,
+            reconnect_on = policy
+                .reconnect_on
+                .iter()
+                .map(|reason| format!("{:?}", reason.as_str()))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+    }
+
+    template
+}
@@ -3,26 +3,46 @@
 //! Handles loading and saving VPN configuration to/from TOML files
 //! in the user's configuration directory.
 
-use crate::config::VpnConfig;
+use crate::config::interpolate::interpolate_toml;
 #[cfg(test)]
 use crate::config::VpnProtocol;
+use crate::config::{validate_profile_name, BehaviorConfig, VpnConfig, DEFAULT_PROFILE};
 use crate::error::{AkonError, ConfigError};
 use crate::vpn::reconnection::ReconnectionPolicy;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Table, TableLike};
 
 /// Complete TOML configuration structure
 ///
 /// Contains both VPN configuration and reconnection policy settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TomlConfig {
-    /// VPN connection settings
+    /// VPN connection settings, loaded as the "default" profile
     #[serde(rename = "vpn")]
     pub vpn_config: VpnConfig,
 
+    /// Further named connections, as `[profiles.<name>]` sections
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, VpnConfig>,
+
+    /// Profile used when none is selected with `--profile` (default: "default")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+
     /// Reconnection policy settings (optional)
     #[serde(rename = "reconnection", default)]
     pub reconnection: Option<ReconnectionPolicy>,
+
+    /// Reconnection policies of single profiles, as
+    /// `[reconnection_profiles.<name>]` sections replacing `[reconnection]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub reconnection_profiles: BTreeMap<String, ReconnectionPolicy>,
+
+    /// Command-line behavior settings
+    #[serde(default)]
+    pub behavior: BehaviorConfig,
 }
 
 impl TomlConfig {
@@ -30,7 +50,11 @@ impl TomlConfig {
     pub fn new(vpn_config: VpnConfig, reconnection: Option<ReconnectionPolicy>) -> Self {
         Self {
             vpn_config,
+            profiles: BTreeMap::new(),
+            default_profile: None,
             reconnection,
+            reconnection_profiles: BTreeMap::new(),
+            behavior: BehaviorConfig::default(),
         }
     }
 
@@ -44,22 +68,40 @@ impl TomlConfig {
             })
         })?;
 
-        let config: TomlConfig = toml::from_str(&contents).map_err(|e| {
+        let mut document: toml::Value = toml::from_str(&contents).map_err(|e| {
             AkonError::Config(ConfigError::ValidationError {
                 message: format!("Failed to parse config file: {}", e),
             })
         })?;
 
+        // Resolve ${ENV_VAR} references before deserializing
+        interpolate_toml(&mut document)?;
+
+        let config: TomlConfig = document.try_into().map_err(|e| {
+            AkonError::Config(ConfigError::ValidationError {
+                message: format!("Failed to parse config file: {}", e),
+            })
+        })?;
+        config.validate_profiles()?;
+
         // Validate reconnection policy if present
         if let Some(ref policy) = config.reconnection {
             debug!("Validating reconnection policy from config");
 
-            policy.validate().map_err(|e| {
-                warn!("Reconnection policy validation failed: {}", e);
-                AkonError::Config(ConfigError::ValidationError {
-                    message: format!("Invalid reconnection policy: {}", e),
+            policy
+                .validate()
+                .and_then(|_| {
+                    for profile in config.profile_configs() {
+                        policy.resolve_health_check_endpoint(&profile.server)?;
+                    }
+                    Ok(())
                 })
-            })?;
+                .map_err(|e| {
+                    warn!("Reconnection policy validation failed: {}", e);
+                    AkonError::Config(ConfigError::ValidationError {
+                        message: format!("Invalid reconnection policy: {}", e),
+                    })
+                })?;
 
             info!(
                 "Loaded reconnection policy: max_attempts={}, base_interval={}s, backoff_multiplier={}, max_interval={}s, consecutive_failures={}, health_check_interval={}s, endpoint={}",
@@ -75,6 +117,33 @@ impl TomlConfig {
             debug!("No reconnection policy specified in config, defaults will be used if needed");
         }
 
+        for (name, policy) in &config.reconnection_profiles {
+            let profile = config.profile(Some(name)).map_err(|_| {
+                AkonError::Config(ConfigError::ValidationError {
+                    message: format!(
+                        "[reconnection_profiles.{}] is not for a configured profile",
+                        name
+                    ),
+                })
+            })?;
+            policy
+                .validate()
+                .and_then(|_| {
+                    policy
+                        .resolve_health_check_endpoint(&profile.server)
+                        .map(|_| ())
+                })
+                .map_err(|e| {
+                    AkonError::Config(ConfigError::ValidationError {
+                        message: format!(
+                            "Invalid reconnection policy of profile '{}': {}",
+                            name, e
+                        ),
+                    })
+                })?;
+            debug!("Loaded reconnection policy for profile '{}'", name);
+        }
+
         Ok(config)
     }
 
@@ -113,6 +182,81 @@ impl TomlConfig {
     pub fn reconnection_policy(&self) -> Option<&ReconnectionPolicy> {
         self.reconnection.as_ref()
     }
+
+    /// Reconnection policy of profile `name`, or of the default profile
+    ///
+    /// A `[reconnection_profiles.<name>]` section wins over `[reconnection]`.
+    pub fn reconnection_policy_for(&self, name: Option<&str>) -> Option<&ReconnectionPolicy> {
+        let name = name
+            .or(self.default_profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE);
+        self.reconnection_profiles
+            .get(name)
+            .or(self.reconnection.as_ref())
+    }
+
+    /// Names of the configured profiles, "default" first
+    pub fn profile_names(&self) -> Vec<String> {
+        std::iter::once(DEFAULT_PROFILE.to_string())
+            .chain(
+                self.profiles
+                    .keys()
+                    .filter(|name| *name != DEFAULT_PROFILE)
+                    .cloned(),
+            )
+            .collect()
+    }
+
+    /// Every configured profile's settings
+    fn profile_configs(&self) -> impl Iterator<Item = &VpnConfig> {
+        std::iter::once(&self.vpn_config).chain(self.profiles.values())
+    }
+
+    /// Settings of profile `name`, or of the default profile
+    ///
+    /// Without a name, `default_profile` is used, then "default": the
+    /// `[vpn]` section unless a `[profiles.default]` replaces it.
+    pub fn profile(&self, name: Option<&str>) -> Result<VpnConfig, AkonError> {
+        let name = name
+            .or(self.default_profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE);
+
+        let config = match self.profiles.get(name) {
+            Some(config) => config,
+            None if name == DEFAULT_PROFILE => &self.vpn_config,
+            None => {
+                return Err(AkonError::Config(ConfigError::ValidationError {
+                    message: format!(
+                        "No profile named '{}' (configured: {})",
+                        name,
+                        self.profile_names().join(", ")
+                    ),
+                }))
+            }
+        };
+
+        let mut config = config.clone();
+        config.profile = Some(name.to_string());
+        Ok(config)
+    }
+
+    /// Check the profile names and `default_profile`
+    fn validate_profiles(&self) -> Result<(), AkonError> {
+        let invalid = |message: String| AkonError::Config(ConfigError::ValidationError { message });
+
+        for name in self.profiles.keys() {
+            validate_profile_name(name).map_err(invalid)?;
+        }
+        if let Some(name) = &self.default_profile {
+            if !self.profile_names().contains(name) {
+                return Err(invalid(format!(
+                    "default_profile '{}' is not a configured profile",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Default configuration file name
@@ -173,14 +317,19 @@ pub fn ensure_config_dir() -> Result<(), AkonError> {
     Ok(())
 }
 
-/// Load VPN configuration from the default TOML file
-pub fn load_config() -> Result<VpnConfig, AkonError> {
+/// Load the VPN configuration of `profile` from the default TOML file
+///
+/// Without a profile, the file's `default_profile` is loaded.
+pub fn load_config(profile: Option<&str>) -> Result<VpnConfig, AkonError> {
     let config_path = get_config_path()?;
-    load_config_from_path(&config_path)
+    load_config_from_path(&config_path, profile)
 }
 
-/// Load VPN configuration from a specific TOML file
-pub fn load_config_from_path<P: AsRef<Path>>(path: P) -> Result<VpnConfig, AkonError> {
+/// Load the VPN configuration of `profile` from a specific TOML file
+pub fn load_config_from_path<P: AsRef<Path>>(
+    path: P,
+    profile: Option<&str>,
+) -> Result<VpnConfig, AkonError> {
     let contents = std::fs::read_to_string(&path).map_err(|e| match e.kind() {
         std::io::ErrorKind::NotFound => AkonError::Config(ConfigError::LoadFailed {
             path: path.as_ref().to_string_lossy().to_string(),
@@ -190,19 +339,31 @@ pub fn load_config_from_path<P: AsRef<Path>>(path: P) -> Result<VpnConfig, AkonE
         }),
     })?;
 
-    // Support both historical formats: nested [vpn] table and flat top-level fields.
-    let parsed_vpn_config = toml::from_str::<TomlConfig>(&contents)
-        .map(|complete| complete.vpn_config)
-        .or_else(|nested_err| {
-            toml::from_str::<VpnConfig>(&contents).map_err(|flat_err| {
-                AkonError::Config(ConfigError::ValidationError {
-                    message: format!(
-                        "Failed to parse config file as either TomlConfig ({}) or VpnConfig ({})",
-                        nested_err, flat_err
-                    ),
-                })
+    let mut document: toml::Value = toml::from_str(&contents).map_err(|e| {
+        AkonError::Config(ConfigError::ValidationError {
+            message: format!("Failed to parse config file: {}", e),
+        })
+    })?;
+
+    // Resolve ${ENV_VAR} references before deserializing
+    interpolate_toml(&mut document)?;
+
+    // Support both historical formats: nested [vpn] table (with any
+    // profiles) and flat top-level fields.
+    let parsed_vpn_config = match TomlConfig::deserialize(document.clone()) {
+        Ok(complete) => {
+            complete.validate_profiles()?;
+            complete.profile(profile)?
+        }
+        Err(nested_err) => VpnConfig::deserialize(document).map_err(|flat_err| {
+            AkonError::Config(ConfigError::ValidationError {
+                message: format!(
+                    "Failed to parse config file as either TomlConfig ({}) or VpnConfig ({})",
+                    nested_err, flat_err
+                ),
             })
-        })?;
+        })?,
+    };
 
     // Validate the loaded configuration
     parsed_vpn_config
@@ -212,6 +373,52 @@ pub fn load_config_from_path<P: AsRef<Path>>(path: P) -> Result<VpnConfig, AkonE
     Ok(parsed_vpn_config)
 }
 
+/// Read `default_profile` from the default TOML file, if it sets one
+///
+/// Missing or unreadable files give `None`; loading the config reports those.
+pub fn load_default_profile() -> Option<String> {
+    let contents = std::fs::read_to_string(get_config_path().ok()?).ok()?;
+    let document: toml::Table = toml::from_str(&contents).ok()?;
+    document
+        .get("default_profile")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Load the `[behavior]` section from the default TOML file
+pub fn load_behavior() -> Result<BehaviorConfig, AkonError> {
+    let config_path = get_config_path()?;
+    load_behavior_from_path(&config_path)
+}
+
+/// Load the `[behavior]` section from a specific TOML file
+///
+/// Files without the section, including the flat historical layout, get
+/// the default behavior.
+pub fn load_behavior_from_path<P: AsRef<Path>>(path: P) -> Result<BehaviorConfig, AkonError> {
+    let contents = std::fs::read_to_string(&path).map_err(|e| {
+        AkonError::Config(ConfigError::IoError {
+            message: format!("Failed to read config file: {}", e),
+        })
+    })?;
+
+    let mut document: toml::Table = toml::from_str(&contents).map_err(|e| {
+        AkonError::Config(ConfigError::ValidationError {
+            message: format!("Failed to parse config file: {}", e),
+        })
+    })?;
+
+    let Some(mut behavior) = document.remove("behavior") else {
+        return Ok(BehaviorConfig::default());
+    };
+    interpolate_toml(&mut behavior)?;
+    BehaviorConfig::deserialize(behavior).map_err(|e| {
+        AkonError::Config(ConfigError::ValidationError {
+            message: format!("Invalid [behavior] section: {}", e),
+        })
+    })
+}
+
 /// Save VPN configuration to the default TOML file
 pub fn save_config(config: &VpnConfig) -> Result<(), AkonError> {
     let config_path = get_config_path()?;
@@ -254,6 +461,234 @@ pub fn save_config_to_path<P: AsRef<Path>>(config: &VpnConfig, path: P) -> Resul
     Ok(())
 }
 
+/// Set `auth_group` of `profile` in an existing config file, leaving other
+/// values untouched
+///
+/// Works on the raw document so `${ENV_VAR}` references are not replaced by
+/// their resolved values.
+pub fn save_auth_group_to_path<P: AsRef<Path>>(
+    group: &str,
+    profile: Option<&str>,
+    path: P,
+) -> Result<(), AkonError> {
+    save_vpn_value_to_path("auth_group", group, profile, path.as_ref())
+}
+
+/// Set `username` of `profile` in an existing config file, leaving other
+/// values untouched
+///
+/// The file is replaced atomically, so it holds either the old or the new
+/// username.
+pub fn save_username_to_path<P: AsRef<Path>>(
+    username: &str,
+    profile: Option<&str>,
+    path: P,
+) -> Result<(), AkonError> {
+    save_vpn_value_to_path("username", username, profile, path.as_ref())
+}
+
+/// Make `name` the `default_profile` of an existing config file
+///
+/// Fails, leaving the file untouched, unless `name` is a configured profile.
+pub fn save_default_profile_to_path<P: AsRef<Path>>(name: &str, path: P) -> Result<(), AkonError> {
+    let path = path.as_ref();
+    TomlConfig::from_file(path)?.profile(Some(name))?;
+
+    let mut document = read_document(path)?;
+    set_item(
+        document.as_table_mut(),
+        "default_profile",
+        toml_edit::value(name),
+    );
+    write_document(&document, path)
+}
+
+fn save_vpn_value_to_path(
+    key: &str,
+    value: &str,
+    profile: Option<&str>,
+    path: &Path,
+) -> Result<(), AkonError> {
+    let mut document = read_document(path)?;
+
+    // The profile's table, the nested [vpn] table, or the historical flat
+    // layout
+    let profile = profile.filter(|name| {
+        document
+            .get("profiles")
+            .and_then(Item::as_table_like)
+            .is_some_and(|profiles| profiles.contains_key(name))
+    });
+    let vpn_table: &mut dyn TableLike = if let Some(name) = profile {
+        document["profiles"][name]
+            .as_table_like_mut()
+            .ok_or_else(|| {
+                AkonError::Config(ConfigError::ValidationError {
+                    message: "Profile sections must be tables".to_string(),
+                })
+            })?
+    } else if document.get("vpn").is_some_and(Item::is_table_like) {
+        document["vpn"]
+            .as_table_like_mut()
+            .expect("checked to be a table")
+    } else {
+        document.as_table_mut()
+    };
+    set_item(vpn_table, key, toml_edit::value(value));
+
+    write_document(&document, path)
+}
+
+/// Save the settings of profile `config.profile`, keeping the rest of the file
+///
+/// A "default" profile goes in the `[vpn]` section (unless a
+/// `[profiles.default]` exists), any other in `[profiles.<name>]`. Comments
+/// on settings that are kept stay in place. The file is created if missing.
+pub fn save_profile_to_path<P: AsRef<Path>>(
+    config: &VpnConfig,
+    reconnection: Option<&ReconnectionPolicy>,
+    path: P,
+) -> Result<(), AkonError> {
+    let path = path.as_ref();
+    config
+        .validate()
+        .map_err(|e| AkonError::Config(ConfigError::ValidationError { message: e }))?;
+    let name = config.profile.as_deref().unwrap_or(DEFAULT_PROFILE);
+    validate_profile_name(name)
+        .map_err(|message| AkonError::Config(ConfigError::ValidationError { message }))?;
+
+    let mut document = if path.exists() {
+        read_document(path)?
+    } else {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                AkonError::Config(ConfigError::IoError {
+                    message: format!("Failed to create config directory: {}", e),
+                })
+            })?;
+        }
+        DocumentMut::new()
+    };
+
+    let settings = settings_table(config)?;
+    let has_profile_section = document
+        .get("profiles")
+        .and_then(Item::as_table_like)
+        .is_some_and(|profiles| profiles.contains_key(name));
+    if name == DEFAULT_PROFILE && !has_profile_section {
+        set_item(document.as_table_mut(), "vpn", settings);
+    } else {
+        let profiles = document.entry("profiles").or_insert_with(|| {
+            let mut profiles = Table::new();
+            profiles.set_implicit(true);
+            Item::Table(profiles)
+        });
+        let Some(profiles) = profiles.as_table_like_mut() else {
+            return Err(AkonError::Config(ConfigError::ValidationError {
+                message: "[profiles] must be a table".to_string(),
+            }));
+        };
+        set_item(profiles, name, settings);
+    }
+    if let Some(policy) = reconnection {
+        policy.validate().map_err(|e| {
+            AkonError::Config(ConfigError::ValidationError {
+                message: format!("Invalid reconnection policy: {}", e),
+            })
+        })?;
+        set_item(
+            document.as_table_mut(),
+            "reconnection",
+            settings_table(policy)?,
+        );
+    }
+
+    write_document(&document, path)
+}
+
+/// Serialize `settings` as a TOML table, its nested tables as sections
+fn settings_table<T: Serialize>(settings: &T) -> Result<Item, AkonError> {
+    let document = toml_edit::ser::to_document(settings).map_err(|e| {
+        AkonError::Config(ConfigError::ValidationError {
+            message: format!("Failed to serialize config: {}", e),
+        })
+    })?;
+    let mut table = document.as_table().clone();
+    for (_, item) in table.iter_mut() {
+        if let Item::Value(toml_edit::Value::InlineTable(inline)) = item {
+            *item = Item::Table(std::mem::take(inline).into_table());
+        }
+    }
+    Ok(Item::Table(table))
+}
+
+/// Set `key` of `table` to `item`, keeping the comments and position of an
+/// existing entry
+///
+/// Tables are updated entry by entry; entries `item` lacks are dropped.
+fn set_item(table: &mut dyn TableLike, key: &str, item: Item) {
+    let Some(existing) = table.get_mut(key) else {
+        table.insert(key, item);
+        return;
+    };
+    match (existing, item) {
+        (Item::Value(old), Item::Value(mut new)) => {
+            *new.decor_mut() = old.decor().clone();
+            *old = new;
+        }
+        (Item::Table(old), Item::Table(new)) => {
+            let stale: Vec<String> = old
+                .iter()
+                .map(|(key, _)| key.to_string())
+                .filter(|key| !new.contains_key(key))
+                .collect();
+            for key in stale {
+                old.remove(&key);
+            }
+            for (key, item) in new {
+                set_item(old, &key, item);
+            }
+        }
+        (existing, item) => *existing = item,
+    }
+}
+
+/// Read a config file as a raw TOML document, without resolving `${ENV_VAR}`s
+///
+/// Comments and the order of entries are kept for writing the file back.
+fn read_document(path: &Path) -> Result<DocumentMut, AkonError> {
+    let contents = std::fs::read_to_string(path).map_err(|e| {
+        AkonError::Config(ConfigError::IoError {
+            message: format!("Failed to read config file: {}", e),
+        })
+    })?;
+
+    contents.parse().map_err(|e| {
+        AkonError::Config(ConfigError::ValidationError {
+            message: format!("Failed to parse config file: {}", e),
+        })
+    })
+}
+
+/// Write a raw TOML document to `path`, replacing the file atomically
+fn write_document(document: &DocumentMut, path: &Path) -> Result<(), AkonError> {
+    let toml_string = document.to_string();
+    let save_failed = |_e: std::io::Error| {
+        AkonError::Config(ConfigError::SaveFailed {
+            path: path.to_string_lossy().to_string(),
+        })
+    };
+    // Write beside the config and rename over it
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = PathBuf::from(temp_name);
+    std::fs::write(&temp_path, toml_string).map_err(save_failed)?;
+    std::fs::rename(&temp_path, path).map_err(|e| {
+        let _ = std::fs::remove_file(&temp_path);
+        save_failed(e)
+    })
+}
+
 /// Save complete configuration (VPN + reconnection policy) to a specific TOML file
 pub fn save_complete_config_to_path<P: AsRef<Path>>(
     config: &VpnConfig,
@@ -333,13 +768,14 @@ mod tests {
             timeout: Some(60),
             no_dtls: false,
             lazy_mode: false,
+            ..Default::default()
         };
 
         // Save config
         save_config_to_path(&original_config, &config_path).unwrap();
 
         // Load config
-        let loaded_config = load_config_from_path(&config_path).unwrap();
+        let loaded_config = load_config_from_path(&config_path, None).unwrap();
 
         assert_eq!(original_config, loaded_config);
     }
@@ -374,10 +810,38 @@ lazy_mode = true
         )
         .unwrap();
 
-        let config = load_config_from_path(&config_path).expect("should parse nested config");
+        let config = load_config_from_path(&config_path, None).expect("should parse nested config");
         assert_eq!(config.server, "vpn.example.com");
         assert_eq!(config.username, "testuser");
         assert_eq!(config.protocol, VpnProtocol::F5);
         assert!(config.lazy_mode);
     }
+
+    #[test]
+    fn test_load_behavior_defaults_to_connect() {
+        use crate::config::LazyAction;
+
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        let vpn = "[vpn]\nserver = \"vpn.example.com\"\nusername = \"testuser\"\n";
+
+        std::fs::write(&config_path, vpn).unwrap();
+        let behavior = load_behavior_from_path(&config_path).unwrap();
+        assert_eq!(behavior.lazy_action, LazyAction::Connect);
+
+        std::fs::write(
+            &config_path,
+            format!("{}\n[behavior]\nlazy_action = \"toggle\"\n", vpn),
+        )
+        .unwrap();
+        let behavior = load_behavior_from_path(&config_path).unwrap();
+        assert_eq!(behavior.lazy_action, LazyAction::Toggle);
+
+        std::fs::write(
+            &config_path,
+            format!("{}\n[behavior]\nlazy_action = \"sometimes\"\n", vpn),
+        )
+        .unwrap();
+        assert!(load_behavior_from_path(&config_path).is_err());
+    }
 }
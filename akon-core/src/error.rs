@@ -35,6 +35,41 @@ pub enum AkonError {
     /// TOML serialization errors
     #[error("TOML serialization error: {0}")]
     TomlSerialize(#[from] toml::ser::Error),
+
+    /// The command did not finish within the `--deadline`
+    #[error("Operation did not finish within the {seconds}s deadline")]
+    DeadlineExceeded { seconds: u64 },
+}
+
+impl AkonError {
+    /// Stable identifier for this kind of error, for scripts and documentation
+    ///
+    /// VPN errors report their specific kind; other categories share one.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Config(_) => "config",
+            Self::Keyring(_) => "keyring",
+            Self::Vpn(e) => e.kind(),
+            Self::Otp(_) => "otp",
+            Self::Io(_) => "io",
+            Self::Toml(_) => "config.parse",
+            Self::TomlSerialize(_) => "config.serialize",
+            Self::DeadlineExceeded { .. } => "deadline_exceeded",
+        }
+    }
+
+    /// Process exit code: 1 for runtime failures, 2 for configuration/setup issues
+    ///
+    /// A missed deadline exits with 124, like `timeout(1)`.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Config(_) | Self::Toml(_) | Self::TomlSerialize(_) => 2,
+            Self::Keyring(_) | Self::Otp(_) => 2,
+            Self::Vpn(e) => e.exit_code(),
+            Self::Io(_) => 1,
+            Self::DeadlineExceeded { .. } => 124,
+        }
+    }
 }
 
 /// Configuration-related errors
@@ -57,6 +92,9 @@ pub enum ConfigError {
 
     #[error("I/O error: {message}")]
     IoError { message: String },
+
+    #[error("Environment variable '{name}' referenced in configuration is not set")]
+    UnsetEnvVar { name: String },
 }
 
 /// GNOME Keyring operation errors
@@ -71,6 +109,9 @@ pub enum KeyringError {
     #[error("Failed to retrieve credential from keyring")]
     RetrieveFailed,
 
+    #[error("Credentials already stored for '{username}'; pass --overwrite to replace them")]
+    CredentialsExist { username: String },
+
     #[error("Credential not found in keyring")]
     NotFound,
 
@@ -85,6 +126,17 @@ pub enum KeyringError {
 
     #[error("OTP secret not found in keyring")]
     OtpSecretNotFound,
+
+    #[error("PIN found but TOTP secret missing from keyring; run 'akon setup' to store it")]
+    OtpSecretMissing,
+
+    #[error("TOTP secret found but PIN missing from keyring; run 'akon setup' to store it")]
+    PinMissing,
+
+    #[error(
+        "{credential} is not stored in the keyring and stdin is not a terminal to prompt for it"
+    )]
+    PromptUnavailable { credential: String },
 }
 
 /// VPN connection operation errors
@@ -99,18 +151,48 @@ pub enum VpnError {
     #[error("Network error: {reason}")]
     NetworkError { reason: String },
 
+    /// The server name didn't resolve; `transient` unless it doesn't exist
+    #[error(
+        "DNS resolution failed{}",
+        if *.transient { " temporarily" } else { " - check server address" }
+    )]
+    DnsFailure { transient: bool },
+
     #[error("OpenConnect library error: {code}")]
     OpenConnectError { code: i32 },
 
     #[error("Invalid connection state transition")]
     InvalidStateTransition,
 
+    #[error("OpenConnect is not installed or not in PATH")]
+    OpenConnectNotFound,
+
     #[error("Failed to spawn OpenConnect process: {reason}")]
     ProcessSpawnError { reason: String },
 
     #[error("Connection timeout after {seconds} seconds")]
     ConnectionTimeout { seconds: u64 },
 
+    #[error("Connection stuck in {phase} for {seconds}s")]
+    PhaseTimeout { phase: String, seconds: u64 },
+
+    #[error("OpenConnect exited with status {code}: {reason}")]
+    ProcessExited { code: i32, reason: String },
+
+    #[error("Tunnel is up but routing was not configured: {reason}")]
+    RoutingNotConfigured { reason: String },
+
+    #[error("Server requires an authentication group: {}", .groups.join(", "))]
+    AuthGroupRequired { groups: Vec<String> },
+
+    #[error(
+        "Rate limited by akon: connection attempt budget exhausted, retry in {retry_after_secs}s"
+    )]
+    RateLimited { retry_after_secs: u64 },
+
+    #[error("Connection already in progress (akon PID {pid})")]
+    ConnectionInProgress { pid: u32 },
+
     #[error("Failed to terminate OpenConnect process")]
     TerminationError,
 
@@ -118,20 +200,68 @@ pub enum VpnError {
     ParseError { line: String },
 }
 
+impl VpnError {
+    /// Stable identifier for this kind of error, for scripts and documentation
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::ConnectionFailed { .. } => "vpn.connection_failed",
+            Self::AuthenticationFailed => "vpn.authentication_failed",
+            Self::NetworkError { .. } => "vpn.network_error",
+            Self::DnsFailure { .. } => "vpn.dns_failure",
+            Self::OpenConnectError { .. } => "vpn.openconnect_error",
+            Self::InvalidStateTransition => "vpn.invalid_state_transition",
+            Self::OpenConnectNotFound => "vpn.openconnect_not_found",
+            Self::ProcessSpawnError { .. } => "vpn.process_spawn_error",
+            Self::ConnectionTimeout { .. } => "vpn.connection_timeout",
+            Self::PhaseTimeout { .. } => "vpn.phase_timeout",
+            Self::ProcessExited { .. } => "vpn.process_exited",
+            Self::RoutingNotConfigured { .. } => "vpn.routing_not_configured",
+            Self::AuthGroupRequired { .. } => "vpn.auth_group_required",
+            Self::RateLimited { .. } => "vpn.rate_limited",
+            Self::ConnectionInProgress { .. } => "vpn.connection_in_progress",
+            Self::TerminationError => "vpn.termination_error",
+            Self::ParseError { .. } => "vpn.parse_error",
+        }
+    }
+
+    /// Process exit code for this error
+    ///
+    /// Runtime failures exit with 1; errors the user must fix in their
+    /// configuration exit with 2.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::AuthGroupRequired { .. } => 2,
+            _ => 1,
+        }
+    }
+}
+
 /// OTP/TOTP operation errors
 #[derive(Error, Debug, PartialEq)]
 pub enum OtpError {
     #[error("Invalid Base32 secret")]
     InvalidBase32,
 
-    #[error("TOTP generation failed")]
-    GenerationFailed,
+    #[error("OTP secret is empty")]
+    EmptySecret,
 
-    #[error("Time synchronization issue")]
-    TimeSyncError,
+    #[error("TOTP tokens must have 6 to 8 digits, got {digits}")]
+    InvalidDigits { digits: u32 },
 
-    #[error("System time error")]
-    TimeError,
+    #[error("TOTP time step must be at least one second")]
+    InvalidPeriod,
+
+    #[error("Invalid otpauth:// URI: {reason}")]
+    InvalidUri { reason: String },
+
+    #[error("System clock is before the Unix epoch; cannot compute a TOTP code")]
+    ClockError,
+
+    #[error("The TOTP code for this time step was already used; next code in {retry_after_secs}s")]
+    RateLimited { retry_after_secs: u64 },
+
+    #[error("TOTP generation failed")]
+    GenerationFailed,
 
     #[error("Invalid PIN format: must be exactly 4 numeric digits")]
     InvalidPinFormat,
@@ -10,32 +10,105 @@ pub mod auth;
 pub mod config;
 pub mod vpn;
 
+/// Version of this crate
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Optional features this crate was compiled with
+pub fn compiled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "mock-keyring") {
+        features.push("mock-keyring");
+    }
+    if cfg!(feature = "async-keyring") {
+        features.push("async-keyring");
+    }
+    features
+}
+
 /// Initialize logging infrastructure
 ///
 /// Sets up tracing with systemd journal logging for production use.
-/// In development, logs to stderr with appropriate formatting.
+/// In development, logs to stderr with appropriate formatting. If the
+/// journal can't be used, akon warns once and logs to stderr instead.
 pub fn init_logging() -> Result<(), Box<dyn std::error::Error>> {
-    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-
-    // Try to use systemd journal logging if available
-    #[cfg(target_os = "linux")]
-    {
-        if std::env::var("JOURNAL_STREAM").is_ok() {
-            // We're running under systemd, use journal logging
-            let journal_layer = tracing_journald::layer()?;
-            tracing_subscriber::registry()
-                .with(journal_layer)
-                .with(tracing_subscriber::filter::LevelFilter::INFO)
-                .init();
-            return Ok(());
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    // We're running under systemd if it connected our output to the journal
+    let journal_stream = cfg!(target_os = "linux") && std::env::var("JOURNAL_STREAM").is_ok();
+    let (subscriber, warning) = logging_subscriber(journal_stream, tracing_journald::layer);
+    subscriber.try_init()?;
+
+    if let Some(warning) = warning {
+        tracing::warn!("{}", warning);
+    }
+    Ok(())
+}
+
+/// Subscriber to log through, plus a warning if the journal was wanted but unusable
+///
+/// `journald_layer` is only called when `journal_stream` is set.
+fn logging_subscriber<F>(
+    journal_stream: bool,
+    journald_layer: F,
+) -> (Box<dyn tracing::Subscriber + Send + Sync>, Option<String>)
+where
+    F: FnOnce() -> std::io::Result<tracing_journald::Layer>,
+{
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let mut warning = None;
+    if journal_stream {
+        match journald_layer() {
+            Ok(journal_layer) => {
+                let subscriber = tracing_subscriber::registry()
+                    .with(journal_layer)
+                    .with(tracing_subscriber::filter::LevelFilter::INFO);
+                return (Box::new(subscriber), None);
+            }
+            Err(e) => {
+                warning = Some(format!(
+                    "Failed to connect to the systemd journal, logging to stderr instead: {}",
+                    e
+                ));
+            }
         }
     }
 
     // Fallback to stderr logging with pretty formatting
-    tracing_subscriber::registry()
+    let subscriber = tracing_subscriber::registry()
         .with(tracing_subscriber::fmt::layer().pretty())
-        .with(tracing_subscriber::filter::LevelFilter::INFO)
-        .init();
+        .with(tracing_subscriber::filter::LevelFilter::INFO);
+    (Box::new(subscriber), warning)
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failing_journald_layer_falls_back_to_stderr() {
+        let (subscriber, warning) = logging_subscriber(true, || {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::ConnectionRefused,
+                "journal socket refused the connection",
+            ))
+        });
+
+        let warning = warning.expect("fallback should be reported");
+        assert!(warning.contains("logging to stderr instead"));
+        assert!(warning.contains("journal socket refused the connection"));
+        // The stderr subscriber is live and accepts events
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(tracing::enabled!(tracing::Level::INFO));
+            tracing::info!("logged through the fallback");
+        });
+    }
+
+    #[test]
+    fn test_journald_layer_is_not_used_outside_systemd() {
+        let (_, warning) = logging_subscriber(false, || -> std::io::Result<_> {
+            panic!("journald layer must not be created without JOURNAL_STREAM")
+        });
+        assert!(warning.is_none());
+    }
 }
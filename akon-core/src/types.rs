@@ -147,6 +147,24 @@ impl VpnPassword {
     }
 }
 
+/// Pre-authenticated session cookie, e.g. from a browser SSO flow
+///
+/// Handed to OpenConnect in place of a password, so it gets the same care.
+#[derive(Clone, Debug)]
+pub struct SessionCookie(Secret<String>);
+
+impl SessionCookie {
+    /// Wrap a cookie value
+    pub fn new(cookie: String) -> Self {
+        Self(Secret::new(cookie))
+    }
+
+    /// Expose the cookie value; only for passing it to OpenConnect
+    pub fn expose(&self) -> &str {
+        self.0.expose_secret()
+    }
+}
+
 /// Connection state for VPN operations
 ///
 /// Tracks the current state of the VPN connection with associated metadata.
@@ -0,0 +1,254 @@
+//! Budget of authentication attempts shared by all akon processes
+//!
+//! A token bucket persisted in the config directory bounds how often akon
+//! authenticates against the gateway, across `akon vpn on` and the
+//! reconnection daemon alike. A flapping link then cannot turn into hundreds
+//! of logins and a locked account.
+
+use crate::config::toml_config::get_config_dir;
+use crate::error::{AkonError, ConfigError, VpnError};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Period over which the configured number of attempts is allowed
+pub const BUDGET_PERIOD: Duration = Duration::from_secs(3600);
+
+/// Token bucket of connection attempts
+///
+/// All methods take the current time explicitly so callers (and tests)
+/// control the clock. Wall-clock time is used because the bucket is shared
+/// between processes through a file.
+///
+/// The bucket is kept in integer "token-seconds": one attempt costs
+/// `period_secs` and every second refills `capacity`, so refill times are
+/// exact.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttemptBudget {
+    capacity: u32,
+    period_secs: u64,
+    credit: u64,
+    /// Last refill (Unix timestamp, seconds)
+    updated_at: u64,
+}
+
+impl AttemptBudget {
+    /// Create a full bucket allowing `capacity` attempts per `period`
+    pub fn new(capacity: u32, period: Duration, now: SystemTime) -> Self {
+        let period_secs = period.as_secs().max(1);
+        Self {
+            capacity,
+            period_secs,
+            credit: capacity as u64 * period_secs,
+            updated_at: unix_secs(now),
+        }
+    }
+
+    /// Take one attempt from the budget
+    ///
+    /// Returns the time until the next attempt is available if the budget
+    /// is exhausted.
+    pub fn try_acquire(&mut self, now: SystemTime) -> Result<(), Duration> {
+        self.refill(now);
+
+        if self.credit >= self.period_secs {
+            self.credit -= self.period_secs;
+            return Ok(());
+        }
+
+        let missing = self.period_secs - self.credit;
+        let rate = (self.capacity as u64).max(1);
+        Err(Duration::from_secs((missing + rate - 1) / rate))
+    }
+
+    /// Number of whole attempts currently available
+    pub fn available(&mut self, now: SystemTime) -> u32 {
+        self.refill(now);
+        (self.credit / self.period_secs) as u32
+    }
+
+    /// Adopt a new capacity/period, keeping the attempts already spent
+    fn reconfigure(&mut self, capacity: u32, period: Duration) {
+        let available = self.credit as f64 / self.period_secs.max(1) as f64;
+        self.capacity = capacity;
+        self.period_secs = period.as_secs().max(1);
+        self.credit = ((available * self.period_secs as f64) as u64).min(self.max_credit());
+    }
+
+    fn refill(&mut self, now: SystemTime) {
+        let now = unix_secs(now);
+        // A clock that went backwards refills nothing
+        let elapsed = now.saturating_sub(self.updated_at);
+        self.credit = self
+            .credit
+            .saturating_add(elapsed.saturating_mul(self.capacity as u64))
+            .min(self.max_credit());
+        self.updated_at = self.updated_at.max(now);
+    }
+
+    fn max_credit(&self) -> u64 {
+        self.capacity as u64 * self.period_secs
+    }
+
+    /// Load the budget from a file, starting full if it doesn't exist
+    pub fn load(
+        path: &Path,
+        capacity: u32,
+        period: Duration,
+        now: SystemTime,
+    ) -> Result<Self, AkonError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::new(capacity, period, now))
+            }
+            Err(e) => {
+                return Err(AkonError::Config(ConfigError::IoError {
+                    message: format!("Failed to read attempt budget: {}", e),
+                }))
+            }
+        };
+
+        let mut budget: Self = serde_json::from_str(&contents).map_err(|e| {
+            AkonError::Config(ConfigError::ValidationError {
+                message: format!("Failed to parse attempt budget: {}", e),
+            })
+        })?;
+        budget.reconfigure(capacity, period);
+        Ok(budget)
+    }
+
+    /// Save the budget to a file
+    pub fn save(&self, path: &Path) -> Result<(), AkonError> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| {
+            AkonError::Config(ConfigError::ValidationError {
+                message: format!("Failed to serialize attempt budget: {}", e),
+            })
+        })?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                AkonError::Config(ConfigError::IoError {
+                    message: format!("Failed to create config directory: {}", e),
+                })
+            })?;
+        }
+
+        std::fs::write(path, contents).map_err(|e| {
+            AkonError::Config(ConfigError::IoError {
+                message: format!("Failed to write attempt budget: {}", e),
+            })
+        })
+    }
+}
+
+/// Spend one attempt from the budget stored at `path`
+///
+/// Fails with [`VpnError::RateLimited`] when the budget is exhausted. A budget
+/// file that can't be read or written is logged and replaced, rather than
+/// blocking connections.
+pub fn acquire_attempt(path: &Path, capacity: u32, now: SystemTime) -> Result<(), VpnError> {
+    let mut budget = AttemptBudget::load(path, capacity, BUDGET_PERIOD, now).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring unreadable attempt budget: {}", e);
+        AttemptBudget::new(capacity, BUDGET_PERIOD, now)
+    });
+    let result = budget.try_acquire(now);
+    if let Err(e) = budget.save(path) {
+        tracing::warn!("Failed to save attempt budget: {}", e);
+    }
+
+    result.map_err(|retry_after| VpnError::RateLimited {
+        retry_after_secs: retry_after.as_secs(),
+    })
+}
+
+/// Path of the shared attempt budget file
+pub fn attempt_budget_path() -> Result<PathBuf, AkonError> {
+    Ok(get_config_dir()?.join("attempt_budget.json"))
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000 + secs)
+    }
+
+    #[test]
+    fn test_budget_drains_then_rate_limits() {
+        let mut budget = AttemptBudget::new(3, Duration::from_secs(3600), at(0));
+
+        for _ in 0..3 {
+            assert!(budget.try_acquire(at(0)).is_ok());
+        }
+        // One token comes back every 3600 / 3 = 1200s
+        assert_eq!(budget.try_acquire(at(0)), Err(Duration::from_secs(1200)));
+        assert_eq!(budget.try_acquire(at(200)), Err(Duration::from_secs(1000)));
+    }
+
+    #[test]
+    fn test_budget_refills_over_time_up_to_capacity() {
+        let mut budget = AttemptBudget::new(3, Duration::from_secs(3600), at(0));
+        for _ in 0..3 {
+            budget.try_acquire(at(0)).unwrap();
+        }
+
+        assert_eq!(budget.available(at(1199)), 0);
+        assert_eq!(budget.available(at(1200)), 1);
+        assert!(budget.try_acquire(at(1200)).is_ok());
+        assert!(budget.try_acquire(at(1200)).is_err());
+
+        // Never refills past capacity
+        assert_eq!(budget.available(at(100_000)), 3);
+    }
+
+    #[test]
+    fn test_budget_is_shared_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("attempt_budget.json");
+
+        acquire_attempt(&path, 2, at(0)).unwrap();
+        acquire_attempt(&path, 2, at(1)).unwrap();
+
+        let err = acquire_attempt(&path, 2, at(2)).unwrap_err();
+        assert_eq!(
+            err,
+            VpnError::RateLimited {
+                retry_after_secs: 1798
+            }
+        );
+        assert!(err.to_string().contains("Rate limited by akon"));
+
+        // Half an hour later one attempt is available again
+        assert!(acquire_attempt(&path, 2, at(1801)).is_ok());
+    }
+
+    #[test]
+    fn test_corrupt_budget_file_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("attempt_budget.json");
+        std::fs::write(&path, "not json").unwrap();
+
+        acquire_attempt(&path, 1, at(0)).unwrap();
+        assert!(acquire_attempt(&path, 1, at(0)).is_err());
+    }
+
+    #[test]
+    fn test_lowering_capacity_clamps_saved_tokens() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("attempt_budget.json");
+        AttemptBudget::new(10, BUDGET_PERIOD, at(0))
+            .save(&path)
+            .unwrap();
+
+        let mut budget = AttemptBudget::load(&path, 2, BUDGET_PERIOD, at(0)).unwrap();
+        assert_eq!(budget.available(at(0)), 2);
+    }
+}
@@ -0,0 +1,129 @@
+//! Circuit breaker for repeated reconnection exhaustion
+//!
+//! When reconnection keeps running out of attempts, the gateway is most likely
+//! down for real. After a configured number of consecutive exhausted runs the
+//! breaker opens and retries are paused for a cool-down period, protecting the
+//! gateway (and the account) from being hammered.
+
+use std::time::{Duration, Instant};
+
+/// Tracks consecutive exhausted reconnection runs
+///
+/// All methods take the current instant explicitly so callers (and tests)
+/// control the clock.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    consecutive_exhaustions: u32,
+    opened_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Create a breaker that opens after `threshold` exhausted runs
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            consecutive_exhaustions: 0,
+            opened_at: None,
+        }
+    }
+
+    /// Record a reconnection run that hit `max_attempts`
+    ///
+    /// Opens the breaker once the threshold is reached. A breaker that has
+    /// just finished cooling down re-opens on the next exhaustion.
+    pub fn record_exhaustion(&mut self, now: Instant) {
+        self.consecutive_exhaustions = self.consecutive_exhaustions.saturating_add(1);
+        if self.consecutive_exhaustions >= self.threshold {
+            self.opened_at = Some(now);
+        }
+    }
+
+    /// Record a successful connection, fully closing the breaker
+    pub fn record_success(&mut self) {
+        self.consecutive_exhaustions = 0;
+        self.opened_at = None;
+    }
+
+    /// Check whether retries are currently paused
+    pub fn is_open(&self, now: Instant) -> bool {
+        self.remaining_cooldown(now).is_some()
+    }
+
+    /// Time left before retries resume, if the breaker is open
+    pub fn remaining_cooldown(&self, now: Instant) -> Option<Duration> {
+        let opened_at = self.opened_at?;
+        let elapsed = now.saturating_duration_since(opened_at);
+        self.cooldown.checked_sub(elapsed).filter(|d| !d.is_zero())
+    }
+
+    /// Number of consecutive exhausted runs since the last success
+    pub fn consecutive_exhaustions(&self) -> u32 {
+        self.consecutive_exhaustions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_after_threshold_exhaustions() {
+        let start = Instant::now();
+        let mut breaker = CircuitBreaker::new(3, Duration::from_secs(600));
+
+        breaker.record_exhaustion(start);
+        breaker.record_exhaustion(start);
+        assert!(!breaker.is_open(start));
+
+        breaker.record_exhaustion(start);
+        assert!(breaker.is_open(start));
+        assert_eq!(
+            breaker.remaining_cooldown(start + Duration::from_secs(100)),
+            Some(Duration::from_secs(500))
+        );
+    }
+
+    #[test]
+    fn test_breaker_closes_after_cooldown() {
+        let start = Instant::now();
+        let mut breaker = CircuitBreaker::new(2, Duration::from_secs(600));
+        breaker.record_exhaustion(start);
+        breaker.record_exhaustion(start);
+
+        assert!(breaker.is_open(start + Duration::from_secs(599)));
+        assert!(!breaker.is_open(start + Duration::from_secs(600)));
+        assert!(!breaker.is_open(start + Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn test_breaker_reopens_on_next_exhaustion_after_cooldown() {
+        let start = Instant::now();
+        let mut breaker = CircuitBreaker::new(2, Duration::from_secs(600));
+        breaker.record_exhaustion(start);
+        breaker.record_exhaustion(start);
+
+        let after_cooldown = start + Duration::from_secs(700);
+        assert!(!breaker.is_open(after_cooldown));
+
+        breaker.record_exhaustion(after_cooldown);
+        assert!(breaker.is_open(after_cooldown));
+    }
+
+    #[test]
+    fn test_success_resets_breaker() {
+        let start = Instant::now();
+        let mut breaker = CircuitBreaker::new(2, Duration::from_secs(600));
+        breaker.record_exhaustion(start);
+        breaker.record_exhaustion(start);
+
+        breaker.record_success();
+        assert!(!breaker.is_open(start));
+        assert_eq!(breaker.consecutive_exhaustions(), 0);
+
+        breaker.record_exhaustion(start);
+        assert!(!breaker.is_open(start));
+    }
+}
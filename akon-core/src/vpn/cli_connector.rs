@@ -4,14 +4,144 @@
 
 use crate::config::VpnConfig;
 use crate::error::{AkonError, VpnError};
-use crate::vpn::{ConnectionEvent, ConnectionState, DisconnectReason, OutputParser};
+use crate::vpn::iface_check::find_tunnel_device;
+use crate::vpn::output_parser::parse_transport;
+use crate::vpn::{
+    process, ConnectionEvent, ConnectionPhase, ConnectionState, DisconnectReason, OutputParser,
+    TimedEvent, Transport,
+};
+use std::collections::VecDeque;
+use std::ffi::OsStr;
+use std::net::IpAddr;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, Command};
 use tokio::sync::{mpsc, Mutex};
 
+/// How many times to look for the daemonized openconnect (100ms apart) by default
+const DEFAULT_PID_LOOKUP_ATTEMPTS: u32 = 15;
+
+/// How long to wait for openconnect to exit once its output has closed
+const EXIT_STATUS_WAIT: Duration = Duration::from_secs(5);
+
+/// Number of trailing stderr lines kept for error reports
+const STDERR_TAIL_LINES: usize = 5;
+
+/// Longest line of OpenConnect output kept; the rest of a longer line is
+/// discarded so a runaway stream can't exhaust memory
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
+/// How much of an over-long line is kept for the log
+const OVERSIZED_PREVIEW_BYTES: usize = 200;
+
+/// Extra time given to approve a push notification on top of the configured
+/// timeouts; the gateway is silent until then
+const APPROVAL_WAIT: Duration = Duration::from_secs(120);
+
+/// Callback told the push-approval prompt while `connect` is still waiting
+type ApprovalNotice = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Find the openconnect binary: `configured` if set, otherwise on `PATH`
+///
+/// Fails with [`VpnError::OpenConnectNotFound`] when there is none, and with
+/// [`VpnError::ProcessSpawnError`] when there is one but it can't be run.
+pub fn locate_openconnect(configured: Option<&Path>) -> Result<PathBuf, VpnError> {
+    match configured {
+        Some(path) => check_program(path),
+        None => find_program("openconnect", &std::env::var_os("PATH").unwrap_or_default()),
+    }
+}
+
+/// Check that the binary at `path` can be run
+fn check_program(path: &Path) -> Result<PathBuf, VpnError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => {
+            if metadata.permissions().mode() & 0o111 != 0 {
+                Ok(path.to_path_buf())
+            } else {
+                Err(VpnError::ProcessSpawnError {
+                    reason: format!("{} is not executable", path.display()),
+                })
+            }
+        }
+        _ => Err(VpnError::OpenConnectNotFound),
+    }
+}
+
+/// Find `name` in the directories of `search_path`
+///
+/// A non-executable file is skipped in favour of a later executable one,
+/// but reported if nothing else is found.
+fn find_program(name: &str, search_path: &OsStr) -> Result<PathBuf, VpnError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut not_executable = None;
+    for dir in std::env::split_paths(search_path) {
+        let candidate = dir.join(name);
+        let Ok(metadata) = std::fs::metadata(&candidate) else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        if metadata.permissions().mode() & 0o111 != 0 {
+            return Ok(candidate);
+        }
+        not_executable.get_or_insert(candidate);
+    }
+
+    match not_executable {
+        Some(path) => Err(VpnError::ProcessSpawnError {
+            reason: format!("{} is not executable", path.display()),
+        }),
+        None => Err(VpnError::OpenConnectNotFound),
+    }
+}
+
+/// Spawned openconnect process, killed if `connect` is abandoned
+///
+/// A caller's timeout drops the `connect` future part way through; without
+/// this the half-started process would keep running.
+struct KillOnAbandon(Option<Child>);
+
+impl KillOnAbandon {
+    /// Let the process run on after `connect` returns
+    fn detach(mut self) {
+        self.0.take();
+    }
+}
+
+impl Deref for KillOnAbandon {
+    type Target = Child;
+
+    fn deref(&self) -> &Child {
+        self.0.as_ref().expect("process already detached")
+    }
+}
+
+impl DerefMut for KillOnAbandon {
+    fn deref_mut(&mut self) -> &mut Child {
+        self.0.as_mut().expect("process already detached")
+    }
+}
+
+impl Drop for KillOnAbandon {
+    fn drop(&mut self) {
+        if let Some(child) = self.0.as_mut() {
+            if let Ok(None) = child.try_wait() {
+                tracing::warn!("Connection abandoned, killing OpenConnect process");
+                let _ = child.start_kill();
+            }
+        }
+    }
+}
+
 /// CLI-based OpenConnect connection manager
 pub struct CliConnector {
     /// Current connection state
@@ -23,6 +153,9 @@ pub struct CliConnector {
     /// Actual OpenConnect process PID (not the sudo wrapper)
     openconnect_pid: Arc<Mutex<Option<u32>>>,
 
+    /// Transport in use when the connection was established
+    transport: Arc<Mutex<Option<Transport>>>,
+
     /// OpenConnect stdin - kept alive to prevent process termination
     process_stdin: Arc<Mutex<Option<ChildStdin>>>,
 
@@ -37,6 +170,18 @@ pub struct CliConnector {
 
     /// Configuration (server URL, protocol)
     config: VpnConfig,
+
+    /// File to copy OpenConnect's raw output to, if requested
+    raw_capture_path: Option<PathBuf>,
+
+    /// Open raw output capture while connecting
+    raw_capture: Arc<Mutex<Option<RawCapture>>>,
+
+    /// Authenticate with a session cookie instead of a password
+    cookie_auth: bool,
+
+    /// Told when the gateway waits for a push notification to be approved
+    approval_notice: Option<ApprovalNotice>,
 }
 
 impl CliConnector {
@@ -48,14 +193,46 @@ impl CliConnector {
             state: Arc::new(Mutex::new(ConnectionState::Idle)),
             child_process: Arc::new(Mutex::new(None)),
             openconnect_pid: Arc::new(Mutex::new(None)),
+            transport: Arc::new(Mutex::new(None)),
             process_stdin: Arc::new(Mutex::new(None)),
             event_receiver,
             event_sender,
-            parser: Arc::new(OutputParser::new()),
+            parser: Arc::new(OutputParser::new_for(&config.protocol)),
             config,
+            raw_capture_path: None,
+            raw_capture: Arc::new(Mutex::new(None)),
+            cookie_auth: false,
+            approval_notice: None,
         })
     }
 
+    /// Append OpenConnect's raw stdout and stderr lines to `path` while connecting
+    pub fn with_raw_capture(mut self, path: PathBuf) -> Self {
+        self.raw_capture_path = Some(path);
+        self
+    }
+
+    /// Authenticate with a session cookie, skipping the login form
+    ///
+    /// `connect` then takes the cookie instead of a password and sends it
+    /// the same way, on stdin.
+    pub fn with_cookie_auth(mut self) -> Self {
+        self.cookie_auth = true;
+        self
+    }
+
+    /// Call `notice` with the prompt when the gateway sends a push notification
+    ///
+    /// Events only reach `next_event` once `connect` returns, too late to
+    /// tell the user to pick up their phone.
+    pub fn with_approval_notice<F>(mut self, notice: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.approval_notice = Some(Arc::new(notice));
+        self
+    }
+
     /// Get current connection state
     pub fn state(&self) -> ConnectionState {
         // This is a synchronous method, but we need to handle the async Mutex
@@ -81,16 +258,29 @@ impl CliConnector {
             .and_then(|guard| *guard)
     }
 
+    /// Get the transport that carried traffic when the connection was established
+    ///
+    /// DTLS or ESP may still come up after that.
+    pub fn transport(&self) -> Option<Transport> {
+        self.transport.try_lock().ok().and_then(|guard| *guard)
+    }
+
     /// Find the OpenConnect daemon process PID
     ///
     /// When openconnect uses --background, it daemonizes and we need to find
-    /// it by process name and command line matching our server
-    async fn find_openconnect_daemon_pid(server: &str) -> Option<u32> {
+    /// it by process name and command line matching our server. If that fails
+    /// (e.g. the server was given as a hostname but resolved to an IP), the
+    /// process tree below the sudo wrapper is searched instead.
+    async fn find_openconnect_daemon_pid(
+        server: &str,
+        sudo_pid: u32,
+        attempts: u32,
+    ) -> Option<u32> {
         // Wait a bit for daemon to start
         tokio::time::sleep(Duration::from_millis(200)).await;
 
         // Try multiple times in case daemon hasn't started yet
-        for attempt in 0..15 {
+        for attempt in 0..attempts {
             // Use pgrep to find openconnect processes matching our server
             let output = tokio::process::Command::new("pgrep")
                 .args(["-f", &format!("openconnect.*{}", server)])
@@ -114,8 +304,22 @@ impl CliConnector {
                 }
             }
 
+            // Fall back to the wrapper's process tree
+            if sudo_pid != 0 {
+                if let Some(pid) =
+                    process::find_openconnect_descendant(Path::new("/proc"), sudo_pid)
+                {
+                    tracing::debug!(
+                        "Found OpenConnect PID {} below sudo wrapper {}",
+                        pid,
+                        sudo_pid
+                    );
+                    return Some(pid);
+                }
+            }
+
             // Wait a bit and retry
-            if attempt < 14 {
+            if attempt + 1 < attempts {
                 tokio::time::sleep(Duration::from_millis(100)).await;
             }
         }
@@ -127,28 +331,88 @@ impl CliConnector {
         None
     }
 
-    /// Spawn OpenConnect process with credentials
+    /// Build the OpenConnect command-line arguments for the configuration
     ///
-    /// Returns the spawned child process
-    async fn spawn_process(&self) -> Result<Child, VpnError> {
-        // Use sudo to run openconnect since it requires root privileges for network configuration
-        let mut cmd = Command::new("sudo");
-        cmd.arg("openconnect")
-            .arg("--protocol")
-            .arg(self.config.protocol.as_str())
-            .arg("--user")
-            .arg(&self.config.username)
-            .arg("--passwd-on-stdin")
-            .arg("--background"); // Daemonize to stay running
+    /// The password or cookie is never part of the arguments; it is sent via stdin.
+    pub fn openconnect_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "--protocol".to_string(),
+            self.config.protocol.as_str().to_string(),
+        ];
+        if self.cookie_auth {
+            args.push("--cookie-on-stdin".to_string());
+        } else {
+            args.push("--user".to_string());
+            args.push(self.config.username.clone());
+            args.push("--passwd-on-stdin".to_string());
+        }
+        args.push("--background".to_string()); // Daemonize to stay running
 
         // Add --no-dtls flag if configured
         if self.config.no_dtls {
-            cmd.arg("--no-dtls");
+            args.push("--no-dtls".to_string());
             tracing::debug!("DTLS disabled per configuration");
         }
 
+        if let Some(group) = &self.config.auth_group {
+            args.push("--authgroup".to_string());
+            args.push(group.clone());
+        }
+
+        if let Some(user_agent) = self.config.effective_user_agent() {
+            args.push("--useragent".to_string());
+            args.push(user_agent.to_string());
+        }
+
+        if let Some(timeout) = self.config.reconnect_timeout {
+            args.push("--reconnect-timeout".to_string());
+            args.push(timeout.to_string());
+        }
+
+        if self.config.insecure_skip_tls_verify {
+            args.push("--no-cert-check".to_string());
+            tracing::warn!("TLS certificate validation disabled per configuration");
+        }
+
+        // Through a jump host, connect to the local end of the SSH forward
+        // while TLS keeps seeing (and validating) the server's own name
+        if let Some(port) = self.config.jump_forward_port() {
+            args.push("--resolve".to_string());
+            args.push(format!("{}:127.0.0.1", self.config.server));
+            args.push(format!("{}:{}", self.config.server, port));
+            return args;
+        }
+
+        // Pin the connection to one of the server's gateways, again keeping
+        // its name for TLS
+        if let Some(address) = self.config.server_address {
+            args.push("--resolve".to_string());
+            args.push(format!("{}:{}", self.config.server, address));
+        }
+
         // Add server (without explicit port, let openconnect use default)
-        cmd.arg(&self.config.server)
+        args.push(self.config.server.clone());
+        args
+    }
+
+    /// Spawn OpenConnect process with credentials
+    ///
+    /// Returns the spawned child process
+    async fn spawn_process(&self) -> Result<Child, VpnError> {
+        // sudo or pkexec would only report a missing binary on stderr once running
+        let openconnect = locate_openconnect(self.config.openconnect_path.as_deref())?;
+        tracing::debug!("Using openconnect at {}", openconnect.display());
+
+        // A configured binary is run by its path; otherwise the bare name is
+        // kept, as existing sudoers rules expect
+        let program = match &self.config.openconnect_path {
+            Some(path) => path.as_os_str(),
+            None => OsStr::new("openconnect"),
+        };
+
+        // openconnect needs root privileges for network configuration
+        let mut cmd = Command::from(self.config.privilege_escalation.command(program));
+        cmd.args(self.openconnect_args())
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
@@ -162,7 +426,7 @@ impl CliConnector {
         Ok(child)
     }
 
-    /// Send password to OpenConnect via stdin
+    /// Send password (or session cookie) to OpenConnect via stdin
     ///
     /// Writes password and keeps stdin open (closing it would terminate openconnect)
     async fn send_password(&self, child: &mut Child, password: &str) -> Result<(), VpnError> {
@@ -199,7 +463,8 @@ impl CliConnector {
 
     /// Connect to VPN
     ///
-    /// Spawns OpenConnect, sends credentials, waits for connection, then detaches
+    /// Spawns OpenConnect, sends credentials, waits for connection, then detaches.
+    /// With cookie auth, `password` is the session cookie.
     pub async fn connect(&mut self, password: String) -> Result<(), VpnError> {
         // Update state to Connecting
         {
@@ -207,8 +472,12 @@ impl CliConnector {
             *state = ConnectionState::Connecting;
         }
 
+        if let Some(path) = &self.raw_capture_path {
+            *self.raw_capture.lock().await = Some(RawCapture::create(path, &password).await?);
+        }
+
         // Spawn OpenConnect process (via sudo wrapper with --background flag)
-        let mut child = self.spawn_process().await?;
+        let mut child = KillOnAbandon(Some(self.spawn_process().await?));
         let sudo_pid = child.id().unwrap_or(0);
 
         tracing::info!("Spawned sudo wrapper with PID {}", sudo_pid);
@@ -231,115 +500,389 @@ impl CliConnector {
                 reason: "Failed to capture stderr".to_string(),
             })?;
 
-        // Monitor both stdout and stderr until we see connection success or error
-        let parser = Arc::clone(&self.parser);
+        // Monitor both stdout and stderr until we see connection success or error,
+        // within the configured connection timeout
+        let monitored = self
+            .monitor_connection(&mut child, BufReader::new(stdout), BufReader::new(stderr))
+            .await;
+        // openconnect's output after it backgrounds itself goes to syslog
+        if let Some(mut capture) = self.raw_capture.lock().await.take() {
+            capture.flush().await;
+        }
+        let (ip_address, device) = monitored?;
         let event_sender = self.event_sender.clone();
-        let parser_stderr = Arc::clone(&self.parser);
-        let event_sender_stderr = self.event_sender.clone();
-
-        let mut stdout_reader = BufReader::new(stdout).lines();
-        let mut stderr_reader = BufReader::new(stderr).lines();
-        let mut connected = false;
-        let mut ip_address = None;
-        let mut device = None;
+
+        // Find the daemonized OpenConnect process PID
+        let daemon_pid = Self::find_openconnect_daemon_pid(
+            &self.config.server,
+            sudo_pid,
+            self.config
+                .pid_lookup_attempts
+                .unwrap_or(DEFAULT_PID_LOOKUP_ATTEMPTS),
+        )
+        .await;
+
+        // Store the daemon PID
+        let final_pid = daemon_pid.ok_or_else(|| VpnError::ProcessSpawnError {
+            reason: "Could not find openconnect daemon process".to_string(),
+        })?;
+
+        {
+            let mut pid_lock = self.openconnect_pid.lock().await;
+            *pid_lock = Some(final_pid);
+        }
+
+        tracing::info!("OpenConnect daemonized with PID {}", final_pid);
+
+        // Send ProcessStarted event with the actual PID
+        let _ = event_sender.send(ConnectionEvent::ProcessStarted { pid: final_pid });
+
+        // Update state to Established
+        {
+            let mut state = self.state.lock().await;
+            *state = ConnectionState::Established {
+                ip: ip_address,
+                device,
+            };
+        }
+
+        // Drop child handle - let openconnect run independently as a daemon
+        // We only keep the PID for status checks and disconnect operations
+        child.detach();
+        tracing::info!("Detached from OpenConnect daemon, returning control to user");
+
+        Ok(())
+    }
+
+    /// Read OpenConnect output until the connection is established or fails
+    ///
+    /// Applies the per-phase watchdog: if no output arrives within
+    /// `phase_timeout_secs`, the stalled process is killed and the phase reported.
+    /// The whole attempt is bounded by `timeout`. A pending push approval
+    /// extends both by [`APPROVAL_WAIT`], as the gateway says nothing until
+    /// the user responds.
+    /// If the server asks for an authentication group, the process is stopped
+    /// and the offered groups are returned so the caller can retry with one.
+    async fn monitor_connection<R, E>(
+        &self,
+        child: &mut Child,
+        reader: R,
+        stderr: E,
+    ) -> Result<(IpAddr, Option<String>), VpnError>
+    where
+        R: AsyncBufRead + Unpin,
+        E: AsyncBufRead + Unpin,
+    {
+        let phase_timeout = self
+            .config
+            .phase_timeout_secs
+            .map(|secs| Duration::from_secs(secs.into()));
+        let mut stdout_reader = BoundedLines::new(reader);
+        let mut stderr_reader = Some(BoundedLines::new(stderr));
+        let mut phase = ConnectionPhase::Startup;
+        let mut phase_started = Instant::now();
+        let _ = self.event_sender.send(phase.progress_event());
         let mut authenticating_sent = false;
-        let mut last_error: Option<String> = None;
-
-        // Spawn a task to monitor stderr in parallel
-        let stderr_handle = tokio::spawn(async move {
-            while let Ok(Some(line)) = stderr_reader.next_line().await {
-                tracing::debug!("OpenConnect stderr: {}", line);
-                let event = parser_stderr.parse_error(&line);
-                let _ = event_sender_stderr.send(event);
-            }
-        });
+        let mut cstp_connected = false;
+        // The last error reported, with the report for the user
+        let mut last_error: Option<(VpnError, String)> = None;
+        let mut stderr_tail: VecDeque<String> = VecDeque::new();
+        let mut transport = Transport::Tls;
+        let mut allowed = self
+            .config
+            .timeout
+            .map(|secs| Duration::from_secs(secs.into()));
+        let started = Instant::now();
+        let mut awaiting_approval = false;
+
+        let result = loop {
+            let phase_limit = phase_timeout.map(|limit| {
+                if awaiting_approval {
+                    limit.max(APPROVAL_WAIT)
+                } else {
+                    limit
+                }
+            });
+            let remaining = allowed.map(|allowed| allowed.saturating_sub(started.elapsed()));
+            let wait = match (phase_limit, remaining) {
+                (Some(limit), Some(remaining)) => Some(limit.min(remaining)),
+                (limit, remaining) => limit.or(remaining),
+            };
 
-        // Read stdout until connection is established or error occurs
-        while let Ok(Some(line)) = stdout_reader.next_line().await {
-            tracing::debug!("OpenConnect stdout: {}", line);
+            let next_line = next_output_line(&mut stdout_reader, &mut stderr_reader);
+            let next_line = match wait {
+                Some(wait) => match tokio::time::timeout(wait, next_line).await {
+                    Ok(next_line) => next_line,
+                    Err(_) => {
+                        if let Err(e) = child.start_kill() {
+                            tracing::warn!("Failed to kill stalled OpenConnect process: {}", e);
+                        }
+                        match (phase_limit, allowed) {
+                            (Some(limit), _) if limit == wait => {
+                                tracing::warn!(
+                                    "No OpenConnect output for {:?} during {}",
+                                    limit,
+                                    phase.as_str()
+                                );
+                                break Err(VpnError::PhaseTimeout {
+                                    phase: phase.as_str().to_string(),
+                                    seconds: limit.as_secs(),
+                                });
+                            }
+                            (_, allowed) => {
+                                let seconds = allowed.unwrap_or(wait).as_secs();
+                                tracing::warn!("OpenConnect did not connect within {}s", seconds);
+                                break Err(VpnError::ConnectionTimeout { seconds });
+                            }
+                        }
+                    }
+                },
+                None => next_line.await,
+            };
+
+            let Some(line) = next_line else {
+                // Collect whatever openconnect wrote to stderr on its way out
+                if let Some(lines) = stderr_reader.as_mut() {
+                    let drain = async {
+                        while let Ok(Some(line)) = lines.next_line().await {
+                            match line {
+                                ReadLine::Text(line) => {
+                                    self.capture_raw_line(&line).await;
+                                    let _ = self.handle_stderr_line(
+                                        line,
+                                        &mut stderr_tail,
+                                        &mut last_error,
+                                    );
+                                }
+                                ReadLine::Oversized(summary) => {
+                                    self.capture_raw_line(&summary).await;
+                                }
+                            }
+                        }
+                    };
+                    let _ = tokio::time::timeout(EXIT_STATUS_WAIT, drain).await;
+                }
+
+                // Authenticated, but openconnect gave up before TUN/routing was set up
+                if cstp_connected {
+                    break Err(VpnError::RoutingNotConfigured {
+                        reason: last_error.map(|(_, reason)| reason).unwrap_or_else(|| {
+                            "TUN device was never configured (missing root privileges?)".to_string()
+                        }),
+                    });
+                }
+
+                // The server name didn't resolve; kept distinct so the caller
+                // can tell whether retrying is worthwhile
+                if let Some((error @ VpnError::DnsFailure { .. }, reason)) = &last_error {
+                    tracing::warn!("OpenConnect could not resolve the server: {}", reason);
+                    break Err(error.clone());
+                }
+
+                // Exited with an error before the connection was established
+                if let Ok(Ok(status)) = tokio::time::timeout(EXIT_STATUS_WAIT, child.wait()).await {
+                    if let Some(code) = status.code().filter(|code| *code != 0) {
+                        let reason = last_error
+                            .map(|(_, reason)| reason)
+                            .or_else(|| {
+                                (!stderr_tail.is_empty())
+                                    .then(|| Vec::from(stderr_tail).join(" | "))
+                            })
+                            .unwrap_or_else(|| "no error output".to_string());
+                        break Err(VpnError::ProcessExited { code, reason });
+                    }
+                }
+
+                // Output closed before the connection was established
+                break Err(match last_error {
+                    Some((_, error)) => VpnError::ConnectionFailed { reason: error },
+                    None => VpnError::ConnectionFailed {
+                        reason: format!(
+                            "No response from server '{}'. Please verify the server address is correct.",
+                            self.config.server
+                        ),
+                    },
+                });
+            };
+
+            match &line {
+                OutputLine::Stdout(line) | OutputLine::Stderr(line) => {
+                    self.capture_raw_line(line).await;
+                    self.answer_prompt(line).await;
+                    transport = parse_transport(line).unwrap_or(transport);
+                }
+                OutputLine::Oversized(summary) => self.capture_raw_line(summary).await,
+            }
 
             // Parse the line for connection events
-            let event = parser.parse_line(&line);
+            let TimedEvent { at, event } = match line {
+                OutputLine::Stdout(line) => {
+                    tracing::debug!("OpenConnect stdout: {}", line);
+                    self.parser.parse_line_timed(&line)
+                }
+                OutputLine::Stderr(line) => {
+                    match self.handle_stderr_line(line, &mut stderr_tail, &mut last_error) {
+                        Some(event) => TimedEvent::now(event),
+                        None => continue,
+                    }
+                }
+                // Not worth parsing: no OpenConnect message is this long
+                OutputLine::Oversized(summary) => {
+                    tracing::warn!("Ignoring over-long OpenConnect output: {}", summary);
+                    TimedEvent::now(ConnectionEvent::UnknownOutput { line: summary })
+                }
+            };
+            let reached = event.phase().filter(|next| *next > phase);
             match &event {
-                ConnectionEvent::Connected { ip, device: dev } => {
-                    connected = true;
-                    ip_address = Some(ip.to_string());
-                    device = Some(dev.clone());
-                    let _ = event_sender.send(event.clone());
-                    break; // Stop monitoring once connected
+                ConnectionEvent::Connected { ip, device } => {
+                    *self.transport.lock().await = Some(transport);
+                    // Protocol output names only the address; openconnect has
+                    // put it on the tunnel by now
+                    let device = device.clone().or_else(|| find_tunnel_device(*ip));
+                    let _ = self.event_sender.send(ConnectionEvent::Connected {
+                        ip: *ip,
+                        device: device.clone(),
+                    });
+                    let _ = self
+                        .event_sender
+                        .send(ConnectionPhase::Connected.progress_event());
+                    break Ok((*ip, device)); // Stop monitoring once connected
                 }
                 ConnectionEvent::Error { kind, raw_output } => {
-                    let error_msg = format!("{:?}: {}", kind, raw_output);
-                    last_error = Some(error_msg.clone());
-                    let _ = event_sender.send(event.clone());
+                    last_error = Some((kind.clone(), format!("{} ({})", kind, raw_output.trim())));
+                    let _ = self.event_sender.send(event.clone());
                     // Continue reading to see if there are more specific errors
                 }
                 ConnectionEvent::Authenticating { .. } => {
+                    *self.state.lock().await = ConnectionState::Authenticating;
                     // Only send the first authenticating event to avoid duplicates
                     if !authenticating_sent {
-                        let _ = event_sender.send(event.clone());
+                        let _ = self.event_sender.send(event.clone());
                         authenticating_sent = true;
                     }
                 }
+                ConnectionEvent::AuthGroupsAvailable { groups } => {
+                    let _ = self.event_sender.send(event.clone());
+                    // openconnect now waits for a group on stdin; stop it instead
+                    if let Err(e) = child.start_kill() {
+                        tracing::warn!("Failed to stop OpenConnect at group prompt: {}", e);
+                    }
+                    break Err(VpnError::AuthGroupRequired {
+                        groups: groups.clone(),
+                    });
+                }
+                ConnectionEvent::AwaitingApproval { message } => {
+                    let _ = self.event_sender.send(event.clone());
+                    // A repeated prompt (e.g. a resent push) gets no more time
+                    if !awaiting_approval {
+                        awaiting_approval = true;
+                        allowed = allowed.map(|allowed| allowed + APPROVAL_WAIT);
+                        tracing::info!("Waiting for push approval: {}", message);
+                        if let Some(notice) = &self.approval_notice {
+                            notice(message);
+                        }
+                    }
+                }
+                ConnectionEvent::CstpConnected => {
+                    cstp_connected = true;
+                    let _ = self.event_sender.send(event.clone());
+                }
                 _ => {
-                    let _ = event_sender.send(event.clone());
+                    let _ = self.event_sender.send(event.clone());
                 }
             }
-        }
-
-        // Cancel stderr monitoring
-        stderr_handle.abort();
 
-        if !connected {
-            // Check if we captured any error messages
-            if let Some(error) = last_error {
-                return Err(VpnError::ConnectionFailed { reason: error });
+            // Report progress only when a later phase is reached
+            if let Some(next) = reached {
+                tracing::info!(
+                    "{} took {:.1?}",
+                    phase.as_str(),
+                    at.saturating_duration_since(phase_started)
+                );
+                phase = next;
+                phase_started = at;
+                let _ = self.event_sender.send(phase.progress_event());
+                // Past authentication, the push was approved
+                if phase > ConnectionPhase::Authenticating {
+                    awaiting_approval = false;
+                }
             }
+        };
 
-            return Err(VpnError::ConnectionFailed {
-                reason: format!(
-                    "No response from server '{}'. Please verify the server address is correct.",
-                    self.config.server
-                ),
-            });
+        if let Err(ref e) = result {
+            *self.state.lock().await = ConnectionState::Failed {
+                error: e.to_string(),
+            };
         }
 
-        // Find the daemonized OpenConnect process PID
-        let daemon_pid = Self::find_openconnect_daemon_pid(&self.config.server).await;
-
-        // Store the daemon PID
-        let final_pid = daemon_pid.ok_or_else(|| VpnError::ProcessSpawnError {
-            reason: "Could not find openconnect daemon process".to_string(),
-        })?;
+        result
+    }
 
-        {
-            let mut pid_lock = self.openconnect_pid.lock().await;
-            *pid_lock = Some(final_pid);
+    /// Copy a line of OpenConnect output to the raw capture file, if open
+    async fn capture_raw_line(&self, line: &str) {
+        if let Some(capture) = self.raw_capture.lock().await.as_mut() {
+            capture.write_line(line).await;
         }
+    }
 
-        tracing::info!("OpenConnect daemonized with PID {}", final_pid);
+    /// Answer a prompt configured in `prompt_responses` on OpenConnect's stdin
+    async fn answer_prompt(&self, line: &str) {
+        if !self.parser.is_prompt(line) {
+            return;
+        }
+        let Some(response) = self.config.prompt_response(line) else {
+            return;
+        };
 
-        // Send ProcessStarted event with the actual PID
-        let _ = event_sender.send(ConnectionEvent::ProcessStarted { pid: final_pid });
+        let mut stdin = self.process_stdin.lock().await;
+        let Some(stdin) = stdin.as_mut() else {
+            tracing::warn!("Cannot answer prompt, stdin is closed: {}", line.trim());
+            return;
+        };
+        // The response itself may be a secret, so only the prompt is logged
+        tracing::info!("Answering prompt: {}", line.trim());
+        let written = async {
+            stdin
+                .write_all(format!("{}\n", response).as_bytes())
+                .await?;
+            stdin.flush().await
+        };
+        if let Err(e) = written.await {
+            tracing::warn!("Failed to answer prompt: {}", e);
+        }
+    }
 
-        // Update state to Established
+    /// Classify and record a line of OpenConnect stderr
+    ///
+    /// Errors are remembered for the final report and forwarded as events.
+    /// Returns the event only if it needs handling by the monitor loop.
+    fn handle_stderr_line(
+        &self,
+        line: String,
+        tail: &mut VecDeque<String>,
+        last_error: &mut Option<(VpnError, String)>,
+    ) -> Option<ConnectionEvent> {
+        tracing::debug!("OpenConnect stderr: {}", line);
+
+        let event = self.parser.parse_error(&line);
+        if let ConnectionEvent::AuthGroupsAvailable { .. }
+        | ConnectionEvent::AwaitingApproval { .. } = event
         {
-            let mut state = self.state.lock().await;
-            *state = ConnectionState::Established {
-                ip: ip_address
-                    .unwrap_or_default()
-                    .parse()
-                    .unwrap_or("0.0.0.0".parse().unwrap()),
-                device: device.unwrap_or_default(),
-            };
+            return Some(event);
+        }
+        if let ConnectionEvent::Error { kind, raw_output } = &event {
+            *last_error = Some((kind.clone(), format!("{} ({})", kind, raw_output.trim())));
         }
 
-        // Drop child handle - let openconnect run independently as a daemon
-        // We only keep the PID for status checks and disconnect operations
-        drop(child);
-        tracing::info!("Detached from OpenConnect daemon, returning control to user");
+        if !line.trim().is_empty() {
+            if tail.len() == STDERR_TAIL_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(line.trim().to_string());
+        }
 
-        Ok(())
+        let _ = self.event_sender.send(event);
+        None
     }
 
     /// Get next connection event
@@ -491,3 +1034,780 @@ impl CliConnector {
         Ok(())
     }
 }
+
+/// Verbatim copy of OpenConnect's output, for debugging
+///
+/// Writes are buffered; a failing write is logged and doesn't affect the
+/// connection.
+struct RawCapture {
+    writer: tokio::io::BufWriter<tokio::fs::File>,
+    /// Never expected in the output (it goes to stdin), but redacted if it is
+    password: String,
+}
+
+impl RawCapture {
+    async fn create(path: &Path, password: &str) -> Result<Self, VpnError> {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(|e| VpnError::ConnectionFailed {
+                reason: format!("Failed to open raw capture file {}: {}", path.display(), e),
+            })?;
+
+        Ok(Self {
+            writer: tokio::io::BufWriter::new(file),
+            password: password.to_string(),
+        })
+    }
+
+    async fn write_line(&mut self, line: &str) {
+        let mut line = if self.password.is_empty() {
+            line.to_string()
+        } else {
+            line.replace(&self.password, "[REDACTED]")
+        };
+        line.push('\n');
+
+        if let Err(e) = self.writer.write_all(line.as_bytes()).await {
+            tracing::warn!("Failed to write raw capture: {}", e);
+        }
+    }
+
+    async fn flush(&mut self) {
+        if let Err(e) = self.writer.flush().await {
+            tracing::warn!("Failed to write raw capture: {}", e);
+        }
+    }
+}
+
+/// A line read by [`BoundedLines`]
+enum ReadLine {
+    Text(String),
+    /// A line over [`MAX_LINE_BYTES`], summarized
+    Oversized(String),
+}
+
+/// Line reader that caps line length and decodes invalid UTF-8 lossily
+///
+/// Unlike [`tokio::io::Lines`], a binary blob or a line that never ends can
+/// neither fail the stream nor grow without bound. `next_line` is cancel
+/// safe: a partial line is kept between calls.
+struct BoundedLines<R> {
+    reader: R,
+    buf: Vec<u8>,
+    /// Length of the current line, including bytes past the cap
+    len: usize,
+}
+
+impl<R: AsyncBufRead + Unpin> BoundedLines<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Read the next line; `None` at the end of the stream
+    async fn next_line(&mut self) -> std::io::Result<Option<ReadLine>> {
+        loop {
+            let available = self.reader.fill_buf().await?;
+            if available.is_empty() {
+                return Ok((self.len > 0).then(|| self.take_line()));
+            }
+
+            let (chunk, complete) = match available.iter().position(|byte| *byte == b'\n') {
+                Some(newline) => (&available[..newline], true),
+                None => (available, false),
+            };
+            let room = MAX_LINE_BYTES.saturating_sub(self.buf.len());
+            self.buf.extend_from_slice(&chunk[..chunk.len().min(room)]);
+            self.len += chunk.len();
+            let consumed = chunk.len() + usize::from(complete);
+            self.reader.consume(consumed);
+
+            if complete {
+                return Ok(Some(self.take_line()));
+            }
+        }
+    }
+
+    fn take_line(&mut self) -> ReadLine {
+        let len = std::mem::take(&mut self.len);
+        let bytes = std::mem::take(&mut self.buf);
+        if len > MAX_LINE_BYTES {
+            let preview = String::from_utf8_lossy(&bytes[..OVERSIZED_PREVIEW_BYTES]);
+            return ReadLine::Oversized(format!("{}... [{} byte line truncated]", preview, len));
+        }
+
+        let mut line = String::from_utf8_lossy(&bytes).into_owned();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+        ReadLine::Text(line)
+    }
+}
+
+/// A line of OpenConnect output and the stream it came from
+enum OutputLine {
+    Stdout(String),
+    Stderr(String),
+    /// An over-long line from either stream, summarized
+    Oversized(String),
+}
+
+impl OutputLine {
+    fn from_stdout(line: ReadLine) -> Self {
+        match line {
+            ReadLine::Text(line) => Self::Stdout(line),
+            ReadLine::Oversized(summary) => Self::Oversized(summary),
+        }
+    }
+
+    fn from_stderr(line: ReadLine) -> Self {
+        match line {
+            ReadLine::Text(line) => Self::Stderr(line),
+            ReadLine::Oversized(summary) => Self::Oversized(summary),
+        }
+    }
+}
+
+/// Read the next line from either stream
+///
+/// Returns `None` once stdout closes; a closed stderr is simply no longer polled.
+async fn next_output_line<R, E>(
+    stdout: &mut BoundedLines<R>,
+    stderr: &mut Option<BoundedLines<E>>,
+) -> Option<OutputLine>
+where
+    R: AsyncBufRead + Unpin,
+    E: AsyncBufRead + Unpin,
+{
+    loop {
+        let Some(stderr_lines) = stderr.as_mut() else {
+            return stdout
+                .next_line()
+                .await
+                .ok()
+                .flatten()
+                .map(OutputLine::from_stdout);
+        };
+
+        tokio::select! {
+            line = stdout.next_line() => return line.ok().flatten().map(OutputLine::from_stdout),
+            line = stderr_lines.next_line() => match line {
+                Ok(Some(line)) => return Some(OutputLine::from_stderr(line)),
+                _ => *stderr = None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_openconnect_is_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        // A directory by that name isn't the binary either
+        std::fs::create_dir(dir.path().join("openconnect")).unwrap();
+
+        let error = find_program("openconnect", dir.path().as_os_str()).unwrap_err();
+        assert!(matches!(error, VpnError::OpenConnectNotFound));
+        assert_eq!(error.kind(), "vpn.openconnect_not_found");
+    }
+
+    #[test]
+    fn test_configured_openconnect_is_used_instead_of_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("openconnect-sso");
+        assert!(matches!(
+            locate_openconnect(Some(&binary)).unwrap_err(),
+            VpnError::OpenConnectNotFound
+        ));
+
+        std::fs::write(&binary, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(matches!(
+            locate_openconnect(Some(&binary)).unwrap_err(),
+            VpnError::ProcessSpawnError { .. }
+        ));
+
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(locate_openconnect(Some(&binary)).unwrap(), binary);
+    }
+
+    #[test]
+    fn test_non_executable_openconnect_fails_to_spawn() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        let broken = first.path().join("openconnect");
+        std::fs::write(&broken, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&broken, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let search_path = std::env::join_paths([first.path(), second.path()]).unwrap();
+
+        let error = find_program("openconnect", &search_path).unwrap_err();
+        assert!(
+            matches!(&error, VpnError::ProcessSpawnError { reason } if reason.contains("not executable")),
+            "{:?}",
+            error
+        );
+
+        // An executable one later on PATH is used instead
+        let working = second.path().join("openconnect");
+        std::fs::write(&working, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&working, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(find_program("openconnect", &search_path).unwrap(), working);
+    }
+
+    #[tokio::test]
+    async fn test_phase_timeout_reports_stalled_authentication() {
+        let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+        config.phase_timeout_secs = Some(1);
+        let mut connector = CliConnector::new(config).unwrap();
+
+        // Stand-in for openconnect: starts authenticating, then goes silent
+        let mut child = Command::new("sh")
+            .args(["-c", "echo 'POST https://vpn.example.com/'; sleep 30"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let result = connector
+            .monitor_connection(&mut child, BufReader::new(stdout), tokio::io::empty())
+            .await;
+
+        let error = result.unwrap_err();
+        assert_eq!(
+            error,
+            VpnError::PhaseTimeout {
+                phase: "authentication".to_string(),
+                seconds: 1,
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Connection stuck in authentication for 1s"
+        );
+
+        // The stalled process is killed and the connector marked failed
+        let status = tokio::time::timeout(Duration::from_secs(5), child.wait())
+            .await
+            .expect("stalled process should be killed")
+            .unwrap();
+        assert!(!status.success());
+        assert!(matches!(connector.state(), ConnectionState::Failed { .. }));
+        assert_eq!(
+            connector.next_event().await,
+            Some(ConnectionPhase::Startup.progress_event())
+        );
+        assert!(matches!(
+            connector.next_event().await,
+            Some(ConnectionEvent::Authenticating { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_push_approval_extends_timeouts_and_is_surfaced() {
+        let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+        config.timeout = Some(1);
+        config.phase_timeout_secs = Some(1);
+        let notices = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = Arc::clone(&notices);
+        let mut connector = CliConnector::new(config)
+            .unwrap()
+            .with_approval_notice(move |message| seen.lock().unwrap().push(message.to_string()));
+
+        // Stand-in for openconnect: sends a push, then stays silent past both
+        // timeouts until it is "approved"
+        let mut child = Command::new("sh")
+            .args([
+                "-c",
+                "echo 'POST https://vpn.example.com/'; \
+                 echo 'Duo Push sent to your phone' >&2; sleep 2; \
+                 echo 'Configured as 10.0.0.5, with SSL connected and DTLS in progress'",
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+
+        let (ip, _) = connector
+            .monitor_connection(&mut child, BufReader::new(stdout), BufReader::new(stderr))
+            .await
+            .unwrap();
+
+        assert_eq!(ip, "10.0.0.5".parse::<IpAddr>().unwrap());
+        assert_eq!(
+            *notices.lock().unwrap(),
+            vec!["Duo Push sent to your phone".to_string()]
+        );
+        let mut events = Vec::new();
+        while let Ok(event) = connector.event_receiver.try_recv() {
+            events.push(event);
+        }
+        assert!(events.contains(&ConnectionEvent::AwaitingApproval {
+            message: "Duo Push sent to your phone".to_string(),
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_over_long_and_binary_lines_do_not_end_monitoring() {
+        let config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+        let mut connector = CliConnector::new(config).unwrap();
+        let mut child = Command::new("true").spawn().unwrap();
+
+        let mut stdout = vec![b'x'; 4 * MAX_LINE_BYTES];
+        stdout.extend_from_slice(b"\nPOST https://vpn.example.com/\n\xff\xfe\x00\x9c binary\n");
+        stdout.extend_from_slice(
+            b"Configured as 10.0.0.5, with SSL connected and DTLS in progress\n",
+        );
+        let stderr: &[u8] = b"\xc3\x28 not utf-8\r\n";
+
+        let (ip, _) = connector
+            .monitor_connection(&mut child, stdout.as_slice(), stderr)
+            .await
+            .unwrap();
+        assert_eq!(ip, "10.0.0.5".parse::<IpAddr>().unwrap());
+
+        let mut events = Vec::new();
+        while let Ok(event) = connector.event_receiver.try_recv() {
+            events.push(event);
+        }
+        let summary = format!("... [{} byte line truncated]", 4 * MAX_LINE_BYTES);
+        assert!(events.iter().any(|event| matches!(
+            event,
+            ConnectionEvent::UnknownOutput { line }
+                if line.ends_with(&summary) && line.len() < OVERSIZED_PREVIEW_BYTES + summary.len() + 1
+        )));
+        let _ = child.wait().await;
+    }
+
+    #[tokio::test]
+    async fn test_bounded_lines_cap_length_and_decode_lossily() {
+        let mut input = b"first\r\n\xffsecond\n".to_vec();
+        input.extend(std::iter::repeat(b'y').take(MAX_LINE_BYTES + 1));
+        input.extend_from_slice(b"\nlast without newline");
+        let mut lines = BoundedLines::new(input.as_slice());
+
+        let mut read = Vec::new();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            read.push(match line {
+                ReadLine::Text(line) => line,
+                ReadLine::Oversized(summary) => format!("oversized: {}", summary),
+            });
+        }
+
+        assert_eq!(read[0], "first");
+        assert_eq!(read[1], "\u{FFFD}second");
+        assert!(read[2].starts_with("oversized: yyy"));
+        assert!(read[2].len() < OVERSIZED_PREVIEW_BYTES + 64);
+        assert_eq!(read[3], "last without newline");
+        assert_eq!(read.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_overall_timeout_still_applies_without_approval() {
+        let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+        config.timeout = Some(1);
+        let connector = CliConnector::new(config).unwrap();
+
+        let mut child = Command::new("sh")
+            .args(["-c", "echo 'POST https://vpn.example.com/'; sleep 30"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let result = connector
+            .monitor_connection(&mut child, BufReader::new(stdout), tokio::io::empty())
+            .await;
+
+        assert_eq!(
+            result.unwrap_err(),
+            VpnError::ConnectionTimeout { seconds: 1 }
+        );
+        let _ = child.wait().await;
+    }
+
+    #[tokio::test]
+    async fn test_cookie_is_fed_to_openconnect_on_stdin() {
+        let config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+        let connector = CliConnector::new(config).unwrap().with_cookie_auth();
+        let cookie = "webvpn=0123456789ABCDEF";
+
+        let args = connector.openconnect_args();
+        assert!(args.contains(&"--cookie-on-stdin".to_string()));
+        assert!(!args.contains(&"--passwd-on-stdin".to_string()));
+        assert!(!args.contains(&"--user".to_string()));
+        assert!(!args.iter().any(|arg| arg.contains(cookie)));
+
+        // Stand-in for openconnect: echoes the first line it reads from stdin
+        let mut child = Command::new("sh")
+            .args(["-c", "read -r line; echo \"$line\""])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        connector.send_password(&mut child, cookie).await.unwrap();
+        // Close the kept-alive stdin as a disconnect would
+        *connector.process_stdin.lock().await = None;
+
+        let output = child.wait_with_output().await.unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            format!("{}\n", cookie)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_raw_output_is_captured_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("openconnect-raw.log");
+        let config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+        let connector = CliConnector::new(config).unwrap();
+        *connector.raw_capture.lock().await =
+            Some(RawCapture::create(&path, "1234567890").await.unwrap());
+
+        // Stand-in for openconnect writing to both streams
+        let script = "\
+            echo 'POST https://vpn.example.com/'; \
+            echo 'password 1234567890 echoed' >&2; \
+            sleep 0.2; \
+            echo 'Configured as 10.0.0.5, with SSL connected and DTLS in progress'";
+        let mut child = Command::new("sh")
+            .args(["-c", script])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+
+        connector
+            .monitor_connection(&mut child, BufReader::new(stdout), BufReader::new(stderr))
+            .await
+            .unwrap();
+        connector
+            .raw_capture
+            .lock()
+            .await
+            .as_mut()
+            .unwrap()
+            .flush()
+            .await;
+
+        let captured = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = captured.lines().collect();
+        assert!(lines.contains(&"POST https://vpn.example.com/"));
+        assert!(lines.contains(&"Configured as 10.0.0.5, with SSL connected and DTLS in progress"));
+        assert!(lines.contains(&"password [REDACTED] echoed"));
+        assert!(!captured.contains("1234567890"));
+    }
+
+    #[tokio::test]
+    async fn test_connection_summary_records_established_transport() {
+        use crate::vpn::summary::ConnectionSummary;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("summary.json");
+        let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+        config.protocol = crate::config::VpnProtocol::Pulse;
+        let connector = CliConnector::new(config).unwrap();
+
+        // The latest channel to come up wins
+        let script = "\
+            echo 'POST https://vpn.example.com/'; \
+            echo 'Established DTLS connection (using GnuTLS)'; \
+            echo 'Configured as 10.9.0.12, with SSL disconnected and ESP established'";
+        let mut child = Command::new("sh")
+            .args(["-c", script])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+
+        let (ip, device) = connector
+            .monitor_connection(&mut child, BufReader::new(stdout), BufReader::new(stderr))
+            .await
+            .unwrap();
+        let transport = connector.transport().unwrap();
+        assert_eq!(transport, Transport::Esp);
+
+        ConnectionSummary::new(ip, device.as_deref(), Some(4242), transport)
+            .write_to(&path)
+            .unwrap();
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["ip"], "10.9.0.12");
+        assert_eq!(written["pid"], 4242);
+        assert_eq!(written["transport"], "esp");
+        assert!(written["connected_at"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_cstp_up_without_tun_reports_missing_routing() {
+        let config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+        let mut connector = CliConnector::new(config).unwrap();
+
+        // Authenticates and brings CSTP up, then exits without configuring TUN
+        let script = "\
+            echo 'POST https://vpn.example.com/'; \
+            echo 'Got CONNECT response: HTTP/1.1 200 OK'; \
+            echo 'CSTP connected. DPD 30, Keepalive 20'";
+        let mut child = Command::new("sh")
+            .args(["-c", script])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let result = connector
+            .monitor_connection(&mut child, BufReader::new(stdout), tokio::io::empty())
+            .await;
+        let _ = child.wait().await;
+
+        let error = result.unwrap_err();
+        assert!(
+            matches!(error, VpnError::RoutingNotConfigured { .. }),
+            "expected RoutingNotConfigured, got {:?}",
+            error
+        );
+        assert!(error.to_string().contains("routing was not configured"));
+        assert!(matches!(connector.state(), ConnectionState::Failed { .. }));
+
+        let mut events = Vec::new();
+        while let Ok(event) = connector.event_receiver.try_recv() {
+            events.push(event);
+        }
+        assert!(events.contains(&ConnectionEvent::CstpConnected));
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, ConnectionEvent::Connected { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_progress_is_monotonic_and_ends_at_100() {
+        let config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+        let mut connector = CliConnector::new(config).unwrap();
+
+        // Scripted F5 session, including repeated and out-of-order phase lines
+        let script = "\
+            echo 'POST https://vpn.example.com/my.policy'; \
+            echo 'Got CONNECT response: HTTP/1.1 200 OK'; \
+            echo 'Connected to F5 Session Manager'; \
+            echo 'POST https://vpn.example.com/vdesk/'; \
+            echo 'Connected tun0 as 10.0.1.100'; \
+            echo 'Configured as 10.0.1.100, with SSL connected and DTLS disabled'";
+        let mut child = Command::new("sh")
+            .args(["-c", script])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let result = connector
+            .monitor_connection(&mut child, BufReader::new(stdout), tokio::io::empty())
+            .await;
+        assert!(result.is_ok());
+        let _ = child.wait().await;
+
+        let mut percents = Vec::new();
+        while let Ok(event) = connector.event_receiver.try_recv() {
+            if let ConnectionEvent::Progress { percent, .. } = event {
+                percents.push(percent);
+            }
+        }
+
+        assert_eq!(percents, vec![10, 40, 70, 90, 100]);
+        assert!(percents.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[tokio::test]
+    async fn test_group_prompt_on_stderr_stops_and_reports_groups() {
+        let config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+        let mut connector = CliConnector::new(config).unwrap();
+
+        // Asks for a group on stderr, then waits for an answer that never comes
+        let script = "\
+            echo 'POST https://vpn.example.com/'; \
+            echo 'GROUP: [Employees|Contractors]:' >&2; \
+            sleep 30";
+        let mut child = Command::new("sh")
+            .args(["-c", script])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(10),
+            connector.monitor_connection(
+                &mut child,
+                BufReader::new(stdout),
+                BufReader::new(stderr),
+            ),
+        )
+        .await
+        .expect("group prompt should end monitoring");
+
+        assert_eq!(
+            result.unwrap_err(),
+            VpnError::AuthGroupRequired {
+                groups: vec!["Employees".to_string(), "Contractors".to_string()],
+            }
+        );
+        let status = tokio::time::timeout(Duration::from_secs(5), child.wait())
+            .await
+            .expect("waiting process should be stopped")
+            .unwrap();
+        assert!(!status.success());
+
+        let mut events = Vec::new();
+        while let Ok(event) = connector.event_receiver.try_recv() {
+            events.push(event);
+        }
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, ConnectionEvent::AuthGroupsAvailable { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_nonzero_exit_without_success_reports_status_and_stderr() {
+        let config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+        let connector = CliConnector::new(config).unwrap();
+
+        // Stand-in for openconnect that fails before connecting
+        let script = "\
+            echo 'POST https://vpn.example.com/'; \
+            echo 'Creating SSL connection failed' >&2; \
+            echo 'Failed to open HTTPS connection to vpn.example.com' >&2; \
+            exit 3";
+        let mut child = Command::new("sh")
+            .args(["-c", script])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+
+        let error = connector
+            .monitor_connection(&mut child, BufReader::new(stdout), BufReader::new(stderr))
+            .await
+            .unwrap_err();
+
+        match &error {
+            VpnError::ProcessExited { code, reason } => {
+                assert_eq!(*code, 3);
+                // Classified from stderr rather than a generic message
+                assert!(reason.contains("SSL/TLS connection failure"), "{}", reason);
+            }
+            other => panic!("expected ProcessExited, got {:?}", other),
+        }
+        assert!(error
+            .to_string()
+            .starts_with("OpenConnect exited with status 3"));
+    }
+
+    #[tokio::test]
+    async fn test_unclassified_stderr_is_included_on_nonzero_exit() {
+        let config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+        let connector = CliConnector::new(config).unwrap();
+
+        let mut child = Command::new("sh")
+            .args(["-c", "echo 'unexpected option --frobnicate' >&2; exit 1"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+
+        let error = connector
+            .monitor_connection(&mut child, BufReader::new(stdout), BufReader::new(stderr))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            VpnError::ProcessExited {
+                code: 1,
+                reason: "unexpected option --frobnicate".to_string(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_configured_prompt_is_answered_on_stdin() {
+        let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+        config
+            .prompt_responses
+            .insert("acceptable use policy".to_string(), "yes".to_string());
+        let connector = CliConnector::new(config).unwrap();
+
+        // Stand-in for openconnect that only connects once the policy is accepted
+        let script = "\
+            echo 'Do you accept the acceptable use policy? [yes/no]' >&2; \
+            read answer; \
+            [ \"$answer\" = yes ] || exit 1; \
+            echo 'Configured as 10.0.0.5, with SSL connected and DTLS in progress'";
+        let mut child = Command::new("sh")
+            .args(["-c", script])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        *connector.process_stdin.lock().await = child.stdin.take();
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+
+        let (ip, _) = connector
+            .monitor_connection(&mut child, BufReader::new(stdout), BufReader::new(stderr))
+            .await
+            .unwrap();
+        assert_eq!(ip, "10.0.0.5".parse::<IpAddr>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_dns_failure_is_reported_as_such_on_exit() {
+        let config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+        let connector = CliConnector::new(config).unwrap();
+
+        let script = "\
+            echo \"getaddrinfo failed for host 'vpn.example.com': \
+            Temporary failure in name resolution\" >&2; \
+            echo 'Failed to open HTTPS connection to vpn.example.com' >&2; \
+            exit 1";
+        let mut child = Command::new("sh")
+            .args(["-c", script])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+
+        let error = connector
+            .monitor_connection(&mut child, BufReader::new(stdout), BufReader::new(stderr))
+            .await
+            .unwrap_err();
+
+        assert_eq!(error, VpnError::DnsFailure { transient: true });
+    }
+}
@@ -0,0 +1,206 @@
+//! Lock held while a connection is being established
+//!
+//! The state file only exists once a connection is up, so two `akon vpn on`
+//! run in quick succession would both start openconnect. The first takes a
+//! lock file recording its PID for the whole attempt; later ones see it and
+//! stop. A lock left behind by a crashed attempt names a dead process and is
+//! taken over; removing it happens under an `flock` on a guard file next to
+//! the lock, so two attempts can't both take over the same stale lock.
+
+use crate::error::{AkonError, ConfigError, VpnError};
+use nix::fcntl::{flock, FlockArg};
+use std::fs::File;
+use std::io::ErrorKind;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+
+/// Held for the duration of a connection attempt; released on drop
+#[derive(Debug)]
+pub struct ConnectLock {
+    path: PathBuf,
+}
+
+/// Whether a process with `pid` exists
+///
+/// `EPERM` means it exists but belongs to someone else (e.g. under sudo).
+fn process_alive(pid: u32) -> bool {
+    use nix::errno::Errno;
+    use nix::sys::signal::kill;
+    use nix::unistd::Pid;
+
+    let Ok(pid) = i32::try_from(pid) else {
+        return false;
+    };
+    matches!(kill(Pid::from_raw(pid), None), Ok(()) | Err(Errno::EPERM))
+}
+
+/// PID recorded in the lock file at `path`, if it can be read
+fn lock_owner(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn lock_error(path: &Path, e: std::io::Error) -> AkonError {
+    AkonError::Config(ConfigError::IoError {
+        message: format!("Failed to create lock {}: {}", path.display(), e),
+    })
+}
+
+impl ConnectLock {
+    /// Take the lock at `path` for this process
+    ///
+    /// Fails with [`VpnError::ConnectionInProgress`] while another live
+    /// process holds it.
+    pub fn acquire(path: &Path) -> Result<Self, AkonError> {
+        Self::acquire_as(path, std::process::id())
+    }
+
+    fn acquire_as(path: &Path, pid: u32) -> Result<Self, AkonError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| lock_error(path, e))?;
+        }
+
+        // The PID is written to a private file first and linked into place,
+        // so the lock never exists without its owner
+        let staged = path.with_extension(format!("{}.tmp", pid));
+        std::fs::write(&staged, format!("{}\n", pid)).map_err(|e| lock_error(path, e))?;
+        let result = Self::link_lock(&staged, path, pid);
+        let _ = std::fs::remove_file(&staged);
+        result
+    }
+
+    fn link_lock(staged: &Path, path: &Path, pid: u32) -> Result<Self, AkonError> {
+        // A stale lock is removed once and linking retried; losing that
+        // race to another process means it now holds the lock
+        for _ in 0..2 {
+            match std::fs::hard_link(staged, path) {
+                Ok(()) => {
+                    return Ok(Self {
+                        path: path.to_path_buf(),
+                    })
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => match lock_owner(path) {
+                    Some(owner) if owner != pid && process_alive(owner) => {
+                        return Err(AkonError::Vpn(VpnError::ConnectionInProgress {
+                            pid: owner,
+                        }));
+                    }
+                    owner => {
+                        tracing::info!(
+                            "Removing stale connect lock (PID: {})",
+                            owner.map_or("unknown".to_string(), |pid| pid.to_string())
+                        );
+                        Self::remove_stale(path, owner)?;
+                    }
+                },
+                Err(e) => return Err(lock_error(path, e)),
+            }
+        }
+
+        Err(AkonError::Vpn(VpnError::ConnectionInProgress {
+            pid: lock_owner(path).unwrap_or_default(),
+        }))
+    }
+
+    /// Remove the lock at `path` if it still names `owner`
+    ///
+    /// Another attempt may have removed the stale lock and linked its own
+    /// since `owner` was read; the guard keeps that from happening between
+    /// reading the owner again and unlinking.
+    fn remove_stale(path: &Path, owner: Option<u32>) -> Result<(), AkonError> {
+        let guard = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path.with_extension("guard"))
+            .map_err(|e| lock_error(path, e))?;
+        flock(guard.as_raw_fd(), FlockArg::LockExclusive)
+            .map_err(|e| lock_error(path, e.into()))?;
+
+        if lock_owner(path) == owner {
+            let _ = std::fs::remove_file(path);
+        }
+        // The guard is unlocked when closed
+        Ok(())
+    }
+}
+
+impl Drop for ConnectLock {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            if e.kind() != ErrorKind::NotFound {
+                tracing::warn!("Failed to remove connect lock: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A PID that no running process has
+    fn dead_pid() -> u32 {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        pid
+    }
+
+    #[test]
+    fn test_second_attempt_is_rejected_while_first_holds_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("akon.lock");
+
+        // Another live process (the test runner's parent stands in for it)
+        let other = std::os::unix::process::parent_id();
+        let first = ConnectLock::acquire_as(&path, other).unwrap();
+
+        let error = ConnectLock::acquire(&path).unwrap_err();
+        assert!(matches!(
+            error,
+            AkonError::Vpn(VpnError::ConnectionInProgress { pid }) if pid == other
+        ));
+        assert!(error.to_string().contains("already in progress"));
+
+        // Released on drop, after which the next attempt goes ahead
+        drop(first);
+        assert!(!path.exists());
+        let second = ConnectLock::acquire(&path).unwrap();
+        assert_eq!(lock_owner(&path), Some(std::process::id()));
+        drop(second);
+    }
+
+    #[test]
+    fn test_stale_lock_is_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("akon.lock");
+
+        std::fs::write(&path, format!("{}\n", dead_pid())).unwrap();
+        let _lock = ConnectLock::acquire(&path).unwrap();
+        assert_eq!(lock_owner(&path), Some(std::process::id()));
+    }
+
+    #[test]
+    fn test_lock_taken_over_meanwhile_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("akon.lock");
+
+        // Another attempt replaced the stale lock after it was read
+        let stale = dead_pid();
+        std::fs::write(&path, format!("{}\n", std::process::id())).unwrap();
+        ConnectLock::remove_stale(&path, Some(stale)).unwrap();
+        assert_eq!(lock_owner(&path), Some(std::process::id()));
+
+        ConnectLock::remove_stale(&path, Some(std::process::id())).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_unreadable_lock_is_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("akon.lock");
+
+        std::fs::write(&path, "").unwrap();
+        assert!(ConnectLock::acquire(&path).is_ok());
+    }
+}
@@ -3,7 +3,9 @@
 //! Defines events emitted during OpenConnect CLI connection lifecycle
 
 use crate::error::VpnError;
+use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
+use std::time::{Duration, Instant};
 
 /// Events emitted during OpenConnect CLI connection lifecycle
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -14,16 +16,27 @@ pub enum ConnectionEvent {
     /// Authentication phase in progress
     Authenticating { message: String },
 
+    /// Server asked which authentication group (realm) to use
+    AuthGroupsAvailable { groups: Vec<String> },
+
+    /// A push notification was sent; the gateway waits for it to be approved
+    AwaitingApproval { message: String },
+
     /// F5 session manager connection established
     F5SessionEstablished {
         session_token: Option<String>, // May be redacted for security
     },
 
+    /// CSTP tunnel up (authenticated), before the TUN device and routes are set up
+    CstpConnected,
+
     /// TUN device configured with assigned IP
-    TunConfigured { device: String, ip: IpAddr },
+    ///
+    /// The device is `None` when openconnect's output doesn't name it.
+    TunConfigured { device: Option<String>, ip: IpAddr },
 
     /// Full VPN connection established
-    Connected { ip: IpAddr, device: String },
+    Connected { ip: IpAddr, device: Option<String> },
 
     /// Connection disconnected normally
     Disconnected { reason: DisconnectReason },
@@ -33,15 +46,154 @@ pub enum ConnectionEvent {
 
     /// Unparsed output line (fallback)
     UnknownOutput { line: String },
+
+    /// Rough connection progress, emitted each time a new phase is reached
+    Progress { percent: u8, phase: ConnectionPhase },
 }
 
-/// Reasons for disconnection
+impl ConnectionEvent {
+    /// Connection phase this event indicates, if any
+    pub fn phase(&self) -> Option<ConnectionPhase> {
+        match self {
+            Self::Authenticating { .. }
+            | Self::AuthGroupsAvailable { .. }
+            | Self::AwaitingApproval { .. } => Some(ConnectionPhase::Authenticating),
+            Self::F5SessionEstablished { .. } | Self::CstpConnected => {
+                Some(ConnectionPhase::SessionSetup)
+            }
+            Self::TunConfigured { .. } => Some(ConnectionPhase::TunnelConfiguration),
+            Self::Connected { .. } => Some(ConnectionPhase::Connected),
+            _ => None,
+        }
+    }
+}
+
+/// A connection event with the instant it was produced
+///
+/// Kept as a wrapper so `ConnectionEvent` stays easy to construct and match.
 #[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimedEvent {
+    pub at: Instant,
+    pub event: ConnectionEvent,
+}
+
+impl TimedEvent {
+    /// Timestamp an event produced now
+    pub fn now(event: ConnectionEvent) -> Self {
+        Self {
+            at: Instant::now(),
+            event,
+        }
+    }
+}
+
+/// How long each phase lasted in a sequence of timed events
+///
+/// A phase lasts from the first event of the sequence (for
+/// [`ConnectionPhase::Startup`]) or the event that reached it, until the
+/// event that reached the next phase. Phases that are never left are not
+/// reported.
+pub fn phase_durations(events: &[TimedEvent]) -> Vec<(ConnectionPhase, Duration)> {
+    let Some(first) = events.first() else {
+        return Vec::new();
+    };
+
+    let mut durations = Vec::new();
+    let mut phase = ConnectionPhase::Startup;
+    let mut started = first.at;
+    for timed in events {
+        if let Some(next) = timed.event.phase().filter(|next| *next > phase) {
+            durations.push((phase, timed.at.saturating_duration_since(started)));
+            phase = next;
+            started = timed.at;
+        }
+    }
+    durations
+}
+
+/// Phases of establishing a connection, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConnectionPhase {
+    /// OpenConnect spawned, waiting for the server
+    Startup,
+    /// Exchanging credentials with the server
+    Authenticating,
+    /// Server accepted the login and is setting up the session
+    SessionSetup,
+    /// TUN device is being configured
+    TunnelConfiguration,
+    /// Tunnel is up
+    Connected,
+}
+
+impl ConnectionPhase {
+    /// Estimated percent complete when this phase is reached
+    pub fn percent(&self) -> u8 {
+        match self {
+            Self::Startup => 10,
+            Self::Authenticating => 40,
+            Self::SessionSetup => 70,
+            Self::TunnelConfiguration => 90,
+            Self::Connected => 100,
+        }
+    }
+
+    /// Human-readable phase name (e.g. "stuck in authentication")
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Startup => "startup",
+            Self::Authenticating => "authentication",
+            Self::SessionSetup => "session setup",
+            Self::TunnelConfiguration => "tunnel configuration",
+            Self::Connected => "connected",
+        }
+    }
+
+    /// Progress event for this phase
+    pub fn progress_event(self) -> ConnectionEvent {
+        ConnectionEvent::Progress {
+            percent: self.percent(),
+            phase: self,
+        }
+    }
+}
+
+/// Reasons for disconnection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DisconnectReason {
     UserRequested,
     ServerDisconnect,
     ProcessTerminated,
     Timeout,
+    /// Health checks stopped getting through the tunnel
+    NetworkDown,
+}
+
+impl DisconnectReason {
+    /// Name used in the config file
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UserRequested => "user_requested",
+            Self::ServerDisconnect => "server_disconnect",
+            Self::ProcessTerminated => "process_terminated",
+            Self::Timeout => "timeout",
+            Self::NetworkDown => "network_down",
+        }
+    }
+}
+
+/// Channel carrying the tunnel's traffic
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    /// The TLS connection (openconnect calls it SSL)
+    #[default]
+    Tls,
+    /// DTLS over UDP
+    Dtls,
+    /// ESP over UDP (GlobalProtect, Pulse)
+    Esp,
 }
 
 /// Internal connection state
@@ -50,7 +202,113 @@ pub enum ConnectionState {
     Idle,
     Connecting,
     Authenticating,
-    Established { ip: IpAddr, device: String },
+    Established { ip: IpAddr, device: Option<String> },
     Disconnecting,
     Failed { error: String },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase_percentages_increase_with_phase_order() {
+        let phases = [
+            ConnectionPhase::Startup,
+            ConnectionPhase::Authenticating,
+            ConnectionPhase::SessionSetup,
+            ConnectionPhase::TunnelConfiguration,
+            ConnectionPhase::Connected,
+        ];
+        let percents: Vec<u8> = phases.iter().map(|p| p.percent()).collect();
+
+        assert_eq!(percents, vec![10, 40, 70, 90, 100]);
+        assert!(phases.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_replayed_events_preserve_phase_timing() {
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let ip: IpAddr = "10.0.1.100".parse().unwrap();
+        let events = vec![
+            TimedEvent {
+                at: at(0),
+                event: ConnectionEvent::UnknownOutput {
+                    line: "POST https://vpn.example.com/".to_string(),
+                },
+            },
+            TimedEvent {
+                at: at(400),
+                event: ConnectionEvent::Authenticating {
+                    message: "Please enter your username and password".to_string(),
+                },
+            },
+            TimedEvent {
+                at: at(2700),
+                event: ConnectionEvent::CstpConnected,
+            },
+            TimedEvent {
+                at: at(3000),
+                event: ConnectionEvent::Connected {
+                    ip,
+                    device: Some("tun0".to_string()),
+                },
+            },
+        ];
+
+        // Replaying (e.g. after a dump) keeps the same relative timing
+        let replayed: Vec<TimedEvent> = events
+            .iter()
+            .map(|timed| TimedEvent {
+                at: at(60_000) + timed.at.duration_since(start),
+                event: timed.event.clone(),
+            })
+            .collect();
+
+        let expected = vec![
+            (ConnectionPhase::Startup, Duration::from_millis(400)),
+            (ConnectionPhase::Authenticating, Duration::from_millis(2300)),
+            (ConnectionPhase::SessionSetup, Duration::from_millis(300)),
+        ];
+        assert_eq!(phase_durations(&events), expected);
+        assert_eq!(phase_durations(&replayed), expected);
+        assert!(phase_durations(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_event_phase_mapping() {
+        let ip: IpAddr = "10.0.1.100".parse().unwrap();
+
+        assert_eq!(
+            ConnectionEvent::Authenticating {
+                message: String::new()
+            }
+            .phase(),
+            Some(ConnectionPhase::Authenticating)
+        );
+        assert_eq!(
+            ConnectionEvent::TunConfigured {
+                device: Some("tun0".to_string()),
+                ip
+            }
+            .phase(),
+            Some(ConnectionPhase::TunnelConfiguration)
+        );
+        assert_eq!(
+            ConnectionEvent::Connected {
+                ip,
+                device: Some("tun0".to_string())
+            }
+            .phase(),
+            Some(ConnectionPhase::Connected)
+        );
+        assert_eq!(
+            ConnectionEvent::UnknownOutput {
+                line: String::new()
+            }
+            .phase(),
+            None
+        );
+    }
+}
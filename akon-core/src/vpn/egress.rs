@@ -0,0 +1,219 @@
+//! Pinning the VPN's outer connection to one interface
+//!
+//! openconnect has no option to choose the interface its encrypted traffic
+//! leaves through. On multi-homed hosts akon instead installs a host route
+//! for the VPN server over the requested interface before connecting
+//! (`ip route replace <server>/32 via <gateway> dev <interface>`), and
+//! removes it on disconnect. vpnc-script keeps an existing route to the
+//! server while connected but deletes it when openconnect exits, so
+//! reconnections install it again.
+
+use crate::error::VpnError;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::Path;
+use std::process::Command;
+
+/// Runs the `ip` commands that set up and remove the egress route
+///
+/// Abstracted so the command sequence can be tested without root.
+pub trait CommandRunner {
+    /// Run `program` with `args`, returning stdout or an error message
+    fn run(&mut self, program: &str, args: &[String]) -> Result<String, String>;
+}
+
+/// Runs commands on the system; route changes go through sudo
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&mut self, program: &str, args: &[String]) -> Result<String, String> {
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .map_err(|e| format!("failed to run {}: {}", program, e))?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    }
+}
+
+/// Check that a network interface exists, by looking in `/sys/class/net`
+pub fn interface_exists(sys_class_net: &Path, interface: &str) -> bool {
+    !interface.is_empty() && !interface.contains('/') && sys_class_net.join(interface).exists()
+}
+
+/// Extract the gateway from `ip route get` output, if the route has one
+///
+/// Example: `203.0.113.7 via 192.168.1.1 dev eth0 src 192.168.1.23 uid 1000`
+pub fn parse_route_gateway(output: &str) -> Option<IpAddr> {
+    let mut tokens = output.split_whitespace();
+    while let Some(token) = tokens.next() {
+        if token == "via" {
+            return tokens.next()?.parse().ok();
+        }
+    }
+    None
+}
+
+/// Host route sending traffic for the VPN server through one interface
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EgressRoute {
+    pub server_ip: IpAddr,
+    pub interface: String,
+    pub gateway: Option<IpAddr>,
+}
+
+impl EgressRoute {
+    fn destination(&self) -> String {
+        let prefix = if self.server_ip.is_ipv4() { 32 } else { 128 };
+        format!("{}/{}", self.server_ip, prefix)
+    }
+
+    /// Arguments for `sudo` that install the route
+    pub fn setup_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "ip".to_string(),
+            "route".to_string(),
+            "replace".to_string(),
+            self.destination(),
+        ];
+        if let Some(gateway) = self.gateway {
+            args.push("via".to_string());
+            args.push(gateway.to_string());
+        }
+        args.push("dev".to_string());
+        args.push(self.interface.clone());
+        args
+    }
+
+    /// Arguments for `sudo` that remove the route
+    pub fn teardown_args(&self) -> Vec<String> {
+        vec![
+            "ip".to_string(),
+            "route".to_string(),
+            "del".to_string(),
+            self.destination(),
+            "dev".to_string(),
+            self.interface.clone(),
+        ]
+    }
+
+    /// Route `server_ip` through `interface`, using that interface's gateway
+    pub fn install<R: CommandRunner>(
+        runner: &mut R,
+        server_ip: IpAddr,
+        interface: &str,
+    ) -> Result<Self, VpnError> {
+        let lookup = vec![
+            "route".to_string(),
+            "get".to_string(),
+            server_ip.to_string(),
+            "oif".to_string(),
+            interface.to_string(),
+        ];
+        let output = runner
+            .run("ip", &lookup)
+            .map_err(|e| VpnError::NetworkError {
+                reason: format!("No route to {} via {}: {}", server_ip, interface, e),
+            })?;
+
+        let route = Self {
+            server_ip,
+            interface: interface.to_string(),
+            gateway: parse_route_gateway(&output),
+        };
+        runner
+            .run("sudo", &route.setup_args())
+            .map_err(|e| VpnError::NetworkError {
+                reason: format!("Failed to route {} via {}: {}", server_ip, interface, e),
+            })?;
+
+        Ok(route)
+    }
+
+    /// Remove the route again
+    pub fn remove<R: CommandRunner>(&self, runner: &mut R) -> Result<(), VpnError> {
+        runner
+            .run("sudo", &self.teardown_args())
+            .map(|_| ())
+            .map_err(|e| VpnError::NetworkError {
+                reason: format!(
+                    "Failed to remove route for {} via {}: {}",
+                    self.server_ip, self.interface, e
+                ),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records commands and answers `ip route get` with canned output
+    struct StubRunner {
+        route_get_output: &'static str,
+        commands: Vec<String>,
+    }
+
+    impl CommandRunner for StubRunner {
+        fn run(&mut self, program: &str, args: &[String]) -> Result<String, String> {
+            self.commands
+                .push(format!("{} {}", program, args.join(" ")));
+            Ok(if program == "ip" {
+                self.route_get_output.to_string()
+            } else {
+                String::new()
+            })
+        }
+    }
+
+    #[test]
+    fn test_install_and_remove_route_through_gateway() {
+        let mut runner = StubRunner {
+            route_get_output: "203.0.113.7 via 192.168.1.1 dev eth0 src 192.168.1.23 uid 1000\n",
+            commands: Vec::new(),
+        };
+
+        let route =
+            EgressRoute::install(&mut runner, "203.0.113.7".parse().unwrap(), "eth0").unwrap();
+        assert_eq!(route.gateway, Some("192.168.1.1".parse().unwrap()));
+        route.remove(&mut runner).unwrap();
+
+        assert_eq!(
+            runner.commands,
+            vec![
+                "ip route get 203.0.113.7 oif eth0",
+                "sudo ip route replace 203.0.113.7/32 via 192.168.1.1 dev eth0",
+                "sudo ip route del 203.0.113.7/32 dev eth0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_on_link_route_has_no_gateway() {
+        let route = EgressRoute {
+            server_ip: "2001:db8::7".parse().unwrap(),
+            interface: "eth1".to_string(),
+            gateway: parse_route_gateway("2001:db8::7 dev eth1 src 2001:db8::2 metric 100"),
+        };
+
+        assert_eq!(
+            route.setup_args().join(" "),
+            "ip route replace 2001:db8::7/128 dev eth1"
+        );
+    }
+
+    #[test]
+    fn test_interface_exists() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("eth0")).unwrap();
+
+        assert!(interface_exists(dir.path(), "eth0"));
+        assert!(!interface_exists(dir.path(), "wlan9"));
+        assert!(!interface_exists(dir.path(), ""));
+        assert!(!interface_exists(dir.path(), "../eth0"));
+    }
+}
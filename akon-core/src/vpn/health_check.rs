@@ -1,12 +1,22 @@
-//! VPN connectivity health checking via HTTP/HTTPS
+//! VPN connectivity health checking via HTTP/HTTPS, TCP or ICMP
 //!
 //! This module provides HealthChecker for verifying VPN connectivity
-//! through periodic HTTP/HTTPS requests to a configured endpoint.
+//! through periodic probes of a configured endpoint: an HTTP/HTTPS request,
+//! a TCP connect (`tcp://host:port`) or an ICMP echo (`icmp://host`).
+//! `iface://` only checks that the tunnel device still holds its address,
+//! which can also run before every probe to skip it once the device is gone.
 
+use crate::vpn::iface_check::check_tunnel_interface;
+use crate::vpn::state_file::state_file_path;
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::process::Stdio;
 use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
 use tracing::{debug, warn};
-use url::Url;
+use url::{Host, Url};
 
 /// Result of a health check attempt
 #[derive(Debug, Clone)]
@@ -51,12 +61,113 @@ impl HealthCheckResult {
     }
 }
 
-/// Performs HTTP/HTTPS health checks to verify VPN connectivity
+/// IP address family used to reach the health check endpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressFamily {
+    /// Use whatever the resolver returns (default)
+    #[default]
+    Auto,
+    /// Only connect over IPv4
+    V4,
+    /// Only connect over IPv6
+    V6,
+}
+
+impl AddressFamily {
+    /// Local address to bind to, which restricts connections to this family
+    fn local_address(self) -> Option<IpAddr> {
+        match self {
+            Self::Auto => None,
+            Self::V4 => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            Self::V6 => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        }
+    }
+
+    /// Whether `addr` may be used under this family
+    fn allows(self, addr: &SocketAddr) -> bool {
+        match self {
+            Self::Auto => true,
+            Self::V4 => addr.is_ipv4(),
+            Self::V6 => addr.is_ipv6(),
+        }
+    }
+
+    /// `ping` flag restricting it to this family
+    fn ping_flag(self) -> Option<&'static str> {
+        match self {
+            Self::Auto => None,
+            Self::V4 => Some("-4"),
+            Self::V6 => Some("-6"),
+        }
+    }
+}
+
+/// What a health check endpoint asks to be probed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthProbe {
+    /// GET request to an `http://` or `https://` URL
+    Http,
+    /// TCP connect to `tcp://host:port`
+    Tcp { host: String, port: u16 },
+    /// ICMP echo (via `ping`) to `icmp://host`
+    Icmp { host: String },
+    /// Tunnel device still holding its address, for `iface://`
+    Interface,
+}
+
+/// Parse a health check endpoint into the probe it asks for
+pub fn parse_endpoint(endpoint: &str) -> Result<HealthProbe, String> {
+    let url = Url::parse(endpoint).map_err(|e| format!("Failed to parse URL: {}", e))?;
+
+    let host = || match url.host() {
+        Some(Host::Domain(domain)) if !domain.is_empty() => Ok(domain.to_string()),
+        Some(Host::Ipv4(ip)) => Ok(ip.to_string()),
+        Some(Host::Ipv6(ip)) => Ok(ip.to_string()),
+        _ => Err(format!("{}:// endpoint needs a host", url.scheme())),
+    };
+
+    match url.scheme() {
+        "http" | "https" => Ok(HealthProbe::Http),
+        "tcp" => {
+            let host = host()?;
+            let port = url
+                .port()
+                .filter(|port| *port > 0)
+                .ok_or_else(|| "tcp:// endpoint needs a port (tcp://host:port)".to_string())?;
+            Ok(HealthProbe::Tcp { host, port })
+        }
+        "icmp" => Ok(HealthProbe::Icmp { host: host()? }),
+        "iface" => Ok(HealthProbe::Interface),
+        scheme => Err(format!(
+            "Only HTTP/HTTPS schemes are supported, besides tcp://host:port, icmp://host and iface://, got: {}",
+            scheme
+        )),
+    }
+}
+
+/// Options controlling how health check requests are made
+#[derive(Debug, Clone, Default)]
+pub struct HealthCheckOptions {
+    /// Accept invalid TLS certificates (mirrors `insecure_skip_tls_verify`)
+    pub accept_invalid_certs: bool,
+
+    /// Address family to connect over
+    pub address_family: AddressFamily,
+
+    /// State file naming the tunnel device and address to check before
+    /// each probe; a check fails without probing once the device is gone
+    pub tunnel_state_file: Option<PathBuf>,
+}
+
+/// Performs HTTP/HTTPS, TCP or ICMP health checks to verify VPN connectivity
 #[derive(Debug)]
 pub struct HealthChecker {
     client: Client,
     endpoint: String,
+    probe: HealthProbe,
     timeout: Duration,
+    options: HealthCheckOptions,
 }
 
 /// Errors that can occur during health check operations
@@ -73,33 +184,41 @@ impl HealthChecker {
     /// Create a new health checker
     ///
     /// # Arguments
-    /// * `endpoint` - HTTP/HTTPS URL to check (must use http:// or https:// scheme)
+    /// * `endpoint` - HTTP/HTTPS URL to check, or `tcp://host:port` / `icmp://host`
     /// * `timeout` - Maximum duration to wait for a response
     ///
     /// # Returns
     /// * `Ok(HealthChecker)` if the endpoint URL is valid
-    /// * `Err(HealthCheckError)` if the URL is invalid or doesn't use HTTP/HTTPS
-    #[tracing::instrument(skip(timeout), fields(endpoint = %endpoint, timeout_ms = timeout.as_millis()))]
+    /// * `Err(HealthCheckError)` if the URL is invalid or uses another scheme
     pub fn new(endpoint: String, timeout: Duration) -> Result<Self, HealthCheckError> {
-        // Validate endpoint URL
-        let url = Url::parse(&endpoint)
-            .map_err(|e| HealthCheckError::InvalidUrl(format!("Failed to parse URL: {}", e)))?;
-
-        // Ensure scheme is HTTP or HTTPS
-        match url.scheme() {
-            "http" | "https" => {}
-            scheme => {
-                return Err(HealthCheckError::InvalidUrl(format!(
-                    "Only HTTP/HTTPS schemes are supported, got: {}",
-                    scheme
-                )));
-            }
+        Self::with_options(endpoint, timeout, HealthCheckOptions::default())
+    }
+
+    /// Create a new health checker with explicit request options
+    ///
+    /// # Arguments
+    /// * `endpoint` - HTTP/HTTPS URL to check, or `tcp://host:port` / `icmp://host`
+    /// * `timeout` - Maximum duration to wait for a response
+    /// * `options` - TLS and transport options for the requests
+    #[tracing::instrument(skip(timeout, options), fields(endpoint = %endpoint, timeout_ms = timeout.as_millis()))]
+    pub fn with_options(
+        endpoint: String,
+        timeout: Duration,
+        options: HealthCheckOptions,
+    ) -> Result<Self, HealthCheckError> {
+        // Validate endpoint URL and scheme
+        let probe = parse_endpoint(&endpoint).map_err(HealthCheckError::InvalidUrl)?;
+
+        if options.accept_invalid_certs && probe == HealthProbe::Http {
+            warn!(endpoint = %endpoint, "Health check TLS certificate validation disabled");
         }
 
         // Create HTTP client with rustls-tls
         let client = Client::builder()
             .timeout(timeout)
             .use_rustls_tls()
+            .danger_accept_invalid_certs(options.accept_invalid_certs)
+            .local_address(options.address_family.local_address())
             .build()
             .map_err(|e| {
                 HealthCheckError::InvalidUrl(format!("Failed to create HTTP client: {}", e))
@@ -108,23 +227,89 @@ impl HealthChecker {
         Ok(Self {
             client,
             endpoint,
+            probe,
             timeout,
+            options,
         })
     }
 
+    /// Get the options this checker was created with
+    pub fn options(&self) -> &HealthCheckOptions {
+        &self.options
+    }
+
+    /// Get what this checker probes
+    pub fn probe(&self) -> &HealthProbe {
+        &self.probe
+    }
+
+    /// State file whose tunnel interface is checked, if any
+    ///
+    /// `iface://` checks the default profile's state file unless told otherwise.
+    fn interface_state_file(&self) -> Option<PathBuf> {
+        match (&self.options.tunnel_state_file, &self.probe) {
+            (Some(path), _) => Some(path.clone()),
+            (None, HealthProbe::Interface) => Some(state_file_path(None)),
+            (None, _) => None,
+        }
+    }
+
     /// Perform a health check
     ///
-    /// Sends a GET request to the configured endpoint and measures the response time.
+    /// For HTTP/HTTPS endpoints, sends a GET request and measures the response time.
     /// A check is considered successful if:
     /// - The endpoint responds within the timeout
     /// - The response status code is 2xx or 3xx
     ///
+    /// For `tcp://` and `icmp://` endpoints, the check succeeds if a TCP
+    /// connection is accepted, or an echo reply arrives, within the timeout.
+    ///
+    /// With a tunnel state file (and for `iface://`), the check first fails if
+    /// the tunnel device is gone or has lost its address; for `iface://` that
+    /// is the whole check.
+    ///
     /// # Returns
     /// * `HealthCheckResult` containing success status, duration, and any error
     #[tracing::instrument(skip(self), fields(endpoint = %self.endpoint))]
     pub async fn check(&self) -> HealthCheckResult {
         let start = Instant::now();
 
+        if let Some(state_path) = self.interface_state_file() {
+            if let Err(error_msg) = check_tunnel_interface(&state_path) {
+                let duration = start.elapsed();
+                warn!(
+                    endpoint = %self.endpoint,
+                    error = %error_msg,
+                    "Tunnel interface check failed"
+                );
+                return HealthCheckResult::failure(duration, error_msg);
+            }
+        }
+
+        if self.probe != HealthProbe::Http {
+            let probed = self.probe_connectivity().await;
+            let duration = start.elapsed();
+            return match probed {
+                Ok(()) => {
+                    debug!(
+                        endpoint = %self.endpoint,
+                        duration_ms = duration.as_millis(),
+                        "Health check succeeded"
+                    );
+                    HealthCheckResult::success(duration)
+                }
+                Err(error_msg) => {
+                    warn!(
+                        endpoint = %self.endpoint,
+                        error = %error_msg,
+                        duration_ms = duration.as_millis(),
+                        "Health check failed"
+                    );
+                    HealthCheckResult::failure(duration, error_msg)
+                }
+            };
+        }
+
         match self.client.get(&self.endpoint).send().await {
             Ok(response) => {
                 let duration = start.elapsed();
@@ -186,6 +371,10 @@ impl HealthChecker {
     /// * `false` if there's a network-level failure
     #[tracing::instrument(skip(self), fields(endpoint = %self.endpoint))]
     pub async fn is_reachable(&self) -> bool {
+        if self.probe != HealthProbe::Http {
+            return self.probe_connectivity().await.is_ok();
+        }
+
         match self.client.get(&self.endpoint).send().await {
             Ok(_) => {
                 // Any response means the endpoint is reachable
@@ -202,11 +391,80 @@ impl HealthChecker {
             }
         }
     }
+
+    /// Run a TCP or ICMP probe within the timeout
+    async fn probe_connectivity(&self) -> Result<(), String> {
+        let probe = async {
+            match &self.probe {
+                HealthProbe::Tcp { host, port } => self.tcp_connect(host, *port).await,
+                HealthProbe::Icmp { host } => self.ping(host).await,
+                // Already checked by `check`
+                HealthProbe::Http | HealthProbe::Interface => Ok(()),
+            }
+        };
+        tokio::time::timeout(self.timeout, probe)
+            .await
+            .unwrap_or_else(|_| Err(format!("Request timeout after {:?}", self.timeout)))
+    }
+
+    /// Connect to `host:port`, trying each resolved address of the allowed family
+    async fn tcp_connect(&self, host: &str, port: u16) -> Result<(), String> {
+        let family = self.options.address_family;
+        let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+            .filter(|addr| family.allows(addr))
+            .collect();
+        if addresses.is_empty() {
+            return Err(format!("No {:?} address found for {}", family, host));
+        }
+
+        let mut last_error = None;
+        for address in addresses {
+            match TcpStream::connect(address).await {
+                Ok(_) => return Ok(()),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(match last_error {
+            Some(e) => format!("Connection refused or unreachable: {}", e),
+            None => "Connection refused or unreachable".to_string(),
+        })
+    }
+
+    /// Send one ICMP echo request with the system `ping`
+    ///
+    /// `ping` is setuid or capability-enabled, so no raw socket privileges
+    /// are needed here.
+    async fn ping(&self, host: &str) -> Result<(), String> {
+        let wait_secs = self.timeout.as_secs().max(1).to_string();
+        let mut command = tokio::process::Command::new("ping");
+        command.args(["-c", "1", "-W", &wait_secs]);
+        if let Some(flag) = self.options.address_family.ping_flag() {
+            command.arg(flag);
+        }
+        let status = command
+            .arg(host)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .status()
+            .await
+            .map_err(|e| format!("Failed to run ping: {}", e))?;
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("No echo reply from {}", host))
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::vpn::state_file::VpnStateFile;
 
     #[test]
     fn test_health_checker_new_valid_http() {
@@ -239,6 +497,35 @@ mod tests {
             .contains("Only HTTP/HTTPS schemes"));
     }
 
+    #[tokio::test]
+    async fn test_interface_only_check_sends_no_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let checker = HealthChecker::with_options(
+            "iface://".to_string(),
+            Duration::from_secs(5),
+            HealthCheckOptions {
+                tunnel_state_file: Some(dir.path().join("state.json")),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        // No state file yet, so there is no device to miss
+        assert!(checker.check().await.is_success());
+
+        VpnStateFile::connected(
+            "work",
+            "10.255.255.254".parse().unwrap(),
+            Some("akon-test-absent0"),
+            None,
+            None,
+        )
+        .store_to(&dir.path().join("state.json"))
+        .unwrap();
+        let result = checker.check().await;
+        assert!(!result.is_success());
+        assert_eq!(result.error(), Some("Interface akon-test-absent0 is gone"));
+    }
+
     #[test]
     fn test_health_checker_new_invalid_url() {
         let result = HealthChecker::new("not a url".to_string(), Duration::from_secs(5));
@@ -246,6 +533,98 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("parse URL"));
     }
 
+    #[test]
+    fn test_health_checker_validates_certs_by_default() {
+        let checker = HealthChecker::new(
+            "https://example.com/health".to_string(),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert!(!checker.options().accept_invalid_certs);
+    }
+
+    #[test]
+    fn test_health_checker_accepts_invalid_certs_when_requested() {
+        let checker = HealthChecker::with_options(
+            "https://example.com/health".to_string(),
+            Duration::from_secs(5),
+            HealthCheckOptions {
+                accept_invalid_certs: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(checker.options().accept_invalid_certs);
+    }
+
+    #[test]
+    fn test_parse_endpoint_probes() {
+        assert_eq!(
+            parse_endpoint("https://example.com/"),
+            Ok(HealthProbe::Http)
+        );
+        assert_eq!(
+            parse_endpoint("tcp://10.1.2.3:22"),
+            Ok(HealthProbe::Tcp {
+                host: "10.1.2.3".to_string(),
+                port: 22,
+            })
+        );
+        assert_eq!(
+            parse_endpoint("tcp://[fd00::1]:443"),
+            Ok(HealthProbe::Tcp {
+                host: "fd00::1".to_string(),
+                port: 443,
+            })
+        );
+        assert_eq!(
+            parse_endpoint("icmp://intranet.example.com"),
+            Ok(HealthProbe::Icmp {
+                host: "intranet.example.com".to_string(),
+            })
+        );
+        assert!(parse_endpoint("tcp://10.1.2.3")
+            .unwrap_err()
+            .contains("port"));
+        assert!(parse_endpoint("icmp://").unwrap_err().contains("host"));
+        assert_eq!(parse_endpoint("iface://"), Ok(HealthProbe::Interface));
+    }
+
+    #[tokio::test]
+    async fn test_tcp_check_succeeds_against_listening_port() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let checker =
+            HealthChecker::new(format!("tcp://127.0.0.1:{}", port), Duration::from_secs(2))
+                .unwrap();
+
+        let result = checker.check().await;
+        assert!(result.is_success(), "{:?}", result.error());
+        assert!(checker.is_reachable().await);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_check_fails_against_closed_port() {
+        // Bind then release a port so nothing is listening on it
+        let port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+
+        let checker =
+            HealthChecker::new(format!("tcp://127.0.0.1:{}", port), Duration::from_secs(2))
+                .unwrap();
+
+        let result = checker.check().await;
+        assert!(!result.is_success());
+        assert!(result
+            .error()
+            .unwrap()
+            .contains("Connection refused or unreachable"));
+        assert!(!checker.is_reachable().await);
+    }
+
     #[test]
     fn test_health_check_result_success() {
         let result = HealthCheckResult::success(Duration::from_millis(123));
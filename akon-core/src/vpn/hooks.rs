@@ -0,0 +1,160 @@
+//! User-defined commands run on reconnection transitions
+//!
+//! Hooks are shell commands from the reconnection policy. They receive the
+//! event, attempt number and server through environment variables. A failing
+//! hook is logged and never interrupts reconnection.
+
+use crate::vpn::reconnection::ReconnectionPolicy;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+/// Maximum time a hook may run before it is killed
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reconnection transition that triggers a hook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    /// A reconnection attempt is starting
+    ReconnectAttempt,
+    /// A reconnection attempt succeeded
+    ReconnectSuccess,
+}
+
+impl HookEvent {
+    /// Name exported to hooks as `AKON_HOOK_EVENT`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ReconnectAttempt => "reconnect_attempt",
+            Self::ReconnectSuccess => "reconnect_success",
+        }
+    }
+}
+
+/// Hook commands configured for the reconnection daemon
+#[derive(Debug, Clone, Default)]
+pub struct ReconnectionHooks {
+    on_attempt: Option<String>,
+    on_success: Option<String>,
+}
+
+impl ReconnectionHooks {
+    /// Build hooks from the commands in a reconnection policy
+    pub fn from_policy(policy: &ReconnectionPolicy) -> Self {
+        Self {
+            on_attempt: policy.on_reconnect_attempt.clone(),
+            on_success: policy.on_reconnect_success.clone(),
+        }
+    }
+
+    /// Run the `on_reconnect_attempt` hook, if configured
+    pub async fn attempt_started(&self, attempt: u32, server: &str) {
+        if let Some(command) = &self.on_attempt {
+            run_hook(command, HookEvent::ReconnectAttempt, attempt, server).await;
+        }
+    }
+
+    /// Run the `on_reconnect_success` hook, if configured
+    pub async fn succeeded(&self, attempt: u32, server: &str) {
+        if let Some(command) = &self.on_success {
+            run_hook(command, HookEvent::ReconnectSuccess, attempt, server).await;
+        }
+    }
+}
+
+/// Run a hook command through `sh -c`, logging any failure
+///
+/// Returns `true` if the command ran and exited successfully.
+pub async fn run_hook(command: &str, event: HookEvent, attempt: u32, server: &str) -> bool {
+    info!(event = event.as_str(), attempt, "Running reconnection hook");
+
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("AKON_HOOK_EVENT", event.as_str())
+        .env("AKON_RECONNECT_ATTEMPT", attempt.to_string())
+        .env("AKON_SERVER", server)
+        .kill_on_drop(true)
+        .spawn();
+
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            warn!(event = event.as_str(), "Failed to start hook: {}", e);
+            return false;
+        }
+    };
+
+    match tokio::time::timeout(HOOK_TIMEOUT, child.wait()).await {
+        Ok(Ok(status)) if status.success() => true,
+        Ok(Ok(status)) => {
+            warn!(event = event.as_str(), "Hook exited with {}", status);
+            false
+        }
+        Ok(Err(e)) => {
+            warn!(event = event.as_str(), "Failed to wait for hook: {}", e);
+            false
+        }
+        Err(_) => {
+            warn!(
+                event = event.as_str(),
+                "Hook did not finish within {:?}, killing it", HOOK_TIMEOUT
+            );
+            let _ = child.kill().await;
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker_hooks(dir: &std::path::Path) -> ReconnectionHooks {
+        ReconnectionHooks {
+            on_attempt: Some(format!(
+                "echo \"$AKON_HOOK_EVENT $AKON_RECONNECT_ATTEMPT $AKON_SERVER\" >> {}/attempt",
+                dir.display()
+            )),
+            on_success: Some(format!(
+                "echo \"$AKON_HOOK_EVENT $AKON_RECONNECT_ATTEMPT $AKON_SERVER\" >> {}/success",
+                dir.display()
+            )),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_attempt_hook_fires_only_on_attempt() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = marker_hooks(dir.path());
+
+        hooks.attempt_started(2, "vpn.example.com").await;
+
+        let attempt = std::fs::read_to_string(dir.path().join("attempt")).unwrap();
+        assert_eq!(attempt.trim(), "reconnect_attempt 2 vpn.example.com");
+        assert!(!dir.path().join("success").exists());
+    }
+
+    #[tokio::test]
+    async fn test_success_hook_fires_only_on_success() {
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = marker_hooks(dir.path());
+
+        hooks.succeeded(3, "vpn.example.com").await;
+
+        let success = std::fs::read_to_string(dir.path().join("success")).unwrap();
+        assert_eq!(success.trim(), "reconnect_success 3 vpn.example.com");
+        assert!(!dir.path().join("attempt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_failing_hook_is_reported_not_propagated() {
+        assert!(!run_hook("exit 7", HookEvent::ReconnectAttempt, 1, "vpn.example.com").await);
+        assert!(run_hook("true", HookEvent::ReconnectAttempt, 1, "vpn.example.com").await);
+
+        // Unconfigured hooks are a no-op
+        ReconnectionHooks::default()
+            .attempt_started(1, "vpn.example.com")
+            .await;
+    }
+}
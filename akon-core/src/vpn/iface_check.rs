@@ -0,0 +1,208 @@
+//! Liveness check of the tunnel interface
+//!
+//! A cheaper signal than a probe across the tunnel: when openconnect's tun
+//! device is gone, or no longer holds the address the gateway assigned, the
+//! connection is dead whatever an HTTP probe would say. The device and address
+//! come from the state file; the kernel's address list comes from
+//! `getifaddrs`, so the check runs no external command.
+
+use crate::vpn::state_file::VpnStateFile;
+use nix::ifaddrs::getifaddrs;
+use std::net::{IpAddr, SocketAddrV4, SocketAddrV6};
+use std::path::Path;
+
+/// One entry of the kernel's interface address list
+///
+/// A device without any address still has an entry, with `ip` unset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceAddress {
+    /// Device name, e.g. `tun0`
+    pub name: String,
+    /// IPv4 or IPv6 address, if the entry carries one
+    pub ip: Option<IpAddr>,
+}
+
+/// State of the tunnel interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterfaceCheck {
+    /// The device exists and holds the address
+    Bound,
+    /// The device exists without the address
+    MissingIp,
+    /// There is no such device
+    Absent,
+}
+
+impl InterfaceCheck {
+    /// Why the connection is considered down, unless the address is bound
+    pub fn problem(&self, device: &str, ip: IpAddr) -> Option<String> {
+        match self {
+            Self::Bound => None,
+            Self::MissingIp => Some(format!("Interface {} no longer holds {}", device, ip)),
+            Self::Absent => Some(format!("Interface {} is gone", device)),
+        }
+    }
+}
+
+/// Check `device` for `ip` in an interface address list
+pub fn check_interface_ip(
+    listing: &[InterfaceAddress],
+    device: &str,
+    ip: IpAddr,
+) -> InterfaceCheck {
+    let mut result = InterfaceCheck::Absent;
+    for entry in listing.iter().filter(|entry| entry.name == device) {
+        if entry.ip == Some(ip) {
+            return InterfaceCheck::Bound;
+        }
+        result = InterfaceCheck::MissingIp;
+    }
+    result
+}
+
+/// Device holding `ip` in an interface address list
+pub fn device_with_ip(listing: &[InterfaceAddress], ip: IpAddr) -> Option<String> {
+    listing
+        .iter()
+        .find(|entry| entry.ip == Some(ip))
+        .map(|entry| entry.name.clone())
+}
+
+/// Device the kernel has `ip` on, for output that doesn't name the tunnel
+pub fn find_tunnel_device(ip: IpAddr) -> Option<String> {
+    device_with_ip(&interface_addresses().ok()?, ip)
+}
+
+/// List the kernel's interface addresses
+pub fn interface_addresses() -> Result<Vec<InterfaceAddress>, nix::Error> {
+    Ok(getifaddrs()?
+        .map(|entry| {
+            let ip = entry.address.and_then(|address| {
+                if let Some(sin) = address.as_sockaddr_in() {
+                    Some(IpAddr::V4(*SocketAddrV4::from(*sin).ip()))
+                } else {
+                    address
+                        .as_sockaddr_in6()
+                        .map(|sin6| IpAddr::V6(*SocketAddrV6::from(*sin6).ip()))
+                }
+            });
+            InterfaceAddress {
+                name: entry.interface_name,
+                ip,
+            }
+        })
+        .collect())
+}
+
+/// Check that the tunnel recorded in the state file at `state_path` is up
+///
+/// Passes when there is nothing to check: no state file, a state without a
+/// device or address (e.g. while reconnecting), or no address list to read.
+pub fn check_tunnel_interface(state_path: &Path) -> Result<(), String> {
+    let Ok(state) = VpnStateFile::load_from(state_path) else {
+        return Ok(());
+    };
+    let (Some(device), Some(ip)) = (state.device, state.ip) else {
+        return Ok(());
+    };
+    let Ok(ip) = ip.parse::<IpAddr>() else {
+        return Ok(());
+    };
+
+    let Ok(listing) = interface_addresses() else {
+        return Ok(());
+    };
+    match check_interface_ip(&listing, &device, ip).problem(&device, ip) {
+        Some(problem) => Err(problem),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, ip: Option<&str>) -> InterfaceAddress {
+        InterfaceAddress {
+            name: name.to_string(),
+            ip: ip.map(|ip| ip.parse().unwrap()),
+        }
+    }
+
+    fn listing() -> Vec<InterfaceAddress> {
+        vec![
+            entry("lo", None),
+            entry("lo", Some("127.0.0.1")),
+            entry("eth0", None),
+            entry("eth0", Some("192.168.1.23")),
+            entry("tun0", Some("10.0.0.5")),
+            entry("tun0", Some("fd00::5")),
+            entry("tun1", None),
+        ]
+    }
+
+    #[test]
+    fn test_interface_present_with_ip() {
+        let listing = listing();
+        assert_eq!(
+            check_interface_ip(&listing, "tun0", "10.0.0.5".parse().unwrap()),
+            InterfaceCheck::Bound
+        );
+        assert_eq!(
+            check_interface_ip(&listing, "tun0", "fd00::5".parse().unwrap()),
+            InterfaceCheck::Bound
+        );
+    }
+
+    #[test]
+    fn test_interface_present_without_ip() {
+        let listing = listing();
+        let ip: IpAddr = "10.0.0.5".parse().unwrap();
+        assert_eq!(
+            check_interface_ip(&listing, "tun1", ip),
+            InterfaceCheck::MissingIp
+        );
+        // Another device's address doesn't count
+        assert_eq!(
+            check_interface_ip(&listing, "eth0", ip),
+            InterfaceCheck::MissingIp
+        );
+        assert_eq!(
+            InterfaceCheck::MissingIp.problem("tun1", ip).unwrap(),
+            "Interface tun1 no longer holds 10.0.0.5"
+        );
+    }
+
+    #[test]
+    fn test_interface_absent() {
+        let ip: IpAddr = "10.0.0.5".parse().unwrap();
+        assert_eq!(
+            check_interface_ip(&listing(), "tun9", ip),
+            InterfaceCheck::Absent
+        );
+        assert_eq!(check_interface_ip(&[], "tun0", ip), InterfaceCheck::Absent);
+        assert!(InterfaceCheck::Bound.problem("tun0", ip).is_none());
+    }
+
+    #[test]
+    fn test_device_found_by_its_address() {
+        let listing = listing();
+        assert_eq!(
+            device_with_ip(&listing, "fd00::5".parse().unwrap()).as_deref(),
+            Some("tun0")
+        );
+        assert_eq!(device_with_ip(&listing, "10.0.0.9".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn test_tunnel_check_passes_without_a_recorded_device() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        assert!(check_tunnel_interface(&path).is_ok());
+
+        VpnStateFile::reconnecting("work", 1, 3, None, Some("10.0.0.5".to_string()))
+            .store_to(&path)
+            .unwrap();
+        assert!(check_tunnel_interface(&path).is_ok());
+    }
+}
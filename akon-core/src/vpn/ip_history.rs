@@ -0,0 +1,178 @@
+//! History of IP addresses assigned by the VPN gateway
+//!
+//! Keeps a small persisted record of recently assigned tunnel IPs so that an
+//! address from an unexpected pool (often a realm or config problem) can be
+//! pointed out to the user.
+
+use crate::config::toml_config::get_config_dir;
+use crate::error::{AkonError, ConfigError};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Maximum number of entries kept in the history
+const HISTORY_LIMIT: usize = 20;
+
+/// Minimum number of entries before an IP can be flagged as unusual
+const MIN_ENTRIES_FOR_DETECTION: usize = 3;
+
+/// A single assigned IP address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpHistoryEntry {
+    /// Address assigned to the tunnel device
+    pub ip: IpAddr,
+    /// When the address was assigned (Unix timestamp)
+    pub seen_at: u64,
+}
+
+/// Recently assigned IP addresses, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpHistory {
+    pub entries: Vec<IpHistoryEntry>,
+}
+
+impl IpHistory {
+    /// Load the history from a file, returning an empty history if it doesn't exist
+    pub fn load(path: &Path) -> Result<Self, AkonError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(AkonError::Config(ConfigError::IoError {
+                    message: format!("Failed to read IP history: {}", e),
+                }))
+            }
+        };
+
+        serde_json::from_str(&contents).map_err(|e| {
+            AkonError::Config(ConfigError::ValidationError {
+                message: format!("Failed to parse IP history: {}", e),
+            })
+        })
+    }
+
+    /// Save the history to a file
+    pub fn save(&self, path: &Path) -> Result<(), AkonError> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| {
+            AkonError::Config(ConfigError::ValidationError {
+                message: format!("Failed to serialize IP history: {}", e),
+            })
+        })?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                AkonError::Config(ConfigError::IoError {
+                    message: format!("Failed to create config directory: {}", e),
+                })
+            })?;
+        }
+
+        std::fs::write(path, contents).map_err(|e| {
+            AkonError::Config(ConfigError::IoError {
+                message: format!("Failed to write IP history: {}", e),
+            })
+        })
+    }
+
+    /// Record a newly assigned IP, dropping the oldest entries past the limit
+    pub fn record(&mut self, ip: IpAddr) {
+        let seen_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.entries.push(IpHistoryEntry { ip, seen_at });
+
+        if self.entries.len() > HISTORY_LIMIT {
+            let excess = self.entries.len() - HISTORY_LIMIT;
+            self.entries.drain(..excess);
+        }
+    }
+
+    /// Check whether an IP falls outside every range seen so far
+    ///
+    /// Ranges are /24 networks for IPv4 and /64 networks for IPv6. Returns
+    /// `false` until enough history has been collected to judge.
+    pub fn is_unusual(&self, ip: IpAddr) -> bool {
+        if self.entries.len() < MIN_ENTRIES_FOR_DETECTION {
+            return false;
+        }
+
+        !self.entries.iter().any(|entry| same_network(entry.ip, ip))
+    }
+}
+
+/// Check whether two addresses share a /24 (IPv4) or /64 (IPv6) network
+fn same_network(a: IpAddr, b: IpAddr) -> bool {
+    match (a, b) {
+        (IpAddr::V4(a), IpAddr::V4(b)) => a.octets()[..3] == b.octets()[..3],
+        (IpAddr::V6(a), IpAddr::V6(b)) => a.segments()[..4] == b.segments()[..4],
+        _ => false,
+    }
+}
+
+/// Path of the IP history file for a VPN server
+pub fn ip_history_path(server: &str) -> Result<PathBuf, AkonError> {
+    Ok(get_config_dir()?
+        .join("ip_history")
+        .join(format!("{}.json", server)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history_of(ips: &[&str]) -> IpHistory {
+        let mut history = IpHistory::default();
+        for ip in ips {
+            history.record(ip.parse().unwrap());
+        }
+        history
+    }
+
+    #[test]
+    fn test_ip_from_different_subnet_is_unusual() {
+        let history = history_of(&["10.8.1.12", "10.8.1.40", "10.8.1.7", "10.8.1.200"]);
+
+        assert!(history.is_unusual("10.8.2.15".parse().unwrap()));
+        assert!(!history.is_unusual("10.8.1.99".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_short_history_never_flags() {
+        let history = history_of(&["10.8.1.12", "10.8.1.40"]);
+        assert!(!history.is_unusual("192.168.50.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_ipv6_uses_64_prefix() {
+        let history = history_of(&["fd00:1:2:3::10", "fd00:1:2:3::20", "fd00:1:2:3::30"]);
+
+        assert!(!history.is_unusual("fd00:1:2:3::99".parse().unwrap()));
+        assert!(history.is_unusual("fd00:1:2:4::10".parse().unwrap()));
+        assert!(history.is_unusual("10.8.1.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut history = IpHistory::default();
+        for i in 0..(HISTORY_LIMIT + 5) {
+            history.record(format!("10.8.1.{}", i).parse().unwrap());
+        }
+
+        assert_eq!(history.entries.len(), HISTORY_LIMIT);
+        assert_eq!(history.entries[0].ip, "10.8.1.5".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn test_history_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("vpn.example.com.json");
+
+        assert_eq!(IpHistory::load(&path).unwrap(), IpHistory::default());
+
+        let history = history_of(&["10.8.1.12", "fd00::1"]);
+        history.save(&path).unwrap();
+        assert_eq!(IpHistory::load(&path).unwrap(), history);
+    }
+}
@@ -0,0 +1,271 @@
+//! Reaching the VPN gateway through an SSH jump host
+//!
+//! When the gateway is only reachable from a bastion, akon opens an SSH
+//! local forward (`ssh -L 127.0.0.1:<local_port>:<server>:443 <jump_host>`)
+//! before connecting and points openconnect at the local end, using
+//! `--resolve` so TLS still sees the server's own name. The ssh master runs
+//! in the background with a control socket, which is how the forward is
+//! closed again (`ssh -O exit`) after openconnect has stopped.
+
+use crate::error::VpnError;
+use crate::vpn::egress::CommandRunner;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Local port forwarded to the gateway when `jump_local_port` isn't set
+pub const DEFAULT_JUMP_LOCAL_PORT: u16 = 10443;
+
+/// Port the gateway is reached on through the forward
+pub const GATEWAY_PORT: u16 = 443;
+
+/// SSH destination of a `jump_host` setting: `[user@]host[:port]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JumpHost {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl JumpHost {
+    /// Destination argument for ssh: `user@host` or `host`
+    fn destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{}@{}", user, self.host),
+            None => self.host.clone(),
+        }
+    }
+}
+
+/// Parse a `jump_host` setting
+pub fn parse_jump_host(value: &str) -> Result<JumpHost, String> {
+    let invalid = |reason: &str| format!("Invalid jump host '{}': {}", value, reason);
+
+    let (user, rest) = match value.split_once('@') {
+        Some((user, rest)) => (Some(user), rest),
+        None => (None, value),
+    };
+    let (host, port) = match rest.rsplit_once(':') {
+        Some((host, port)) => {
+            let port = port
+                .parse::<u16>()
+                .ok()
+                .filter(|port| *port > 0)
+                .ok_or_else(|| invalid("port must be between 1 and 65535"))?;
+            (host, Some(port))
+        }
+        None => (rest, None),
+    };
+
+    if let Some(user) = user {
+        // ssh reads a leading '-' as an option
+        if user.is_empty()
+            || user.starts_with('-')
+            || !user
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        {
+            return Err(invalid("invalid user name"));
+        }
+    }
+    if host.is_empty()
+        || host.starts_with('-')
+        || !host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+    {
+        return Err(invalid("expected [user@]host[:port]"));
+    }
+
+    Ok(JumpHost {
+        user: user.map(str::to_string),
+        host: host.to_string(),
+        port,
+    })
+}
+
+/// SSH local forward to the gateway through a jump host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JumpForward {
+    /// The `jump_host` setting
+    pub jump_host: String,
+    /// Local port forwarded to the gateway
+    pub local_port: u16,
+    /// VPN server the forward leads to
+    pub server: String,
+    /// Control socket of the background ssh master
+    pub control_socket: PathBuf,
+}
+
+impl JumpForward {
+    fn jump(&self) -> Result<JumpHost, VpnError> {
+        parse_jump_host(&self.jump_host).map_err(|reason| VpnError::NetworkError { reason })
+    }
+
+    /// Arguments for `ssh` that open the forward and go to the background
+    ///
+    /// `ExitOnForwardFailure` makes ssh fail instead of backgrounding when
+    /// the local port can't be bound.
+    pub fn setup_args(&self) -> Result<Vec<String>, VpnError> {
+        let jump = self.jump()?;
+        let mut args = vec![
+            "-f".to_string(),
+            "-N".to_string(),
+            "-M".to_string(),
+            "-S".to_string(),
+            self.control_socket.display().to_string(),
+            "-o".to_string(),
+            "ExitOnForwardFailure=yes".to_string(),
+            "-L".to_string(),
+            format!(
+                "127.0.0.1:{}:{}:{}",
+                self.local_port, self.server, GATEWAY_PORT
+            ),
+        ];
+        if let Some(port) = jump.port {
+            args.push("-p".to_string());
+            args.push(port.to_string());
+        }
+        args.push(jump.destination());
+        Ok(args)
+    }
+
+    /// Arguments for `ssh` that stop the background master and its forward
+    pub fn teardown_args(&self) -> Result<Vec<String>, VpnError> {
+        Ok(vec![
+            "-S".to_string(),
+            self.control_socket.display().to_string(),
+            "-O".to_string(),
+            "exit".to_string(),
+            self.jump()?.destination(),
+        ])
+    }
+
+    /// Open the forward, returning once ssh has it listening
+    pub fn open<R: CommandRunner>(
+        runner: &mut R,
+        jump_host: &str,
+        local_port: u16,
+        server: &str,
+        control_socket: PathBuf,
+    ) -> Result<Self, VpnError> {
+        let forward = Self {
+            jump_host: jump_host.to_string(),
+            local_port,
+            server: server.to_string(),
+            control_socket,
+        };
+        runner
+            .run("ssh", &forward.setup_args()?)
+            .map_err(|e| VpnError::NetworkError {
+                reason: format!("Failed to open SSH forward through {}: {}", jump_host, e),
+            })?;
+        Ok(forward)
+    }
+
+    /// Close the forward
+    ///
+    /// Stop openconnect first: its connection runs through the forward.
+    pub fn close<R: CommandRunner>(&self, runner: &mut R) -> Result<(), VpnError> {
+        runner
+            .run("ssh", &self.teardown_args()?)
+            .map(|_| ())
+            .map_err(|e| VpnError::NetworkError {
+                reason: format!(
+                    "Failed to close SSH forward through {}: {}",
+                    self.jump_host, e
+                ),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records the commands it is asked to run
+    #[derive(Default)]
+    struct RecordingRunner {
+        commands: Vec<String>,
+    }
+
+    impl CommandRunner for RecordingRunner {
+        fn run(&mut self, program: &str, args: &[String]) -> Result<String, String> {
+            self.commands
+                .push(format!("{} {}", program, args.join(" ")));
+            Ok(String::new())
+        }
+    }
+
+    #[test]
+    fn test_parse_jump_host() {
+        assert_eq!(
+            parse_jump_host("alice@bastion.example.com:2222"),
+            Ok(JumpHost {
+                user: Some("alice".to_string()),
+                host: "bastion.example.com".to_string(),
+                port: Some(2222),
+            })
+        );
+        assert_eq!(
+            parse_jump_host("bastion"),
+            Ok(JumpHost {
+                user: None,
+                host: "bastion".to_string(),
+                port: None,
+            })
+        );
+        assert!(parse_jump_host("").is_err());
+        assert!(parse_jump_host("bastion:0").is_err());
+        assert!(parse_jump_host("bastion:70000").is_err());
+        assert!(parse_jump_host("-oProxyCommand=sh").is_err());
+        assert!(parse_jump_host("@bastion").is_err());
+        assert!(parse_jump_host("bad host").is_err());
+    }
+
+    #[test]
+    fn test_forward_is_opened_then_closed_through_control_socket() {
+        let mut runner = RecordingRunner::default();
+
+        let forward = JumpForward::open(
+            &mut runner,
+            "alice@bastion.example.com:2222",
+            10443,
+            "vpn.example.com",
+            PathBuf::from("/tmp/akon-jump.sock"),
+        )
+        .unwrap();
+        forward.close(&mut runner).unwrap();
+
+        assert_eq!(
+            runner.commands,
+            vec![
+                "ssh -f -N -M -S /tmp/akon-jump.sock -o ExitOnForwardFailure=yes \
+                 -L 127.0.0.1:10443:vpn.example.com:443 -p 2222 alice@bastion.example.com",
+                "ssh -S /tmp/akon-jump.sock -O exit alice@bastion.example.com",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failed_forward_is_reported() {
+        struct FailingRunner;
+        impl CommandRunner for FailingRunner {
+            fn run(&mut self, _program: &str, _args: &[String]) -> Result<String, String> {
+                Err("Permission denied (publickey)".to_string())
+            }
+        }
+
+        let error = JumpForward::open(
+            &mut FailingRunner,
+            "bastion",
+            10443,
+            "vpn.example.com",
+            PathBuf::from("/tmp/akon-jump.sock"),
+        )
+        .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("Failed to open SSH forward through bastion: Permission denied"));
+    }
+}
@@ -0,0 +1,349 @@
+//! Blocking traffic outside the tunnel
+//!
+//! With `kill_switch` enabled, akon installs an nftables table once the
+//! tunnel is up whose output chain drops everything except loopback, the
+//! tunnel device, DNS to the system resolvers and the VPN server itself (so
+//! reconnections can reach it). The table outlives openconnect: when the
+//! connection drops, traffic stays blocked instead of leaking over the default
+//! route, until a reconnection updates the allowed device or `vpn off` removes
+//! the table.
+
+use crate::error::VpnError;
+use crate::vpn::egress::CommandRunner;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::Path;
+
+/// nftables table holding the kill switch rules
+pub const KILL_SWITCH_TABLE: &str = "akon_kill_switch";
+
+/// Output chain of [`KILL_SWITCH_TABLE`]
+const CHAIN: &str = "output";
+
+/// Resolver configuration naming the system's nameservers
+pub const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// Upstream nameservers of systemd-resolved, whose stub listens on loopback
+pub const RESOLVED_UPSTREAM_CONF: &str = "/run/systemd/resolve/resolv.conf";
+
+/// Firewall rules allowing traffic only through the tunnel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KillSwitch {
+    /// Tunnel device traffic is allowed through
+    pub device: String,
+    /// Every address of the VPN server, reachable outside the tunnel for
+    /// reconnections
+    pub server_ips: Vec<IpAddr>,
+    /// Resolvers DNS queries may go to outside the tunnel
+    pub nameservers: Vec<IpAddr>,
+}
+
+/// Nameserver addresses listed in resolv.conf `content`
+///
+/// A zone suffix (`fe80::1%eth0`) is dropped, as nft matches addresses only.
+pub fn parse_nameservers(content: &str) -> Vec<IpAddr> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("nameserver") => tokens.next(),
+                _ => None,
+            }
+        })
+        .filter_map(|address| address.split('%').next()?.parse().ok())
+        .collect()
+}
+
+/// Nameservers the system resolves through
+///
+/// When resolv.conf only points at a local stub (systemd-resolved), the
+/// stub's upstream servers are the ones queried outside the tunnel.
+pub fn system_nameservers() -> Vec<IpAddr> {
+    let read = |path: &str| {
+        std::fs::read_to_string(Path::new(path))
+            .map(|content| parse_nameservers(&content))
+            .unwrap_or_default()
+    };
+    let nameservers = read(RESOLV_CONF);
+    if nameservers.iter().all(|ip| ip.is_loopback()) {
+        let upstream = read(RESOLVED_UPSTREAM_CONF);
+        if !upstream.is_empty() {
+            return upstream;
+        }
+    }
+    nameservers
+}
+
+/// nft address match keyword for `ip`
+fn family(ip: &IpAddr) -> &'static str {
+    if ip.is_ipv4() {
+        "ip"
+    } else {
+        "ip6"
+    }
+}
+
+/// Arguments for `sudo` that run an `nft` command on the kill switch table
+fn nft(command: &[&str]) -> Vec<String> {
+    let mut args = vec!["nft".to_string()];
+    args.extend(command.iter().map(|arg| arg.to_string()));
+    args
+}
+
+/// Arguments for `sudo` that append an accept rule to the output chain
+fn accept_rule(matches: &[&str]) -> Vec<String> {
+    let mut args = nft(&["add", "rule", "inet", KILL_SWITCH_TABLE, CHAIN]);
+    args.extend(matches.iter().map(|arg| arg.to_string()));
+    args.push("accept".to_string());
+    args
+}
+
+/// Check a device name before it goes into an nft rule
+fn validate_device(device: &str) -> Result<(), VpnError> {
+    let valid = !device.is_empty()
+        && device.len() < 16
+        && device
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(VpnError::NetworkError {
+            reason: format!("Invalid tunnel device name for kill switch: {:?}", device),
+        })
+    }
+}
+
+impl KillSwitch {
+    /// `sudo` argument lists that install (or update) the rules, in order
+    ///
+    /// The chain's drop policy is in place before its rules are flushed and
+    /// re-added, so updating the device never opens a gap.
+    pub fn setup_commands(&self) -> Result<Vec<Vec<String>>, VpnError> {
+        validate_device(&self.device)?;
+
+        let mut commands = vec![
+            nft(&["add", "table", "inet", KILL_SWITCH_TABLE]),
+            nft(&[
+                "add",
+                "chain",
+                "inet",
+                KILL_SWITCH_TABLE,
+                CHAIN,
+                "{ type filter hook output priority 0 ; policy drop ; }",
+            ]),
+            nft(&["flush", "chain", "inet", KILL_SWITCH_TABLE, CHAIN]),
+            accept_rule(&["oifname", "lo"]),
+            accept_rule(&["oifname", &self.device]),
+        ];
+        for server_ip in &self.server_ips {
+            commands.push(accept_rule(&[
+                family(server_ip),
+                "daddr",
+                &server_ip.to_string(),
+            ]));
+        }
+        // openconnect resolves the server again when reconnecting
+        for nameserver in &self.nameservers {
+            let address = nameserver.to_string();
+            for protocol in ["udp", "tcp"] {
+                commands.push(accept_rule(&[
+                    family(nameserver),
+                    "daddr",
+                    &address,
+                    protocol,
+                    "dport",
+                    "53",
+                ]));
+            }
+        }
+        Ok(commands)
+    }
+
+    /// `sudo` argument lists that remove the rules
+    pub fn teardown_commands() -> Vec<Vec<String>> {
+        vec![nft(&["delete", "table", "inet", KILL_SWITCH_TABLE])]
+    }
+
+    /// Block traffic outside `device`, replacing any earlier kill switch rules
+    pub fn install<R: CommandRunner>(
+        runner: &mut R,
+        device: &str,
+        server_ips: Vec<IpAddr>,
+        nameservers: Vec<IpAddr>,
+    ) -> Result<Self, VpnError> {
+        let kill_switch = Self {
+            device: device.to_string(),
+            server_ips,
+            nameservers,
+        };
+        for args in kill_switch.setup_commands()? {
+            runner
+                .run("sudo", &args)
+                .map_err(|e| VpnError::NetworkError {
+                    reason: format!("Failed to install kill switch: {}", e),
+                })?;
+        }
+        Ok(kill_switch)
+    }
+
+    /// Remove the rules, unblocking traffic outside the tunnel
+    ///
+    /// Only for an explicit disconnect, once openconnect has stopped.
+    pub fn remove<R: CommandRunner>(runner: &mut R) -> Result<(), VpnError> {
+        for args in Self::teardown_commands() {
+            runner
+                .run("sudo", &args)
+                .map_err(|e| VpnError::NetworkError {
+                    reason: format!("Failed to remove kill switch: {}", e),
+                })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records commands, failing those that contain `fail_on`
+    #[derive(Default)]
+    struct RecordingRunner {
+        commands: Vec<String>,
+        fail_on: Option<&'static str>,
+    }
+
+    impl CommandRunner for RecordingRunner {
+        fn run(&mut self, program: &str, args: &[String]) -> Result<String, String> {
+            let command = format!("{} {}", program, args.join(" "));
+            self.commands.push(command.clone());
+            match self.fail_on {
+                Some(pattern) if command.contains(pattern) => Err("Operation not permitted".into()),
+                _ => Ok(String::new()),
+            }
+        }
+    }
+
+    fn ips(addresses: &[&str]) -> Vec<IpAddr> {
+        addresses.iter().map(|ip| ip.parse().unwrap()).collect()
+    }
+
+    fn kill_switch(device: &str, server_ips: &[&str]) -> KillSwitch {
+        KillSwitch {
+            device: device.to_string(),
+            server_ips: ips(server_ips),
+            nameservers: ips(&["192.0.2.53"]),
+        }
+    }
+
+    #[test]
+    fn test_rules_block_everything_but_tunnel_server_and_dns() {
+        let mut runner = RecordingRunner::default();
+
+        let kill_switch = KillSwitch::install(
+            &mut runner,
+            "tun0",
+            ips(&["203.0.113.7", "203.0.113.8"]),
+            ips(&["192.0.2.53"]),
+        )
+        .unwrap();
+        assert_eq!(kill_switch.device, "tun0");
+
+        assert_eq!(
+            runner.commands,
+            vec![
+                "sudo nft add table inet akon_kill_switch",
+                "sudo nft add chain inet akon_kill_switch output \
+                 { type filter hook output priority 0 ; policy drop ; }",
+                "sudo nft flush chain inet akon_kill_switch output",
+                "sudo nft add rule inet akon_kill_switch output oifname lo accept",
+                "sudo nft add rule inet akon_kill_switch output oifname tun0 accept",
+                "sudo nft add rule inet akon_kill_switch output ip daddr 203.0.113.7 accept",
+                "sudo nft add rule inet akon_kill_switch output ip daddr 203.0.113.8 accept",
+                "sudo nft add rule inet akon_kill_switch output \
+                 ip daddr 192.0.2.53 udp dport 53 accept",
+                "sudo nft add rule inet akon_kill_switch output \
+                 ip daddr 192.0.2.53 tcp dport 53 accept",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ipv6_server_uses_ip6_match() {
+        let kill_switch = kill_switch("tun1", &["203.0.113.7", "2001:db8::7"]);
+        let commands = kill_switch.setup_commands().unwrap();
+        assert!(commands.contains(&accept_rule(&["ip", "daddr", "203.0.113.7"])));
+        assert!(commands.contains(&accept_rule(&["ip6", "daddr", "2001:db8::7"])));
+    }
+
+    #[test]
+    fn test_dns_is_only_allowed_to_the_listed_nameservers() {
+        let rules: Vec<String> = kill_switch("tun0", &["203.0.113.7"])
+            .setup_commands()
+            .unwrap()
+            .iter()
+            .map(|args| args.join(" "))
+            .collect();
+        let dns: Vec<&String> = rules.iter().filter(|r| r.contains("dport 53")).collect();
+        assert_eq!(dns.len(), 2);
+        assert!(dns.iter().all(|r| r.contains("daddr 192.0.2.53")));
+    }
+
+    #[test]
+    fn test_parse_nameservers() {
+        let content = "\
+# Generated by NetworkManager
+search corp.example
+nameserver 192.0.2.53
+nameserver fe80::1%eth0
+nameserver not-an-address
+options edns0
+";
+        assert_eq!(parse_nameservers(content), ips(&["192.0.2.53", "fe80::1"]));
+        assert!(parse_nameservers("").is_empty());
+    }
+
+    #[test]
+    fn test_invalid_device_is_rejected_before_any_rule() {
+        let mut runner = RecordingRunner::default();
+        let result = KillSwitch::install(
+            &mut runner,
+            "tun0 accept; flush ruleset",
+            ips(&["203.0.113.7"]),
+            Vec::new(),
+        );
+        assert!(result.is_err());
+        assert!(runner.commands.is_empty());
+    }
+
+    #[test]
+    fn test_drop_policy_precedes_flush_so_updates_never_leak() {
+        let commands: Vec<String> = kill_switch("tun0", &["203.0.113.7"])
+            .setup_commands()
+            .unwrap()
+            .iter()
+            .map(|args| args.join(" "))
+            .collect();
+        let policy = commands.iter().position(|c| c.contains("policy drop"));
+        let flush = commands.iter().position(|c| c.contains("flush"));
+        assert!(policy < flush);
+    }
+
+    #[test]
+    fn test_remove_deletes_the_table() {
+        let mut runner = RecordingRunner::default();
+        KillSwitch::remove(&mut runner).unwrap();
+        assert_eq!(
+            runner.commands,
+            vec!["sudo nft delete table inet akon_kill_switch"]
+        );
+
+        let mut failing = RecordingRunner {
+            fail_on: Some("delete"),
+            ..Default::default()
+        };
+        let error = KillSwitch::remove(&mut failing).unwrap_err();
+        assert!(error.to_string().contains("Failed to remove kill switch"));
+    }
+}
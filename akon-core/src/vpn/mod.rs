@@ -2,17 +2,36 @@
 //!
 //! Handles OpenConnect CLI integration and connection state management.
 
+pub mod attempt_budget;
+pub mod circuit_breaker;
 pub mod cli_connector;
+pub mod connect_lock;
 pub mod connection_event;
+pub mod ip_history;
 pub mod output_parser;
 pub mod state;
+pub mod state_file;
+pub mod stats;
+pub mod summary;
 
 // Network interruption detection and automatic reconnection
+pub mod egress;
 pub mod health_check;
+pub mod hooks;
+pub mod iface_check;
+pub mod jump;
+pub mod kill_switch;
 pub mod process;
 pub mod reconnection;
+pub mod route_check;
+pub mod schedule;
+pub mod server_addresses;
+pub mod session;
 
 // Public re-exports
 pub use cli_connector::CliConnector;
-pub use connection_event::{ConnectionEvent, ConnectionState, DisconnectReason};
+pub use connection_event::{
+    phase_durations, ConnectionEvent, ConnectionPhase, ConnectionState, DisconnectReason,
+    TimedEvent, Transport,
+};
 pub use output_parser::OutputParser;
@@ -2,8 +2,9 @@
 //!
 //! Extracts ConnectionEvents from OpenConnect stdout/stderr using regex patterns
 
+use crate::config::VpnProtocol;
 use crate::error::VpnError;
-use crate::vpn::ConnectionEvent;
+use crate::vpn::{ConnectionEvent, TimedEvent, Transport};
 use regex::Regex;
 use std::net::IpAddr;
 
@@ -21,6 +22,8 @@ pub struct OutputParser {
     connect_response_pattern: Regex,
     /// Pattern for "Connected to F5 Session Manager"
     f5_session_pattern: Regex,
+    /// Pattern for "CSTP connected. DPD 30, Keepalive 20"
+    cstp_connected_pattern: Regex,
     /// Pattern for SSL/TLS errors
     ssl_error_pattern: Regex,
     /// Pattern for certificate validation errors
@@ -29,10 +32,24 @@ pub struct OutputParser {
     tun_error_pattern: Regex,
     /// Pattern for DNS resolution errors
     dns_error_pattern: Regex,
+    /// Pattern for DNS errors worth retrying, e.g. EAI_AGAIN
+    dns_transient_pattern: Regex,
+    /// Pattern for the group prompt "GROUP: [Employees|Contractors]:"
+    auth_group_pattern: Regex,
+    /// Pattern for push-approval prompts, e.g. "Duo Push sent" or "Waiting for approval"
+    approval_pattern: Regex,
+    /// Pattern for a question waiting for input, e.g. "Accept? [yes/no]:"
+    prompt_pattern: Regex,
+    /// Protocol-specific pattern for the line reporting a usable tunnel (group 1 is the IP)
+    protocol_connected_pattern: Option<Regex>,
+    /// Protocol-specific pattern for authentication progress
+    protocol_auth_pattern: Option<Regex>,
 }
 
 impl OutputParser {
     /// Create a new OutputParser with compiled regex patterns
+    ///
+    /// Uses only the generic patterns, which are tuned for F5 and AnyConnect output.
     pub fn new() -> Self {
         Self {
             // Match both old format "Connected tun0 as X.X.X.X" and new F5 format "Configured as X.X.X.X"
@@ -49,6 +66,8 @@ impl OutputParser {
                 .expect("Failed to compile connect_response pattern"),
             f5_session_pattern: Regex::new(r"Connected to F5 Session Manager")
                 .expect("Failed to compile f5_session pattern"),
+            cstp_connected_pattern: Regex::new(r"CSTP connected")
+                .expect("Failed to compile cstp_connected pattern"),
             ssl_error_pattern: Regex::new(r"(?i)SSL|TLS|connection failure|handshake")
                 .expect("Failed to compile ssl_error pattern"),
             cert_error_pattern: Regex::new(r"(?i)certificate|cert.*invalid|verification failed")
@@ -59,21 +78,132 @@ impl OutputParser {
                 r"(?i)cannot resolve|unknown host|name resolution|getaddrinfo failed|Name or service not known"
             )
             .expect("Failed to compile dns_error pattern"),
+            dns_transient_pattern: Regex::new(r"(?i)temporary failure|try again")
+                .expect("Failed to compile dns_transient pattern"),
+            auth_group_pattern: Regex::new(r"^\s*(?i:group):\s*\[([^\]]*)\]")
+                .expect("Failed to compile auth_group pattern"),
+            approval_pattern: Regex::new(
+                r"(?i)\bpush\b.*\bsent\b|waiting for (?:your )?approval|approve (?:the |this )?(?:request|login|sign-in)",
+            )
+            .expect("Failed to compile approval pattern"),
+            prompt_pattern: Regex::new(r"(?i)\[(?:y(?:es)?/n(?:o)?)\]|[:?]\s*$")
+                .expect("Failed to compile prompt pattern"),
+            protocol_connected_pattern: None,
+            protocol_auth_pattern: None,
+        }
+    }
+
+    /// Create an OutputParser that also understands protocol-specific output
+    ///
+    /// GlobalProtect, Fortinet, Pulse and Array report their tunnel and
+    /// authentication progress differently from F5; other protocols use the
+    /// generic patterns.
+    pub fn new_for(protocol: &VpnProtocol) -> Self {
+        let (connected, auth) = match protocol {
+            // "Configured as 10.0.0.5, with SSL disconnected and ESP established"
+            // "Connected as 10.0.0.5, using SSL, with ESP in progress" (older openconnect)
+            VpnProtocol::GlobalProtect => (
+                r"(?:Configured|Connected) as\s+([0-9A-Fa-f.:]+),.*\b(?:SSL|ESP)\b",
+                r"GlobalProtect login returned|Portal reports GlobalProtect|prelogin\.esp|SAML (?:REDIRECT|POST) authentication|ESP session established",
+            ),
+            // "Configured as 10.212.134.200, with SSL connected and DTLS in progress"
+            // "Connected as 10.212.134.200, using SSL, with DTLS in progress" (older openconnect)
+            VpnProtocol::Fortinet => (
+                r"(?:Configured|Connected) as\s+([0-9A-Fa-f.:]+),.*\b(?:SSL|DTLS)\b",
+                r"remote/logincheck|remote/saml|Server asked us to submit token code|remote/fortisslvpn",
+            ),
+            // "Configured as 10.9.0.12, with SSL disconnected and ESP established"
+            // "Connected as 10.9.0.12, using SSL, with ESP in progress" (older openconnect)
+            VpnProtocol::Pulse => (
+                r"(?:Configured|Connected) as\s+([0-9A-Fa-f.:]+),.*\b(?:SSL|ESP)\b",
+                r"IF-T/TLS|Pulse (?:realm|password|secondary password)",
+            ),
+            // "Configured as 172.16.4.9, with SSL connected and DTLS in progress"
+            // "Connected as 172.16.4.9, using SSL, with DTLS in progress" (older openconnect)
+            VpnProtocol::Array => (
+                r"(?:Configured|Connected) as\s+([0-9A-Fa-f.:]+),.*\b(?:SSL|DTLS)\b",
+                r"/prx/000/http/localhost/login",
+            ),
+            _ => return Self::new(),
+        };
+
+        Self {
+            protocol_connected_pattern: Some(
+                Regex::new(connected).expect("Failed to compile protocol connected pattern"),
+            ),
+            protocol_auth_pattern: Some(
+                Regex::new(auth).expect("Failed to compile protocol auth pattern"),
+            ),
+            ..Self::new()
         }
     }
 
+    /// Parse the authentication group prompt into the list of offered groups
+    fn parse_auth_groups(&self, line: &str) -> Option<ConnectionEvent> {
+        let captures = self.auth_group_pattern.captures(line)?;
+        let groups: Vec<String> = captures[1]
+            .split('|')
+            .map(str::trim)
+            .filter(|group| !group.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        if groups.is_empty() {
+            return None;
+        }
+
+        Some(ConnectionEvent::AuthGroupsAvailable { groups })
+    }
+
+    /// Parse a push-approval prompt
+    fn parse_approval(&self, line: &str) -> Option<ConnectionEvent> {
+        self.approval_pattern
+            .is_match(line)
+            .then(|| ConnectionEvent::AwaitingApproval {
+                message: line.trim().to_string(),
+            })
+    }
+
+    /// Whether a line of OpenConnect output asks for input
+    ///
+    /// Such lines are matched against `prompt_responses` to answer them.
+    pub fn is_prompt(&self, line: &str) -> bool {
+        self.prompt_pattern.is_match(line)
+    }
+
+    /// Parse a line from OpenConnect stdout, timestamped with when it was parsed
+    pub fn parse_line_timed(&self, line: &str) -> TimedEvent {
+        TimedEvent::now(self.parse_line(line))
+    }
+
     /// Parse a line from OpenConnect stdout
     ///
     /// Returns a ConnectionEvent based on the line content
     pub fn parse_line(&self, line: &str) -> ConnectionEvent {
+        if let Some(event) = self
+            .parse_auth_groups(line)
+            .or_else(|| self.parse_approval(line))
+        {
+            return event;
+        }
+
+        // Protocol-specific connection confirmation takes precedence over generic TUN lines
+        if let Some(captures) = self
+            .protocol_connected_pattern
+            .as_ref()
+            .and_then(|pattern| pattern.captures(line))
+        {
+            if let Ok(ip) = captures[1].trim_end_matches(',').parse::<IpAddr>() {
+                // These lines don't name the device
+                return ConnectionEvent::Connected { device: None, ip };
+            }
+        }
+
         // Check for TUN configuration - F5 format includes connection confirmation
         // Example: "Configured as 10.10.62.228, with SSL connected and DTLS disabled"
         if let Some(captures) = self.tun_configured_pattern.captures(line) {
             // Group 1 is device (optional for F5 format), Group 2 is IP
-            let device = captures
-                .get(1)
-                .map(|m| m.as_str().to_string())
-                .unwrap_or_else(|| "tun".to_string()); // Default for F5 format
+            let device = captures.get(1).map(|m| m.as_str().to_string()); // None for F5 format
 
             // IP is in group 2 for both formats
             let ip_str = captures
@@ -109,6 +239,17 @@ impl OutputParser {
             };
         }
 
+        // Check for protocol-specific authentication progress
+        if self
+            .protocol_auth_pattern
+            .as_ref()
+            .is_some_and(|pattern| pattern.is_match(line))
+        {
+            return ConnectionEvent::Authenticating {
+                message: "Authenticating with server...".to_string(),
+            };
+        }
+
         // Check for CONNECT response
         if self.connect_response_pattern.is_match(line) {
             return ConnectionEvent::Authenticating {
@@ -123,6 +264,11 @@ impl OutputParser {
             };
         }
 
+        // Check for CSTP tunnel up (routing not configured yet)
+        if self.cstp_connected_pattern.is_match(line) {
+            return ConnectionEvent::CstpConnected;
+        }
+
         // Check for established connection
         if self.established_pattern.is_match(line) {
             return ConnectionEvent::Authenticating {
@@ -140,6 +286,14 @@ impl OutputParser {
     ///
     /// Returns an Error event or UnknownOutput
     pub fn parse_error(&self, line: &str) -> ConnectionEvent {
+        // openconnect writes form prompts to stderr
+        if let Some(event) = self
+            .parse_auth_groups(line)
+            .or_else(|| self.parse_approval(line))
+        {
+            return event;
+        }
+
         // Check for authentication failures
         if self.auth_failed_pattern.is_match(line) {
             return ConnectionEvent::Error {
@@ -178,11 +332,12 @@ impl OutputParser {
             };
         }
 
-        // Check for DNS resolution errors
+        // Check for DNS resolution errors; only a name that doesn't exist
+        // (NXDOMAIN) is final, the resolver being unreachable may pass
         if self.dns_error_pattern.is_match(line) {
             return ConnectionEvent::Error {
-                kind: VpnError::NetworkError {
-                    reason: "DNS resolution failed - check server address".to_string(),
+                kind: VpnError::DnsFailure {
+                    transient: self.dns_transient_pattern.is_match(line),
                 },
                 raw_output: line.to_string(),
             };
@@ -195,6 +350,26 @@ impl OutputParser {
     }
 }
 
+/// Detect a UDP data channel coming up in a line of OpenConnect output
+///
+/// Traffic uses TLS until one does, so `None` means no change. A channel
+/// still "in progress" doesn't count.
+///
+/// Examples: `Established DTLS connection (using GnuTLS)`,
+/// `Configured as 10.9.0.12, with SSL disconnected and ESP established`
+pub fn parse_transport(line: &str) -> Option<Transport> {
+    if line.contains("ESP established") || line.contains("ESP session established") {
+        Some(Transport::Esp)
+    } else if line.contains("Established DTLS connection")
+        || line.contains("DTLS connected")
+        || line.contains("DTLS established")
+    {
+        Some(Transport::Dtls)
+    } else {
+        None
+    }
+}
+
 impl Default for OutputParser {
     fn default() -> Self {
         Self::new()
@@ -3,6 +3,8 @@
 //! This module provides functions to find, terminate, and cleanup
 //! OpenConnect VPN processes.
 
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
 use std::process::Command;
 use std::time::Duration;
 use tokio::time::sleep;
@@ -48,6 +50,14 @@ pub fn is_process_alive(pid: u32) -> bool {
     }
 }
 
+/// Check whether any openconnect process is running
+pub fn any_openconnect_running() -> bool {
+    Command::new("pgrep")
+        .args(["-x", "openconnect"])
+        .output()
+        .is_ok_and(|out| out.status.success())
+}
+
 /// Terminate an OpenConnect process gracefully
 ///
 /// Sends SIGTERM first, waits up to 5 seconds, then sends SIGKILL if still alive.
@@ -140,6 +150,62 @@ pub async fn cleanup_all_openconnect_processes() -> Result<Vec<u32>, ProcessErro
     Ok(terminated_pids)
 }
 
+/// Find an openconnect process among the descendants of `root_pid`
+///
+/// Walks the parent links in `<proc_root>/<pid>/stat` (`proc_root` is `/proc`
+/// on a live system). Used to locate openconnect from its sudo wrapper when
+/// matching on the command line fails.
+pub fn find_openconnect_descendant(proc_root: &Path, root_pid: u32) -> Option<u32> {
+    let entries = std::fs::read_dir(proc_root).ok()?;
+
+    let mut children: HashMap<u32, Vec<(u32, String)>> = HashMap::new();
+    for entry in entries.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Ok(stat) = std::fs::read_to_string(entry.path().join("stat")) else {
+            continue;
+        };
+        if let Some((comm, ppid)) = parse_stat(&stat) {
+            children.entry(ppid).or_default().push((pid, comm));
+        }
+    }
+
+    // Breadth-first, so the process closest to the wrapper wins
+    let mut queue = VecDeque::from([root_pid]);
+    while let Some(parent) = queue.pop_front() {
+        let Some(kids) = children.get_mut(&parent) else {
+            continue;
+        };
+        kids.sort();
+        for (pid, comm) in kids.iter() {
+            if comm == "openconnect" {
+                return Some(*pid);
+            }
+            queue.push_back(*pid);
+        }
+    }
+
+    None
+}
+
+/// Extract the command name and parent PID from a `/proc/<pid>/stat` line
+///
+/// The command name is in parentheses and may itself contain spaces or ')'.
+fn parse_stat(stat: &str) -> Option<(String, u32)> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let comm = stat.get(open + 1..close)?.to_string();
+
+    // Fields after the name: state, ppid, ...
+    let ppid = stat[close + 1..].split_whitespace().nth(1)?.parse().ok()?;
+    Some((comm, ppid))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,4 +230,40 @@ mod tests {
         let result = terminate_process(99999999).await;
         assert!(result.is_ok());
     }
+
+    fn fake_process(proc_root: &Path, pid: u32, comm: &str, ppid: u32) {
+        let dir = proc_root.join(pid.to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("stat"),
+            format!("{} ({}) S {} {} {} 0 -1 4194560", pid, comm, ppid, pid, pid),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_find_openconnect_descendant_walks_process_tree() {
+        let proc_root = tempfile::tempdir().unwrap();
+        let root = proc_root.path();
+        fake_process(root, 1, "systemd", 0);
+        fake_process(root, 500, "sudo", 1);
+        fake_process(root, 501, "sudo", 500);
+        fake_process(root, 502, "openconnect", 501);
+        // An unrelated openconnect must not be picked up
+        fake_process(root, 400, "openconnect", 1);
+        std::fs::create_dir_all(root.join("self")).unwrap();
+
+        assert_eq!(find_openconnect_descendant(root, 500), Some(502));
+        assert_eq!(find_openconnect_descendant(root, 501), Some(502));
+        assert_eq!(find_openconnect_descendant(root, 502), None);
+    }
+
+    #[test]
+    fn test_parse_stat_handles_odd_command_names() {
+        assert_eq!(
+            parse_stat("42 (my (odd) prog) S 7 42 42 0"),
+            Some(("my (odd) prog".to_string(), 7))
+        );
+        assert_eq!(parse_stat("garbage"), None);
+    }
 }
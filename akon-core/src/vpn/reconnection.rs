@@ -3,7 +3,13 @@
 //! This module provides ReconnectionManager for orchestrating automatic
 //! VPN reconnection when network interruptions occur.
 
+use crate::vpn::circuit_breaker::CircuitBreaker;
+use crate::vpn::connection_event::DisconnectReason;
+use crate::vpn::health_check::{parse_endpoint, AddressFamily};
+use crate::vpn::schedule::{DailySchedule, InvalidSchedule};
+use crate::vpn::session::SessionState;
 use crate::vpn::state::ConnectionState;
+use std::path::PathBuf;
 use tokio::sync::{mpsc, watch};
 use tracing::{debug, error, info};
 
@@ -34,8 +40,67 @@ pub struct ReconnectionPolicy {
     #[serde(default = "default_health_check_interval")]
     pub health_check_interval_secs: u64,
 
-    /// Health check endpoint URL (HTTP/HTTPS)
+    /// Health check endpoint: an HTTP/HTTPS URL, `tcp://host:port`, `icmp://host`,
+    /// `iface://` to only check the tunnel device, or `"@server"` to probe the VPN gateway
     pub health_check_endpoint: String,
+
+    /// Address family for health checks (auto, v4 or v6)
+    #[serde(default)]
+    pub health_check_address_family: AddressFamily,
+
+    /// Shell command run when a reconnection attempt starts
+    #[serde(default)]
+    pub on_reconnect_attempt: Option<String>,
+
+    /// Shell command run when a reconnection attempt succeeds
+    #[serde(default)]
+    pub on_reconnect_success: Option<String>,
+
+    /// Pause retries after this many consecutive runs exhaust `max_attempts` (disabled if unset)
+    #[serde(default)]
+    pub circuit_breaker_threshold: Option<u32>,
+
+    /// How long retries stay paused once the circuit breaker opens
+    #[serde(default = "default_circuit_breaker_cooldown")]
+    pub circuit_breaker_cooldown_secs: u64,
+
+    /// Disconnect reasons that trigger reconnection (never `user_requested`)
+    #[serde(default = "default_reconnect_on")]
+    pub reconnect_on: Vec<DisconnectReason>,
+
+    /// File the reconnection daemon's stderr is appended to (discarded if unset)
+    #[serde(default)]
+    pub daemon_log: Option<PathBuf>,
+
+    /// Local time (`HH:MM`) to disconnect at every day, not reconnecting afterwards
+    #[serde(default)]
+    pub disconnect_at: Option<String>,
+
+    /// Hold off reconnecting while the desktop session is locked or idle
+    #[serde(default)]
+    pub pause_while_locked: bool,
+}
+
+/// Health check endpoint alias that resolves to the configured VPN server
+pub const SERVER_ENDPOINT_ALIAS: &str = "@server";
+
+/// Resolve a health check endpoint, substituting [`SERVER_ENDPOINT_ALIAS`]
+pub fn resolve_health_check_endpoint(
+    endpoint: &str,
+    server: &str,
+) -> Result<String, PolicyValidationError> {
+    if endpoint != SERVER_ENDPOINT_ALIAS {
+        return Ok(endpoint.to_string());
+    }
+
+    if server.trim().is_empty() {
+        return Err(PolicyValidationError::InvalidEndpointUrl(format!(
+            "{} requires a VPN server to be configured",
+            SERVER_ENDPOINT_ALIAS
+        )));
+    }
+
+    Ok(format!("https://{}/", server.trim()))
 }
 
 fn default_max_attempts() -> u32 {
@@ -56,8 +121,54 @@ fn default_consecutive_failures() -> u32 {
 fn default_health_check_interval() -> u64 {
     10
 }
+fn default_circuit_breaker_cooldown() -> u64 {
+    1800
+}
+fn default_reconnect_on() -> Vec<DisconnectReason> {
+    vec![
+        DisconnectReason::ServerDisconnect,
+        DisconnectReason::ProcessTerminated,
+        DisconnectReason::Timeout,
+        DisconnectReason::NetworkDown,
+    ]
+}
+
+impl Default for ReconnectionPolicy {
+    /// The policy a `[reconnection]` section gets for fields it leaves out,
+    /// probing the VPN server itself for health checks
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            base_interval_secs: default_base_interval(),
+            backoff_multiplier: default_backoff_multiplier(),
+            max_interval_secs: default_max_interval(),
+            consecutive_failures_threshold: default_consecutive_failures(),
+            health_check_interval_secs: default_health_check_interval(),
+            health_check_endpoint: SERVER_ENDPOINT_ALIAS.to_string(),
+            health_check_address_family: AddressFamily::default(),
+            on_reconnect_attempt: None,
+            on_reconnect_success: None,
+            circuit_breaker_threshold: None,
+            circuit_breaker_cooldown_secs: default_circuit_breaker_cooldown(),
+            reconnect_on: default_reconnect_on(),
+            daemon_log: None,
+            disconnect_at: None,
+            pause_while_locked: false,
+        }
+    }
+}
 
 impl ReconnectionPolicy {
+    /// Time between health checks
+    pub fn health_check_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.health_check_interval_secs)
+    }
+
+    /// Whether a disconnect for `reason` should be followed by reconnection
+    pub fn reconnects_on(&self, reason: DisconnectReason) -> bool {
+        self.reconnect_on.contains(&reason)
+    }
+
     /// Validate the entire policy
     ///
     /// Checks all fields against their valid ranges and constraints.
@@ -74,6 +185,11 @@ impl ReconnectionPolicy {
         self.validate_consecutive_failures()?;
         self.validate_health_check_interval()?;
         self.validate_health_check_endpoint()?;
+        self.validate_hooks()?;
+        self.validate_circuit_breaker()?;
+        self.validate_reconnect_on()?;
+        self.validate_daemon_log()?;
+        self.disconnect_schedule()?;
         Ok(())
     }
 
@@ -142,22 +258,87 @@ impl ReconnectionPolicy {
         }
     }
 
-    /// Validate health_check_endpoint is a valid HTTP/HTTPS URL
+    /// Validate health_check_endpoint is an endpoint the checker supports (or the server alias)
     fn validate_health_check_endpoint(&self) -> Result<(), PolicyValidationError> {
-        use url::Url;
-
-        match Url::parse(&self.health_check_endpoint) {
-            Ok(url) => match url.scheme() {
-                "http" | "https" => Ok(()),
-                scheme => Err(PolicyValidationError::InvalidEndpointUrl(format!(
-                    "URL scheme must be http or https, got: {}",
-                    scheme
-                ))),
-            },
-            Err(e) => Err(PolicyValidationError::InvalidEndpointUrl(format!(
-                "Failed to parse URL: {}",
-                e
-            ))),
+        if self.health_check_endpoint == SERVER_ENDPOINT_ALIAS {
+            return Ok(());
+        }
+
+        parse_endpoint(&self.health_check_endpoint)
+            .map(|_| ())
+            .map_err(PolicyValidationError::InvalidEndpointUrl)
+    }
+
+    /// Resolve the health check URL for a VPN server
+    ///
+    /// `"@server"` becomes `https://<server>/`, since the gateway is only
+    /// reachable through a working tunnel. Other endpoints are returned as-is.
+    pub fn resolve_health_check_endpoint(
+        &self,
+        server: &str,
+    ) -> Result<String, PolicyValidationError> {
+        resolve_health_check_endpoint(&self.health_check_endpoint, server)
+    }
+
+    /// Validate hook commands are not empty when set
+    fn validate_hooks(&self) -> Result<(), PolicyValidationError> {
+        let hooks = [
+            ("on_reconnect_attempt", &self.on_reconnect_attempt),
+            ("on_reconnect_success", &self.on_reconnect_success),
+        ];
+        for (name, command) in hooks {
+            if command.as_ref().is_some_and(|c| c.trim().is_empty()) {
+                return Err(PolicyValidationError::EmptyHookCommand(name.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate circuit breaker threshold (1-20) and cool-down (60-86400s)
+    fn validate_circuit_breaker(&self) -> Result<(), PolicyValidationError> {
+        if let Some(threshold) = self.circuit_breaker_threshold {
+            if !(1..=20).contains(&threshold) {
+                return Err(PolicyValidationError::InvalidCircuitBreakerThreshold(
+                    threshold,
+                ));
+            }
+        }
+        if !(60..=86400).contains(&self.circuit_breaker_cooldown_secs) {
+            return Err(PolicyValidationError::InvalidCircuitBreakerCooldown(
+                self.circuit_breaker_cooldown_secs,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate reconnect_on leaves a deliberate disconnect alone
+    fn validate_reconnect_on(&self) -> Result<(), PolicyValidationError> {
+        if self.reconnects_on(DisconnectReason::UserRequested) {
+            Err(PolicyValidationError::ReconnectOnUserRequested)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The parsed `disconnect_at` schedule, if set
+    pub fn disconnect_schedule(&self) -> Result<Option<DailySchedule>, PolicyValidationError> {
+        self.disconnect_at
+            .as_deref()
+            .map(|value| {
+                value.parse().map_err(|e: InvalidSchedule| {
+                    PolicyValidationError::InvalidDisconnectAt(e.to_string())
+                })
+            })
+            .transpose()
+    }
+
+    /// Validate daemon_log is an absolute path
+    fn validate_daemon_log(&self) -> Result<(), PolicyValidationError> {
+        match &self.daemon_log {
+            Some(path) if !path.is_absolute() => Err(PolicyValidationError::RelativeDaemonLog(
+                path.display().to_string(),
+            )),
+            _ => Ok(()),
         }
     }
 }
@@ -170,6 +351,13 @@ pub struct ReconnectionManager {
     command_rx: mpsc::UnboundedReceiver<ReconnectionCommand>,
     command_tx: mpsc::UnboundedSender<ReconnectionCommand>,
     consecutive_failures_counter: std::sync::Arc<std::sync::Mutex<u32>>,
+    circuit_breaker: Option<CircuitBreaker>,
+    /// Retries are paused until then because the attempt budget ran out
+    rate_limited_until: Option<std::time::Instant>,
+    /// Reports whether a VPN process is still running
+    process_check: Option<Box<dyn Fn() -> bool + Send + Sync>>,
+    /// Desktop session state; retries wait while it is locked
+    session_state: Option<watch::Receiver<SessionState>>,
 }
 
 impl ReconnectionManager {
@@ -185,6 +373,12 @@ impl ReconnectionManager {
     pub fn new(policy: ReconnectionPolicy) -> Self {
         let (state_tx, state_rx) = watch::channel(ConnectionState::Disconnected);
         let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let circuit_breaker = policy.circuit_breaker_threshold.map(|threshold| {
+            CircuitBreaker::new(
+                threshold,
+                std::time::Duration::from_secs(policy.circuit_breaker_cooldown_secs),
+            )
+        });
 
         Self {
             policy,
@@ -193,6 +387,87 @@ impl ReconnectionManager {
             command_rx,
             command_tx,
             consecutive_failures_counter: std::sync::Arc::new(std::sync::Mutex::new(0)),
+            circuit_breaker,
+            rate_limited_until: None,
+            process_check: None,
+            session_state: None,
+        }
+    }
+
+    /// Give up once retries are exhausted and `process_alive` reports false
+    ///
+    /// Without this, an exhausted manager waits in the Error state for a
+    /// retry reset. With it, `run` publishes a final Error state and returns
+    /// when no VPN process is left, so a daemon doesn't outlive the
+    /// connection it was looking after.
+    pub fn with_process_check<F>(mut self, process_alive: F) -> Self
+    where
+        F: Fn() -> bool + Send + Sync + 'static,
+    {
+        self.process_check = Some(Box::new(process_alive));
+        self
+    }
+
+    /// Only reconnect while `session_state` reports the session active
+    ///
+    /// A reconnection that comes due while the session is locked waits,
+    /// attempts untouched, and starts once it is unlocked again.
+    pub fn with_session_state(mut self, session_state: watch::Receiver<SessionState>) -> Self {
+        self.session_state = Some(session_state);
+        self
+    }
+
+    /// Check whether the desktop session is locked
+    fn session_locked(&self) -> bool {
+        self.session_state
+            .as_ref()
+            .is_some_and(|state| *state.borrow() == SessionState::Locked)
+    }
+
+    /// Check whether the VPN process is confirmed to have exited
+    fn vpn_process_gone(&self) -> bool {
+        self.process_check
+            .as_ref()
+            .is_some_and(|process_alive| !process_alive())
+    }
+
+    /// Check whether the circuit breaker is currently pausing retries
+    pub fn circuit_breaker_open(&self) -> bool {
+        self.circuit_breaker
+            .as_ref()
+            .is_some_and(|breaker| breaker.is_open(std::time::Instant::now()))
+    }
+
+    /// Check whether retries are paused by the circuit breaker or the attempt budget
+    fn retries_paused(&self) -> bool {
+        self.circuit_breaker_open()
+            || self
+                .rate_limited_until
+                .is_some_and(|until| std::time::Instant::now() < until)
+    }
+
+    /// Record an exhausted reconnection run with the circuit breaker
+    ///
+    /// If this opens the breaker, the Error state is updated to say when
+    /// retries resume.
+    fn record_exhaustion(&mut self) {
+        let Some(breaker) = self.circuit_breaker.as_mut() else {
+            return;
+        };
+
+        let now = std::time::Instant::now();
+        breaker.record_exhaustion(now);
+        if let Some(remaining) = breaker.remaining_cooldown(now) {
+            tracing::warn!(
+                exhaustions = breaker.consecutive_exhaustions(),
+                cooldown_secs = remaining.as_secs(),
+                "Circuit breaker opened, pausing reconnection attempts"
+            );
+            let _ = self.state_tx.send(ConnectionState::Error(format!(
+                "Circuit breaker open after {} exhausted reconnection runs; retries paused for {}s",
+                breaker.consecutive_exhaustions(),
+                remaining.as_secs()
+            )));
         }
     }
 
@@ -348,6 +623,15 @@ impl ReconnectionManager {
 
                 // Check if we've reached the threshold
                 if current_failures >= self.policy.consecutive_failures_threshold {
+                    if !self.policy.reconnects_on(DisconnectReason::NetworkDown) {
+                        tracing::warn!(
+                            failures = current_failures,
+                            "Health checks keep failing, but reconnect_on excludes network_down; not reconnecting"
+                        );
+                        *counter = 0;
+                        return;
+                    }
+
                     tracing::error!(
                         failures = current_failures,
                         threshold = self.policy.consecutive_failures_threshold,
@@ -370,6 +654,10 @@ impl ReconnectionManager {
     /// Processes network events, handles retry timers, performs periodic health checks,
     /// and responds to commands. This should be spawned as a background tokio task.
     ///
+    /// With `disconnect_at` set, the loop ends at that time with a final
+    /// Disconnecting state, for the caller to tear the connection down. The
+    /// wait is measured on the monotonic clock when the loop starts.
+    ///
     /// # Arguments
     ///
     /// * `health_checker` - Optional health checker for periodic connectivity validation
@@ -380,22 +668,55 @@ impl ReconnectionManager {
         retry_timer.tick().await; // Consume first immediate tick
 
         // Create health check interval timer
-        let mut health_check_timer =
-            interval(Duration::from_secs(self.policy.health_check_interval_secs));
+        let mut health_check_timer = interval(self.policy.health_check_interval());
         health_check_timer.tick().await; // Consume first immediate tick
 
         let mut current_attempt = 1u32;
         let mut should_reconnect = false;
+        let mut was_paused = false;
+        let mut held_for_lock = false;
+        // A disconnect the policy doesn't reconnect after (always one the
+        // user asked for): don't reconnect until told to
+        let mut disconnect_requested = false;
 
         // Clone state receiver for monitoring state changes
         let mut state_monitor = self.state_rx.clone();
 
+        let scheduled_disconnect = match self.policy.disconnect_schedule() {
+            Ok(schedule) => schedule,
+            Err(e) => {
+                tracing::warn!("Ignoring scheduled disconnect: {}", e);
+                None
+            }
+        }
+        .map(|schedule| {
+            let now = chrono::Local::now();
+            let fire_at = schedule.next_after(&now);
+            info!(
+                "Scheduled disconnect at {}",
+                fire_at.format("%Y-%m-%d %H:%M %Z")
+            );
+            tokio::time::Instant::now() + (fire_at - now).to_std().unwrap_or_default()
+        });
+
         loop {
             tokio::select! {
+                // Disconnect for good at the scheduled time
+                _ = tokio::time::sleep_until(scheduled_disconnect.unwrap_or_else(tokio::time::Instant::now)),
+                    if scheduled_disconnect.is_some() =>
+                {
+                    info!("Scheduled disconnect time reached, stopping reconnection");
+                    let _ = self.state_tx.send(ConnectionState::Disconnecting);
+                    break;
+                }
+
                 // Monitor for state changes to react immediately to Disconnected state
                 Ok(_) = state_monitor.changed() => {
                     let current_state = state_monitor.borrow().clone();
-                    if matches!(current_state, ConnectionState::Disconnected) && !should_reconnect {
+                    if matches!(current_state, ConnectionState::Disconnected)
+                        && !should_reconnect
+                        && !disconnect_requested
+                    {
                         tracing::info!("State changed to Disconnected, immediately initiating reconnection");
                         should_reconnect = true;
                         current_attempt = 1;
@@ -406,14 +727,25 @@ impl ReconnectionManager {
                 Some(cmd) = self.command_rx.recv() => {
                     match cmd {
                         ReconnectionCommand::Start => {
+                            if self.retries_paused() {
+                                tracing::info!("Retries paused, ignoring start request");
+                                continue;
+                            }
                             should_reconnect = true;
                             current_attempt = 1;
+                            disconnect_requested = false;
                         }
                         ReconnectionCommand::Stop => {
                             should_reconnect = false;
                             let _ = self.state_tx.send(ConnectionState::Disconnected);
                         }
                         ReconnectionCommand::ResetRetries => {
+                            // Don't let resets bypass the cool-down
+                            if self.retries_paused() {
+                                tracing::info!("Retries paused, ignoring retry reset until the pause ends");
+                                continue;
+                            }
+
                             // T050: Reset retry counter and consecutive failures counter
                             current_attempt = 1;
                             if let Ok(mut counter) = self.consecutive_failures_counter.lock() {
@@ -441,9 +773,40 @@ impl ReconnectionManager {
                             if let Ok(mut counter) = self.consecutive_failures_counter.lock() {
                                 *counter = 0;
                             }
+                            if let Some(breaker) = self.circuit_breaker.as_mut() {
+                                breaker.record_success();
+                            }
+                            self.rate_limited_until = None;
+                            was_paused = false;
+                            disconnect_requested = false;
 
                             tracing::info!("State set to Connected, health check monitoring enabled");
                         }
+                        ReconnectionCommand::Disconnected(reason) => {
+                            if self.policy.reconnects_on(reason) {
+                                tracing::info!(?reason, "VPN disconnected, reconnecting");
+                            } else {
+                                tracing::info!(?reason, "VPN disconnected, not reconnecting for this reason");
+                                should_reconnect = false;
+                                current_attempt = 1;
+                                disconnect_requested = true;
+                            }
+                            let _ = self.state_tx.send(ConnectionState::Disconnected);
+                        }
+                        ReconnectionCommand::RateLimited { retry_after } => {
+                            tracing::warn!(
+                                retry_after_secs = retry_after.as_secs(),
+                                "Connection attempt budget exhausted, pausing reconnection attempts"
+                            );
+                            self.rate_limited_until = Some(std::time::Instant::now() + retry_after);
+                            should_reconnect = false;
+                            current_attempt = 1;
+                            was_paused = true;
+                            let _ = self.state_tx.send(ConnectionState::Error(format!(
+                                "Rate limited by akon: connection attempt budget exhausted; retries paused for {}s",
+                                retry_after.as_secs()
+                            )));
+                        }
                         ReconnectionCommand::CheckNow => {
                             // Immediate health check
                             if let Some(ref checker) = health_checker {
@@ -458,14 +821,47 @@ impl ReconnectionManager {
 
                 // Handle retry timer
                 _ = retry_timer.tick() => {
+                    // While the circuit breaker is open or the attempt budget
+                    // is exhausted, no retries are made
+                    if self.retries_paused() {
+                        was_paused = true;
+                        continue;
+                    }
+
+                    // Pause over: resume retries from the Error state
+                    if was_paused {
+                        was_paused = false;
+                        self.rate_limited_until = None;
+                        if matches!(*self.state_rx.borrow(), ConnectionState::Error(_)) {
+                            tracing::info!("Retry pause finished, resuming reconnection");
+                            let _ = self.state_tx.send(ConnectionState::Disconnected);
+                        }
+                    }
+
                     // Check if we need to start reconnection due to Disconnected state
                     let current_state = self.state_rx.borrow().clone();
-                    if matches!(current_state, ConnectionState::Disconnected) && !should_reconnect {
+                    if matches!(current_state, ConnectionState::Disconnected)
+                        && !should_reconnect
+                        && !disconnect_requested
+                    {
                         tracing::info!("Detected Disconnected state, initiating reconnection");
                         should_reconnect = true;
                         current_attempt = 1;
                     }
 
+                    // Nobody is there to approve the login: wait for them
+                    if should_reconnect && self.session_locked() {
+                        if !held_for_lock {
+                            tracing::info!("Session is locked, holding off reconnection until it is unlocked");
+                            held_for_lock = true;
+                        }
+                        continue;
+                    }
+                    if held_for_lock {
+                        held_for_lock = false;
+                        tracing::info!("Session unlocked, resuming reconnection");
+                    }
+
                     if should_reconnect {
                         match self.attempt_reconnect(current_attempt).await {
                             Ok(_) => {
@@ -475,6 +871,18 @@ impl ReconnectionManager {
                             Err(ReconnectionError::MaxAttemptsExceeded) => {
                                 should_reconnect = false;
                                 current_attempt = 1;
+                                self.record_exhaustion();
+
+                                // Nothing left to look after unless the circuit breaker resumes retries
+                                if !self.retries_paused() && self.vpn_process_gone() {
+                                    let message = format!(
+                                        "Max reconnection attempts ({}) exceeded and the VPN process has exited; giving up",
+                                        self.policy.max_attempts
+                                    );
+                                    error!("{}", message);
+                                    let _ = self.state_tx.send(ConnectionState::Error(message));
+                                    break;
+                                }
                             }
                             Err(_) => {
                                 current_attempt += 1;
@@ -486,7 +894,13 @@ impl ReconnectionManager {
                 // Handle periodic health checks
                 _ = health_check_timer.tick(), if health_checker.is_some() => {
                     if let Some(ref checker) = health_checker {
-                        self.handle_health_check(checker).await;
+                        if self.retries_paused() {
+                            // Only observe the endpoint while retries are paused
+                            let reachable = checker.is_reachable().await;
+                            debug!(reachable, "Health check while retries are paused");
+                        } else {
+                            self.handle_health_check(checker).await;
+                        }
                     }
                 }
             }
@@ -511,6 +925,12 @@ pub enum ReconnectionCommand {
     /// Set state to Connected (for initial connection)
     SetConnected { server: String, username: String },
 
+    /// Pause retries because the shared attempt budget is exhausted
+    RateLimited { retry_after: std::time::Duration },
+
+    /// The VPN went down; reconnect unless the user asked for it
+    Disconnected(DisconnectReason),
+
     /// Trigger immediate health check
     CheckNow,
 
@@ -558,6 +978,26 @@ pub enum PolicyValidationError {
     #[error("health_check_interval_secs must be between 10 and 3600, got: {0}")]
     InvalidHealthCheckInterval(u64),
 
-    #[error("health_check_endpoint must be a valid HTTP/HTTPS URL: {0}")]
+    #[error(
+        "health_check_endpoint must be an http(s)://, tcp://host:port, icmp://host or iface:// URL: {0}"
+    )]
     InvalidEndpointUrl(String),
+
+    #[error("{0} must not be empty")]
+    EmptyHookCommand(String),
+
+    #[error("circuit_breaker_threshold must be between 1 and 20, got: {0}")]
+    InvalidCircuitBreakerThreshold(u32),
+
+    #[error("circuit_breaker_cooldown_secs must be between 60 and 86400, got: {0}")]
+    InvalidCircuitBreakerCooldown(u64),
+
+    #[error("reconnect_on must not include user_requested: 'akon vpn off' is never undone")]
+    ReconnectOnUserRequested,
+
+    #[error("daemon_log must be an absolute path, got: {0}")]
+    RelativeDaemonLog(String),
+
+    #[error("disconnect_at: {0}")]
+    InvalidDisconnectAt(String),
 }
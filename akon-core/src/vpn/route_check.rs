@@ -0,0 +1,272 @@
+//! Post-connect route verification
+//!
+//! A misbehaving vpnc-script can fail to install the default route while
+//! openconnect still reports success, leaving traffic on the clear interface.
+//! This asks the kernel (`ip route get`) which device would carry traffic to
+//! a public address and compares it with the tunnel device. The same check
+//! confirms that the destinations listed in `verify_routes` use the tunnel.
+
+use crate::error::VpnError;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, ToSocketAddrs};
+use std::process::Command;
+
+/// Address whose route is looked up to find the default route device
+pub const ROUTE_CHECK_TARGET: IpAddr = IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1));
+
+/// What to do when the tunnel doesn't carry the default route
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteCheck {
+    /// Don't check (default; split-tunnel setups never carry the default route)
+    #[default]
+    Off,
+    /// Warn, but keep the connection
+    Warn,
+    /// Fail the connection
+    Enforce,
+}
+
+impl RouteCheck {
+    /// Get the mode name as written in the config file
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Warn => "warn",
+            Self::Enforce => "enforce",
+        }
+    }
+}
+
+/// Destination listed in `verify_routes`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteTarget {
+    /// An address; a CIDR is checked through its network address
+    Address(IpAddr),
+    /// A hostname, resolved when checked
+    Host(String),
+}
+
+impl RouteTarget {
+    fn resolve(&self) -> Result<IpAddr, VpnError> {
+        match self {
+            Self::Address(address) => Ok(*address),
+            Self::Host(host) => (host.as_str(), 0)
+                .to_socket_addrs()
+                .ok()
+                .and_then(|mut addrs| addrs.next())
+                .map(|addr| addr.ip())
+                .ok_or_else(|| VpnError::NetworkError {
+                    reason: format!("Cannot resolve route check host {}", host),
+                }),
+        }
+    }
+}
+
+/// Parse a `verify_routes` entry: an address, a CIDR, or a hostname
+pub fn parse_route_target(target: &str) -> Result<RouteTarget, String> {
+    if target.is_empty() {
+        return Err("Verify route cannot be empty".to_string());
+    }
+
+    if let Some((address, prefix)) = target.split_once('/') {
+        let address: IpAddr = address
+            .parse()
+            .map_err(|_| format!("Invalid verify route '{}': not a CIDR", target))?;
+        let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+        match prefix.parse::<u8>() {
+            Ok(prefix) if prefix <= max_prefix => return Ok(RouteTarget::Address(address)),
+            _ => {
+                return Err(format!(
+                    "Invalid verify route '{}': prefix length must be at most {}",
+                    target, max_prefix
+                ))
+            }
+        }
+    }
+
+    if let Ok(address) = target.parse() {
+        return Ok(RouteTarget::Address(address));
+    }
+
+    if target
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+    {
+        Ok(RouteTarget::Host(target.to_string()))
+    } else {
+        Err(format!(
+            "Invalid verify route '{}': expected an address, CIDR or hostname",
+            target
+        ))
+    }
+}
+
+/// Extract the output device from `ip route get` output
+///
+/// Example: `1.1.1.1 dev tun0 src 10.0.0.5 uid 1000`
+pub fn parse_route_device(output: &str) -> Option<&str> {
+    let mut tokens = output.split_whitespace();
+    while let Some(token) = tokens.next() {
+        if token == "dev" {
+            return tokens.next();
+        }
+    }
+    None
+}
+
+/// Check `ip route get <target>` output against the tunnel device
+pub fn check_route(output: &str, tunnel_device: &str, target: IpAddr) -> Result<(), VpnError> {
+    match parse_route_device(output) {
+        Some(device) if device == tunnel_device => Ok(()),
+        Some(device) => Err(VpnError::RoutingNotConfigured {
+            reason: format!(
+                "traffic to {} leaves via {}, not the tunnel device {}",
+                target, device, tunnel_device
+            ),
+        }),
+        None => Err(VpnError::RoutingNotConfigured {
+            reason: format!("no route to {} found: {}", target, output.trim()),
+        }),
+    }
+}
+
+/// Check that every target is routed through `tunnel_device`
+///
+/// `route_get` returns the `ip route get` output for an address.
+pub fn check_routes<F>(
+    targets: &[String],
+    tunnel_device: &str,
+    mut route_get: F,
+) -> Result<(), VpnError>
+where
+    F: FnMut(IpAddr) -> Result<String, VpnError>,
+{
+    for target in targets {
+        let address = parse_route_target(target)
+            .map_err(|reason| VpnError::RoutingNotConfigured { reason })?
+            .resolve()?;
+        check_route(&route_get(address)?, tunnel_device, address)?;
+    }
+    Ok(())
+}
+
+/// Verify that traffic to [`ROUTE_CHECK_TARGET`] goes through `tunnel_device`
+pub fn verify_tunnel_route(tunnel_device: &str) -> Result<(), VpnError> {
+    check_route(
+        &ip_route_get(ROUTE_CHECK_TARGET)?,
+        tunnel_device,
+        ROUTE_CHECK_TARGET,
+    )
+}
+
+/// Verify that the `verify_routes` targets go through `tunnel_device`
+pub fn verify_routes(targets: &[String], tunnel_device: &str) -> Result<(), VpnError> {
+    check_routes(targets, tunnel_device, ip_route_get)
+}
+
+/// Run `ip route get <target>`, returning its output
+fn ip_route_get(target: IpAddr) -> Result<String, VpnError> {
+    let output = Command::new("ip")
+        .args(["route", "get", &target.to_string()])
+        .output()
+        .map_err(|e| VpnError::NetworkError {
+            reason: format!("Failed to run ip route get: {}", e),
+        })?;
+
+    if !output.status.success() {
+        return Err(VpnError::RoutingNotConfigured {
+            reason: format!(
+                "no route to {}: {}",
+                target,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VIA_TUNNEL: &str = "1.1.1.1 dev tun0 src 10.0.0.5 uid 1000 \n    cache \n";
+    const VIA_CLEAR: &str =
+        "1.1.1.1 via 192.168.1.1 dev wlan0 src 192.168.1.23 uid 1000 \n    cache \n";
+
+    #[test]
+    fn test_parse_route_device() {
+        assert_eq!(parse_route_device(VIA_TUNNEL), Some("tun0"));
+        assert_eq!(parse_route_device(VIA_CLEAR), Some("wlan0"));
+        assert_eq!(
+            parse_route_device("RTNETLINK answers: Network is unreachable"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_route_through_tunnel_passes() {
+        assert!(check_route(VIA_TUNNEL, "tun0", ROUTE_CHECK_TARGET).is_ok());
+    }
+
+    #[test]
+    fn test_route_through_clear_interface_fails() {
+        let error = check_route(VIA_CLEAR, "tun0", ROUTE_CHECK_TARGET).unwrap_err();
+
+        assert!(matches!(error, VpnError::RoutingNotConfigured { .. }));
+        assert!(error
+            .to_string()
+            .contains("traffic to 1.1.1.1 leaves via wlan0, not the tunnel device tun0"));
+    }
+
+    #[test]
+    fn test_parse_route_target() {
+        assert_eq!(
+            parse_route_target("10.20.0.0/16"),
+            Ok(RouteTarget::Address("10.20.0.0".parse().unwrap()))
+        );
+        assert_eq!(
+            parse_route_target("2001:db8::5"),
+            Ok(RouteTarget::Address("2001:db8::5".parse().unwrap()))
+        );
+        assert_eq!(
+            parse_route_target("intranet.corp.example"),
+            Ok(RouteTarget::Host("intranet.corp.example".to_string()))
+        );
+        assert!(parse_route_target("10.20.0.0/33").is_err());
+        assert!(parse_route_target("").is_err());
+        assert!(parse_route_target("bad host").is_err());
+    }
+
+    #[test]
+    fn test_verify_routes_present_and_absent_through_tunnel() {
+        // 10.20.0.0/16 is pushed by the gateway; 172.16.0.0/12 is not
+        let route_get = |address: IpAddr| -> Result<String, VpnError> {
+            Ok(if address.to_string().starts_with("10.20.") {
+                format!("{} dev tun0 src 10.20.3.4 uid 1000", address)
+            } else {
+                format!(
+                    "{} via 192.168.1.1 dev wlan0 src 192.168.1.23 uid 1000",
+                    address
+                )
+            })
+        };
+
+        let present = vec!["10.20.0.0/16".to_string(), "10.20.8.1".to_string()];
+        assert!(check_routes(&present, "tun0", route_get).is_ok());
+
+        let absent = vec!["10.20.0.0/16".to_string(), "172.16.0.0/12".to_string()];
+        let error = check_routes(&absent, "tun0", route_get).unwrap_err();
+        assert!(matches!(error, VpnError::RoutingNotConfigured { .. }));
+        assert!(error
+            .to_string()
+            .contains("traffic to 172.16.0.0 leaves via wlan0"));
+    }
+
+    #[test]
+    fn test_missing_route_fails() {
+        let error = check_route("", "tun0", ROUTE_CHECK_TARGET).unwrap_err();
+        assert!(matches!(error, VpnError::RoutingNotConfigured { .. }));
+    }
+}
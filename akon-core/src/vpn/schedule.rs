@@ -0,0 +1,117 @@
+//! Daily scheduled disconnect
+//!
+//! `disconnect_at = "18:00"` in `[reconnection]` makes the reconnection
+//! daemon tear the connection down at that local time every day, for
+//! networks that must not stay connected outside business hours.
+
+use chrono::{DateTime, NaiveTime, TimeZone};
+use std::fmt;
+use std::str::FromStr;
+
+/// A time of day, written `HH:MM` (24-hour)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailySchedule {
+    time: NaiveTime,
+}
+
+/// Error for a schedule that isn't `HH:MM`
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("expected a time of day as HH:MM (24-hour), got: {0:?}")]
+pub struct InvalidSchedule(String);
+
+impl FromStr for DailySchedule {
+    type Err = InvalidSchedule;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidSchedule(value.to_string());
+        let (hour, minute) = value.trim().split_once(':').ok_or_else(invalid)?;
+        let digits = |part: &str| {
+            (part.len() == 2 && part.chars().all(|c| c.is_ascii_digit()))
+                .then(|| part.parse::<u32>().ok())
+                .flatten()
+        };
+        let time = NaiveTime::from_hms_opt(
+            digits(hour).ok_or_else(invalid)?,
+            digits(minute).ok_or_else(invalid)?,
+            0,
+        )
+        .ok_or_else(invalid)?;
+        Ok(Self { time })
+    }
+}
+
+impl fmt::Display for DailySchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.time.format("%H:%M"))
+    }
+}
+
+impl DailySchedule {
+    /// The first time strictly after `after` this schedule fires
+    ///
+    /// A day on which the time doesn't exist (skipped by a DST change) is
+    /// passed over; on one where it happens twice, the first is used.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> DateTime<Tz> {
+        let timezone = after.timezone();
+        let mut date = after.date_naive();
+        loop {
+            if let Some(at) = timezone
+                .from_local_datetime(&date.and_time(self.time))
+                .earliest()
+            {
+                if at > *after {
+                    return at;
+                }
+            }
+            date = date.succ_opt().expect("date out of range");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_parses_hh_mm_only() {
+        assert_eq!(
+            "18:00".parse::<DailySchedule>().unwrap().to_string(),
+            "18:00"
+        );
+        assert_eq!(
+            "00:05".parse::<DailySchedule>().unwrap().to_string(),
+            "00:05"
+        );
+
+        for value in [
+            "", "18", "6:00", "18:0", "24:00", "12:60", "18:00:00", "ab:cd", "+1:00",
+        ] {
+            assert!(
+                value.parse::<DailySchedule>().is_err(),
+                "accepted {:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_next_fire_is_later_today_or_tomorrow() {
+        let schedule: DailySchedule = "18:00".parse().unwrap();
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+
+        assert_eq!(
+            schedule.next_after(&at("2024-03-01T09:30:00Z")),
+            at("2024-03-01T18:00:00Z")
+        );
+        // Exactly at the time counts as passed
+        assert_eq!(
+            schedule.next_after(&at("2024-03-01T18:00:00Z")),
+            at("2024-03-02T18:00:00Z")
+        );
+        assert_eq!(
+            schedule.next_after(&at("2024-12-31T23:59:00Z")),
+            at("2025-01-01T18:00:00Z")
+        );
+    }
+}
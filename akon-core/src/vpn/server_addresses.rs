@@ -0,0 +1,164 @@
+//! Trying each address of a load-balanced gateway
+//!
+//! A VPN hostname often resolves to several gateways, and openconnect only
+//! connects to one of them: when that node is down the connection fails
+//! although the others are fine. akon resolves the name itself and, when
+//! connecting to one address fails at the network level, moves on to the
+//! next. openconnect is pinned to each address with `--resolve`, so TLS
+//! still sees (and validates) the server's own name.
+
+use crate::error::{AkonError, VpnError};
+use std::future::Future;
+use std::net::IpAddr;
+
+/// Port the gateway is resolved for; only the addresses matter
+const HTTPS_PORT: u16 = 443;
+
+/// Resolve every address of `server` through the system resolver
+///
+/// Empty if the name doesn't resolve; openconnect then reports that itself.
+pub async fn resolve_gateways(server: &str) -> Vec<IpAddr> {
+    match tokio::net::lookup_host((server, HTTPS_PORT)).await {
+        Ok(addresses) => addresses.map(|address| address.ip()).collect(),
+        Err(e) => {
+            tracing::debug!("Failed to resolve {}: {}", server, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Addresses of `server` to try one after the other, given what it resolved to
+///
+/// Empty when there is no choice to make: `server` is an address itself, or
+/// resolves to a single one or to none.
+pub fn alternate_addresses(server: &str, resolved: Vec<IpAddr>) -> Vec<IpAddr> {
+    if server.parse::<IpAddr>().is_ok() {
+        return Vec::new();
+    }
+    let mut addresses = Vec::new();
+    for address in resolved {
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+    if addresses.len() < 2 {
+        addresses.clear();
+    }
+    addresses
+}
+
+/// Whether another gateway might succeed where one failed with `error`
+///
+/// Authentication and configuration problems would only repeat.
+pub fn is_node_failure(error: &VpnError) -> bool {
+    matches!(
+        error,
+        VpnError::ConnectionFailed { .. }
+            | VpnError::NetworkError { .. }
+            | VpnError::ConnectionTimeout { .. }
+            | VpnError::ProcessExited { .. }
+    )
+}
+
+/// Call `connect` with each of `addresses` until one succeeds
+///
+/// Without addresses, `connect` gets `None` once and lets openconnect pick.
+/// Stops at the first error that isn't a node failure; otherwise returns
+/// the error for the last address.
+pub async fn connect_across<T, F, Fut>(addresses: &[IpAddr], mut connect: F) -> Result<T, AkonError>
+where
+    F: FnMut(Option<IpAddr>) -> Fut,
+    Fut: Future<Output = Result<T, AkonError>>,
+{
+    let Some((last, others)) = addresses.split_last() else {
+        return connect(None).await;
+    };
+    for address in others {
+        match connect(Some(*address)).await {
+            Err(AkonError::Vpn(e)) if is_node_failure(&e) => {
+                tracing::warn!(
+                    "Gateway {} failed ({}), trying the next address",
+                    address,
+                    e
+                );
+            }
+            result => return result,
+        }
+    }
+    connect(Some(*last)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn ips(addresses: &[&str]) -> Vec<IpAddr> {
+        addresses.iter().map(|ip| ip.parse().unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_moves_on_when_the_first_gateway_is_down() {
+        let addresses = alternate_addresses(
+            "vpn.example.com",
+            ips(&["203.0.113.1", "203.0.113.2", "203.0.113.1"]),
+        );
+        assert_eq!(addresses.len(), 2);
+
+        let down = addresses[0];
+        let tried = RefCell::new(Vec::new());
+        let result = connect_across(&addresses, |address| {
+            tried.borrow_mut().push(address);
+            async move {
+                match address {
+                    Some(address) if address == down => {
+                        Err(AkonError::Vpn(VpnError::ConnectionFailed {
+                            reason: "Failed to connect to host".to_string(),
+                        }))
+                    }
+                    address => Ok(address),
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), Some("203.0.113.2".parse().unwrap()));
+        assert_eq!(
+            tried.into_inner(),
+            vec![Some(addresses[0]), Some(addresses[1])]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stops_at_errors_another_gateway_would_repeat() {
+        let addresses = ips(&["203.0.113.1", "203.0.113.2"]);
+        let attempts = RefCell::new(0);
+        let result: Result<(), _> = connect_across(&addresses, |_| {
+            *attempts.borrow_mut() += 1;
+            async { Err(AkonError::Vpn(VpnError::AuthenticationFailed)) }
+        })
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(AkonError::Vpn(VpnError::AuthenticationFailed))
+        ));
+        assert_eq!(attempts.into_inner(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_no_choice_leaves_the_address_to_openconnect() {
+        assert!(alternate_addresses("vpn.example.com", ips(&["203.0.113.1"])).is_empty());
+        assert!(
+            alternate_addresses("203.0.113.9", ips(&["203.0.113.1", "203.0.113.2"])).is_empty()
+        );
+
+        let result = connect_across(&[], |address| async move { Ok(address) }).await;
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_gateways_of_an_address_is_itself() {
+        assert_eq!(resolve_gateways("127.0.0.1").await, ips(&["127.0.0.1"]));
+    }
+}
@@ -0,0 +1,119 @@
+//! Desktop session lock state
+//!
+//! With `pause_while_locked = true` in `[reconnection]`, the reconnection
+//! daemon holds off reconnecting (and re-prompting for OTP approvals) while
+//! the user is away. Whether they are comes from logind over D-Bus.
+
+use std::time::Duration;
+use tokio::sync::watch;
+use zbus::proxy::CacheProperties;
+
+const LOGIND_DESTINATION: &str = "org.freedesktop.login1";
+/// logind resolves `auto` to the session of the calling process
+const SESSION_PATH: &str = "/org/freedesktop/login1/session/auto";
+const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+/// Whether the user is at the session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    /// Unlocked, in the foreground and in use
+    Active,
+    /// Locked, idle, or switched away from
+    Locked,
+}
+
+impl SessionState {
+    fn from_hints(active: bool, locked: bool, idle: bool) -> Self {
+        if active && !locked && !idle {
+            Self::Active
+        } else {
+            Self::Locked
+        }
+    }
+}
+
+/// Ask logind for the state of this process's session
+///
+/// Reads the session's `Active`, `LockedHint` and `IdleHint` properties.
+pub async fn query_session_state(connection: &zbus::Connection) -> zbus::Result<SessionState> {
+    let proxy = zbus::proxy::Builder::<zbus::Proxy>::new(connection)
+        .destination(LOGIND_DESTINATION)?
+        .path(SESSION_PATH)?
+        .interface(SESSION_INTERFACE)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    let active: bool = proxy.get_property("Active").await?;
+    let locked: bool = proxy.get_property("LockedHint").await?;
+    let idle: bool = proxy.get_property("IdleHint").await?;
+    Ok(SessionState::from_hints(active, locked, idle))
+}
+
+/// Follow the session state, asking logind every `poll_interval`
+///
+/// Returns `None` when there is no session to follow: no system bus, or a
+/// process that isn't part of a logind session (e.g. a system service).
+/// Queries failing later on keep the last known state.
+pub async fn watch_session_state(poll_interval: Duration) -> Option<watch::Receiver<SessionState>> {
+    let connection = match zbus::Connection::system().await {
+        Ok(connection) => connection,
+        Err(e) => {
+            tracing::warn!("Failed to connect to the system bus: {}", e);
+            return None;
+        }
+    };
+    let initial = match query_session_state(&connection).await {
+        Ok(state) => state,
+        Err(e) => {
+            tracing::warn!("Failed to query the logind session: {}", e);
+            return None;
+        }
+    };
+
+    let (state_tx, state_rx) = watch::channel(initial);
+    tokio::spawn(async move {
+        while !state_tx.is_closed() {
+            tokio::time::sleep(poll_interval).await;
+            match query_session_state(&connection).await {
+                Ok(state) => {
+                    state_tx.send_if_modified(|current| {
+                        let changed = *current != state;
+                        if changed {
+                            tracing::info!(?state, "Session state changed");
+                            *current = state;
+                        }
+                        changed
+                    });
+                }
+                Err(e) => tracing::debug!("Failed to query the logind session: {}", e),
+            }
+        }
+    });
+    Some(state_rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_an_unlocked_foreground_session_is_active() {
+        assert_eq!(
+            SessionState::from_hints(true, false, false),
+            SessionState::Active
+        );
+        assert_eq!(
+            SessionState::from_hints(true, true, false),
+            SessionState::Locked
+        );
+        assert_eq!(
+            SessionState::from_hints(true, false, true),
+            SessionState::Locked
+        );
+        assert_eq!(
+            SessionState::from_hints(false, false, false),
+            SessionState::Locked
+        );
+    }
+}
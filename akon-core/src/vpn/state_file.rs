@@ -0,0 +1,426 @@
+//! Versioned connection state file
+//!
+//! `akon vpn on` and the reconnection daemon record the connection state as
+//! JSON for `vpn status` and `vpn off` to read. Each file carries the
+//! `schema_version` it was written with: older layouts are migrated forward
+//! on read, and a file from a newer akon is refused rather than misread.
+//!
+//! [`VpnStateFile`] is the typed view of the file that akon reads and writes;
+//! the `Value`-level functions below it handle versioning.
+
+use crate::config::DEFAULT_PROFILE;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+/// Schema version this akon writes
+pub const STATE_SCHEMA_VERSION: u64 = 1;
+
+/// Environment variable naming another state file
+pub const STATE_FILE_ENV: &str = "AKON_STATE_FILE";
+
+/// State file tracking the VPN connection of `profile` (`AKON_STATE_FILE`
+/// overrides it)
+///
+/// Profiles other than the default one each get their own, next to the
+/// overridden file too.
+pub fn state_file_path(profile: Option<&str>) -> PathBuf {
+    let suffix = profile_file_suffix(profile);
+    match std::env::var(STATE_FILE_ENV) {
+        Ok(path) => with_profile_suffix(PathBuf::from(path), &suffix),
+        Err(_) => PathBuf::from(format!("/tmp/akon_vpn_state{}.json", suffix)),
+    }
+}
+
+/// `path` with `suffix` inserted before its extension
+fn with_profile_suffix(path: PathBuf, suffix: &str) -> PathBuf {
+    if suffix.is_empty() {
+        return path;
+    }
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
+
+/// `.<profile>` for a profile other than the default one, else empty
+pub fn profile_file_suffix(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) if profile != DEFAULT_PROFILE => format!(".{}", profile),
+        _ => String::new(),
+    }
+}
+
+/// What the connection was doing when the state file was written
+///
+/// Older akon versions wrote some states in lowercase; both spellings read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordedState {
+    /// Established; files from before the state was recorded for it have none
+    #[default]
+    #[serde(alias = "connected")]
+    Connected,
+    /// The reconnection daemon is retrying
+    #[serde(alias = "reconnecting")]
+    Reconnecting,
+    /// The reconnection daemon saw the connection end
+    #[serde(alias = "disconnected")]
+    Disconnected,
+    /// `vpn on` failed, or reconnection gave up
+    #[serde(alias = "error")]
+    Error,
+}
+
+/// Contents of the connection state file
+///
+/// Which fields are set depends on `state`; the constructors set the ones
+/// each state has.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VpnStateFile {
+    #[serde(default)]
+    pub state: RecordedState,
+    /// Connection label (the server when unnamed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Assigned IP address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+    /// Tunnel device
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// openconnect process
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    /// When the connection was established (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connected_at: Option<String>,
+    /// The IP is outside the usual range for the server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_unusual: Option<bool>,
+    /// Current reconnection attempt (1-indexed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attempt: Option<u32>,
+    /// Attempts the reconnection daemon makes, or made before giving up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    /// Unix time of the next reconnection attempt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_retry_at: Option<u64>,
+    /// IP of the connection being reconnected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_ip: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// [`crate::error::VpnError::kind`] of a failed `vpn on`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<String>,
+    /// What to try after a failed `vpn on`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+    /// When the file was last written (RFC 3339), except for `Connected`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+fn now_rfc3339() -> String {
+    chrono::Utc::now().to_rfc3339()
+}
+
+impl VpnStateFile {
+    /// An established connection
+    pub fn connected(
+        name: &str,
+        ip: IpAddr,
+        device: Option<&str>,
+        pid: Option<u32>,
+        ip_unusual: Option<bool>,
+    ) -> Self {
+        Self {
+            state: RecordedState::Connected,
+            name: Some(name.to_string()),
+            ip: Some(ip.to_string()),
+            device: device.map(str::to_string),
+            pid,
+            connected_at: Some(now_rfc3339()),
+            ip_unusual,
+            ..Self::default()
+        }
+    }
+
+    /// A reconnection in progress
+    pub fn reconnecting(
+        name: &str,
+        attempt: u32,
+        max_attempts: u32,
+        next_retry_at: Option<u64>,
+        last_ip: Option<String>,
+    ) -> Self {
+        Self {
+            state: RecordedState::Reconnecting,
+            name: Some(name.to_string()),
+            attempt: Some(attempt),
+            max_attempts: Some(max_attempts),
+            next_retry_at,
+            last_ip,
+            updated_at: Some(now_rfc3339()),
+            ..Self::default()
+        }
+    }
+
+    /// A connection that ended
+    pub fn disconnected(name: &str) -> Self {
+        Self {
+            state: RecordedState::Disconnected,
+            name: Some(name.to_string()),
+            updated_at: Some(now_rfc3339()),
+            ..Self::default()
+        }
+    }
+
+    /// A failed connection; set `error_kind`/`suggestions` or `max_attempts`
+    /// for what failed
+    pub fn error(name: &str, error: &str) -> Self {
+        Self {
+            state: RecordedState::Error,
+            name: Some(name.to_string()),
+            error: Some(error.to_string()),
+            updated_at: Some(now_rfc3339()),
+            ..Self::default()
+        }
+    }
+
+    /// IP of the connection, or of the one being reconnected
+    pub fn known_ip(&self) -> Option<&str> {
+        self.ip.as_deref().or(self.last_ip.as_deref())
+    }
+
+    /// Read the state file of `profile`, at [`state_file_path`]
+    pub fn load(profile: Option<&str>) -> Result<Self, StateFileError> {
+        Self::load_from(&state_file_path(profile))
+    }
+
+    /// Read the state file at `path`, upgrading older schemas
+    pub fn load_from(path: &Path) -> Result<Self, StateFileError> {
+        serde_json::from_value(read_state_file(path)?)
+            .map_err(|e| StateFileError::Parse(e.to_string()))
+    }
+
+    /// Write the state file of `profile`, at [`state_file_path`]
+    pub fn store(&self, profile: Option<&str>) -> std::io::Result<()> {
+        self.store_to(&state_file_path(profile))
+    }
+
+    /// Write the state file at `path`
+    pub fn store_to(&self, path: &Path) -> std::io::Result<()> {
+        write_state_file(path, &serde_json::to_value(self)?)
+    }
+}
+
+/// Upgrades from each older version to the next, indexed by the old version
+///
+/// Version 0 is a file from before versioning, whose layout version 1 kept.
+const MIGRATIONS: [fn(&mut Value); STATE_SCHEMA_VERSION as usize] = [|_| {}];
+
+/// Errors reading the state file
+#[derive(Debug, thiserror::Error)]
+pub enum StateFileError {
+    #[error("Failed to read state file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to parse state file: {0}")]
+    Parse(String),
+
+    #[error(
+        "This state was written by a newer akon (schema version {found}, \
+         this version reads up to {supported}); upgrade akon or delete the state file"
+    )]
+    NewerSchema { found: u64, supported: u64 },
+}
+
+/// Bring `state` up to [`STATE_SCHEMA_VERSION`]
+///
+/// A state without a `schema_version` is taken to be version 0.
+pub fn upgrade_state(mut state: Value) -> Result<Value, StateFileError> {
+    let fields = state
+        .as_object()
+        .ok_or_else(|| StateFileError::Parse("expected a JSON object".to_string()))?;
+    let found = match fields.get("schema_version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| StateFileError::Parse(format!("invalid schema_version: {}", version)))?,
+    };
+    if found > STATE_SCHEMA_VERSION {
+        return Err(StateFileError::NewerSchema {
+            found,
+            supported: STATE_SCHEMA_VERSION,
+        });
+    }
+
+    for migrate in &MIGRATIONS[found as usize..] {
+        migrate(&mut state);
+    }
+    state["schema_version"] = STATE_SCHEMA_VERSION.into();
+    Ok(state)
+}
+
+/// Read the state file at `path`, upgraded to the current schema
+pub fn read_state_file(path: &Path) -> Result<Value, StateFileError> {
+    let content = std::fs::read_to_string(path)?;
+    let state = serde_json::from_str(&content).map_err(|e| StateFileError::Parse(e.to_string()))?;
+    upgrade_state(state)
+}
+
+/// Write `state` to `path`, tagged with the current schema version
+pub fn write_state_file(path: &Path, state: &Value) -> std::io::Result<()> {
+    let mut state = state.clone();
+    if let Some(fields) = state.as_object_mut() {
+        fields.insert("schema_version".to_string(), STATE_SCHEMA_VERSION.into());
+    }
+    let json = serde_json::to_string_pretty(&state)?;
+    std::fs::write(path, json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn round_trip(state: &VpnStateFile) -> VpnStateFile {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        state.store_to(&path).unwrap();
+        VpnStateFile::load_from(&path).unwrap()
+    }
+
+    #[test]
+    fn test_overridden_state_file_is_kept_per_profile() {
+        let path = PathBuf::from("/run/akon/state.json");
+        assert_eq!(
+            with_profile_suffix(path.clone(), &profile_file_suffix(None)),
+            path
+        );
+        assert_eq!(
+            with_profile_suffix(path.clone(), &profile_file_suffix(Some("work"))),
+            PathBuf::from("/run/akon/state.work.json")
+        );
+        assert_eq!(
+            with_profile_suffix(PathBuf::from("/run/akon/state"), ".work"),
+            PathBuf::from("/run/akon/state.work")
+        );
+    }
+
+    #[test]
+    fn test_connected_state_round_trips() {
+        let state = VpnStateFile::connected(
+            "work",
+            "10.0.0.5".parse().unwrap(),
+            Some("tun0"),
+            Some(1234),
+            Some(false),
+        );
+        assert_eq!(round_trip(&state), state);
+        assert_eq!(state.known_ip(), Some("10.0.0.5"));
+        assert!(state.connected_at.is_some());
+    }
+
+    #[test]
+    fn test_reconnecting_state_round_trips() {
+        let state =
+            VpnStateFile::reconnecting("work", 2, 5, Some(1_767_268_800), Some("10.0.0.5".into()));
+        let read = round_trip(&state);
+        assert_eq!(read, state);
+        assert_eq!(read.state, RecordedState::Reconnecting);
+        assert_eq!(read.known_ip(), Some("10.0.0.5"));
+        assert_eq!(read.pid, None);
+    }
+
+    #[test]
+    fn test_disconnected_state_round_trips() {
+        let state = VpnStateFile::disconnected("work");
+        assert_eq!(round_trip(&state), state);
+    }
+
+    #[test]
+    fn test_error_states_round_trip() {
+        let connect_failed = VpnStateFile {
+            error_kind: Some("vpn.authentication_failed".to_string()),
+            suggestions: vec!["Verify your PIN is correct".to_string()],
+            ..VpnStateFile::error("work", "Authentication failed")
+        };
+        assert_eq!(round_trip(&connect_failed), connect_failed);
+
+        let gave_up = VpnStateFile {
+            max_attempts: Some(5),
+            ..VpnStateFile::error("work", "Max reconnection attempts (5) exceeded")
+        };
+        assert_eq!(round_trip(&gave_up), gave_up);
+    }
+
+    #[test]
+    fn test_older_files_read_with_defaults() {
+        // `vpn on` wrote no state for a connection, and states in either case
+        let connected: VpnStateFile =
+            serde_json::from_value(json!({"ip": "10.0.0.5", "pid": 1234})).unwrap();
+        assert_eq!(connected.state, RecordedState::Connected);
+        assert_eq!(connected.pid, Some(1234));
+
+        for spelling in ["Reconnecting", "reconnecting"] {
+            let state: VpnStateFile =
+                serde_json::from_value(json!({"state": spelling, "attempt": 1})).unwrap();
+            assert_eq!(state.state, RecordedState::Reconnecting);
+        }
+    }
+
+    #[test]
+    fn test_written_state_is_tagged_and_reads_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+
+        write_state_file(&path, &json!({"state": "Disconnected"})).unwrap();
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["schema_version"], STATE_SCHEMA_VERSION);
+        assert_eq!(read_state_file(&path).unwrap(), written);
+    }
+
+    #[test]
+    fn test_unversioned_state_is_upgraded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        std::fs::write(&path, r#"{"ip": "10.0.0.5", "pid": 1234}"#).unwrap();
+
+        let state = read_state_file(&path).unwrap();
+        assert_eq!(
+            state,
+            json!({"ip": "10.0.0.5", "pid": 1234, "schema_version": STATE_SCHEMA_VERSION})
+        );
+    }
+
+    #[test]
+    fn test_state_from_a_newer_akon_is_refused() {
+        let newer = STATE_SCHEMA_VERSION + 1;
+        let error = upgrade_state(json!({"schema_version": newer, "pid": 1234})).unwrap_err();
+        assert!(matches!(
+            error,
+            StateFileError::NewerSchema { found, supported }
+                if found == newer && supported == STATE_SCHEMA_VERSION
+        ));
+        assert!(error
+            .to_string()
+            .starts_with("This state was written by a newer akon"));
+
+        assert!(matches!(
+            upgrade_state(json!({"schema_version": "one"})),
+            Err(StateFileError::Parse(_))
+        ));
+        assert!(matches!(
+            upgrade_state(json!([1, 2])),
+            Err(StateFileError::Parse(_))
+        ));
+    }
+}
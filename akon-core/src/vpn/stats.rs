@@ -0,0 +1,113 @@
+//! Traffic counters of the tunnel device
+//!
+//! The kernel keeps byte counters for every interface under
+//! `/sys/class/net/<device>/statistics`; `akon vpn status` shows them for the
+//! tunnel device recorded in the state file. The counters start at zero when
+//! openconnect creates the device, so they cover the current connection.
+
+use crate::error::{AkonError, VpnError};
+use std::path::Path;
+
+/// Where the kernel exposes network interfaces
+const SYS_CLASS_NET: &str = "/sys/class/net";
+
+/// Bytes that went through an interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IfaceStats {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// Read the byte counters of `device`
+///
+/// Fails if the device is gone, e.g. because the connection dropped.
+pub fn read_interface_stats(device: &str) -> Result<IfaceStats, AkonError> {
+    read_interface_stats_from(Path::new(SYS_CLASS_NET), device)
+}
+
+/// Read the byte counters of `device` from a sysfs-style tree at `root`
+pub fn read_interface_stats_from(root: &Path, device: &str) -> Result<IfaceStats, AkonError> {
+    let failed = |reason: String| {
+        AkonError::Vpn(VpnError::NetworkError {
+            reason: format!("Cannot read traffic of {}: {}", device, reason),
+        })
+    };
+    // The name comes from the state file; keep it from escaping the tree
+    if device.is_empty() || device.contains('/') || device.starts_with('.') {
+        return Err(failed("not an interface name".to_string()));
+    }
+
+    let statistics = root.join(device).join("statistics");
+    let counter = |name: &str| {
+        std::fs::read_to_string(statistics.join(name))
+            .map_err(|e| failed(e.to_string()))?
+            .trim()
+            .parse::<u64>()
+            .map_err(|e| failed(format!("bad {}: {}", name, e)))
+    };
+
+    Ok(IfaceStats {
+        rx_bytes: counter("rx_bytes")?,
+        tx_bytes: counter("tx_bytes")?,
+    })
+}
+
+/// Format a byte count for people, e.g. "1.5 MiB"
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_reads_the_device_counters() {
+        let root = tempdir().unwrap();
+        let statistics = root.path().join("tun0").join("statistics");
+        std::fs::create_dir_all(&statistics).unwrap();
+        std::fs::write(statistics.join("rx_bytes"), "123456\n").unwrap();
+        std::fs::write(statistics.join("tx_bytes"), "789\n").unwrap();
+
+        assert_eq!(
+            read_interface_stats_from(root.path(), "tun0").unwrap(),
+            IfaceStats {
+                rx_bytes: 123456,
+                tx_bytes: 789,
+            }
+        );
+    }
+
+    #[test]
+    fn test_missing_or_odd_devices_fail() {
+        let root = tempdir().unwrap();
+
+        let error = read_interface_stats_from(root.path(), "tun0").unwrap_err();
+        assert!(error.to_string().contains("Cannot read traffic of tun0"));
+
+        for device in ["", "../tun0", ".."] {
+            assert!(read_interface_stats_from(root.path(), device).is_err());
+        }
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+}
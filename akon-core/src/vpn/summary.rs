@@ -0,0 +1,105 @@
+//! Connection summary for scripts
+//!
+//! `akon vpn on --output <file>` writes this summary once the tunnel is up,
+//! so orchestration scripts can read the connection details instead of
+//! scraping stdout or the internal state file, whose layout may change.
+
+use crate::error::{AkonError, ConfigError};
+use crate::vpn::Transport;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+/// Details of an established connection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionSummary {
+    /// Address assigned to the tunnel device
+    pub ip: IpAddr,
+    /// Tunnel device, e.g. `tun0`, if it is known
+    pub device: Option<String>,
+    /// PID of the openconnect process, if it was found
+    pub pid: Option<u32>,
+    /// When the connection was established (RFC 3339)
+    pub connected_at: String,
+    /// Transport in use when the connection was established
+    pub transport: Transport,
+}
+
+impl ConnectionSummary {
+    /// Summarize a connection established now
+    pub fn new(ip: IpAddr, device: Option<&str>, pid: Option<u32>, transport: Transport) -> Self {
+        Self {
+            ip,
+            device: device.map(str::to_string),
+            pid,
+            connected_at: chrono::Utc::now().to_rfc3339(),
+            transport,
+        }
+    }
+
+    /// Write the summary as JSON to `path`
+    ///
+    /// The file is written beside `path` and renamed into place, so a reader
+    /// never sees a partial summary.
+    pub fn write_to(&self, path: &Path) -> Result<(), AkonError> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| {
+            AkonError::Config(ConfigError::ValidationError {
+                message: format!("Failed to serialize connection summary: {}", e),
+            })
+        })?;
+
+        let mut temp_name = path.as_os_str().to_owned();
+        temp_name.push(".tmp");
+        let temp_path = PathBuf::from(temp_name);
+        let write_failed = |e: std::io::Error| {
+            AkonError::Config(ConfigError::IoError {
+                message: format!(
+                    "Failed to write connection summary to {}: {}",
+                    path.display(),
+                    e
+                ),
+            })
+        };
+
+        std::fs::write(&temp_path, json).map_err(write_failed)?;
+        std::fs::rename(&temp_path, path).map_err(|e| {
+            let _ = std::fs::remove_file(&temp_path);
+            write_failed(e)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_is_written_as_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("summary.json");
+        std::fs::write(&path, "stale").unwrap();
+
+        let summary = ConnectionSummary {
+            ip: "10.20.3.4".parse().unwrap(),
+            device: Some("tun0".to_string()),
+            pid: Some(4242),
+            connected_at: "2026-01-05T09:30:00+00:00".to_string(),
+            transport: Transport::Dtls,
+        };
+        summary.write_to(&path).unwrap();
+
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            written,
+            serde_json::json!({
+                "ip": "10.20.3.4",
+                "device": "tun0",
+                "pid": 4242,
+                "connected_at": "2026-01-05T09:30:00+00:00",
+                "transport": "dtls",
+            })
+        );
+        assert!(!dir.path().join("summary.json.tmp").exists());
+    }
+}
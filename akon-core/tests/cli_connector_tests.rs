@@ -1,6 +1,6 @@
 // Unit tests for CliConnector
 
-use akon_core::config::VpnConfig;
+use akon_core::config::{VpnConfig, VpnProtocol};
 use akon_core::vpn::{CliConnector, ConnectionState};
 use std::net::IpAddr;
 
@@ -42,7 +42,7 @@ fn test_connection_state_established() {
     let ip: IpAddr = "10.0.1.100".parse().unwrap();
     let state = ConnectionState::Established {
         ip,
-        device: "tun0".to_string(),
+        device: Some("tun0".to_string()),
     };
 
     match state {
@@ -51,7 +51,7 @@ fn test_connection_state_established() {
             device,
         } => {
             assert_eq!(state_ip.to_string(), "10.0.1.100");
-            assert_eq!(device, "tun0");
+            assert_eq!(device.as_deref(), Some("tun0"));
         }
         _ => panic!("Expected Established state"),
     }
@@ -69,3 +69,132 @@ fn test_is_connected_for_established_state() {
     // Note: We can't easily test state transitions without mocking the actual connection
     // This would require integration tests with mock OpenConnect process
 }
+
+#[test]
+fn test_openconnect_args_validate_certs_by_default() {
+    let config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    let connector = CliConnector::new(config).expect("Failed to create connector");
+
+    let args = connector.openconnect_args();
+
+    assert!(!args.contains(&"--no-cert-check".to_string()));
+    assert_eq!(args.last().map(String::as_str), Some("vpn.example.com"));
+}
+
+#[test]
+fn test_openconnect_args_insecure_skip_tls_verify() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    config.insecure_skip_tls_verify = true;
+    config.insecure_tls_acknowledged = true;
+    let connector = CliConnector::new(config).expect("Failed to create connector");
+
+    let args = connector.openconnect_args();
+
+    assert!(args.contains(&"--no-cert-check".to_string()));
+    assert_eq!(args.last().map(String::as_str), Some("vpn.example.com"));
+}
+
+#[test]
+fn test_openconnect_args_connect_through_jump_forward() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    config.jump_host = Some("alice@bastion.example.com".to_string());
+    config.jump_local_port = Some(20443);
+    let connector = CliConnector::new(config).expect("Failed to create connector");
+
+    let args = connector.openconnect_args();
+
+    let position = args
+        .iter()
+        .position(|arg| arg == "--resolve")
+        .expect("--resolve should be passed");
+    assert_eq!(args[position + 1], "vpn.example.com:127.0.0.1");
+    assert_eq!(
+        args.last().map(String::as_str),
+        Some("vpn.example.com:20443")
+    );
+}
+
+#[test]
+fn test_openconnect_args_pin_the_chosen_gateway() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    config.server_address = Some("203.0.113.2".parse().unwrap());
+    let connector = CliConnector::new(config).expect("Failed to create connector");
+
+    let args = connector.openconnect_args();
+
+    let position = args
+        .iter()
+        .position(|arg| arg == "--resolve")
+        .expect("--resolve should be passed");
+    assert_eq!(args[position + 1], "vpn.example.com:203.0.113.2");
+    assert_eq!(args.last().map(String::as_str), Some("vpn.example.com"));
+}
+
+#[test]
+fn test_openconnect_args_include_configured_user_agent() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    config.user_agent = Some("Custom Client 1.0".to_string());
+    let connector = CliConnector::new(config).expect("Failed to create connector");
+
+    let args = connector.openconnect_args();
+
+    let position = args
+        .iter()
+        .position(|arg| arg == "--useragent")
+        .expect("--useragent should be passed");
+    assert_eq!(args[position + 1], "Custom Client 1.0");
+    assert_eq!(args.last().map(String::as_str), Some("vpn.example.com"));
+}
+
+#[test]
+fn test_openconnect_args_pass_reconnect_timeout() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    // The connection timeout alone leaves openconnect's reconnect window alone
+    config.timeout = Some(30);
+    let connector = CliConnector::new(config.clone()).expect("Failed to create connector");
+    assert!(!connector
+        .openconnect_args()
+        .contains(&"--reconnect-timeout".to_string()));
+
+    config.reconnect_timeout = Some(45);
+    let connector = CliConnector::new(config).expect("Failed to create connector");
+    let args = connector.openconnect_args();
+    let position = args
+        .iter()
+        .position(|arg| arg == "--reconnect-timeout")
+        .expect("--reconnect-timeout should be passed");
+    assert_eq!(args[position + 1], "45");
+    assert_eq!(args.last().map(String::as_str), Some("vpn.example.com"));
+}
+
+#[test]
+fn test_openconnect_args_include_auth_group() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    let connector = CliConnector::new(config.clone()).expect("Failed to create connector");
+    assert!(!connector
+        .openconnect_args()
+        .contains(&"--authgroup".to_string()));
+
+    config.auth_group = Some("Employees".to_string());
+    let connector = CliConnector::new(config).expect("Failed to create connector");
+    let args = connector.openconnect_args();
+    let position = args.iter().position(|arg| arg == "--authgroup").unwrap();
+    assert_eq!(args[position + 1], "Employees");
+}
+
+#[test]
+fn test_openconnect_args_use_protocol_default_user_agent() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    config.protocol = VpnProtocol::GlobalProtect;
+    let connector = CliConnector::new(config).expect("Failed to create connector");
+    let args = connector.openconnect_args();
+    let position = args.iter().position(|arg| arg == "--useragent").unwrap();
+    assert_eq!(args[position + 1], "PAN GlobalProtect");
+
+    // F5 keeps openconnect's own User-Agent
+    let config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    let connector = CliConnector::new(config).expect("Failed to create connector");
+    assert!(!connector
+        .openconnect_args()
+        .contains(&"--useragent".to_string()));
+}
@@ -31,6 +31,7 @@ fn create_test_vpn_config() -> VpnConfig {
         timeout: Some(30),
         no_dtls: false,
         lazy_mode: false,
+        ..Default::default()
     }
 }
 
@@ -46,6 +47,7 @@ fn test_config_with_default_reconnection_policy() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 60,
         health_check_endpoint: "https://www.google.com".to_string(),
+        ..Default::default()
     };
 
     // Save and load
@@ -88,6 +90,7 @@ fn test_config_with_custom_reconnection_policy() {
         consecutive_failures_threshold: 5,
         health_check_interval_secs: 30,
         health_check_endpoint: "https://vpn-gateway.example.com/health".to_string(),
+        ..Default::default()
     };
 
     // Save and load
@@ -149,6 +152,7 @@ fn test_config_validation_rejects_invalid_max_attempts() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 60,
         health_check_endpoint: "https://www.google.com".to_string(),
+        ..Default::default()
     };
 
     let temp_dir = TempDir::new().unwrap();
@@ -171,6 +175,7 @@ fn test_config_validation_rejects_invalid_base_interval() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 60,
         health_check_endpoint: "https://www.google.com".to_string(),
+        ..Default::default()
     };
 
     let temp_dir = TempDir::new().unwrap();
@@ -193,6 +198,7 @@ fn test_config_validation_rejects_invalid_endpoint() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 60,
         health_check_endpoint: "not-a-valid-url".to_string(), // Invalid: not HTTP/HTTPS
+        ..Default::default()
     };
 
     let temp_dir = TempDir::new().unwrap();
@@ -220,6 +226,7 @@ fn test_backoff_calculation_respects_config() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 60,
         health_check_endpoint: "https://www.google.com".to_string(),
+        ..Default::default()
     };
 
     // Create reconnection manager
@@ -253,6 +260,7 @@ fn test_config_roundtrip_preserves_all_values() {
         timeout: Some(45),
         no_dtls: true,
         lazy_mode: true,
+        ..Default::default()
     };
 
     let reconnection_policy = ReconnectionPolicy {
@@ -263,6 +271,7 @@ fn test_config_roundtrip_preserves_all_values() {
         consecutive_failures_threshold: 4,
         health_check_interval_secs: 45,
         health_check_endpoint: "https://health.example.com/check".to_string(),
+        ..Default::default()
     };
 
     // Save and load
@@ -2,7 +2,8 @@
 //!
 //! Tests VpnConfig validation logic to ensure proper input validation.
 
-use akon_core::config::VpnConfig;
+use akon_core::config::{VpnConfig, VpnProtocol};
+use std::time::Duration;
 
 #[test]
 fn test_valid_config() {
@@ -42,6 +43,152 @@ fn test_zero_timeout() {
     assert_eq!(config.validate().unwrap_err(), "Timeout cannot be zero");
 }
 
+#[test]
+fn test_zero_reconnect_timeout() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    config.reconnect_timeout = Some(0);
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "Reconnect timeout cannot be zero"
+    );
+
+    config.reconnect_timeout = Some(600);
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_zero_pid_lookup_attempts() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    config.pid_lookup_attempts = Some(0);
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "PID lookup attempts cannot be zero"
+    );
+
+    config.pid_lookup_attempts = Some(40);
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_zero_cleanup_grace_secs() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    config.cleanup_grace_secs = Some(0);
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "Cleanup grace period cannot be zero"
+    );
+
+    config.cleanup_grace_secs = Some(10);
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_time_offset_is_bounded() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    assert_eq!(config.time_offset_secs, 0);
+
+    config.time_offset_secs = -3601;
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "Time offset must be between -3600 and 3600 seconds"
+    );
+
+    config.time_offset_secs = -45;
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_otp_digits_and_period_are_checked() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    assert_eq!((config.otp_digits, config.otp_period_secs), (6, 30));
+
+    config.otp_digits = 9;
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "OTP digits must be 6, 7 or 8"
+    );
+
+    config.otp_digits = 8;
+    config.otp_period_secs = 0;
+    assert_eq!(config.validate().unwrap_err(), "OTP period cannot be zero");
+
+    config.otp_period_secs = 60;
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_skew_windows_are_bounded() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    assert_eq!(config.skew_windows, 0);
+
+    config.skew_windows = 4;
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "Skew windows must be at most 3"
+    );
+
+    config.skew_windows = 1;
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_dns_retries_back_off_until_used_up() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    assert_eq!(config.dns_retry_delay(1), Some(Duration::from_secs(2)));
+    assert_eq!(config.dns_retry_delay(3), Some(Duration::from_secs(6)));
+    assert_eq!(config.dns_retry_delay(4), None);
+
+    config.dns_retries = Some(0);
+    assert_eq!(config.dns_retry_delay(1), None);
+
+    config.dns_retries = Some(1);
+    config.dns_retry_interval_secs = Some(0);
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "DNS retry interval cannot be zero"
+    );
+    config.dns_retry_interval_secs = Some(5);
+    assert!(config.validate().is_ok());
+    assert_eq!(config.dns_retry_delay(1), Some(Duration::from_secs(5)));
+}
+
+#[test]
+fn test_prompt_responses_prefer_the_most_specific_prompt() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    assert_eq!(config.prompt_response("Realm:"), None);
+
+    config
+        .prompt_responses
+        .insert("Realm".to_string(), "Employees".to_string());
+    config
+        .prompt_responses
+        .insert("Secondary Realm".to_string(), "Staff".to_string());
+    assert_eq!(config.prompt_response("Realm:"), Some("Employees"));
+    assert_eq!(config.prompt_response("Secondary Realm:"), Some("Staff"));
+    assert!(config.validate().is_ok());
+
+    config
+        .prompt_responses
+        .insert(" ".to_string(), "yes".to_string());
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "Prompt response keys cannot be empty"
+    );
+}
+
+#[test]
+fn test_zero_max_auth_attempts_per_hour() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    config.max_auth_attempts_per_hour = Some(0);
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "Max auth attempts per hour cannot be zero"
+    );
+
+    config.max_auth_attempts_per_hour = Some(10);
+    assert!(config.validate().is_ok());
+}
+
 #[test]
 fn test_valid_config_with_optional_fields() {
     let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
@@ -61,6 +208,626 @@ fn test_server_with_numbers() {
     assert!(config.validate().is_ok());
 }
 
+#[test]
+fn test_insecure_tls_requires_acknowledgment() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    config.insecure_skip_tls_verify = true;
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "insecure_skip_tls_verify requires insecure_tls_acknowledged = true"
+    );
+
+    config.insecure_tls_acknowledged = true;
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_insecure_tls_warning_emitted_only_when_enabled() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "testuser".to_string());
+    assert!(config.insecure_tls_warning().is_none());
+
+    config.insecure_skip_tls_verify = true;
+    config.insecure_tls_acknowledged = true;
+    let warning = config.insecure_tls_warning().expect("warning expected");
+    assert!(warning.contains("DISABLED"));
+    assert!(warning.contains("vpn.example.com"));
+}
+
+#[test]
+fn test_default_user_agent_per_protocol() {
+    assert_eq!(VpnProtocol::F5.default_user_agent(), None);
+    assert_eq!(VpnProtocol::Fortinet.default_user_agent(), None);
+    assert_eq!(
+        VpnProtocol::GlobalProtect.default_user_agent(),
+        Some("PAN GlobalProtect")
+    );
+    assert!(VpnProtocol::AnyConnect
+        .default_user_agent()
+        .unwrap()
+        .starts_with("AnyConnect"));
+}
+
+#[test]
+fn test_configured_user_agent_overrides_protocol_default() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "user".to_string());
+    config.protocol = VpnProtocol::GlobalProtect;
+    assert_eq!(config.effective_user_agent(), Some("PAN GlobalProtect"));
+
+    config.user_agent = Some("Custom Client 1.0".to_string());
+    assert_eq!(config.effective_user_agent(), Some("Custom Client 1.0"));
+    assert!(config.validate().is_ok());
+
+    config.user_agent = Some("  ".to_string());
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_empty_egress_interface() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "user".to_string());
+    config.egress_interface = Some("".to_string());
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "Egress interface cannot be empty"
+    );
+
+    config.egress_interface = Some("eth0".to_string());
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_jump_host_validation() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "user".to_string());
+    assert_eq!(config.jump_forward_port(), None);
+
+    config.jump_local_port = Some(20443);
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "jump_local_port requires jump_host"
+    );
+
+    config.jump_host = Some("alice@bastion.example.com:2222".to_string());
+    assert!(config.validate().is_ok());
+    assert_eq!(config.jump_forward_port(), Some(20443));
+
+    config.jump_local_port = None;
+    assert_eq!(config.jump_forward_port(), Some(10443));
+
+    config.jump_local_port = Some(443);
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "Jump local port must be 1024 or higher"
+    );
+
+    config.jump_local_port = None;
+    config.jump_host = Some("-oProxyCommand=sh".to_string());
+    assert!(config.validate().is_err());
+
+    config.jump_host = Some("bastion".to_string());
+    config.egress_interface = Some("eth0".to_string());
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "egress_interface cannot be combined with jump_host"
+    );
+}
+
+#[test]
+fn test_kill_switch_config() {
+    let config: VpnConfig = toml::from_str(
+        r#"
+        server = "vpn.example.com"
+        username = "user"
+        "#,
+    )
+    .unwrap();
+    assert!(!config.kill_switch);
+
+    let mut config: VpnConfig = toml::from_str(
+        r#"
+        server = "vpn.example.com"
+        username = "user"
+        kill_switch = true
+        "#,
+    )
+    .unwrap();
+    assert!(config.kill_switch);
+    assert!(config.validate().is_ok());
+
+    config.jump_host = Some("bastion".to_string());
+    assert_eq!(
+        config.validate().unwrap_err(),
+        "kill_switch cannot be combined with jump_host"
+    );
+}
+
+#[test]
+fn test_stored_credentials_default_to_keyring() {
+    let config: VpnConfig = toml::from_str(
+        r#"
+        server = "vpn.example.com"
+        username = "user"
+        "#,
+    )
+    .unwrap();
+    assert!(config.store_pin);
+    assert!(config.store_secret);
+
+    let config: VpnConfig = toml::from_str(
+        r#"
+        server = "vpn.example.com"
+        username = "user"
+        store_pin = false
+        "#,
+    )
+    .unwrap();
+    assert!(!config.store_pin);
+    assert!(config.store_secret);
+}
+
+#[test]
+fn test_display_name_falls_back_to_server() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "user".to_string());
+    assert_eq!(config.display_name(), "vpn.example.com");
+
+    config.name = Some("work".to_string());
+    assert_eq!(config.display_name(), "work");
+    assert!(config.validate().is_ok());
+
+    config.name = Some(" ".to_string());
+    assert_eq!(config.validate().unwrap_err(), "Name cannot be empty");
+}
+
+#[test]
+fn test_verify_default_route_defaults_to_off() {
+    use akon_core::vpn::route_check::RouteCheck;
+
+    let config: VpnConfig = toml::from_str(
+        r#"
+        server = "vpn.example.com"
+        username = "user"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.verify_default_route, RouteCheck::Off);
+
+    let config: VpnConfig = toml::from_str(
+        r#"
+        server = "vpn.example.com"
+        username = "user"
+        verify_default_route = "enforce"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.verify_default_route, RouteCheck::Enforce);
+}
+
+#[test]
+fn test_openconnect_path_must_be_executable() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let binary = dir.path().join("openconnect");
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "user".to_string());
+
+    config.openconnect_path = Some(binary.clone());
+    assert!(config.validate().unwrap_err().contains("cannot be used"));
+
+    config.openconnect_path = Some(dir.path().to_path_buf());
+    assert!(config.validate().unwrap_err().contains("is not a file"));
+
+    config.openconnect_path = Some(binary.clone());
+    std::fs::write(&binary, "#!/bin/sh\n").unwrap();
+    std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o644)).unwrap();
+    assert!(config.validate().unwrap_err().contains("is not executable"));
+
+    std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_privilege_escalation_defaults_to_sudo() {
+    use akon_core::config::PrivilegeEscalation;
+
+    let config: VpnConfig = toml::from_str(
+        r#"
+        server = "vpn.example.com"
+        username = "user"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.privilege_escalation, PrivilegeEscalation::Sudo);
+
+    for (value, expected) in [
+        ("pkexec", PrivilegeEscalation::Pkexec),
+        ("none", PrivilegeEscalation::None),
+    ] {
+        let config: VpnConfig = toml::from_str(&format!(
+            "server = \"vpn.example.com\"\nusername = \"user\"\nprivilege_escalation = \"{}\"",
+            value
+        ))
+        .unwrap();
+        assert_eq!(config.privilege_escalation, expected);
+    }
+}
+
+#[test]
+fn test_privilege_escalation_wraps_the_command() {
+    use akon_core::config::PrivilegeEscalation;
+
+    let command = PrivilegeEscalation::Pkexec.command("openconnect");
+    assert_eq!(command.get_program(), "pkexec");
+    assert_eq!(command.get_args().collect::<Vec<_>>(), ["openconnect"]);
+
+    let command = PrivilegeEscalation::None.command("openconnect");
+    assert_eq!(command.get_program(), "openconnect");
+    assert_eq!(command.get_args().count(), 0);
+}
+
+#[test]
+fn test_verify_routes_are_validated() {
+    let mut config = VpnConfig::new("vpn.example.com".to_string(), "user".to_string());
+    assert!(config.verify_routes.is_empty());
+
+    config.verify_routes = vec!["10.20.0.0/16".to_string(), "intranet.example".to_string()];
+    assert!(config.validate().is_ok());
+
+    config.verify_routes.push("10.0.0.0/40".to_string());
+    let error = config.validate().unwrap_err();
+    assert!(
+        error.contains("prefix length must be at most 32"),
+        "{}",
+        error
+    );
+}
+
+// ===== Environment variable interpolation =====
+
+mod interpolation_tests {
+    use akon_core::config::toml_config::{save_auth_group_to_path, TomlConfig};
+    use akon_core::error::{AkonError, ConfigError};
+    use tempfile::tempdir;
+
+    fn write_config(contents: &str) -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, contents).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn test_from_file_resolves_env_vars() {
+        std::env::set_var("AKON_TEST_INTERP_HOST", "vpn.example.com");
+        std::env::set_var("AKON_TEST_INTERP_USER", "alice");
+        let (_dir, path) = write_config(
+            r#"
+[vpn]
+server = "${AKON_TEST_INTERP_HOST}"
+username = "${AKON_TEST_INTERP_USER}"
+"#,
+        );
+
+        let config = TomlConfig::from_file(&path).unwrap();
+        assert_eq!(config.vpn_config.server, "vpn.example.com");
+        assert_eq!(config.vpn_config.username, "alice");
+    }
+
+    #[test]
+    fn test_from_file_errors_on_unset_env_var() {
+        std::env::remove_var("AKON_TEST_INTERP_UNSET");
+        let (_dir, path) = write_config(
+            r#"
+[vpn]
+server = "${AKON_TEST_INTERP_UNSET}"
+username = "alice"
+"#,
+        );
+
+        match TomlConfig::from_file(&path) {
+            Err(AkonError::Config(ConfigError::UnsetEnvVar { name })) => {
+                assert_eq!(name, "AKON_TEST_INTERP_UNSET");
+            }
+            other => panic!("Expected UnsetEnvVar error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_from_file_leaves_hook_commands_to_the_shell() {
+        std::env::remove_var("AKON_SERVER");
+        let (_dir, path) = write_config(
+            r#"
+[vpn]
+server = "vpn.example.com"
+username = "alice"
+
+[reconnection]
+health_check_endpoint = "https://vpn.example.com/health"
+on_reconnect_success = "logger \"akon: back on ${AKON_SERVER}\""
+"#,
+        );
+
+        let config = TomlConfig::from_file(&path).unwrap();
+        assert_eq!(
+            config.reconnection.unwrap().on_reconnect_success.as_deref(),
+            Some("logger \"akon: back on ${AKON_SERVER}\"")
+        );
+    }
+
+    #[test]
+    fn test_from_file_rejects_server_alias_without_server() {
+        let (_dir, path) = write_config(
+            r#"
+[vpn]
+server = ""
+username = "alice"
+
+[reconnection]
+health_check_endpoint = "@server"
+"#,
+        );
+
+        let err = TomlConfig::from_file(&path).unwrap_err().to_string();
+        assert!(err.contains("@server"), "unexpected error: {}", err);
+    }
+
+    #[test]
+    fn test_saving_auth_group_keeps_env_references() {
+        let (_dir, path) = write_config(
+            r#"
+[vpn]
+server = "vpn.example.com"
+username = "${AKON_TEST_SAVE_GROUP_USER}"
+"#,
+        );
+
+        save_auth_group_to_path("Contractors", None, &path).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("${AKON_TEST_SAVE_GROUP_USER}"));
+        std::env::set_var("AKON_TEST_SAVE_GROUP_USER", "alice");
+        let config = TomlConfig::from_file(&path).unwrap();
+        assert_eq!(config.vpn_config.auth_group.as_deref(), Some("Contractors"));
+        assert_eq!(config.vpn_config.username, "alice");
+    }
+
+    #[test]
+    fn test_from_file_keeps_escaped_dollar_literal() {
+        let (_dir, path) = write_config(
+            r#"
+[vpn]
+server = "vpn.example.com"
+username = "$${NOT_A_VAR}"
+"#,
+        );
+
+        let config = TomlConfig::from_file(&path).unwrap();
+        assert_eq!(config.vpn_config.username, "${NOT_A_VAR}");
+    }
+}
+
+mod profile_tests {
+    use akon_core::config::toml_config::{
+        save_auth_group_to_path, save_default_profile_to_path, save_profile_to_path, TomlConfig,
+    };
+    use akon_core::config::VpnConfig;
+    use tempfile::tempdir;
+
+    const PROFILES: &str = r#"
+default_profile = "work"
+
+[vpn]
+server = "vpn.example.com"
+username = "alice"
+
+[profiles.work]
+server = "work.example.com"
+username = "alice"
+
+[profiles.lab]
+server = "lab.example.com"
+username = "bob"
+"#;
+
+    fn load(contents: &str) -> Result<TomlConfig, akon_core::error::AkonError> {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, contents).unwrap();
+        TomlConfig::from_file(&path)
+    }
+
+    #[test]
+    fn test_vpn_section_loads_as_default_profile() {
+        let config = load(
+            r#"
+[vpn]
+server = "vpn.example.com"
+username = "alice"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.profile_names(), ["default"]);
+        let default = config.profile(None).unwrap();
+        assert_eq!(default.server, "vpn.example.com");
+        assert_eq!(default.profile.as_deref(), Some("default"));
+        // Credentials stay where they were before profiles existed
+        assert_eq!(default.keyring_account(), "alice");
+    }
+
+    #[test]
+    fn test_profiles_are_selected_by_name_or_default_profile() {
+        let config = load(PROFILES).unwrap();
+        assert_eq!(config.profile_names(), ["default", "lab", "work"]);
+
+        let work = config.profile(None).unwrap();
+        assert_eq!(work.server, "work.example.com");
+        assert_eq!(work.keyring_account(), "work:alice");
+
+        let lab = config.profile(Some("lab")).unwrap();
+        assert_eq!(lab.server, "lab.example.com");
+        assert_eq!(lab.keyring_account(), "lab:bob");
+
+        let default = config.profile(Some("default")).unwrap();
+        assert_eq!(default.server, "vpn.example.com");
+        assert_eq!(default.keyring_account(), "alice");
+
+        let err = config.profile(Some("home")).unwrap_err().to_string();
+        assert!(
+            err.contains("No profile named 'home'") && err.contains("default, lab, work"),
+            "unexpected error: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_invalid_profile_names_are_rejected() {
+        let err = load(&PROFILES.replace("[profiles.lab]", "[profiles.\"my lab\"]"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Invalid profile name 'my lab'"), "{}", err);
+
+        let err =
+            load(&PROFILES.replace(r#"default_profile = "work""#, r#"default_profile = "home""#))
+                .unwrap_err()
+                .to_string();
+        assert!(err.contains("default_profile 'home'"), "{}", err);
+    }
+
+    #[test]
+    fn test_saving_a_profile_keeps_the_rest_of_the_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, PROFILES).unwrap();
+
+        let mut home = VpnConfig::new("home.example.com".to_string(), "carol".to_string());
+        home.profile = Some("home".to_string());
+        save_profile_to_path(&home, None, &path).unwrap();
+
+        let config = TomlConfig::from_file(&path).unwrap();
+        assert_eq!(config.profile_names(), ["default", "home", "lab", "work"]);
+        assert_eq!(config.profile(Some("home")).unwrap().username, "carol");
+        assert_eq!(config.vpn_config.server, "vpn.example.com");
+        assert_eq!(config.default_profile.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn test_saving_a_value_goes_to_the_given_profile() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, PROFILES).unwrap();
+
+        save_auth_group_to_path("Contractors", Some("lab"), &path).unwrap();
+        let config = TomlConfig::from_file(&path).unwrap();
+        assert_eq!(
+            config.profile(Some("lab")).unwrap().auth_group.as_deref(),
+            Some("Contractors")
+        );
+        assert_eq!(config.profile(Some("work")).unwrap().auth_group, None);
+        assert_eq!(config.vpn_config.auth_group, None);
+    }
+
+    #[test]
+    fn test_saving_keeps_comments_and_order() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let commented = r#"# Managed by hand
+[vpn]
+# Gateway of the office
+server = "vpn.example.com"
+username = "alice" # same as the SSO login
+auth_group = "Staff"
+"#;
+        std::fs::write(&path, commented).unwrap();
+
+        save_auth_group_to_path("Contractors", None, &path).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, commented.replace("\"Staff\"", "\"Contractors\""));
+
+        let mut vpn = VpnConfig::new("vpn2.example.com".to_string(), "alice".to_string());
+        vpn.profile = Some("default".to_string());
+        save_profile_to_path(&vpn, None, &path).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("# Managed by hand\n[vpn]\n# Gateway of the office\nserver = \"vpn2.example.com\"\n"), "{}", contents);
+        assert!(
+            contents.contains("username = \"alice\" # same as the SSO login"),
+            "{}",
+            contents
+        );
+        assert!(!contents.contains("auth_group"), "{}", contents);
+        assert_eq!(
+            TomlConfig::from_file(&path).unwrap().vpn_config.server,
+            "vpn2.example.com"
+        );
+    }
+
+    #[test]
+    fn test_switching_the_default_profile() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, PROFILES).unwrap();
+
+        save_default_profile_to_path("lab", &path).unwrap();
+        let config = TomlConfig::from_file(&path).unwrap();
+        assert_eq!(config.default_profile.as_deref(), Some("lab"));
+        assert_eq!(config.profile(None).unwrap().server, "lab.example.com");
+
+        let err = save_default_profile_to_path("home", &path)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("No profile named 'home'"),
+            "unexpected error: {}",
+            err
+        );
+        assert_eq!(
+            TomlConfig::from_file(&path)
+                .unwrap()
+                .default_profile
+                .as_deref(),
+            Some("lab")
+        );
+    }
+
+    #[test]
+    fn test_profiles_can_replace_the_reconnection_policy() {
+        let config = load(&format!(
+            r#"{}
+[reconnection]
+health_check_endpoint = "https://intranet.example.com/"
+
+[reconnection_profiles.lab]
+health_check_endpoint = "@server"
+max_attempts = 2
+"#,
+            PROFILES
+        ))
+        .unwrap();
+
+        let lab = config.reconnection_policy_for(Some("lab")).unwrap();
+        assert_eq!(lab.max_attempts, 2);
+        assert_eq!(lab.health_check_endpoint, "@server");
+        // "work" is the default profile and has no policy of its own
+        let work = config.reconnection_policy_for(None).unwrap();
+        assert_eq!(work.health_check_endpoint, "https://intranet.example.com/");
+    }
+
+    #[test]
+    fn test_reconnection_policy_of_unknown_profile_is_rejected() {
+        let err = load(&format!(
+            r#"{}
+[reconnection_profiles.home]
+health_check_endpoint = "@server"
+"#,
+            PROFILES
+        ))
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.contains("reconnection_profiles.home"),
+            "unexpected error: {}",
+            err
+        );
+    }
+}
+
 // ===== ReconnectionPolicy Tests (T039) =====
 
 mod reconnection_policy_tests {
@@ -115,6 +882,209 @@ mod reconnection_policy_tests {
         );
     }
 
+    #[test]
+    fn test_parse_reconnection_hooks() {
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+            on_reconnect_attempt = "logger akon reconnecting"
+            on_reconnect_success = "systemctl --user restart ssh-tunnel"
+        "#;
+
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            policy.on_reconnect_attempt.as_deref(),
+            Some("logger akon reconnecting")
+        );
+        assert_eq!(
+            policy.on_reconnect_success.as_deref(),
+            Some("systemctl --user restart ssh-tunnel")
+        );
+        assert!(policy.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_empty_hook_commands() {
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+            on_reconnect_success = "   "
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        let err = policy.validate().unwrap_err().to_string();
+        assert!(err.contains("on_reconnect_success"));
+    }
+
+    #[test]
+    fn test_validate_circuit_breaker_settings() {
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+            circuit_breaker_threshold = 3
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert_eq!(policy.circuit_breaker_threshold, Some(3));
+        assert_eq!(policy.circuit_breaker_cooldown_secs, 1800); // default
+        assert!(policy.validate().is_ok());
+
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+            circuit_breaker_threshold = 0
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert!(policy
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("circuit_breaker_threshold"));
+
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+            circuit_breaker_cooldown_secs = 5
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert!(policy
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("circuit_breaker_cooldown_secs"));
+    }
+
+    #[test]
+    fn test_reconnect_on_defaults_and_validation() {
+        use akon_core::vpn::DisconnectReason;
+
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert!(!policy.reconnects_on(DisconnectReason::UserRequested));
+        assert!(policy.reconnects_on(DisconnectReason::ServerDisconnect));
+        assert!(policy.reconnects_on(DisconnectReason::NetworkDown));
+
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+            reconnect_on = ["network_down", "timeout"]
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            policy.reconnect_on,
+            vec![DisconnectReason::NetworkDown, DisconnectReason::Timeout]
+        );
+        assert!(policy.validate().is_ok());
+
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+            reconnect_on = ["user_requested", "network_down"]
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert!(policy
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("reconnect_on"));
+
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+            reconnect_on = ["sunspots"]
+        "#;
+        assert!(toml::from_str::<ReconnectionPolicy>(toml_str).is_err());
+    }
+
+    #[test]
+    fn test_disconnect_at_is_validated() {
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+            disconnect_at = "18:30"
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert!(policy.validate().is_ok());
+        assert_eq!(
+            policy.disconnect_schedule().unwrap().unwrap().to_string(),
+            "18:30"
+        );
+
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+            disconnect_at = "6pm"
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert!(policy
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("disconnect_at"));
+    }
+
+    #[test]
+    fn test_pause_while_locked_is_opt_in() {
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert!(!policy.pause_while_locked);
+
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+            pause_while_locked = true
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert!(policy.pause_while_locked);
+    }
+
+    #[test]
+    fn test_daemon_log_must_be_absolute() {
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+            daemon_log = "/var/tmp/akon-daemon.log"
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            policy.daemon_log.as_deref(),
+            Some(std::path::Path::new("/var/tmp/akon-daemon.log"))
+        );
+        assert!(policy.validate().is_ok());
+
+        let toml_str = r#"
+            health_check_endpoint = "https://vpn.example.com/health"
+            daemon_log = "daemon.log"
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert!(policy
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("daemon_log"));
+    }
+
+    #[test]
+    fn test_server_alias_resolves_to_vpn_gateway() {
+        let toml_str = r#"
+            health_check_endpoint = "@server"
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert!(policy.validate().is_ok());
+
+        assert_eq!(
+            policy
+                .resolve_health_check_endpoint("vpn.example.com")
+                .unwrap(),
+            "https://vpn.example.com/"
+        );
+        assert!(policy.resolve_health_check_endpoint("").is_err());
+    }
+
+    #[test]
+    fn test_explicit_endpoint_is_not_substituted() {
+        let toml_str = r#"
+            health_check_endpoint = "https://intranet.example.com/health"
+        "#;
+        let policy: ReconnectionPolicy = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            policy
+                .resolve_health_check_endpoint("vpn.example.com")
+                .unwrap(),
+            "https://intranet.example.com/health"
+        );
+    }
+
     #[test]
     fn test_validate_max_attempts_range() {
         // max_attempts must be 1-20
@@ -209,6 +1179,23 @@ mod reconnection_policy_tests {
         assert!(policy.validate().is_ok());
     }
 
+    #[test]
+    fn test_validate_tcp_and_icmp_health_check_endpoints() {
+        for endpoint in ["tcp://10.0.0.1:22", "icmp://intranet.example.com"] {
+            let policy = ReconnectionPolicy {
+                health_check_endpoint: endpoint.to_string(),
+                ..Default::default()
+            };
+            assert!(policy.validate().is_ok(), "{} rejected", endpoint);
+        }
+
+        let policy = ReconnectionPolicy {
+            health_check_endpoint: "tcp://10.0.0.1".to_string(),
+            ..Default::default()
+        };
+        assert!(policy.validate().unwrap_err().to_string().contains("port"));
+    }
+
     #[test]
     fn test_invalid_config_returns_error() {
         // Test that invalid configs return descriptive errors
@@ -226,3 +1213,80 @@ mod reconnection_policy_tests {
         assert!(err.contains("max_attempts"));
     }
 }
+
+mod template_tests {
+    use akon_core::config::template::config_template;
+    use akon_core::config::toml_config::TomlConfig;
+    use akon_core::config::{BehaviorConfig, VpnConfig};
+    use akon_core::vpn::reconnection::ReconnectionPolicy;
+    use tempfile::tempdir;
+
+    /// Field names as seen by serde, so new fields can't be missed
+    fn field_names<T: serde::Serialize>(value: &T) -> Vec<String> {
+        match serde_json::to_value(value).unwrap() {
+            serde_json::Value::Object(map) => map.keys().cloned().collect(),
+            other => panic!("expected a struct, got {}", other),
+        }
+    }
+
+    fn documents_field(template: &str, field: &str) -> bool {
+        template.lines().any(|line| {
+            let line = line.trim_start_matches('#').trim_start();
+            line.strip_prefix(field)
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+        })
+    }
+
+    #[test]
+    fn test_template_documents_every_vpn_field() {
+        let template = config_template(false);
+
+        for field in field_names(&VpnConfig::default()) {
+            assert!(
+                documents_field(&template, &field),
+                "missing field: {}",
+                field
+            );
+        }
+        assert!(!template.contains("[reconnection]"));
+    }
+
+    #[test]
+    fn test_template_documents_every_behavior_field() {
+        let template = config_template(false);
+
+        for field in field_names(&BehaviorConfig::default()) {
+            assert!(
+                documents_field(&template, &field),
+                "missing field: {}",
+                field
+            );
+        }
+    }
+
+    #[test]
+    fn test_template_documents_every_reconnection_field() {
+        let template = config_template(true);
+        let policy: ReconnectionPolicy =
+            toml::from_str(r#"health_check_endpoint = "https://example.com/""#).unwrap();
+
+        for field in field_names(&policy) {
+            assert!(
+                documents_field(&template, &field),
+                "missing field: {}",
+                field
+            );
+        }
+    }
+
+    #[test]
+    fn test_template_is_a_valid_config() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, config_template(true)).unwrap();
+
+        let config = TomlConfig::from_file(&path).unwrap();
+        assert_eq!(config.vpn_config.server, "vpn.example.com");
+        assert!(config.reconnection.is_some());
+    }
+}
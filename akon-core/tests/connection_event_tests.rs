@@ -17,13 +17,13 @@ fn test_connection_event_connected_with_ip() {
     let ip: IpAddr = "10.0.1.100".parse().unwrap();
     let event = ConnectionEvent::Connected {
         ip,
-        device: "tun0".to_string(),
+        device: Some("tun0".to_string()),
     };
 
     match event {
         ConnectionEvent::Connected { ip: evt_ip, device } => {
             assert_eq!(evt_ip.to_string(), "10.0.1.100");
-            assert_eq!(device, "tun0");
+            assert_eq!(device.as_deref(), Some("tun0"));
         }
         _ => panic!("Expected Connected event"),
     }
@@ -34,11 +34,11 @@ fn test_connection_event_equality() {
     let ip: IpAddr = "10.0.1.100".parse().unwrap();
     let event1 = ConnectionEvent::Connected {
         ip,
-        device: "tun0".to_string(),
+        device: Some("tun0".to_string()),
     };
     let event2 = ConnectionEvent::Connected {
         ip,
-        device: "tun0".to_string(),
+        device: Some("tun0".to_string()),
     };
 
     assert_eq!(event1, event2);
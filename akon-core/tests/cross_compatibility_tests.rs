@@ -143,26 +143,40 @@ fn test_complete_password_format() {
 
     // Generate OTP with fixed timestamp for reproducibility
     let timestamp = 1234567890u64;
-    let otp = totp::generate_otp(&secret, Some(timestamp)).expect("Valid OTP");
 
-    // Combine into password
-    let password = VpnPassword::from_components(&pin, &otp);
+    for digits in 6..=8 {
+        let settings = totp::TotpSettings {
+            digits,
+            ..totp::TotpSettings::default()
+        };
+        let otp = settings
+            .generate(&secret, Some(timestamp))
+            .expect("Valid OTP");
 
-    // Verify format
-    let password_str = password.expose();
-    assert_eq!(
-        password_str.len(),
-        10,
-        "Password should be exactly 10 characters"
-    );
-    assert!(
-        password_str.chars().all(|c| c.is_ascii_digit()),
-        "Password should be all digits"
-    );
-    assert!(
-        password_str.starts_with("1234"),
-        "Password should start with PIN"
-    );
+        // Combine into password
+        let password = VpnPassword::from_components(&pin, &otp);
+
+        // Verify format: the PIN followed by the whole OTP
+        let password_str = password.expose();
+        assert_eq!(
+            password_str.len(),
+            4 + digits as usize,
+            "Password should be the PIN plus {} OTP digits",
+            digits
+        );
+        assert!(
+            password_str.chars().all(|c| c.is_ascii_digit()),
+            "Password should be all digits"
+        );
+        assert!(
+            password_str.starts_with("1234"),
+            "Password should start with PIN"
+        );
+        assert!(
+            password_str.ends_with(otp.expose()),
+            "Password should end with the OTP"
+        );
+    }
 }
 
 /// Test HOTP counter calculation matches auto-openconnect
@@ -324,3 +338,58 @@ fn test_dynamic_truncation() {
         );
     }
 }
+
+/// RFC 6238 Appendix B test vectors for all three algorithms
+///
+/// The RFC lists 8-digit codes; akon's 6-digit codes are their last six
+/// digits. Each algorithm has its own seed, the ASCII digits repeated to the
+/// hash's output length.
+#[test]
+fn test_rfc6238_vectors_for_each_algorithm() {
+    use totp::OtpAlgorithm;
+
+    let seeds = [
+        (OtpAlgorithm::Sha1, "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"),
+        (
+            OtpAlgorithm::Sha256,
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZA",
+        ),
+        (
+            OtpAlgorithm::Sha512,
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\
+             GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNA",
+        ),
+    ];
+    // (time, SHA-1, SHA-256, SHA-512)
+    let vectors = [
+        (59u64, "94287082", "46119246", "90693936"),
+        (1111111109, "07081804", "68084774", "25091201"),
+        (1111111111, "14050471", "67062674", "99943326"),
+        (1234567890, "89005924", "91819424", "93441116"),
+        (2000000000, "69279037", "90698825", "38618901"),
+        (20000000000, "65353130", "77737706", "47863826"),
+    ];
+
+    for (time, sha1, sha256, sha512) in vectors {
+        for ((algorithm, seed), expected) in seeds.iter().zip([sha1, sha256, sha512]) {
+            let secret = OtpSecret::new(seed.to_string());
+            let otp = totp::generate_otp_with_algorithm(&secret, Some(time), *algorithm)
+                .expect("Should generate OTP");
+            assert_eq!(
+                otp.expose(),
+                &expected[2..],
+                "{:?} at time {}",
+                algorithm,
+                time
+            );
+        }
+    }
+
+    // SHA-1 stays the default
+    let secret = OtpSecret::new(seeds[0].1.to_string());
+    assert_eq!(
+        totp::generate_otp(&secret, Some(59)).unwrap().expose(),
+        "287082"
+    );
+    assert_eq!(OtpAlgorithm::default(), OtpAlgorithm::Sha1);
+}
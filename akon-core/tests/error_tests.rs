@@ -54,3 +54,141 @@ fn test_akon_error_from_toml() {
     let akon_error: AkonError = toml_error.into();
     assert!(matches!(akon_error, AkonError::Toml(_)));
 }
+
+/// One instance of every `VpnError` variant
+///
+/// The exhaustive match makes this fail to compile when a variant is added,
+/// so new variants can't go without a kind and exit code test.
+fn all_vpn_errors() -> Vec<VpnError> {
+    let errors = vec![
+        VpnError::ConnectionFailed {
+            reason: String::new(),
+        },
+        VpnError::AuthenticationFailed,
+        VpnError::NetworkError {
+            reason: String::new(),
+        },
+        VpnError::DnsFailure { transient: true },
+        VpnError::OpenConnectError { code: 1 },
+        VpnError::InvalidStateTransition,
+        VpnError::OpenConnectNotFound,
+        VpnError::ProcessSpawnError {
+            reason: String::new(),
+        },
+        VpnError::ConnectionTimeout { seconds: 30 },
+        VpnError::PhaseTimeout {
+            phase: "authentication".to_string(),
+            seconds: 30,
+        },
+        VpnError::ProcessExited {
+            code: 1,
+            reason: String::new(),
+        },
+        VpnError::RoutingNotConfigured {
+            reason: String::new(),
+        },
+        VpnError::AuthGroupRequired { groups: vec![] },
+        VpnError::RateLimited {
+            retry_after_secs: 60,
+        },
+        VpnError::ConnectionInProgress { pid: 1234 },
+        VpnError::TerminationError,
+        VpnError::ParseError {
+            line: String::new(),
+        },
+    ];
+
+    for error in &errors {
+        match error {
+            VpnError::ConnectionFailed { .. }
+            | VpnError::AuthenticationFailed
+            | VpnError::NetworkError { .. }
+            | VpnError::DnsFailure { .. }
+            | VpnError::OpenConnectError { .. }
+            | VpnError::InvalidStateTransition
+            | VpnError::OpenConnectNotFound
+            | VpnError::ProcessSpawnError { .. }
+            | VpnError::ConnectionTimeout { .. }
+            | VpnError::PhaseTimeout { .. }
+            | VpnError::ProcessExited { .. }
+            | VpnError::RoutingNotConfigured { .. }
+            | VpnError::AuthGroupRequired { .. }
+            | VpnError::RateLimited { .. }
+            | VpnError::ConnectionInProgress { .. }
+            | VpnError::TerminationError
+            | VpnError::ParseError { .. } => {}
+        }
+    }
+
+    errors
+}
+
+#[test]
+fn test_vpn_error_kinds_are_stable_and_unique() {
+    let kinds: Vec<(&str, i32)> = all_vpn_errors()
+        .iter()
+        .map(|e| (e.kind(), e.exit_code()))
+        .collect();
+
+    assert_eq!(
+        kinds,
+        vec![
+            ("vpn.connection_failed", 1),
+            ("vpn.authentication_failed", 1),
+            ("vpn.network_error", 1),
+            ("vpn.dns_failure", 1),
+            ("vpn.openconnect_error", 1),
+            ("vpn.invalid_state_transition", 1),
+            ("vpn.openconnect_not_found", 1),
+            ("vpn.process_spawn_error", 1),
+            ("vpn.connection_timeout", 1),
+            ("vpn.phase_timeout", 1),
+            ("vpn.process_exited", 1),
+            ("vpn.routing_not_configured", 1),
+            ("vpn.auth_group_required", 2),
+            ("vpn.rate_limited", 1),
+            ("vpn.connection_in_progress", 1),
+            ("vpn.termination_error", 1),
+            ("vpn.parse_error", 1),
+        ]
+    );
+
+    let unique: std::collections::HashSet<_> = kinds.iter().map(|(kind, _)| kind).collect();
+    assert_eq!(unique.len(), kinds.len());
+}
+
+#[test]
+fn test_akon_error_kinds_and_exit_codes() {
+    let io_error = std::io::Error::new(std::io::ErrorKind::Other, "boom");
+    let toml_error = toml::from_str::<toml::Table>("not toml").unwrap_err();
+
+    let cases: Vec<(AkonError, &str, i32)> = vec![
+        (
+            ConfigError::MissingField {
+                field: "server".to_string(),
+            }
+            .into(),
+            "config",
+            2,
+        ),
+        (KeyringError::NotFound.into(), "keyring", 2),
+        (OtpError::InvalidBase32.into(), "otp", 2),
+        (io_error.into(), "io", 1),
+        (toml_error.into(), "config.parse", 2),
+        (
+            AkonError::DeadlineExceeded { seconds: 30 },
+            "deadline_exceeded",
+            124,
+        ),
+        (
+            VpnError::AuthenticationFailed.into(),
+            "vpn.authentication_failed",
+            1,
+        ),
+    ];
+
+    for (error, kind, exit_code) in cases {
+        assert_eq!(error.kind(), kind, "{}", error);
+        assert_eq!(error.exit_code(), exit_code, "{}", error);
+    }
+}
@@ -27,6 +27,7 @@ async fn test_health_check_triggers_reconnection_flow() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 1, // Check every 1 second
         health_check_endpoint: format!("{}/health", mock_server.uri()),
+        ..Default::default()
     };
 
     // When: VPN connection established with health checking enabled
@@ -86,6 +87,7 @@ async fn test_consecutive_failure_tracking() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 1,
         health_check_endpoint: format!("{}/health", mock_server.uri()),
+        ..Default::default()
     };
 
     let manager = ReconnectionManager::new(policy);
@@ -119,6 +121,7 @@ async fn test_reconnection_after_threshold() {
         consecutive_failures_threshold: 2, // Low threshold for faster testing
         health_check_interval_secs: 1,
         health_check_endpoint: format!("{}/health", mock_server.uri()),
+        ..Default::default()
     };
 
     let manager = ReconnectionManager::new(policy);
@@ -147,6 +150,7 @@ async fn test_health_check_only_runs_when_connected() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 1,
         health_check_endpoint: format!("{}/health", mock_server.uri()),
+        ..Default::default()
     };
 
     let _manager = ReconnectionManager::new(policy);
@@ -1,4 +1,4 @@
-use akon_core::vpn::health_check::HealthChecker;
+use akon_core::vpn::health_check::{AddressFamily, HealthCheckOptions, HealthChecker};
 use std::time::Duration;
 use wiremock::{
     matchers::{method, path},
@@ -121,3 +121,55 @@ async fn test_is_reachable_false_for_connection_refused() {
 
     assert!(!is_reachable);
 }
+
+/// Start a mock endpoint that only listens on IPv6 loopback
+async fn start_ipv6_only_server() -> MockServer {
+    let listener = std::net::TcpListener::bind("[::1]:0").expect("IPv6 loopback unavailable");
+    let mock_server = MockServer::builder().listener(listener).start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/health"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    mock_server
+}
+
+/// Test forcing IPv6 reaches an IPv6-only endpoint
+#[tokio::test]
+async fn test_forced_ipv6_reaches_ipv6_only_endpoint() {
+    let mock_server = start_ipv6_only_server().await;
+    let endpoint = format!("{}/health", mock_server.uri());
+
+    let health_checker = HealthChecker::with_options(
+        endpoint,
+        Duration::from_secs(5),
+        HealthCheckOptions {
+            address_family: AddressFamily::V6,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert!(health_checker.check().await.is_success());
+}
+
+/// Test forcing IPv4 cannot reach an IPv6-only endpoint
+#[tokio::test]
+async fn test_forced_ipv4_fails_against_ipv6_only_endpoint() {
+    let mock_server = start_ipv6_only_server().await;
+    let endpoint = format!("{}/health", mock_server.uri());
+
+    let health_checker = HealthChecker::with_options(
+        endpoint,
+        Duration::from_secs(5),
+        HealthCheckOptions {
+            address_family: AddressFamily::V4,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert!(!health_checker.check().await.is_success());
+}
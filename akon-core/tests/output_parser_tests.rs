@@ -1,5 +1,7 @@
 // Unit tests for OutputParser
 
+use akon_core::config::VpnProtocol;
+use akon_core::error::VpnError;
 use akon_core::vpn::{ConnectionEvent, OutputParser};
 
 #[test]
@@ -10,7 +12,7 @@ fn test_parse_tun_configured() {
 
     match event {
         ConnectionEvent::TunConfigured { device, ip } => {
-            assert_eq!(device, "tun0");
+            assert_eq!(device.as_deref(), Some("tun0"));
             assert_eq!(ip.to_string(), "10.0.1.100");
         }
         _ => panic!("Expected TunConfigured event, got {:?}", event),
@@ -138,7 +140,7 @@ fn test_parse_ipv6_extraction() {
     match event {
         ConnectionEvent::TunConfigured { ip, device } => {
             assert!(ip.is_ipv6());
-            assert_eq!(device, "tun0");
+            assert_eq!(device.as_deref(), Some("tun0"));
         }
         _ => panic!("Expected TunConfigured event with IPv6, got {:?}", event),
     }
@@ -261,6 +263,34 @@ fn test_parse_dns_error() {
     }
 }
 
+#[test]
+fn test_dns_errors_are_classified_as_transient_or_not() {
+    let parser = OutputParser::new();
+
+    let cases = [
+        (
+            "getaddrinfo failed for host 'vpn.example.com': Temporary failure in name resolution",
+            true,
+        ),
+        (
+            "getaddrinfo failed for host 'vpn.example.com': Name or service not known",
+            false,
+        ),
+        ("unknown host: vpn.example.com", false),
+    ];
+
+    for (line, transient) in cases {
+        assert!(
+            matches!(
+                parser.parse_error(line),
+                ConnectionEvent::Error { kind: VpnError::DnsFailure { transient: t }, .. } if t == transient
+            ),
+            "misclassified: {}",
+            line
+        );
+    }
+}
+
 #[test]
 fn test_parse_auth_error_still_works() {
     let parser = OutputParser::new();
@@ -278,3 +308,237 @@ fn test_parse_auth_error_still_works() {
         _ => panic!("Expected Error event for auth failure, got {:?}", event),
     }
 }
+
+// Protocol-aware parsing
+
+/// Feed captured output through the parser, returning the first Connected event
+fn first_connected(parser: &OutputParser, output: &str) -> Option<ConnectionEvent> {
+    output
+        .lines()
+        .map(|line| parser.parse_line(line))
+        .find(|event| matches!(event, ConnectionEvent::Connected { .. }))
+}
+
+#[test]
+fn test_globalprotect_output_reaches_connected() {
+    let parser = OutputParser::new_for(&VpnProtocol::GlobalProtect);
+    let output = "\
+POST https://gp.example.com/global-protect/prelogin.esp?tmp=tmp&clientVer=4100&clientos=Linux
+GlobalProtect login returned authentication-source=LDAP
+Portal reports GlobalProtect version 5.2.8; we will report the same client version.
+POST https://gp.example.com/ssl-vpn/login.esp
+Tunnel timeout (rekey interval) is 180 minutes.
+Idle timeout is 180 minutes.
+ESP session established with server
+ESP tunnel connected; exiting HTTPS mainloop.
+Configured as 10.40.12.7, with SSL disconnected and ESP established
+Session authentication will expire at Fri Oct 16 20:00:00 2026";
+
+    match first_connected(&parser, output) {
+        Some(ConnectionEvent::Connected { ip, device }) => {
+            assert_eq!(ip.to_string(), "10.40.12.7");
+            // Left to be found from the address rather than guessed
+            assert_eq!(device, None);
+        }
+        other => panic!("Expected Connected event, got {:?}", other),
+    }
+
+    assert!(matches!(
+        parser.parse_line("GlobalProtect login returned authentication-source=LDAP"),
+        ConnectionEvent::Authenticating { .. }
+    ));
+}
+
+#[test]
+fn test_globalprotect_legacy_connected_line() {
+    let parser = OutputParser::new_for(&VpnProtocol::GlobalProtect);
+    let event = parser.parse_line("Connected as 10.40.12.7, using SSL, with ESP in progress");
+
+    assert!(matches!(event, ConnectionEvent::Connected { .. }));
+}
+
+#[test]
+fn test_fortinet_output_reaches_connected() {
+    let parser = OutputParser::new_for(&VpnProtocol::Fortinet);
+    let output = "\
+POST https://fw.example.com/remote/logincheck
+Server asked us to submit token code
+POST https://fw.example.com/remote/logincheck
+Got CONNECT response: HTTP/1.1 200 OK
+Requesting calculated MTU of 1356
+Connected as 10.212.134.200, using SSL, with DTLS in progress
+Established DTLS connection (using GnuTLS). Ciphersuite (DTLS1.2)-(ECDHE-RSA)-(AES-256-GCM).";
+
+    match first_connected(&parser, output) {
+        Some(ConnectionEvent::Connected { ip, .. }) => {
+            assert_eq!(ip.to_string(), "10.212.134.200");
+        }
+        other => panic!("Expected Connected event, got {:?}", other),
+    }
+
+    assert!(matches!(
+        parser.parse_line("Server asked us to submit token code"),
+        ConnectionEvent::Authenticating { .. }
+    ));
+}
+
+#[test]
+fn test_pulse_output_reaches_connected() {
+    let parser = OutputParser::new_for(&VpnProtocol::Pulse);
+    let output = "\
+Connected to 203.0.113.20:443
+SSL negotiation with pulse.example.com
+Connected to HTTPS on pulse.example.com with ciphersuite (TLS1.2)-(ECDHE-RSA-SECP256R1)-(AES-256-GCM)
+Got HTTP response: HTTP/1.1 101 Switching Protocols
+Pulse realm choice: Users
+Pulse password request
+Session authentication will expire at Fri Oct 16 20:00:00 2026
+ESP session established with server
+Configured as 10.9.0.12, with SSL disconnected and ESP established";
+
+    match first_connected(&parser, output) {
+        Some(ConnectionEvent::Connected { ip, .. }) => {
+            assert_eq!(ip.to_string(), "10.9.0.12");
+        }
+        other => panic!("Expected Connected event, got {:?}", other),
+    }
+
+    assert!(matches!(
+        parser.parse_line("Pulse password request"),
+        ConnectionEvent::Authenticating { .. }
+    ));
+    assert!(matches!(
+        parser.parse_line("Connected as 10.9.0.12, using SSL, with ESP in progress"),
+        ConnectionEvent::Connected { .. }
+    ));
+}
+
+#[test]
+fn test_array_output_reaches_connected() {
+    let parser = OutputParser::new_for(&VpnProtocol::Array);
+    let output = "\
+Connected to 198.51.100.9:443
+SSL negotiation with array.example.com
+Connected to HTTPS on array.example.com with ciphersuite (TLS1.2)-(ECDHE-RSA-SECP256R1)-(AES-128-GCM)
+POST https://array.example.com/prx/000/http/localhost/login
+Got HTTP response: HTTP/1.1 200 OK
+Connected as 172.16.4.9, using SSL, with DTLS in progress
+Established DTLS connection (using GnuTLS). Ciphersuite (DTLS1.2)-(RSA)-(AES-128-GCM).";
+
+    match first_connected(&parser, output) {
+        Some(ConnectionEvent::Connected { ip, .. }) => {
+            assert_eq!(ip.to_string(), "172.16.4.9");
+        }
+        other => panic!("Expected Connected event, got {:?}", other),
+    }
+
+    assert!(matches!(
+        parser.parse_line("Configured as 172.16.4.9, with SSL connected and DTLS in progress"),
+        ConnectionEvent::Connected { .. }
+    ));
+}
+
+#[test]
+fn test_generic_parser_misses_globalprotect_esp_line() {
+    // Without protocol awareness the ESP-only line is just a TUN configuration
+    let parser = OutputParser::new();
+    let event =
+        parser.parse_line("Configured as 10.40.12.7, with SSL disconnected and ESP established");
+
+    assert!(matches!(event, ConnectionEvent::TunConfigured { .. }));
+}
+
+#[test]
+fn test_f5_parser_unchanged_by_protocol_selection() {
+    let parser = OutputParser::new_for(&VpnProtocol::F5);
+    let event =
+        parser.parse_line("Configured as 10.10.62.228, with SSL connected and DTLS disabled");
+
+    assert!(matches!(event, ConnectionEvent::Connected { .. }));
+}
+
+#[test]
+fn test_parse_cstp_connected() {
+    let parser = OutputParser::new();
+    let event = parser.parse_line("CSTP connected. DPD 30, Keepalive 20");
+
+    assert_eq!(event, ConnectionEvent::CstpConnected);
+}
+
+#[test]
+fn test_parse_auth_group_prompt() {
+    let parser = OutputParser::new();
+    let expected = ConnectionEvent::AuthGroupsAvailable {
+        groups: vec![
+            "Employees".to_string(),
+            "Contractors".to_string(),
+            "Lab Access".to_string(),
+        ],
+    };
+
+    // openconnect prints form prompts on stderr
+    assert_eq!(
+        parser.parse_error("GROUP: [Employees|Contractors|Lab Access]:"),
+        expected
+    );
+    assert_eq!(
+        parser.parse_line("GROUP: [Employees|Contractors|Lab Access]:"),
+        expected
+    );
+    assert!(matches!(
+        parser.parse_line("Please enter your username and password."),
+        ConnectionEvent::UnknownOutput { .. }
+    ));
+}
+
+#[test]
+fn test_parse_push_approval_prompts() {
+    let parser = OutputParser::new();
+
+    for line in [
+        "Duo Push sent to your phone",
+        "Push notification sent. Approve it on your device.",
+        "Waiting for approval...",
+        "Please approve the login request on your phone",
+    ] {
+        let expected = ConnectionEvent::AwaitingApproval {
+            message: line.to_string(),
+        };
+        assert_eq!(parser.parse_error(line), expected, "{:?}", line);
+        assert_eq!(parser.parse_line(line), expected, "{:?}", line);
+    }
+
+    // Typed-code prompts and push failures are not waits
+    for line in ["Enter passcode:", "Push request was denied"] {
+        assert!(
+            !matches!(
+                parser.parse_error(line),
+                ConnectionEvent::AwaitingApproval { .. }
+            ),
+            "{:?}",
+            line
+        );
+    }
+}
+
+#[test]
+fn test_detect_prompts_waiting_for_input() {
+    let parser = OutputParser::new();
+
+    for line in [
+        "Do you accept the acceptable use policy? [yes/no]",
+        "Accept banner [y/n] ",
+        "Realm:",
+        "Continue anyway?",
+    ] {
+        assert!(parser.is_prompt(line), "{:?}", line);
+    }
+
+    for line in [
+        "POST https://vpn.example.com/",
+        "Connected to 203.0.113.10:443",
+        "Got CONNECT response: HTTP/1.1 200 OK",
+    ] {
+        assert!(!parser.is_prompt(line), "{:?}", line);
+    }
+}
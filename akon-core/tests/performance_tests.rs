@@ -225,6 +225,7 @@ fn test_backoff_calculation_performance() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 60,
         health_check_endpoint: "https://www.google.com".to_string(),
+        ..Default::default()
     };
 
     let manager = ReconnectionManager::new(policy);
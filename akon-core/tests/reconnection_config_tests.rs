@@ -139,3 +139,22 @@ fn test_health_check_endpoint_required() {
     // Then: Should fail because endpoint is required
     assert!(result.is_err(), "Should require health_check_endpoint");
 }
+
+#[test]
+fn test_default_policy_matches_serde_defaults() {
+    use akon_core::vpn::reconnection::{ReconnectionPolicy, SERVER_ENDPOINT_ALIAS};
+
+    // Given: A policy section that only sets the required endpoint
+    let policy: ReconnectionPolicy = toml::from_str(&format!(
+        "health_check_endpoint = \"{}\"",
+        SERVER_ENDPOINT_ALIAS
+    ))
+    .expect("Should parse");
+
+    // Then: Every omitted field gets the value of ReconnectionPolicy::default()
+    assert_eq!(
+        serde_json::to_value(&policy).unwrap(),
+        serde_json::to_value(ReconnectionPolicy::default()).unwrap()
+    );
+    assert!(ReconnectionPolicy::default().validate().is_ok());
+}
@@ -14,6 +14,7 @@ fn test_backoff_calculation_default_policy() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 60,
         health_check_endpoint: "https://vpn.example.com/health".to_string(),
+        ..Default::default()
     };
 
     // When: Calculating backoff for attempts 1-6
@@ -52,6 +53,7 @@ fn test_backoff_cap_at_max_interval() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 60,
         health_check_endpoint: "https://vpn.example.com/health".to_string(),
+        ..Default::default()
     };
 
     // When: Calculating backoff for multiple attempts
@@ -88,6 +90,7 @@ fn test_backoff_with_different_multipliers() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 60,
         health_check_endpoint: "https://vpn.example.com/health".to_string(),
+        ..Default::default()
     };
 
     // When: Calculating backoff
@@ -122,6 +125,7 @@ fn test_backoff_with_multiplier_one() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 60,
         health_check_endpoint: "https://vpn.example.com/health".to_string(),
+        ..Default::default()
     };
 
     // When: Calculating backoff for multiple attempts
@@ -146,6 +150,7 @@ fn test_backoff_first_attempt_is_base_interval() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 60,
         health_check_endpoint: "https://vpn.example.com/health".to_string(),
+        ..Default::default()
     };
 
     // When: Calculating backoff for first attempt
@@ -285,6 +290,7 @@ async fn test_health_check_failure_triggers_reconnection() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 60,
         health_check_endpoint: "https://vpn.example.com/health".to_string(),
+        ..Default::default()
     };
 
     let manager = ReconnectionManager::new(policy);
@@ -314,6 +320,7 @@ async fn test_consecutive_failures_threshold() {
         consecutive_failures_threshold: 2,
         health_check_interval_secs: 60,
         health_check_endpoint: "https://vpn.example.com/health".to_string(),
+        ..Default::default()
     };
 
     let _manager = ReconnectionManager::new(policy);
@@ -340,6 +347,7 @@ async fn test_single_failure_does_not_trigger_reconnection() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 60,
         health_check_endpoint: "https://vpn.example.com/health".to_string(),
+        ..Default::default()
     };
 
     let manager = ReconnectionManager::new(policy);
@@ -369,6 +377,7 @@ async fn test_health_check_success_resets_failure_counter() {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 60,
         health_check_endpoint: "https://vpn.example.com/health".to_string(),
+        ..Default::default()
     };
 
     let manager = ReconnectionManager::new(policy);
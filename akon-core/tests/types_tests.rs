@@ -310,6 +310,15 @@ mod vpn_password_tests {
         assert!(pwd_str.chars().all(|c| c.is_ascii_digit()));
     }
 
+    #[test]
+    fn test_vpn_password_with_eight_digit_otp() {
+        let pin = Pin::new("1234".to_string()).unwrap();
+        let otp = TotpToken::new("07081804".to_string());
+        let password = VpnPassword::from_components(&pin, &otp);
+
+        assert_eq!(password.expose(), "123407081804");
+    }
+
     #[test]
     fn test_vpn_password_new() {
         let password = VpnPassword::new("1234567890".to_string());
@@ -53,6 +53,7 @@ async fn main() {
                 consecutive_failures_threshold: 3,
                 health_check_interval_secs: 10, // Faster for testing
                 health_check_endpoint: "https://example.com/".to_string(),
+                ..Default::default()
             };
 
             println!(
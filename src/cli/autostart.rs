@@ -0,0 +1,172 @@
+//! Autostart command implementation
+//!
+//! This module implements `akon autostart enable/disable`, which manages an
+//! XDG autostart entry that connects the VPN when the user logs in.
+
+use crate::ui::{self, Symbol};
+use akon_core::config::toml_config::load_config;
+use akon_core::config::{PrivilegeEscalation, DEFAULT_PROFILE};
+use akon_core::error::{AkonError, ConfigError};
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+
+/// File name of the autostart entry
+const DESKTOP_FILE_NAME: &str = "akon.desktop";
+
+/// Characters that force an `Exec` argument to be quoted
+const EXEC_RESERVED_CHARS: &str = "\"'\\><~|&;$*?#()`";
+
+/// Build the contents of the XDG autostart `.desktop` entry
+///
+/// `program` is the akon executable; the entry runs `akon vpn on`, with
+/// `--profile` for a profile other than the default one.
+pub fn desktop_entry(program: &Path, profile: Option<&str>) -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=akon VPN\n\
+         Comment=Connect to the VPN on login\n\
+         Exec={} {}\n\
+         Terminal=false\n\
+         X-GNOME-Autostart-enabled=true\n",
+        quote_exec_arg(&program.to_string_lossy()),
+        vpn_on_args(profile)
+    )
+}
+
+/// Arguments connecting `profile` at login
+fn vpn_on_args(profile: Option<&str>) -> String {
+    match profile.filter(|name| *name != DEFAULT_PROFILE) {
+        Some(name) => format!("vpn on --profile {}", quote_exec_arg(name)),
+        None => "vpn on".to_string(),
+    }
+}
+
+/// What connecting at login needs from `escalation`, if anything
+fn login_requirement(escalation: PrivilegeEscalation) -> Option<&'static str> {
+    match escalation {
+        PrivilegeEscalation::Sudo => Some("requires passwordless sudo for openconnect"),
+        PrivilegeEscalation::Pkexec => Some("pkexec asks for authorization through polkit"),
+        PrivilegeEscalation::None => None,
+    }
+}
+
+/// Quote an argument for the `Exec` key, as the desktop entry spec requires
+fn quote_exec_arg(arg: &str) -> String {
+    let needs_quoting = arg
+        .chars()
+        .any(|c| c.is_whitespace() || EXEC_RESERVED_CHARS.contains(c));
+    if !needs_quoting {
+        return arg.to_string();
+    }
+
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+
+    // A literal backslash must itself be escaped in desktop entry strings
+    quoted.replace('\\', "\\\\")
+}
+
+/// Path of the autostart entry: `$XDG_CONFIG_HOME/autostart/akon.desktop`
+fn autostart_path() -> Result<PathBuf, AkonError> {
+    let config_home = match std::env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home = std::env::var("HOME").map_err(|_| {
+                AkonError::Config(ConfigError::IoError {
+                    message: "HOME environment variable not set".to_string(),
+                })
+            })?;
+            PathBuf::from(home).join(".config")
+        }
+    };
+
+    Ok(config_home.join("autostart").join(DESKTOP_FILE_NAME))
+}
+
+/// Run the autostart enable command for `profile`
+pub fn run_autostart_enable(profile: Option<&str>) -> Result<(), AkonError> {
+    let program = std::env::current_exe()?;
+    let path = autostart_path()?;
+    let escalation = load_config(profile)
+        .map(|config| config.privilege_escalation)
+        .unwrap_or_default();
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, desktop_entry(&program, profile))?;
+
+    println!(
+        "{} Autostart enabled: {}",
+        ui::symbol(Symbol::Success),
+        path.display().to_string().bright_cyan()
+    );
+    let mut note = format!("akon {} will run when you log in", vpn_on_args(profile));
+    if let Some(requirement) = login_requirement(escalation) {
+        note.push_str(&format!(" ({})", requirement));
+    }
+    println!("  {}", note.dimmed());
+    Ok(())
+}
+
+/// Run the autostart disable command
+pub fn run_autostart_disable() -> Result<(), AkonError> {
+    let path = autostart_path()?;
+
+    match std::fs::remove_file(&path) {
+        Ok(()) => println!("{} Autostart disabled", ui::symbol(Symbol::Success)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            println!("{} Autostart was not enabled", ui::symbol(Symbol::Info))
+        }
+        Err(e) => return Err(e.into()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desktop_entry_runs_vpn_on() {
+        let entry = desktop_entry(Path::new("/usr/bin/akon"), None);
+
+        assert!(entry.starts_with("[Desktop Entry]\n"));
+        assert!(entry
+            .lines()
+            .any(|line| line == "Exec=/usr/bin/akon vpn on"));
+        assert!(entry.lines().any(|line| line == "Type=Application"));
+    }
+
+    #[test]
+    fn test_desktop_entry_connects_the_selected_profile() {
+        let entry = desktop_entry(Path::new("/usr/bin/akon"), Some("work"));
+        assert!(entry
+            .lines()
+            .any(|line| line == "Exec=/usr/bin/akon vpn on --profile work"));
+
+        // The default profile needs no flag
+        let entry = desktop_entry(Path::new("/usr/bin/akon"), Some(DEFAULT_PROFILE));
+        assert!(entry
+            .lines()
+            .any(|line| line == "Exec=/usr/bin/akon vpn on"));
+    }
+
+    #[test]
+    fn test_exec_path_with_spaces_is_quoted() {
+        let entry = desktop_entry(Path::new("/home/me/my tools/akon"), None);
+
+        assert!(entry
+            .lines()
+            .any(|line| line == "Exec=\"/home/me/my tools/akon\" vpn on"));
+        assert_eq!(quote_exec_arg("/opt/$x"), "\"/opt/\\\\$x\"");
+    }
+}
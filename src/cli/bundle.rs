@@ -0,0 +1,108 @@
+//! Export and import command implementation
+//!
+//! This module implements `akon export`, which writes config.toml and the
+//! stored credentials to a passphrase-encrypted bundle, and `akon import`,
+//! which restores them on another machine.
+
+use crate::ui::{self, Symbol};
+use akon_core::auth::bundle::BundleContents;
+use akon_core::auth::keyring_async;
+use akon_core::config::toml_config::get_config_path;
+use akon_core::error::{AkonError, ConfigError};
+use colored::Colorize;
+use std::io::{BufRead, IsTerminal, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+/// Read the bundle passphrase
+///
+/// Asks on the terminal (twice with `confirm`); without one, the first line
+/// of stdin is used so scripts can pipe it in.
+fn read_passphrase(confirm: bool) -> Result<String, AkonError> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        let mut line = String::new();
+        stdin.lock().read_line(&mut line)?;
+        return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    let ask = |prompt: &str| -> Result<String, AkonError> {
+        let mut stderr = std::io::stderr();
+        write!(stderr, "{}: ", prompt)?;
+        stderr.flush()?;
+        let mut line = String::new();
+        stdin.lock().read_line(&mut line)?;
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    };
+
+    let passphrase = ask("Bundle passphrase")?;
+    if confirm && ask("Repeat passphrase")? != passphrase {
+        return Err(AkonError::Config(ConfigError::ValidationError {
+            message: "Passphrases do not match".to_string(),
+        }));
+    }
+    Ok(passphrase)
+}
+
+/// Run the export command
+pub async fn run_export(out: &Path, force: bool, profile: Option<&str>) -> Result<(), AkonError> {
+    let config_path = get_config_path()?;
+    let contents = keyring_async::collect_bundle(&config_path, profile).await?;
+    let bundle = contents.seal(&read_passphrase(true)?)?;
+
+    // Owner-only, and never over an existing file unless asked to
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).mode(0o600);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    let mut file = options.open(out).map_err(|e| {
+        AkonError::Config(ConfigError::IoError {
+            message: format!("Failed to create {}: {}", out.display(), e),
+        })
+    })?;
+    file.write_all(&bundle)?;
+
+    println!(
+        "{} Exported config and credentials for '{}' to {}",
+        ui::symbol(Symbol::Success),
+        contents.username,
+        out.display().to_string().bright_cyan()
+    );
+    println!(
+        "  {}",
+        "Restore with 'akon import' on the new machine; keep the passphrase safe".dimmed()
+    );
+    Ok(())
+}
+
+/// Run the import command
+pub async fn run_import(bundle_path: &Path, force: bool) -> Result<(), AkonError> {
+    let bundle = std::fs::read(bundle_path).map_err(|e| {
+        AkonError::Config(ConfigError::IoError {
+            message: format!("Failed to read {}: {}", bundle_path.display(), e),
+        })
+    })?;
+    let contents = BundleContents::open(&bundle, &read_passphrase(false)?)?;
+    let username = contents.username.clone();
+
+    let config_path = get_config_path()?;
+    keyring_async::restore_bundle(contents, config_path.clone(), force).await?;
+
+    println!(
+        "{} Imported config to {}",
+        ui::symbol(Symbol::Success),
+        config_path.display().to_string().bright_cyan()
+    );
+    println!(
+        "  {}",
+        format!(
+            "Stored PIN and OTP secret for '{}' in the keyring",
+            username
+        )
+        .dimmed()
+    );
+    Ok(())
+}
@@ -0,0 +1,55 @@
+//! Config command implementation
+//!
+//! This module implements `akon config` helpers for writing configuration
+//! files by hand and finding the files akon uses.
+
+use crate::cli::vpn::{get_daemon_pid_file, state_file_path};
+use akon_core::config::template::config_template;
+use akon_core::config::toml_config::get_config_path;
+use akon_core::error::AkonError;
+
+/// Where akon logs; there is no log file
+const LOG_DESTINATION: &str = "stderr, or the systemd journal under systemd ('akon logs')";
+
+/// Files akon uses for `profile` in the current environment, with their labels
+///
+/// Honors `AKON_CONFIG_DIR` and `AKON_STATE_FILE`.
+pub fn resolved_paths(profile: Option<&str>) -> Result<Vec<(&'static str, String)>, AkonError> {
+    Ok(vec![
+        ("Config", get_config_path()?.display().to_string()),
+        ("State", state_file_path(profile).display().to_string()),
+        (
+            "Daemon PID",
+            get_daemon_pid_file(profile).display().to_string(),
+        ),
+        ("Log", LOG_DESTINATION.to_string()),
+    ])
+}
+
+/// Run the config paths command
+pub fn run_config_paths(profile: Option<&str>) -> Result<(), AkonError> {
+    for (label, path) in resolved_paths(profile)? {
+        println!("{:<11} {}", format!("{}:", label), path);
+    }
+    Ok(())
+}
+
+/// Print the resolved config file path alone, for scripts
+pub fn print_config_path() -> Result<(), AkonError> {
+    println!("{}", get_config_path()?.display());
+    Ok(())
+}
+
+/// Print the resolved state file path alone, for scripts
+pub fn print_state_path(profile: Option<&str>) {
+    println!("{}", state_file_path(profile).display());
+}
+
+/// Run the config template command
+///
+/// Prints a commented example configuration to stdout so it can be
+/// redirected into `~/.config/akon/config.toml` and edited.
+pub fn run_config_template(with_reconnection: bool) -> Result<(), AkonError> {
+    print!("{}", config_template(with_reconnection));
+    Ok(())
+}
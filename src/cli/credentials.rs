@@ -0,0 +1,154 @@
+//! Credentials command implementation
+//!
+//! This module implements `akon credentials verify`, a support tool that
+//! checks whether the stored OTP secret produces a code the user's
+//! authenticator app shows. The secret itself is never printed. It also
+//! implements `akon credentials migrate`, which moves the stored credentials
+//! to a new username.
+
+use crate::ui::{self, Symbol};
+use akon_core::auth::keyring_async;
+use akon_core::auth::totp::{
+    check_code, Clock, CodeCheck, OffsetClock, TotpSettings, SKEW_CHECK_STEPS,
+};
+use akon_core::config::toml_config::{
+    get_config_path, load_config, load_config_from_path, save_username_to_path,
+};
+use akon_core::error::{AkonError, ConfigError};
+use akon_core::types::OtpSecret;
+use colored::Colorize;
+
+/// Describe the outcome of a code check for the user
+fn describe_check(check: CodeCheck, period_secs: u64) -> String {
+    match check {
+        CodeCheck::Match => "Code matches the stored secret".to_string(),
+        CodeCheck::Skewed { offset } => {
+            let seconds = offset.unsigned_abs() * period_secs;
+            if offset > 0 {
+                format!(
+                    "Code is the one for {}s from now: the other device's clock is about {}s ahead of this one",
+                    seconds, seconds
+                )
+            } else {
+                format!(
+                    "Code is the one for {}s ago: the other device's clock is about {}s behind this one",
+                    seconds, seconds
+                )
+            }
+        }
+        CodeCheck::NoMatch => format!(
+            "Code does not match the stored secret (checked ±{}s); the secret differs",
+            SKEW_CHECK_STEPS as u64 * period_secs
+        ),
+    }
+}
+
+/// Run the credentials verify command
+///
+/// Exits with status 1 unless the code matches exactly.
+pub async fn run_credentials_verify(
+    code: &str,
+    at: Option<u64>,
+    profile: Option<&str>,
+) -> Result<(), AkonError> {
+    let config = load_config(profile)?;
+    let secret =
+        OtpSecret::new(keyring_async::retrieve_otp_secret(&config.keyring_account()).await?);
+
+    let at = match at {
+        Some(at) => at,
+        None => OffsetClock::for_config(&config).unix_time()?,
+    };
+    let check = check_code(&secret, code, Some(at), TotpSettings::for_config(&config))?;
+    let message = describe_check(check, config.otp_period_secs);
+    match check {
+        CodeCheck::Match => {
+            println!("{} {}", ui::symbol(Symbol::Success), message.bright_green());
+            Ok(())
+        }
+        CodeCheck::Skewed { .. } => {
+            println!(
+                "{} {}",
+                ui::symbol(Symbol::Warning),
+                message.bright_yellow()
+            );
+            println!(
+                "  {}",
+                "Enable time synchronization on both devices (timedatectl set-ntp true)".dimmed()
+            );
+            std::process::exit(1);
+        }
+        CodeCheck::NoMatch => {
+            println!("{} {}", ui::symbol(Symbol::Failure), message.bright_red());
+            println!(
+                "  {}",
+                "Run 'akon setup' to store the secret from your authenticator enrollment".dimmed()
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Run the credentials migrate command
+///
+/// Moves the PIN and OTP secret from `from` to `to` and sets `to` as the
+/// username in config.toml.
+pub async fn run_credentials_migrate(
+    from: &str,
+    to: &str,
+    overwrite: bool,
+    profile: Option<&str>,
+) -> Result<(), AkonError> {
+    let config_path = get_config_path()?;
+    let config = load_config_from_path(&config_path, profile)?;
+    if config.username != from {
+        return Err(AkonError::Config(ConfigError::ValidationError {
+            message: format!(
+                "config.toml uses username '{}', not '{}'",
+                config.username, from
+            ),
+        }));
+    }
+
+    // Credentials of a named profile are stored under `profile:username`
+    let mut target = config.clone();
+    target.username = to.to_string();
+    let new_username = to.to_string();
+    let path = config_path.clone();
+    let profile = config.profile.clone();
+    keyring_async::migrate_credentials(
+        &config.keyring_account(),
+        &target.keyring_account(),
+        overwrite,
+        move || save_username_to_path(&new_username, profile.as_deref(), &path),
+    )
+    .await?;
+
+    println!(
+        "{} {}",
+        ui::symbol(Symbol::Success),
+        format!("Moved credentials from '{}' to '{}'", from, to).bright_green()
+    );
+    println!(
+        "  {}",
+        format!("Updated username in {}", config_path.display()).dimmed()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_skew_direction() {
+        assert_eq!(
+            describe_check(CodeCheck::Skewed { offset: 1 }, 30),
+            "Code is the one for 30s from now: the other device's clock is about 30s ahead of this one"
+        );
+        assert!(describe_check(CodeCheck::Skewed { offset: -2 }, 30).contains("about 60s behind"));
+        assert!(describe_check(CodeCheck::NoMatch, 30).contains("does not match"));
+        // Steps are as long as the configured period
+        assert!(describe_check(CodeCheck::Skewed { offset: 1 }, 60).contains("about 60s ahead"));
+    }
+}
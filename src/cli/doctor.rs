@@ -0,0 +1,86 @@
+//! Doctor command implementation
+//!
+//! This module implements `akon doctor`, which checks the stored OTP secret
+//! before a reconnection depends on it: the secret must yield a well-formed
+//! code and decode to a key of plausible length. The secret and the code are
+//! never printed. With `check_otp_secret`, `akon vpn on` runs the same check.
+
+use crate::ui::{self, Symbol};
+use akon_core::auth::keyring_async;
+use akon_core::auth::totp::SecretAnomaly;
+use akon_core::auth::totp::{inspect_secret, Clock, OffsetClock, TotpSettings};
+use akon_core::config::toml_config::load_config;
+use akon_core::config::VpnConfig;
+use akon_core::error::AkonError;
+use akon_core::types::OtpSecret;
+use colored::Colorize;
+use tracing::{debug, warn};
+
+/// Anomalies of the OTP secret stored for `config`
+async fn secret_anomalies(config: &VpnConfig) -> Result<Vec<SecretAnomaly>, AkonError> {
+    let secret =
+        OtpSecret::new(keyring_async::retrieve_otp_secret(&config.keyring_account()).await?);
+    let now = OffsetClock::for_config(config).unix_time()?;
+    inspect_secret(&secret, TotpSettings::for_config(config), Some(now))
+}
+
+/// Run the doctor command
+///
+/// Exits with status 1 if the secret looks damaged.
+pub async fn run_doctor(profile: Option<&str>) -> Result<(), AkonError> {
+    let config = load_config(profile)?;
+    let anomalies = secret_anomalies(&config).await?;
+    if anomalies.is_empty() {
+        println!(
+            "{} {}",
+            ui::symbol(Symbol::Success),
+            "OTP secret produces well-formed codes".bright_green()
+        );
+        return Ok(());
+    }
+
+    for anomaly in &anomalies {
+        println!(
+            "{} {}",
+            ui::symbol(Symbol::Warning),
+            anomaly.message().bright_yellow()
+        );
+    }
+    println!(
+        "  {}",
+        "Store the secret again with 'akon setup', copying it in full".dimmed()
+    );
+    std::process::exit(1);
+}
+
+/// Warn before connecting when the stored OTP secret looks damaged
+///
+/// Only with `check_otp_secret` and a secret kept in the keyring. Failing to
+/// read the secret only skips the check; connecting reports that itself.
+pub async fn warn_about_damaged_secret(config: &VpnConfig) {
+    if !config.check_otp_secret || !config.store_secret {
+        return;
+    }
+
+    let anomalies = match secret_anomalies(config).await {
+        Ok(anomalies) => anomalies,
+        Err(e) => {
+            debug!("Skipping OTP secret check: {}", e);
+            return;
+        }
+    };
+    for anomaly in &anomalies {
+        warn!("{}", anomaly.message());
+        eprintln!(
+            "{} {}",
+            ui::symbol(Symbol::Warning),
+            anomaly.message().bright_yellow()
+        );
+    }
+    if !anomalies.is_empty() {
+        eprintln!(
+            "   {}",
+            "Store the secret again with 'akon setup', copying it in full".dimmed()
+        );
+    }
+}
@@ -3,7 +3,9 @@
 //! This module implements the `akon get-password` command that generates
 //! and outputs complete VPN passwords (PIN + OTP) for manual use.
 
-use akon_core::auth::password::generate_password;
+use crate::cli::TerminalPrompt;
+use akon_core::auth::password::{generate_password_async_with, StoredCredentials};
+use akon_core::auth::totp::{OffsetClock, TotpSettings};
 use akon_core::config::toml_config::load_config;
 use akon_core::error::AkonError;
 
@@ -11,12 +13,19 @@ use akon_core::error::AkonError;
 ///
 /// Outputs the complete VPN password (PIN + OTP) to stdout for machine-parsable usage.
 /// Errors are sent to stderr. No additional formatting or text.
-pub fn run_get_password() -> Result<(), AkonError> {
+pub async fn run_get_password(profile: Option<&str>) -> Result<(), AkonError> {
     // Load configuration to get username
-    let config = load_config()?;
+    let config = load_config(profile)?;
 
     // Generate complete password (PIN + OTP) from keyring credentials
-    let password = generate_password(&config.username)?;
+    let password = generate_password_async_with(
+        &config.keyring_account(),
+        StoredCredentials::for_config(&config),
+        TerminalPrompt::new(),
+        &OffsetClock::for_config(&config),
+        TotpSettings::for_config(&config),
+    )
+    .await?;
 
     // Output only the password to stdout (machine-parsable)
     println!("{}", password.expose());
@@ -0,0 +1,171 @@
+//! Logs command implementation
+//!
+//! This module implements `akon logs`, which shows akon's entries in the
+//! systemd journal by running `journalctl` with the right filters. Outside
+//! systemd akon only logs to stderr, so there is nothing to show.
+
+use crate::ui::{self, Symbol};
+use akon_core::error::{AkonError, ConfigError};
+use std::path::Path;
+use std::process::Command;
+
+/// Syslog identifier akon's journal entries are tagged with
+const SYSLOG_IDENTIFIER: &str = "akon";
+
+/// Socket journald listens on; its absence means there is no journal
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Options for `akon logs`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogsOptions {
+    /// Keep printing new entries as they arrive
+    pub follow: bool,
+    /// Number of most recent entries to show
+    pub lines: Option<usize>,
+    /// Only show entries newer than this duration, e.g. `30m` or `2h`
+    pub since: Option<String>,
+}
+
+/// Parse a `--since` duration (`<number><s|m|h|d>`) into seconds
+fn parse_since(value: &str) -> Result<u64, AkonError> {
+    let invalid = || {
+        AkonError::Config(ConfigError::ValidationError {
+            message: format!(
+                "Invalid --since '{}': expected a number followed by s, m, h or d (e.g. 30m)",
+                value
+            ),
+        })
+    };
+
+    if !value.is_ascii() {
+        return Err(invalid());
+    }
+    let split = value.len().saturating_sub(1);
+    let (number, unit) = value.split_at(split);
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(invalid)
+}
+
+/// Arguments for `journalctl` that select akon's entries for `options`
+pub fn journalctl_args(options: &LogsOptions) -> Result<Vec<String>, AkonError> {
+    let mut args = vec![
+        "--identifier".to_string(),
+        SYSLOG_IDENTIFIER.to_string(),
+        format!("_UID={}", nix::unistd::getuid()),
+    ];
+    if let Some(lines) = options.lines {
+        args.push(format!("--lines={}", lines));
+    }
+    if let Some(since) = &options.since {
+        // A relative timestamp; the '=' keeps the leading '-' from reading as a flag
+        args.push(format!("--since=-{}s", parse_since(since)?));
+    }
+    if options.follow {
+        args.push("--follow".to_string());
+    }
+    Ok(args)
+}
+
+/// Print why there are no logs to show
+fn print_no_journal() {
+    println!("{} No systemd journal found", ui::symbol(Symbol::Hint));
+    println!("  akon only writes to the journal when run under systemd; otherwise it");
+    println!("  logs to stderr. Run 'akon vpn on' in a terminal to see its output.");
+}
+
+/// Run the logs command
+pub fn run_logs(options: LogsOptions) -> Result<(), AkonError> {
+    let args = journalctl_args(&options)?;
+
+    if !Path::new(JOURNAL_SOCKET).exists() {
+        print_no_journal();
+        return Ok(());
+    }
+
+    match Command::new("journalctl").args(&args).status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(AkonError::Config(ConfigError::IoError {
+            message: format!("journalctl exited with status {}", status),
+        })),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            print_no_journal();
+            Ok(())
+        }
+        Err(e) => Err(AkonError::Config(ConfigError::IoError {
+            message: format!("Failed to run journalctl: {}", e),
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uid_filter() -> String {
+        format!("_UID={}", nix::unistd::getuid())
+    }
+
+    #[test]
+    fn test_default_options_select_akon_entries() {
+        assert_eq!(
+            journalctl_args(&LogsOptions::default()).unwrap(),
+            vec!["--identifier".to_string(), "akon".to_string(), uid_filter()]
+        );
+    }
+
+    #[test]
+    fn test_options_map_to_journalctl_flags() {
+        let options = LogsOptions {
+            follow: true,
+            lines: Some(50),
+            since: Some("2h".to_string()),
+        };
+
+        assert_eq!(
+            journalctl_args(&options).unwrap(),
+            vec![
+                "--identifier".to_string(),
+                "akon".to_string(),
+                uid_filter(),
+                "--lines=50".to_string(),
+                "--since=-7200s".to_string(),
+                "--follow".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_since_is_rejected() {
+        for since in [
+            "",
+            "m",
+            "10",
+            "10w",
+            "-5m",
+            "1.5h",
+            "5é",
+            "99999999999999999999d",
+        ] {
+            let options = LogsOptions {
+                since: Some(since.to_string()),
+                ..Default::default()
+            };
+            assert!(journalctl_args(&options).is_err(), "accepted {:?}", since);
+        }
+        assert_eq!(parse_since("45s").unwrap(), 45);
+        assert_eq!(parse_since("1d").unwrap(), 86400);
+    }
+}
@@ -2,6 +2,217 @@
 //!
 //! This module contains the implementation of all CLI subcommands.
 
+pub mod autostart;
+pub mod bundle;
+pub mod config;
+pub mod credentials;
+pub mod doctor;
 pub mod get_password;
+pub mod logs;
+pub mod otp;
+pub mod profile;
 pub mod setup;
+pub mod version;
 pub mod vpn;
+
+use crate::ui::{self, Symbol};
+use akon_core::auth::base32;
+use akon_core::auth::password::CredentialPrompt;
+use akon_core::error::{AkonError, KeyringError, OtpError};
+use akon_core::types::{OtpSecret, Pin};
+use std::future::Future;
+use std::io::{BufRead, BufReader, IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// File descriptor to read the PIN from instead of prompting for it
+pub const PIN_FD_ENV: &str = "AKON_PIN_FD";
+
+/// Asks on the terminal for credentials that `store_pin`/`store_secret` keep
+/// out of the keyring
+///
+/// The PIN can instead come from the file descriptor in `AKON_PIN_FD`, for
+/// scripts and password managers. Without a terminal on stdin there is no
+/// one to ask, and the request fails.
+pub struct TerminalPrompt {
+    interactive: bool,
+    pin_source: Option<PathBuf>,
+}
+
+impl Default for TerminalPrompt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TerminalPrompt {
+    pub fn new() -> Self {
+        Self {
+            interactive: std::io::stdin().is_terminal(),
+            pin_source: std::env::var(PIN_FD_ENV)
+                .ok()
+                .and_then(|fd| fd.trim().parse::<u32>().ok())
+                .map(|fd| PathBuf::from(format!("/dev/fd/{}", fd))),
+        }
+    }
+
+    /// Ask for `credential` on stderr (stdout may be a password pipe)
+    fn ask(&self, credential: &str) -> Result<String, AkonError> {
+        if !self.interactive {
+            return Err(AkonError::Keyring(KeyringError::PromptUnavailable {
+                credential: credential.to_string(),
+            }));
+        }
+        let mut stderr = std::io::stderr();
+        write!(stderr, "{}: ", credential).map_err(AkonError::Io)?;
+        stderr.flush().map_err(AkonError::Io)?;
+
+        let mut answer = String::new();
+        std::io::stdin()
+            .read_line(&mut answer)
+            .map_err(AkonError::Io)?;
+        Ok(answer.trim().to_string())
+    }
+}
+
+impl CredentialPrompt for TerminalPrompt {
+    fn pin(&mut self) -> Result<Pin, AkonError> {
+        let pin = match &self.pin_source {
+            Some(path) => {
+                let file = std::fs::File::open(path).map_err(AkonError::Io)?;
+                let mut line = String::new();
+                BufReader::new(file)
+                    .read_line(&mut line)
+                    .map_err(AkonError::Io)?;
+                line.trim().to_string()
+            }
+            None => self.ask("PIN")?,
+        };
+        if pin.is_empty() {
+            return Err(AkonError::Otp(OtpError::InvalidPinFormat));
+        }
+        Ok(Pin::from_unchecked(pin))
+    }
+
+    fn otp_secret(&mut self) -> Result<OtpSecret, AkonError> {
+        let secret = self.ask("TOTP secret")?;
+        Ok(OtpSecret::new(base32::normalize(&secret)?))
+    }
+}
+
+/// Run a command, failing it if it takes longer than `deadline_secs`
+///
+/// The command's future is dropped when the deadline passes, which kills an
+/// openconnect process it was still starting. Blocking calls only yield to
+/// the deadline if they run on the blocking pool, which is why keyring
+/// access goes through `keyring_async`.
+pub async fn with_deadline<F>(deadline_secs: Option<u64>, command: F) -> Result<(), AkonError>
+where
+    F: Future<Output = Result<(), AkonError>>,
+{
+    let Some(seconds) = deadline_secs else {
+        return command.await;
+    };
+    tokio::time::timeout(Duration::from_secs(seconds), command)
+        .await
+        .unwrap_or(Err(AkonError::DeadlineExceeded { seconds }))
+}
+
+/// Print remediation steps for an OTP failure
+pub fn print_otp_suggestions(error: &OtpError) {
+    let suggestions: Vec<String> = match error {
+        OtpError::InvalidBase32 => vec![
+            "The stored OTP secret is not valid Base32 (A-Z, 2-7)".to_string(),
+            "Copy the secret again from your authenticator enrollment".to_string(),
+            "Run 'akon setup' to store the corrected secret".to_string(),
+        ],
+        OtpError::EmptySecret => vec![
+            "The OTP secret in the keyring is empty".to_string(),
+            "Run 'akon setup' to store your OTP secret".to_string(),
+        ],
+        OtpError::InvalidDigits { .. } => {
+            vec!["TOTP codes have 6, 7 or 8 digits; check otp_digits in config.toml".to_string()]
+        }
+        OtpError::InvalidPeriod => {
+            vec!["Set otp_period_secs in config.toml to your token's time step".to_string()]
+        }
+        OtpError::InvalidUri { .. } => {
+            vec!["Paste the whole otpauth://totp/... link, or just its secret".to_string()]
+        }
+        OtpError::ClockError => vec![
+            "Your system clock is wrong; TOTP codes depend on the current time".to_string(),
+            "Enable time synchronization: timedatectl set-ntp true".to_string(),
+        ],
+        OtpError::RateLimited { retry_after_secs } => vec![format!(
+            "Wait {}s for the next code; gateways reject a code used twice",
+            retry_after_secs
+        )],
+        OtpError::InvalidPinFormat => vec!["Run 'akon setup' to store a valid PIN".to_string()],
+        _ => vec!["Run 'akon setup' to reconfigure your credentials".to_string()],
+    };
+
+    eprintln!("\n{} Suggestions:", ui::symbol(Symbol::Hint));
+    for suggestion in suggestions {
+        eprintln!("   {} {}", ui::symbol(Symbol::Bullet), suggestion);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_deadline_aborts_hanging_command() {
+        // Stands in for a keyring call wedged on D-Bus
+        let hanging = async {
+            tokio::task::spawn_blocking(|| std::thread::sleep(Duration::from_secs(3)))
+                .await
+                .ok();
+            Ok(())
+        };
+
+        let started = Instant::now();
+        let result = with_deadline(Some(1), hanging).await;
+
+        assert!(matches!(
+            result,
+            Err(AkonError::DeadlineExceeded { seconds: 1 })
+        ));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_command_within_deadline_completes() {
+        assert!(with_deadline(Some(5), async { Ok(()) }).await.is_ok());
+        assert!(with_deadline(None, async { Ok(()) }).await.is_ok());
+    }
+
+    #[test]
+    fn test_prompt_without_terminal_fails() {
+        let mut prompt = TerminalPrompt {
+            interactive: false,
+            pin_source: None,
+        };
+
+        for result in [prompt.pin().map(|_| ()), prompt.otp_secret().map(|_| ())] {
+            assert!(matches!(
+                result,
+                Err(AkonError::Keyring(KeyringError::PromptUnavailable { .. }))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_pin_is_read_from_pin_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pin");
+        std::fs::write(&path, "2468\n").unwrap();
+        let mut prompt = TerminalPrompt {
+            interactive: false,
+            pin_source: Some(path),
+        };
+
+        assert_eq!(prompt.pin().unwrap().expose(), "2468");
+    }
+}
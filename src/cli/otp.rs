@@ -0,0 +1,94 @@
+//! OTP command implementation
+//!
+//! This module implements `akon otp verify`, which checks a code from the
+//! user's token against the stored secret before they rely on it. Unlike
+//! `akon credentials verify`, a code from the previous or next time window
+//! is accepted, as the gateway would; the offset is reported to help spot
+//! clock drift.
+
+use crate::ui::{self, Symbol};
+use akon_core::auth::keyring_async;
+use akon_core::auth::totp::{check_code_within, Clock, CodeCheck, OffsetClock, TotpSettings};
+use akon_core::config::toml_config::load_config;
+use akon_core::error::AkonError;
+use akon_core::types::OtpSecret;
+use colored::Colorize;
+
+/// Time windows accepted on either side of the current one
+const VERIFY_WINDOW_STEPS: i64 = 1;
+
+/// Describe a code accepted `offset` windows from the current one
+fn describe_match(offset: i64) -> String {
+    match offset {
+        0 => "Code is valid for the current time window".to_string(),
+        1 => {
+            "Code is valid, one window ahead: the token's clock runs ahead of this one".to_string()
+        }
+        -1 => {
+            "Code is valid, one window behind: the token's clock runs behind this one".to_string()
+        }
+        _ => format!("Code is valid, {} windows away", offset),
+    }
+}
+
+/// Run the otp verify command
+///
+/// Exits with status 1 unless the code matches within one window.
+pub async fn run_otp_verify(code: &str, profile: Option<&str>) -> Result<(), AkonError> {
+    let config = load_config(profile)?;
+    let secret =
+        OtpSecret::new(keyring_async::retrieve_otp_secret(&config.keyring_account()).await?);
+    let now = OffsetClock::for_config(&config).unix_time()?;
+
+    let check = check_code_within(
+        &secret,
+        code,
+        Some(now),
+        TotpSettings::for_config(&config),
+        VERIFY_WINDOW_STEPS,
+    )?;
+    let offset = match check {
+        CodeCheck::Match => 0,
+        CodeCheck::Skewed { offset } => offset,
+        CodeCheck::NoMatch => {
+            println!(
+                "{} {}",
+                ui::symbol(Symbol::Failure),
+                "Code is not valid for the stored secret".bright_red()
+            );
+            println!(
+                "  {}",
+                "Check the secret, algorithm, digits and period with 'akon setup'".dimmed()
+            );
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "{} {}",
+        ui::symbol(Symbol::Success),
+        describe_match(offset).bright_green()
+    );
+    if offset != 0 {
+        println!(
+            "  {}",
+            "Enable time synchronization on both devices (timedatectl set-ntp true)".dimmed()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_match_reports_the_window() {
+        assert_eq!(
+            describe_match(0),
+            "Code is valid for the current time window"
+        );
+        assert!(describe_match(1).contains("one window ahead"));
+        assert!(describe_match(-1).contains("one window behind"));
+    }
+}
@@ -0,0 +1,79 @@
+//! Profile command implementation
+//!
+//! This module implements `akon profile list/switch`, which show the
+//! profiles configured in config.toml and change the one used without
+//! `--profile`.
+
+use crate::ui::{self, Symbol};
+use akon_core::config::toml_config::{get_config_path, save_default_profile_to_path, TomlConfig};
+use akon_core::config::DEFAULT_PROFILE;
+use akon_core::error::AkonError;
+use colored::Colorize;
+
+/// One line of `akon profile list`
+pub fn profile_line(name: &str, server: &str, protocol: &str, is_default: bool) -> String {
+    let marker = if is_default { "*" } else { " " };
+    let suffix = if is_default { " (default)" } else { "" };
+    format!(
+        "{} {:<12} {} [{}]{}",
+        marker, name, server, protocol, suffix
+    )
+}
+
+/// Run the profile list command
+///
+/// The default profile is marked with `*`.
+pub fn run_profile_list() -> Result<(), AkonError> {
+    let config = TomlConfig::from_file(&get_config_path()?)?;
+    let default = config.default_profile.as_deref().unwrap_or(DEFAULT_PROFILE);
+
+    for name in config.profile_names() {
+        let profile = config.profile(Some(&name))?;
+        let line = profile_line(
+            &name,
+            &profile.server,
+            profile.protocol.as_str(),
+            name == default,
+        );
+        if name == default {
+            println!("{}", line.bright_green());
+        } else {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
+
+/// Run the profile switch command
+///
+/// Fails if `name` is not a configured profile.
+pub fn run_profile_switch(name: &str) -> Result<(), AkonError> {
+    save_default_profile_to_path(name, get_config_path()?)?;
+    println!(
+        "{} {}",
+        ui::symbol(Symbol::Success),
+        format!("Default profile is now '{}'", name).bright_green()
+    );
+    println!(
+        "  {}",
+        "A connection that is already up keeps its profile until 'akon vpn off'".dimmed()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_line_marks_the_default() {
+        assert_eq!(
+            profile_line("work", "vpn.example.com", "f5", true),
+            "* work         vpn.example.com [f5] (default)"
+        );
+        assert_eq!(
+            profile_line("lab", "lab.example.com", "anyconnect", false),
+            "  lab          lab.example.com [anyconnect]"
+        );
+    }
+}
@@ -2,17 +2,23 @@
 //!
 //! Interactive command for first-time VPN configuration with secure credential storage.
 
+use crate::ui::{self, Symbol};
+use akon_core::vpn::health_check::{parse_endpoint, HealthChecker};
 use akon_core::{
-    auth::keyring,
+    auth::{
+        base32, keyring_async, otpauth,
+        totp::{OtpAlgorithm, TotpSettings},
+    },
     config::{toml_config, VpnConfig},
     error::AkonError,
     types::{OtpSecret, Pin},
 };
 use colored::Colorize;
 use std::io::{self, Write};
+use std::time::Duration;
 
 /// Run the setup command
-pub fn run_setup() -> Result<(), AkonError> {
+pub async fn run_setup(profile: Option<&str>) -> Result<(), AkonError> {
     println!(
         "{} {}",
         "🔐".bright_magenta(),
@@ -34,12 +40,20 @@ pub fn run_setup() -> Result<(), AkonError> {
     );
     println!();
 
+    // With a selected profile, only that profile is set up; the rest of the
+    // file is kept
+    let profile = profile.map(str::to_string);
+
     // Check if already configured
-    if let Ok(true) = toml_config::config_exists() {
+    if profile_configured(profile.as_deref()) {
+        let message = match &profile {
+            Some(name) => format!("Existing configuration detected for profile '{}'.", name),
+            None => "Existing configuration detected.".to_string(),
+        };
         println!(
             "{} {}",
-            "⚠".bright_yellow(),
-            "Existing configuration detected.".bright_yellow()
+            ui::symbol(Symbol::Warning),
+            message.bright_yellow()
         );
         if !prompt_yes_no("Overwrite existing setup?", false)? {
             println!("{}", "Setup cancelled.".dimmed());
@@ -49,14 +63,29 @@ pub fn run_setup() -> Result<(), AkonError> {
     }
 
     // Check keyring availability
-    check_keyring_availability()?;
+    check_keyring_availability().await?;
 
     // Collect configuration interactively
-    let config = collect_vpn_config()?;
-    let otp_secret = collect_otp_secret()?;
-    let pin = collect_pin()?;
+    let mut config = collect_vpn_config()?;
+    config.profile = profile.clone();
+    let account = config.keyring_account();
+    // Credentials already stored for this username can be kept by leaving them blank
+    let (otp_secret, uri_settings) = collect_otp_secret(
+        keyring_async::has_otp_secret(&account)
+            .await
+            .unwrap_or(false),
+    )?;
+    // An otpauth:// URI says how codes are generated; otherwise ask
+    let totp = match uri_settings {
+        Some(settings) => settings,
+        None => collect_totp_settings()?,
+    };
+    config.otp_algorithm = totp.algorithm;
+    config.otp_digits = totp.digits;
+    config.otp_period_secs = totp.period_secs;
+    let pin = collect_pin(keyring_async::has_pin(&account).await.unwrap_or(false))?;
 
-    let reconnection_policy = collect_reconnection_config()?;
+    let reconnection_policy = collect_reconnection_config(&config.server).await?;
 
     // Validate configuration
     config.validate().map_err(|e| {
@@ -66,7 +95,9 @@ pub fn run_setup() -> Result<(), AkonError> {
     })?;
 
     // Validate OTP secret
-    otp_secret.validate_base32().map_err(AkonError::Otp)?;
+    if let Some(otp_secret) = &otp_secret {
+        otp_secret.validate_base32().map_err(AkonError::Otp)?;
+    }
 
     // Save configuration
     println!();
@@ -77,11 +108,18 @@ pub fn run_setup() -> Result<(), AkonError> {
     );
 
     // Save config to TOML file with reconnection policy
-    toml_config::save_config_with_reconnection(&config, reconnection_policy.as_ref())?;
+    if profile.is_some() {
+        toml_config::save_profile_to_path(
+            &config,
+            reconnection_policy.as_ref(),
+            toml_config::get_config_path()?,
+        )?;
+    } else {
+        toml_config::save_config_with_reconnection(&config, reconnection_policy.as_ref())?;
+    }
 
-    // Store PIN and OTP secret in keyring
-    keyring::store_pin(&config.username, &pin)?;
-    keyring::store_otp_secret(&config.username, otp_secret.expose())?;
+    // Store PIN and OTP secret in keyring, keeping the ones not re-entered
+    keyring_async::store_credentials(&account, pin, otp_secret).await?;
 
     println!(
         "{} {}",
@@ -100,13 +138,28 @@ pub fn run_setup() -> Result<(), AkonError> {
     Ok(())
 }
 
+/// Whether setup would replace an existing configuration
+///
+/// For a selected profile, that is whether the config file has that profile.
+fn profile_configured(profile: Option<&str>) -> bool {
+    let Ok(path) = toml_config::get_config_path() else {
+        return false;
+    };
+    match profile {
+        None => path.exists(),
+        Some(name) => toml_config::TomlConfig::from_file(&path)
+            .map(|config| config.profile_names().iter().any(|known| known == name))
+            .unwrap_or(false),
+    }
+}
+
 /// Check if the keyring is available
-fn check_keyring_availability() -> Result<(), AkonError> {
+async fn check_keyring_availability() -> Result<(), AkonError> {
     // Try to create a test entry to check keyring availability
-    match keyring::store_otp_secret("__akon_test__", "test") {
+    match keyring_async::store_otp_secret("__akon_test__", "test").await {
         Ok(_) => {
             // Clean up test entry
-            let _ = keyring::delete_otp_secret("__akon_test__");
+            let _ = keyring_async::delete_otp_secret("__akon_test__").await;
             Ok(())
         }
         Err(AkonError::Keyring(_)) => {
@@ -170,13 +223,17 @@ fn collect_vpn_config() -> Result<VpnConfig, AkonError> {
         timeout,
         no_dtls,
         lazy_mode,
+        ..VpnConfig::default()
     })
 }
 
 /// Collect reconnection configuration interactively
-fn collect_reconnection_config(
+async fn collect_reconnection_config(
+    server: &str,
 ) -> Result<Option<akon_core::vpn::reconnection::ReconnectionPolicy>, AkonError> {
-    use akon_core::vpn::reconnection::ReconnectionPolicy;
+    use akon_core::vpn::reconnection::{
+        resolve_health_check_endpoint, ReconnectionPolicy, SERVER_ENDPOINT_ALIAS,
+    };
 
     println!();
     println!("Reconnection Configuration (Optional):");
@@ -198,34 +255,64 @@ fn collect_reconnection_config(
 
     // Health check endpoint (required for reconnection)
     println!("Enter the health check endpoint (HTTP/HTTPS URL to verify connectivity)");
+    println!(
+        "{}",
+        "tcp://host:port or icmp://host checks a TCP connect or a ping instead, iface:// the tunnel device".dimmed()
+    );
     println!(
         "{}",
         "Example: https://vpn-gateway.example.com/health".dimmed()
     );
+    println!(
+        "{}",
+        "Use @server to probe the VPN gateway itself (only reachable through the tunnel)".dimmed()
+    );
     let health_check_endpoint = prompt_required("Health Check Endpoint", "https://www.google.com")?;
 
     // Validate URL
-    if !health_check_endpoint.starts_with("http://")
-        && !health_check_endpoint.starts_with("https://")
+    if health_check_endpoint != SERVER_ENDPOINT_ALIAS
+        && parse_endpoint(&health_check_endpoint).is_err()
     {
         return Err(AkonError::Config(
             akon_core::error::ConfigError::ValidationError {
-                message: "Health check endpoint must be an HTTP or HTTPS URL".to_string(),
+                message: "Health check endpoint must be an HTTP/HTTPS, tcp://host:port, icmp://host or iface:// URL".to_string(),
             },
         ));
     }
 
+    if prompt_yes_no("Test the health check endpoint now?", true)? {
+        let probe_url =
+            resolve_health_check_endpoint(&health_check_endpoint, server).map_err(|e| {
+                AkonError::Config(akon_core::error::ConfigError::ValidationError {
+                    message: e.to_string(),
+                })
+            })?;
+        match check_health_endpoint(&probe_url).await {
+            None => println!(
+                "{} {}",
+                ui::symbol(Symbol::Success),
+                "Health check endpoint is reachable".bright_green()
+            ),
+            Some(warning) => {
+                println!(
+                    "{} {}",
+                    ui::symbol(Symbol::Warning),
+                    warning.bright_yellow()
+                );
+                println!(
+                    "  {}",
+                    "Internal endpoints can only be verified once the VPN is connected.".dimmed()
+                );
+            }
+        }
+    }
+
     println!();
     if !prompt_yes_no("Configure advanced reconnection settings?", false)? {
         // Use defaults for everything else
         let policy = ReconnectionPolicy {
-            max_attempts: 5,
-            base_interval_secs: 5,
-            backoff_multiplier: 2,
-            max_interval_secs: 60,
-            consecutive_failures_threshold: 2,
-            health_check_interval_secs: 60,
             health_check_endpoint,
+            ..Default::default()
         };
 
         policy.validate().map_err(|e| {
@@ -240,17 +327,25 @@ fn collect_reconnection_config(
     println!();
     println!("{}", "Advanced Settings:".bright_white().bold());
     println!();
+    let defaults = ReconnectionPolicy::default();
 
     // Max attempts
     println!("Maximum reconnection attempts before requiring manual intervention (1-20)");
-    let max_attempts_str = prompt_optional("Max Attempts", "5")?;
-    let max_attempts = max_attempts_str.parse::<u32>().unwrap_or(5);
+    let max_attempts_str = prompt_optional("Max Attempts", &defaults.max_attempts.to_string())?;
+    let max_attempts = max_attempts_str
+        .parse::<u32>()
+        .unwrap_or(defaults.max_attempts);
 
     // Base interval
     println!();
     println!("Base interval in seconds for exponential backoff (1-300)");
-    let base_interval_str = prompt_optional("Base Interval (seconds)", "5")?;
-    let base_interval_secs = base_interval_str.parse::<u32>().unwrap_or(5);
+    let base_interval_str = prompt_optional(
+        "Base Interval (seconds)",
+        &defaults.base_interval_secs.to_string(),
+    )?;
+    let base_interval_secs = base_interval_str
+        .parse::<u32>()
+        .unwrap_or(defaults.base_interval_secs);
 
     // Backoff multiplier
     println!();
@@ -259,26 +354,46 @@ fn collect_reconnection_config(
         "{}",
         "Intervals will be: base × multiplier^(attempt-1)".dimmed()
     );
-    let backoff_multiplier_str = prompt_optional("Backoff Multiplier", "2")?;
-    let backoff_multiplier = backoff_multiplier_str.parse::<u32>().unwrap_or(2);
+    let backoff_multiplier_str = prompt_optional(
+        "Backoff Multiplier",
+        &defaults.backoff_multiplier.to_string(),
+    )?;
+    let backoff_multiplier = backoff_multiplier_str
+        .parse::<u32>()
+        .unwrap_or(defaults.backoff_multiplier);
 
     // Max interval
     println!();
     println!("Maximum interval in seconds (cap for exponential growth)");
-    let max_interval_str = prompt_optional("Max Interval (seconds)", "60")?;
-    let max_interval_secs = max_interval_str.parse::<u32>().unwrap_or(60);
+    let max_interval_str = prompt_optional(
+        "Max Interval (seconds)",
+        &defaults.max_interval_secs.to_string(),
+    )?;
+    let max_interval_secs = max_interval_str
+        .parse::<u32>()
+        .unwrap_or(defaults.max_interval_secs);
 
     // Consecutive failures
     println!();
     println!("Number of consecutive health check failures before triggering reconnection (1-10)");
-    let consecutive_failures_str = prompt_optional("Consecutive Failures Threshold", "2")?;
-    let consecutive_failures_threshold = consecutive_failures_str.parse::<u32>().unwrap_or(2);
+    let consecutive_failures_str = prompt_optional(
+        "Consecutive Failures Threshold",
+        &defaults.consecutive_failures_threshold.to_string(),
+    )?;
+    let consecutive_failures_threshold = consecutive_failures_str
+        .parse::<u32>()
+        .unwrap_or(defaults.consecutive_failures_threshold);
 
     // Health check interval
     println!();
     println!("Health check interval in seconds (10-3600)");
-    let health_check_interval_str = prompt_optional("Health Check Interval (seconds)", "60")?;
-    let health_check_interval_secs = health_check_interval_str.parse::<u64>().unwrap_or(60);
+    let health_check_interval_str = prompt_optional(
+        "Health Check Interval (seconds)",
+        &defaults.health_check_interval_secs.to_string(),
+    )?;
+    let health_check_interval_secs = health_check_interval_str
+        .parse::<u64>()
+        .unwrap_or(defaults.health_check_interval_secs);
 
     let policy = ReconnectionPolicy {
         max_attempts,
@@ -288,6 +403,7 @@ fn collect_reconnection_config(
         consecutive_failures_threshold,
         health_check_interval_secs,
         health_check_endpoint,
+        ..defaults
     };
 
     // Validate the policy
@@ -307,28 +423,79 @@ fn collect_reconnection_config(
     Ok(Some(policy))
 }
 
+/// Test the health check endpoint from the current network
+///
+/// Returns a warning if the endpoint is unreachable. This is never fatal:
+/// endpoints that are only reachable over the tunnel fail before connecting.
+async fn check_health_endpoint(endpoint: &str) -> Option<String> {
+    let checker = match HealthChecker::new(endpoint.to_string(), Duration::from_secs(5)) {
+        Ok(checker) => checker,
+        Err(e) => return Some(format!("Could not test health check endpoint: {}", e)),
+    };
+
+    let result = checker.check().await;
+    if result.is_success() {
+        None
+    } else {
+        Some(format!(
+            "Health check endpoint is not reachable right now: {}",
+            result.error().unwrap_or("unknown error")
+        ))
+    }
+}
+
 /// Collect OTP secret interactively
-fn collect_otp_secret() -> Result<OtpSecret, AkonError> {
+///
+/// With `keep_existing`, a blank answer keeps the stored secret (`None`).
+/// An otpauth:// URI may be pasted instead of the secret; its code settings
+/// are returned as well.
+fn collect_otp_secret(
+    keep_existing: bool,
+) -> Result<(Option<OtpSecret>, Option<TotpSettings>), AkonError> {
     println!();
     println!("OTP Configuration:");
     println!("-----------------");
 
     println!("Enter your TOTP secret (Base32-encoded, e.g., JBSWY3DPEHPK3PXP)");
+    println!("or the otpauth://totp/... link from your enrollment QR code.");
     println!("This will be stored securely in your system keyring.");
+    if keep_existing {
+        println!("A secret is already stored; leave blank to keep it.");
+    }
     println!();
 
     loop {
         let secret = prompt_password("TOTP Secret")?;
 
         if secret.trim().is_empty() {
+            if keep_existing {
+                println!("Keeping the stored TOTP secret.");
+                return Ok((None, None));
+            }
             println!("❌ Secret cannot be empty. Please try again.");
             continue;
         }
 
-        let otp_secret = OtpSecret::new(secret);
+        if otpauth::is_uri(&secret) {
+            match otpauth::parse_uri(&secret) {
+                Ok(params) => {
+                    let settings = params.settings;
+                    println!(
+                        "Using {:?}, {} digits every {}s from the link.",
+                        settings.algorithm, settings.digits, settings.period_secs
+                    );
+                    return Ok((Some(params.secret), Some(settings)));
+                }
+                Err(e) => {
+                    println!("❌ {}. Please check the link and try again.", e);
+                    continue;
+                }
+            }
+        }
 
-        match otp_secret.validate_base32() {
-            Ok(_) => return Ok(otp_secret),
+        // Store the canonical form, not whatever was pasted
+        match base32::normalize(&secret) {
+            Ok(canonical) => return Ok((Some(OtpSecret::new(canonical)), None)),
             Err(_) => {
                 println!("❌ Invalid Base32 format. Please check your secret and try again.");
                 println!("   Valid characters: A-Z, 2-7, =, /");
@@ -338,8 +505,60 @@ fn collect_otp_secret() -> Result<OtpSecret, AkonError> {
     }
 }
 
+/// Ask how the TOTP secret's codes are generated
+///
+/// The algorithm is always asked for; digits and time step only when the
+/// token doesn't use the usual 6 digits every 30 seconds.
+fn collect_totp_settings() -> Result<TotpSettings, AkonError> {
+    println!();
+    println!("TOTP algorithm (the `algorithm` of an otpauth:// link; most secrets use SHA-1):");
+    println!("  1. SHA-1 [default]");
+    println!("  2. SHA-256");
+    println!("  3. SHA-512");
+
+    let choice = prompt_optional("Select algorithm (1-3)", "1")?;
+    let mut settings = TotpSettings {
+        algorithm: match choice.trim() {
+            "2" => OtpAlgorithm::Sha256,
+            "3" => OtpAlgorithm::Sha512,
+            _ => OtpAlgorithm::Sha1, // Default
+        },
+        ..TotpSettings::default()
+    };
+
+    if !prompt_yes_no(
+        "Does your token show other than 6 digits, or change other than every 30s?",
+        false,
+    )? {
+        return Ok(settings);
+    }
+
+    loop {
+        match prompt_optional("Digits per code (6-8)", "6")?.parse::<u8>() {
+            Ok(digits) if (6..=8).contains(&digits) => {
+                settings.digits = digits;
+                break;
+            }
+            _ => println!("❌ Enter 6, 7 or 8."),
+        }
+    }
+    loop {
+        match prompt_optional("Seconds per code", "30")?.parse::<u64>() {
+            Ok(period) if period > 0 => {
+                settings.period_secs = period;
+                break;
+            }
+            _ => println!("❌ Enter a number of seconds above zero."),
+        }
+    }
+
+    Ok(settings)
+}
+
 /// Collect 4-digit PIN interactively
-fn collect_pin() -> Result<Pin, AkonError> {
+///
+/// With `keep_existing`, a blank answer keeps the stored PIN (`None`).
+fn collect_pin(keep_existing: bool) -> Result<Option<Pin>, AkonError> {
     println!();
     println!("PIN Configuration:");
     println!("-----------------");
@@ -347,6 +566,9 @@ fn collect_pin() -> Result<Pin, AkonError> {
     println!(
         "Enter your VPN PIN (any format). This will be stored securely in your system keyring."
     );
+    if keep_existing {
+        println!("A PIN is already stored; leave blank to keep it.");
+    }
     println!();
 
     loop {
@@ -354,6 +576,10 @@ fn collect_pin() -> Result<Pin, AkonError> {
         let candidate = pin_str.trim().to_string();
 
         if candidate.is_empty() {
+            if keep_existing {
+                println!("Keeping the stored PIN.");
+                return Ok(None);
+            }
             println!("❌ PIN cannot be empty. Please try again.");
             continue;
         }
@@ -366,7 +592,7 @@ fn collect_pin() -> Result<Pin, AkonError> {
             candidate.clone()
         };
 
-        return Ok(Pin::from_unchecked(stored));
+        return Ok(Some(Pin::from_unchecked(stored)));
     }
 }
 
@@ -441,3 +667,47 @@ fn prompt_input(prompt: &str) -> Result<String, AkonError> {
 
     Ok(input.trim_end().to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Serve a single `200 OK` response on a local port
+    fn serve_ok_once() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                );
+            }
+        });
+        format!("http://{}/health", addr)
+    }
+
+    #[tokio::test]
+    async fn test_reachable_health_endpoint_passes() {
+        let endpoint = serve_ok_once();
+        assert_eq!(check_health_endpoint(&endpoint).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_health_endpoint_warns() {
+        // Bind then drop to get a local port nothing listens on
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let endpoint = format!("http://{}/health", addr);
+
+        let warning = check_health_endpoint(&endpoint)
+            .await
+            .expect("warning expected");
+        assert!(warning.contains("not reachable"));
+    }
+}
@@ -0,0 +1,135 @@
+//! Version command implementation
+//!
+//! `akon version` prints the version; `--verbose` adds what a bug report
+//! needs: the akon-core and openconnect versions, the TLS backend and the
+//! optional features compiled in.
+
+use akon_core::config::toml_config::load_config;
+use akon_core::error::AkonError;
+use serde::Serialize;
+use std::process::Command;
+
+/// TLS implementation used for health checks and the clock skew check
+const TLS_BACKEND: &str = "rustls";
+
+/// Build and environment details for bug reports
+#[derive(Debug, Serialize)]
+pub struct VersionInfo {
+    pub akon: &'static str,
+    pub akon_core: &'static str,
+    /// `None` when openconnect isn't installed
+    pub openconnect: Option<String>,
+    pub tls_backend: &'static str,
+    pub features: Vec<&'static str>,
+}
+
+/// Optional features of akon and akon-core that were compiled in
+fn compiled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "systemd-notify") {
+        features.push("systemd-notify");
+    }
+    features.extend(akon_core::compiled_features());
+    features
+}
+
+/// Version from the first line of `openconnect --version`
+///
+/// e.g. "OpenConnect version v9.12" gives "v9.12".
+fn parse_openconnect_version(output: &str) -> Option<String> {
+    let line = output.lines().next()?.trim();
+    let version = line
+        .strip_prefix("OpenConnect version")
+        .unwrap_or(line)
+        .trim();
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+fn openconnect_version(profile: Option<&str>) -> Option<String> {
+    let program = load_config(profile)
+        .ok()
+        .and_then(|config| config.openconnect_path)
+        .unwrap_or_else(|| "openconnect".into());
+    let output = Command::new(program).arg("--version").output().ok()?;
+    parse_openconnect_version(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Collect the version details
+pub fn version_info(profile: Option<&str>) -> VersionInfo {
+    VersionInfo {
+        akon: env!("CARGO_PKG_VERSION"),
+        akon_core: akon_core::VERSION,
+        openconnect: openconnect_version(profile),
+        tls_backend: TLS_BACKEND,
+        features: compiled_features(),
+    }
+}
+
+/// Lines printed by `akon version --verbose`
+fn verbose_lines(info: &VersionInfo) -> Vec<String> {
+    let features = if info.features.is_empty() {
+        "none".to_string()
+    } else {
+        info.features.join(", ")
+    };
+    vec![
+        format!("akon {}", info.akon),
+        format!("akon-core:   {}", info.akon_core),
+        format!(
+            "openconnect: {}",
+            info.openconnect.as_deref().unwrap_or("not found")
+        ),
+        format!("TLS backend: {}", info.tls_backend),
+        format!("Features:    {}", features),
+    ]
+}
+
+/// Run the version command
+///
+/// `--json` prints every detail, with or without `--verbose`.
+pub fn run_version(verbose: bool, json: bool, profile: Option<&str>) -> Result<(), AkonError> {
+    if json {
+        let info = version_info(profile);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&info).expect("version info serializes")
+        );
+    } else if verbose {
+        for line in verbose_lines(&version_info(profile)) {
+            println!("{}", line);
+        }
+    } else {
+        println!("akon {}", env!("CARGO_PKG_VERSION"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_openconnect_version() {
+        assert_eq!(
+            parse_openconnect_version("OpenConnect version v9.12\nUsing GnuTLS 3.8.3.\n"),
+            Some("v9.12".to_string())
+        );
+        assert_eq!(parse_openconnect_version(""), None);
+    }
+
+    #[test]
+    fn test_verbose_lines_report_missing_openconnect() {
+        let info = VersionInfo {
+            akon: "1.2.3",
+            akon_core: "1.2.3",
+            openconnect: None,
+            tls_backend: TLS_BACKEND,
+            features: vec![],
+        };
+        let lines = verbose_lines(&info);
+
+        assert_eq!(lines[0], "akon 1.2.3");
+        assert!(lines.contains(&"openconnect: not found".to_string()));
+        assert!(lines.contains(&"Features:    none".to_string()));
+    }
+}
@@ -2,42 +2,529 @@
 //!
 //! CLI-based OpenConnect integration using process delegation
 
-use crate::daemon::process::cleanup_orphaned_processes;
-use akon_core::auth::password::generate_password;
-use akon_core::config::toml_config::{get_config_path, TomlConfig};
-use akon_core::error::{AkonError, VpnError};
-use akon_core::vpn::health_check::HealthChecker;
-use akon_core::vpn::reconnection::ReconnectionManager;
+use crate::cli::doctor::warn_about_damaged_secret;
+use crate::cli::TerminalPrompt;
+use crate::daemon::notify::{self, ServiceState};
+use crate::daemon::process::{cleanup_orphaned_processes, CleanupOptions, CleanupReport};
+use crate::ui::{self, Symbol};
+use akon_core::auth::clock_skew::{query_clock_offset, skew_warrants_warning};
+use akon_core::auth::password::{generate_password_async_with, StoredCredentials};
+use akon_core::auth::totp::{ensure_unused_step, Clock, OffsetClock, TotpSettings};
+use akon_core::config::toml_config::{
+    get_config_path, load_config, save_auth_group_to_path, TomlConfig,
+};
+use akon_core::config::{LazyAction, PrivilegeEscalation, VpnConfig, PROFILE_ENV};
+use akon_core::error::{AkonError, ConfigError, OtpError, VpnError};
+use akon_core::types::SessionCookie;
+use akon_core::vpn::attempt_budget::{acquire_attempt, attempt_budget_path};
+use akon_core::vpn::cli_connector::locate_openconnect;
+use akon_core::vpn::connect_lock::ConnectLock;
+use akon_core::vpn::egress::{interface_exists, EgressRoute, SystemRunner};
+use akon_core::vpn::health_check::{HealthCheckOptions, HealthChecker};
+use akon_core::vpn::hooks::ReconnectionHooks;
+use akon_core::vpn::ip_history::{ip_history_path, IpHistory};
+use akon_core::vpn::jump::JumpForward;
+use akon_core::vpn::kill_switch::{system_nameservers, KillSwitch};
+use akon_core::vpn::process::any_openconnect_running;
+use akon_core::vpn::reconnection::{ReconnectionManager, ReconnectionPolicy};
+use akon_core::vpn::route_check::{verify_routes, verify_tunnel_route, RouteCheck};
+use akon_core::vpn::server_addresses::{alternate_addresses, connect_across, resolve_gateways};
+use akon_core::vpn::session::watch_session_state;
+pub use akon_core::vpn::state_file::state_file_path;
+use akon_core::vpn::state_file::{
+    profile_file_suffix, RecordedState, StateFileError, VpnStateFile,
+};
+use akon_core::vpn::stats::{format_bytes, read_interface_stats};
+use akon_core::vpn::summary::ConnectionSummary;
 use akon_core::vpn::{CliConnector, ConnectionEvent};
+use chrono::{DateTime, Utc};
 use colored::Colorize;
+use serde::Serialize;
 use std::fs;
-use std::path::PathBuf;
+use std::future::Future;
+use std::io::{BufRead, IsTerminal, Write};
+use std::net::{IpAddr, ToSocketAddrs};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use tracing::{debug, error, info, warn};
 
-/// State file for tracking VPN connection
-fn state_file_path() -> PathBuf {
-    std::env::var("AKON_STATE_FILE")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| PathBuf::from("/tmp/akon_vpn_state.json"))
+/// Environment variable naming a file for the reconnection daemon's stderr
+const DAEMON_LOG_ENV: &str = "AKON_DAEMON_LOG";
+
+/// Lock held by `vpn on` while a connection is being established
+fn connect_lock_path(profile: Option<&str>) -> PathBuf {
+    state_file_path(profile).with_extension("lock")
+}
+
+/// File recording the egress route installed for the current connection
+fn egress_route_path(profile: Option<&str>) -> PathBuf {
+    state_file_path(profile).with_extension("egress.json")
+}
+
+/// Resolve every address of the VPN server
+fn resolve_server_ips(config: &VpnConfig) -> Result<Vec<IpAddr>, VpnError> {
+    let mut ips: Vec<IpAddr> = (config.server.as_str(), 443)
+        .to_socket_addrs()
+        .map(|addrs| addrs.map(|addr| addr.ip()).collect())
+        .unwrap_or_default();
+    ips.dedup();
+    if ips.is_empty() {
+        return Err(VpnError::NetworkError {
+            reason: format!("Failed to resolve {}", config.server),
+        });
+    }
+    Ok(ips)
+}
+
+/// Resolve the VPN server's address
+fn resolve_server_ip(config: &VpnConfig) -> Result<IpAddr, VpnError> {
+    Ok(resolve_server_ips(config)?[0])
+}
+
+/// Route the VPN server through `egress_interface`, if one is configured
+fn install_egress_route(config: &VpnConfig) -> Result<Option<EgressRoute>, AkonError> {
+    let Some(interface) = config.egress_interface.as_deref() else {
+        return Ok(None);
+    };
+
+    if !interface_exists(Path::new("/sys/class/net"), interface) {
+        return Err(AkonError::Config(ConfigError::ValidationError {
+            message: format!("egress_interface '{}' does not exist", interface),
+        }));
+    }
+
+    let server_ip = resolve_server_ip(config)?;
+    let route = EgressRoute::install(&mut SystemRunner, server_ip, interface)?;
+    info!(server_ip = %server_ip, interface = %interface, "Installed egress route");
+
+    match serde_json::to_string_pretty(&route) {
+        Ok(json) => {
+            if let Err(e) = fs::write(egress_route_path(config.profile.as_deref()), json) {
+                warn!("Failed to record egress route: {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize egress route: {}", e),
+    }
+
+    Ok(Some(route))
+}
+
+/// Remove the egress route recorded for the current connection, if any
+fn remove_egress_route(profile: Option<&str>) {
+    let path = egress_route_path(profile);
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return;
+    };
+
+    match serde_json::from_str::<EgressRoute>(&contents) {
+        Ok(route) => match route.remove(&mut SystemRunner) {
+            Ok(()) => info!(interface = %route.interface, "Removed egress route"),
+            Err(e) => warn!("{}", e),
+        },
+        Err(e) => warn!("Ignoring unreadable egress route record: {}", e),
+    }
+
+    if let Err(e) = fs::remove_file(&path) {
+        warn!("Failed to remove egress route record: {}", e);
+    }
+}
+
+/// File recording the SSH forward opened for the current connection
+fn jump_forward_path(profile: Option<&str>) -> PathBuf {
+    state_file_path(profile).with_extension("jump.json")
+}
+
+/// Forward a local port to the VPN server through `jump_host`, if one is configured
+fn open_jump_forward(config: &VpnConfig) -> Result<Option<JumpForward>, AkonError> {
+    let (Some(jump_host), Some(local_port)) =
+        (config.jump_host.as_deref(), config.jump_forward_port())
+    else {
+        return Ok(None);
+    };
+
+    let forward = JumpForward::open(
+        &mut SystemRunner,
+        jump_host,
+        local_port,
+        &config.server,
+        state_file_path(config.profile.as_deref()).with_extension("jump.sock"),
+    )?;
+    info!(jump_host = %jump_host, local_port = local_port, "Opened SSH forward");
+
+    match serde_json::to_string_pretty(&forward) {
+        Ok(json) => {
+            if let Err(e) = fs::write(jump_forward_path(config.profile.as_deref()), json) {
+                warn!("Failed to record SSH forward: {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize SSH forward: {}", e),
+    }
+
+    Ok(Some(forward))
+}
+
+/// Close the SSH forward recorded for the current connection, if any
+///
+/// Call once openconnect has stopped, as its connection runs through the forward.
+fn close_jump_forward(profile: Option<&str>) {
+    let path = jump_forward_path(profile);
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return;
+    };
+
+    match serde_json::from_str::<JumpForward>(&contents) {
+        Ok(forward) => match forward.close(&mut SystemRunner) {
+            Ok(()) => info!(jump_host = %forward.jump_host, "Closed SSH forward"),
+            Err(e) => warn!("{}", e),
+        },
+        Err(e) => warn!("Ignoring unreadable SSH forward record: {}", e),
+    }
+
+    if let Err(e) = fs::remove_file(&path) {
+        warn!("Failed to remove SSH forward record: {}", e);
+    }
+}
+
+/// File recording the kill switch installed for the current session
+fn kill_switch_path(profile: Option<&str>) -> PathBuf {
+    state_file_path(profile).with_extension("kill_switch.json")
+}
+
+/// The tunnel device, for the checks that can't do without it
+fn known_device(device: Option<&str>) -> Result<&str, VpnError> {
+    device.ok_or_else(|| VpnError::RoutingNotConfigured {
+        reason: "no interface holds the assigned address".to_string(),
+    })
+}
+
+/// Block traffic outside `device`, if `kill_switch` is configured
+///
+/// Called each time the tunnel comes up, so a reconnection moves the
+/// allowed device to the new tunnel.
+fn install_kill_switch(
+    config: &VpnConfig,
+    device: Option<&str>,
+) -> Result<Option<KillSwitch>, VpnError> {
+    if !config.kill_switch {
+        return Ok(None);
+    }
+    let device = known_device(device)?;
+
+    // Every gateway stays reachable, for a reconnection moving on to another
+    let mut server_ips = resolve_server_ips(config)?;
+    if let Some(address) = config.server_address {
+        if !server_ips.contains(&address) {
+            server_ips.push(address);
+        }
+    }
+    let kill_switch =
+        KillSwitch::install(&mut SystemRunner, device, server_ips, system_nameservers())?;
+    info!(
+        device = %device,
+        server_ips = ?kill_switch.server_ips,
+        nameservers = ?kill_switch.nameservers,
+        "Installed kill switch"
+    );
+
+    match serde_json::to_string_pretty(&kill_switch) {
+        Ok(json) => {
+            if let Err(e) = fs::write(kill_switch_path(config.profile.as_deref()), json) {
+                warn!("Failed to record kill switch: {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize kill switch: {}", e),
+    }
+
+    Ok(Some(kill_switch))
+}
+
+/// Lift the kill switch recorded for the current session, if any
+///
+/// The record is kept if removal fails, so the next `vpn off` retries it.
+fn remove_kill_switch(profile: Option<&str>) {
+    let path = kill_switch_path(profile);
+    if !path.exists() {
+        return;
+    }
+
+    match KillSwitch::remove(&mut SystemRunner) {
+        Ok(()) => {
+            info!("Removed kill switch");
+            println!(
+                "{} {}",
+                ui::symbol(Symbol::Success),
+                "Kill switch lifted".bright_green()
+            );
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("Failed to remove kill switch record: {}", e);
+            }
+        }
+        Err(e) => {
+            warn!("{}", e);
+            println!(
+                "{} {}",
+                ui::symbol(Symbol::Warning),
+                format!("{}; traffic outside the VPN is still blocked", e).bright_yellow()
+            );
+        }
+    }
+}
+
+/// Resources `vpn off` releases once openconnect has stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TeardownStep {
+    EgressRoute,
+    JumpForward,
+    ReconnectionDaemon,
+    OrphanedProcesses,
+    KillSwitch,
+}
+
+/// Order `vpn off` releases resources in
+///
+/// The daemon is stopped before the kill switch is lifted so it can't
+/// reconnect and reinstall it, and the kill switch goes last: until
+/// everything else is down, traffic stays blocked rather than leaking.
+const TEARDOWN_ORDER: [TeardownStep; 5] = [
+    TeardownStep::EgressRoute,
+    TeardownStep::JumpForward,
+    TeardownStep::ReconnectionDaemon,
+    TeardownStep::OrphanedProcesses,
+    TeardownStep::KillSwitch,
+];
+
+/// Release one resource of a disconnected session of `profile`, whose
+/// openconnect process was `openconnect_pid`
+fn run_teardown_step(step: TeardownStep, profile: Option<&str>, openconnect_pid: Option<u32>) {
+    match step {
+        TeardownStep::EgressRoute => remove_egress_route(profile),
+        TeardownStep::JumpForward => close_jump_forward(profile),
+        TeardownStep::ReconnectionDaemon => stop_reconnection_manager_daemon(profile),
+        TeardownStep::OrphanedProcesses => {
+            println!(
+                "{} {}",
+                ui::symbol(Symbol::Cleanup),
+                "Cleaning up any orphaned OpenConnect processes...".bright_white()
+            );
+            info!("Starting comprehensive cleanup of orphaned processes");
+
+            let result = cleanup_orphaned_processes(openconnect_pid, &cleanup_options(profile));
+            handle_cleanup_result(result, "run_vpn_off (after disconnect)");
+        }
+        TeardownStep::KillSwitch => remove_kill_switch(profile),
+    }
+}
+
+/// State file contents for an established connection
+fn connected_state(
+    config: &VpnConfig,
+    ip: IpAddr,
+    device: Option<&str>,
+    pid: Option<u32>,
+    ip_unusual: Option<bool>,
+) -> VpnStateFile {
+    VpnStateFile::connected(config.display_name(), ip, device, pid, ip_unusual)
+}
+
+/// Status line naming the connection a state file belongs to
+fn connection_name_line(name: &str) -> String {
+    format!(
+        "  {} {}",
+        "Connection:".bright_white(),
+        name.bright_cyan().bold()
+    )
+}
+
+/// Message for `vpn on` when the state file shows a live connection
+fn already_connected_message(state: &VpnStateFile) -> String {
+    match &state.name {
+        Some(name) => format!("{} is already connected", name),
+        None => "VPN is already connected".to_string(),
+    }
+}
+
+/// Record the assigned IP in the history, returning whether it is from an unusual range
+///
+/// Returns `None` when history tracking is disabled or the history can't be updated.
+fn record_ip_history(config: &akon_core::config::VpnConfig, ip: IpAddr) -> Option<bool> {
+    if !config.track_ip_history {
+        return None;
+    }
+
+    let path = match ip_history_path(&config.server) {
+        Ok(path) => path,
+        Err(e) => {
+            warn!("Failed to locate IP history: {}", e);
+            return None;
+        }
+    };
+
+    let mut history = IpHistory::load(&path).unwrap_or_else(|e| {
+        warn!("Ignoring unreadable IP history: {}", e);
+        IpHistory::default()
+    });
+    let unusual = history.is_unusual(ip);
+    if unusual {
+        warn!(ip = %ip, "Assigned IP is outside the usual range for this server");
+    }
+
+    history.record(ip);
+    if let Err(e) = history.save(&path) {
+        warn!("Failed to save IP history: {}", e);
+    }
+
+    Some(unusual)
+}
+
+/// Spend one attempt from the shared authentication budget, if one is configured
+///
+/// Called before every authentication, so `akon vpn on` and the reconnection
+/// daemon draw from the same budget.
+fn spend_attempt_budget(config: &VpnConfig) -> Result<(), AkonError> {
+    let Some(capacity) = config.max_auth_attempts_per_hour else {
+        return Ok(());
+    };
+
+    let path = match attempt_budget_path() {
+        Ok(path) => path,
+        Err(e) => {
+            warn!("Failed to locate attempt budget: {}", e);
+            return Ok(());
+        }
+    };
+
+    acquire_attempt(&path, capacity, SystemTime::now()).map_err(AkonError::Vpn)
+}
+
+/// Warn when the clock is far enough off the VPN server's to get OTP codes rejected
+///
+/// Only with `check_clock_skew`. The configured `time_offset_secs` counts as
+/// corrected; failing to reach the server only skips the check.
+async fn warn_about_clock_skew(config: &VpnConfig) {
+    // Through a jump host the server is only reachable under another name
+    if !config.check_clock_skew || config.jump_host.is_some() {
+        return;
+    }
+
+    let url = format!("https://{}/", config.server);
+    let offset = match query_clock_offset(
+        &url,
+        config.insecure_skip_tls_verify,
+        Duration::from_secs(5),
+    )
+    .await
+    {
+        Ok(offset) => offset,
+        Err(e) => {
+            debug!("Skipping clock skew check: {}", e);
+            return;
+        }
+    };
+
+    let skew = offset - chrono::Duration::seconds(config.time_offset_secs);
+    if !skew_warrants_warning(skew) {
+        debug!(
+            "Clock is within {}ms of the VPN server's",
+            skew.num_milliseconds()
+        );
+        return;
+    }
+
+    let seconds = skew.num_seconds();
+    warn!("System clock is {}s off the VPN server's", seconds);
+    eprintln!(
+        "{} {}",
+        ui::symbol(Symbol::Warning),
+        format!(
+            "System clock is about {}s {} the VPN server's: OTP codes may be rejected",
+            seconds.abs(),
+            if seconds > 0 { "behind" } else { "ahead of" }
+        )
+        .bright_yellow()
+    );
+    eprintln!(
+        "   {}",
+        format!(
+            "Enable time synchronization (timedatectl set-ntp true), or set time_offset_secs = {}",
+            offset.num_seconds()
+        )
+        .dimmed()
+    );
+}
+
+/// Check that the tunnel carries the default route, if `verify_default_route` is set
+///
+/// Returns the problem found; callers fail the connection only in enforce mode.
+fn default_route_problem(config: &VpnConfig, device: Option<&str>) -> Option<VpnError> {
+    if config.verify_default_route == RouteCheck::Off {
+        return None;
+    }
+    let device = match known_device(device) {
+        Ok(device) => device,
+        Err(e) => return Some(e),
+    };
+
+    let problem = verify_tunnel_route(device).err();
+    if problem.is_none() {
+        info!(device = %device, "Default route goes through the tunnel");
+    }
+    problem
+}
+
+/// Check that the `verify_routes` destinations go through the tunnel
+///
+/// Any problem fails the connection.
+fn required_route_problem(config: &VpnConfig, device: Option<&str>) -> Option<VpnError> {
+    if config.verify_routes.is_empty() {
+        return None;
+    }
+    let device = match known_device(device) {
+        Ok(device) => device,
+        Err(e) => return Some(e),
+    };
+
+    let problem = verify_routes(&config.verify_routes, device).err();
+    if problem.is_none() {
+        info!(device = %device, "Required routes go through the tunnel");
+    }
+    problem
+}
+
+/// Cleanup options of `profile` from config.toml, or the defaults if it
+/// can't be loaded
+fn cleanup_options(profile: Option<&str>) -> CleanupOptions {
+    load_config(profile)
+        .map(|config| CleanupOptions::for_config(&config))
+        .unwrap_or_default()
 }
 
 /// Handle cleanup_orphaned_processes result with user feedback
-fn handle_cleanup_result(result: Result<usize, AkonError>, context: &str) {
+fn handle_cleanup_result(result: Result<CleanupReport, AkonError>, context: &str) {
     match result {
-        Ok(0) => {
-            println!("  {} No orphaned processes found", "✓".bright_green());
+        Ok(report) if report.terminated == 0 => {
+            println!(
+                "  {} No orphaned processes found",
+                ui::symbol(Symbol::Success)
+            );
             debug!("{}: No orphaned OpenConnect processes to clean up", context);
         }
-        Ok(count) => {
+        Ok(report) => {
             println!(
                 "  {} Terminated {} orphaned process(es)",
-                "✓".bright_green(),
-                count.to_string().bright_yellow()
+                ui::symbol(Symbol::Success),
+                report.terminated.to_string().bright_yellow()
             );
+            if !report.killed.is_empty() {
+                println!(
+                    "  {} Killed after ignoring SIGTERM: {:?}",
+                    ui::symbol(Symbol::Warning),
+                    report.killed
+                );
+            }
             info!(
-                count,
+                count = report.terminated,
+                killed = ?report.killed,
                 "{}: Terminated orphaned OpenConnect processes", context
             );
         }
@@ -45,140 +532,233 @@ fn handle_cleanup_result(result: Result<usize, AkonError>, context: &str) {
             warn!("{}: Orphan cleanup failed: {}", context, e);
             println!(
                 "  {} Warning: Could not verify all processes cleaned up",
-                "⚠".bright_yellow()
+                ui::symbol(Symbol::Warning)
             );
         }
     }
 }
 
-/// Print actionable suggestions based on VPN error type
-fn print_error_suggestions(error: &VpnError) {
-    match error {
-        VpnError::AuthenticationFailed => {
-            eprintln!(
-                "\n{} {}",
-                "💡".bright_yellow(),
-                "Suggestions:".bright_white().bold()
-            );
-            eprintln!("   {} Verify your PIN is correct", "•".bright_blue());
-            eprintln!(
-                "   {} Check if your TOTP secret is valid",
-                "•".bright_blue()
-            );
-            eprintln!(
-                "   {} Run {} to reconfigure credentials",
-                "•".bright_blue(),
-                "akon setup".bright_cyan()
-            );
-            eprintln!("   {} Ensure your account is not locked", "•".bright_blue());
-        }
-        VpnError::NetworkError { reason } if reason.contains("SSL") || reason.contains("TLS") => {
-            eprintln!("\n💡 Suggestions:");
-            eprintln!("   • Check your internet connection");
-            eprintln!("   • Verify the VPN server address is correct");
-            eprintln!("   • The server may be experiencing issues");
-            eprintln!("   • Try again in a few moments");
-        }
-        VpnError::NetworkError { reason } if reason.contains("Certificate") => {
-            eprintln!("\n💡 Suggestions:");
-            eprintln!("   • The server certificate may be self-signed");
-            eprintln!("   • Contact your VPN administrator for certificate details");
-            eprintln!("   • You may need to add the certificate to your trusted store");
-        }
-        VpnError::NetworkError { reason } if reason.contains("DNS") => {
-            eprintln!("\n💡 Suggestions:");
-            eprintln!("   • Check your DNS configuration");
-            eprintln!("   • Verify the VPN server hostname in config.toml");
-            eprintln!("   • Try using the server's IP address instead");
-            eprintln!("   • Check /etc/resolv.conf for DNS settings");
+/// How to check that `escalation` can run openconnect as root
+fn escalation_check(escalation: PrivilegeEscalation) -> String {
+    match escalation {
+        PrivilegeEscalation::Sudo => {
+            "Check that sudo works for openconnect: sudo -n openconnect --version".to_string()
+        }
+        PrivilegeEscalation::Pkexec => {
+            "Check that pkexec works for openconnect (needs a polkit agent): pkexec openconnect --version"
+                .to_string()
         }
+        PrivilegeEscalation::None => {
+            "With privilege_escalation = \"none\", run akon as root or give openconnect CAP_NET_ADMIN"
+                .to_string()
+        }
+    }
+}
+
+/// Actionable suggestions for a VPN error
+///
+/// Plain text, as they are also saved in the state file for `vpn status`.
+/// Privilege problems point at the configured `escalation` tool.
+fn error_suggestions(error: &VpnError, escalation: PrivilegeEscalation) -> Vec<String> {
+    let lines: &[&str] = match error {
+        VpnError::AuthenticationFailed => &[
+            "Verify your PIN is correct",
+            "Check if your TOTP secret is valid",
+            "Run akon setup to reconfigure credentials",
+            "Ensure your account is not locked",
+        ],
+        VpnError::NetworkError { reason } if reason.contains("SSL") || reason.contains("TLS") => &[
+            "Check your internet connection",
+            "Verify the VPN server address is correct",
+            "The server may be experiencing issues",
+            "Try again in a few moments",
+        ],
+        VpnError::NetworkError { reason } if reason.contains("Certificate") => &[
+            "The server certificate may be self-signed",
+            "Contact your VPN administrator for certificate details",
+            "You may need to add the certificate to your trusted store",
+        ],
+        VpnError::DnsFailure { transient: true } => &[
+            "The network may not be up yet (e.g. right after resume)",
+            "Raise dns_retries or dns_retry_interval_secs in config.toml",
+            "Check /etc/resolv.conf for DNS settings",
+        ],
+        VpnError::DnsFailure { transient: false } => &[
+            "Check your DNS configuration",
+            "Verify the VPN server hostname in config.toml",
+            "Try using the server's IP address instead",
+            "Check /etc/resolv.conf for DNS settings",
+        ],
         VpnError::ConnectionFailed { reason }
-            if reason.contains("TUN") || reason.contains("sudo") =>
+            if reason.contains("TUN") || reason.contains("sudo") || reason.contains("pkexec") =>
         {
-            eprintln!("\n💡 Suggestions:");
-            eprintln!("   • VPN requires root privileges to create TUN device");
-            eprintln!("   • Run with: sudo akon vpn on");
-            eprintln!("   • Ensure the 'tun' kernel module is loaded");
-            eprintln!("   • Check: lsmod | grep tun");
+            return vec![
+                "VPN requires root privileges to create TUN device".to_string(),
+                escalation_check(escalation),
+                "Ensure the 'tun' kernel module is loaded".to_string(),
+                "Check: lsmod | grep tun".to_string(),
+            ];
+        }
+        VpnError::RoutingNotConfigured { .. } => {
+            return vec![
+                "Authentication succeeded, but no traffic will flow through the VPN".to_string(),
+                "OpenConnect needs root privileges to configure the TUN device and routes"
+                    .to_string(),
+                escalation_check(escalation),
+                "Ensure the 'tun' kernel module is loaded: lsmod | grep tun".to_string(),
+            ];
         }
+        VpnError::AuthGroupRequired { groups } => {
+            let mut suggestions =
+                vec!["Run 'akon vpn on' from a terminal to pick a group from a menu".to_string()];
+            if let Some(group) = groups.first() {
+                suggestions.push(format!(
+                    "Or set it in config.toml under [vpn]: auth_group = \"{}\"",
+                    group
+                ));
+            }
+            return suggestions;
+        }
+        VpnError::RateLimited { .. } => &[
+            "Too many authentication attempts were made in the last hour",
+            "Wait until the retry time above, or raise max_auth_attempts_per_hour",
+            "Repeated failures may lock your account: check 'akon vpn status'",
+        ],
+        VpnError::OpenConnectNotFound => &[
+            "Install with: sudo apt install openconnect",
+            "Or for RHEL/Fedora: sudo dnf install openconnect",
+            "If it is installed elsewhere, set openconnect_path in config.toml",
+            "Verify installation: which openconnect",
+        ],
         VpnError::ProcessSpawnError { .. } => {
-            eprintln!(
-                "\n{} {}",
-                "💡".bright_yellow(),
-                "Suggestions:".bright_white().bold()
-            );
-            eprintln!("   {} OpenConnect may not be installed", "•".bright_blue());
-            eprintln!(
-                "   {} Install with: {}",
-                "•".bright_blue(),
-                "sudo apt install openconnect".bright_cyan()
-            );
-            eprintln!(
-                "   {} Or for RHEL/Fedora: {}",
-                "•".bright_blue(),
-                "sudo dnf install openconnect".bright_cyan()
-            );
-            eprintln!(
-                "   {} Verify installation: {}",
-                "•".bright_blue(),
-                "which openconnect".bright_cyan()
-            );
+            let mut suggestions = vec![
+                "OpenConnect is installed but could not be started".to_string(),
+                "Check that it is executable: ls -l $(command -v openconnect)".to_string(),
+                "Reinstall it if the binary is damaged or built for another architecture"
+                    .to_string(),
+            ];
+            if let Some(tool) = escalation.tool() {
+                suggestions.push(format!(
+                    "Check that {} is installed: command -v {}",
+                    tool, tool
+                ));
+            }
+            return suggestions;
         }
         VpnError::ConnectionFailed { reason } if reason.contains("Permission denied") => {
-            eprintln!(
-                "\n{} {}",
-                "💡".bright_yellow(),
-                "Suggestions:".bright_white().bold()
-            );
-            eprintln!(
-                "   {} This command requires elevated privileges",
-                "•".bright_blue()
-            );
-            eprintln!(
-                "   {} Run with: {}",
-                "•".bright_blue(),
-                "sudo akon vpn on".bright_cyan()
-            );
+            return vec![
+                "This command requires elevated privileges".to_string(),
+                escalation_check(escalation),
+            ];
         }
-        _ => {
-            // Generic suggestions for other errors
-            eprintln!(
-                "\n{} {}",
-                "💡".bright_yellow(),
-                "Suggestions:".bright_white().bold()
-            );
-            eprintln!(
-                "   {} Check system logs: {}",
-                "•".bright_blue(),
-                "journalctl -xe".bright_cyan()
-            );
-            eprintln!(
-                "   {} Verify configuration: {}",
-                "•".bright_blue(),
-                "cat ~/.config/akon/config.toml".bright_cyan()
-            );
-            eprintln!(
-                "   {} Try reconnecting: {}",
-                "•".bright_blue(),
-                "akon vpn on".bright_cyan()
-            );
+        // Generic suggestions for other errors
+        _ => &[
+            "Check system logs: journalctl -xe",
+            "Verify configuration: cat ~/.config/akon/config.toml",
+            "Try reconnecting: akon vpn on",
+        ],
+    };
+    lines.iter().map(|line| line.to_string()).collect()
+}
+
+/// Format suggestions as printed after an error
+fn format_suggestions(suggestions: &[String]) -> Vec<String> {
+    let mut lines = vec![format!(
+        "\n{} {}",
+        ui::symbol(Symbol::Hint),
+        "Suggestions:".bright_white().bold()
+    )];
+    lines.extend(
+        suggestions
+            .iter()
+            .map(|suggestion| format!("   {} {}", ui::symbol(Symbol::Bullet), suggestion)),
+    );
+    lines
+}
+
+/// Print actionable suggestions based on VPN error type
+fn print_error_suggestions(error: &VpnError, escalation: PrivilegeEscalation) {
+    for line in format_suggestions(&error_suggestions(error, escalation)) {
+        eprintln!("{}", line);
+    }
+}
+
+/// State file contents for a failed connection
+fn connect_error_state(config: &VpnConfig, error: &VpnError) -> VpnStateFile {
+    VpnStateFile {
+        error_kind: Some(error.kind().to_string()),
+        suggestions: error_suggestions(error, config.privilege_escalation),
+        ..VpnStateFile::error(config.display_name(), &error.to_string())
+    }
+}
+
+/// Record a failed connection in the state file
+///
+/// Keeps the error kind and suggestions so `vpn status` can show them later.
+fn write_connect_error_state(config: &VpnConfig, error: &VpnError) {
+    if let Err(e) = connect_error_state(config, error).store(config.profile.as_deref()) {
+        warn!("Failed to record connection error: {}", e);
+    }
+}
+
+/// TOTP step used by the last reconnection attempt in this process
+static LAST_RECONNECT_OTP_STEP: std::sync::Mutex<Option<u64>> = std::sync::Mutex::new(None);
+
+/// Wait until a TOTP code not used by a previous reconnection attempt is available
+///
+/// Retries can come faster than the TOTP step (30s unless configured
+/// otherwise), and gateways reject a code that was already used.
+async fn wait_for_unused_otp_step(clock: &dyn Clock, period_secs: u64) -> Result<(), AkonError> {
+    loop {
+        let last_used = *LAST_RECONNECT_OTP_STEP
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        match ensure_unused_step(last_used, Some(clock.unix_time()?), period_secs) {
+            Ok(step) => {
+                *LAST_RECONNECT_OTP_STEP
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(step);
+                return Ok(());
+            }
+            Err(AkonError::Otp(OtpError::RateLimited { retry_after_secs })) => {
+                info!(
+                    "TOTP code already used by the previous attempt, waiting {}s for a new one",
+                    retry_after_secs
+                );
+                tokio::time::sleep(Duration::from_secs(retry_after_secs)).await;
+            }
+            Err(e) => return Err(e),
         }
     }
 }
 
 /// Perform VPN reconnection by cleaning up stale processes and establishing new connection
-async fn perform_reconnection(config: akon_core::config::VpnConfig) -> Result<(), AkonError> {
+///
+/// `openconnect_pid` is the process of the connection being replaced;
+/// returns the one of the new connection.
+async fn perform_reconnection(
+    config: akon_core::config::VpnConfig,
+    openconnect_pid: Option<u32>,
+) -> Result<Option<u32>, AkonError> {
     info!("Performing VPN reconnection");
 
-    // Step 1: Cleanup all stale OpenConnect processes
+    spend_attempt_budget(&config)?;
+
+    if let Some(warning) = config.insecure_tls_warning() {
+        warn!("{}", warning);
+    }
+
+    // Step 1: Cleanup the stale OpenConnect processes of this connection
     info!("Cleaning up stale OpenConnect processes");
 
-    match cleanup_orphaned_processes() {
-        Ok(count) => {
-            if count > 0 {
+    match cleanup_orphaned_processes(openconnect_pid, &CleanupOptions::for_config(&config)) {
+        Ok(report) => {
+            if report.terminated > 0 {
                 info!(
+                    killed = ?report.killed,
                     "Terminated {} orphaned process(es) before reconnection",
-                    count
+                    report.terminated
                 );
             } else {
                 debug!("No orphaned processes found before reconnection");
@@ -193,19 +773,41 @@ async fn perform_reconnection(config: akon_core::config::VpnConfig) -> Result<()
     // Step 2: Wait a moment for cleanup to complete
     tokio::time::sleep(Duration::from_millis(1000)).await;
 
-    // Step 3: Generate new password
-    let password = generate_password(&config.username).map_err(|e| {
+    // openconnect's vpnc-script drops the route to the server on exit
+    install_egress_route(&config)?;
+
+    // The SSH forward may have died with the network; start a fresh one
+    close_jump_forward(config.profile.as_deref());
+    open_jump_forward(&config)?;
+
+    // Step 3: Generate new password, from a TOTP step no earlier attempt used
+    wait_for_unused_otp_step(&OffsetClock::for_config(&config), config.otp_period_secs).await?;
+    let password = generate_password_async_with(
+        &config.keyring_account(),
+        StoredCredentials::for_config(&config),
+        TerminalPrompt::new(),
+        &OffsetClock::for_config(&config),
+        TotpSettings::for_config(&config),
+    )
+    .await
+    .map_err(|e| {
         error!("Failed to generate password for reconnection: {}", e);
         e
     })?;
     info!("Generated password for reconnection");
 
-    // Step 4: Create new connector and establish connection
-    let mut connector = akon_core::vpn::CliConnector::new(config.clone())?;
-    info!("Created new CLI connector for reconnection");
-
-    // Step 5: Connect
-    connector.connect(password.expose().to_string()).await?;
+    // Step 4 and 5: Create new connector and connect, trying each of the
+    // server's gateways
+    let mut connector = retry_alternate_addresses(config.clone(), |config| {
+        let password = password.expose().to_string();
+        async move {
+            let mut connector = akon_core::vpn::CliConnector::new(config)?;
+            info!("Created new CLI connector for reconnection");
+            connector.connect(password).await?;
+            Ok(connector)
+        }
+    })
+    .await?;
     info!("Reconnection initiated, waiting for connection events");
 
     // Step 6: Wait for connection to establish
@@ -214,22 +816,44 @@ async fn perform_reconnection(config: akon_core::config::VpnConfig) -> Result<()
         while let Some(event) = connector.next_event().await {
             match event {
                 akon_core::vpn::ConnectionEvent::Connected { ip, device } => {
-                    info!(ip = %ip, device = %device, "Reconnection successful");
+                    if let Some(e) = default_route_problem(&config, device.as_deref()) {
+                        if config.verify_default_route == RouteCheck::Enforce {
+                            error!("Reconnected, but {}", e);
+                            let _ = connector.disconnect().await;
+                            return Err(AkonError::Vpn(e));
+                        }
+                        warn!("Default route check failed: {}", e);
+                    }
+                    if let Some(e) = required_route_problem(&config, device.as_deref()) {
+                        error!("Reconnected, but {}", e);
+                        let _ = connector.disconnect().await;
+                        return Err(AkonError::Vpn(e));
+                    }
+                    // The old rules still block traffic; let the new tunnel through
+                    if let Err(e) = install_kill_switch(&config, device.as_deref()) {
+                        error!("Reconnected, but {}", e);
+                        let _ = connector.disconnect().await;
+                        return Err(AkonError::Vpn(e));
+                    }
+                    info!(ip = %ip, device = ?device, "Reconnection successful");
+                    notify::notify(ServiceState::Connected {
+                        server: &config.server,
+                        ip,
+                    });
 
                     // Update state file
                     let pid = connector.get_pid();
-                    let state = serde_json::json!({
-                        "ip": ip.to_string(),
-                        "device": device,
-                        "connected_at": chrono::Utc::now().to_rfc3339(),
-                        "pid": pid,
-                    });
+                    let state = connected_state(
+                        &config,
+                        ip,
+                        device.as_deref(),
+                        pid,
+                        record_ip_history(&config, ip),
+                    );
 
-                    if let Ok(state_json) = serde_json::to_string_pretty(&state) {
-                        let _ = fs::write(state_file_path(), state_json);
-                    }
+                    let _ = state.store(config.profile.as_deref());
 
-                    return Ok::<(), AkonError>(());
+                    return Ok::<Option<u32>, AkonError>(pid);
                 }
                 akon_core::vpn::ConnectionEvent::Error { kind, .. } => {
                     error!("Reconnection failed: {}", kind);
@@ -268,19 +892,11 @@ fn spawn_reconnection_manager_daemon(
     config: akon_core::config::VpnConfig,
     _initial_pid: u32,
 ) -> Result<(), AkonError> {
-    use std::process::Command;
-
     info!("Spawning reconnection manager daemon");
 
-    // Kill any existing reconnection manager daemons before starting a new one
-    info!("Cleaning up any existing reconnection manager daemons");
-    let _ = Command::new("pkill")
-        .arg("-f")
-        .arg("__internal_reconnection_daemon")
-        .output();
-
-    // Give processes time to terminate
-    std::thread::sleep(std::time::Duration::from_millis(500));
+    // Stop this profile's previous daemon before starting a new one
+    info!("Cleaning up any existing reconnection manager daemon");
+    stop_reconnection_manager_daemon(config.profile.as_deref());
 
     // Get the current executable path
     let exe_path = std::env::current_exe().map_err(|e| {
@@ -290,44 +906,31 @@ fn spawn_reconnection_manager_daemon(
         })
     })?;
 
-    // Serialize the policy and config to pass to daemon
-    let policy_json = serde_json::to_string(&policy).map_err(|e| {
-        error!("Failed to serialize reconnection policy: {}", e);
-        AkonError::Vpn(VpnError::ConnectionFailed {
-            reason: format!("Failed to serialize policy: {}", e),
+    // Spawn the daemon as a detached child process
+    let log = daemon_log_path(&policy);
+    if let Some(log) = &log {
+        info!("Capturing reconnection daemon stderr in {}", log.display());
+    }
+    let mut command = daemon_command(&exe_path, daemon_args(&policy, &config)?, log.as_deref());
+    // The profile isn't serialized with the config; the daemon reads it back
+    // from its environment
+    if let Some(profile) = &config.profile {
+        command.env(PROFILE_ENV, profile);
+    }
+    let child = command.spawn().map_err(|e| {
+        error!("Failed to spawn reconnection manager daemon: {}", e);
+        AkonError::Vpn(VpnError::ProcessSpawnError {
+            reason: format!("Failed to spawn daemon: {}", e),
         })
     })?;
 
-    let config_json = serde_json::to_string(&config).map_err(|e| {
-        error!("Failed to serialize VPN config: {}", e);
-        AkonError::Vpn(VpnError::ConnectionFailed {
-            reason: format!("Failed to serialize config: {}", e),
-        })
-    })?;
-
-    // Spawn the daemon as a detached child process
-    let child = Command::new(&exe_path)
-        .arg("__internal_reconnection_daemon")
-        .arg(&policy_json)
-        .arg(&config_json)
-        .stdin(std::process::Stdio::null())
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null())
-        .spawn()
-        .map_err(|e| {
-            error!("Failed to spawn reconnection manager daemon: {}", e);
-            AkonError::Vpn(VpnError::ProcessSpawnError {
-                reason: format!("Failed to spawn daemon: {}", e),
-            })
-        })?;
-
     info!(
         "Reconnection manager daemon spawned with PID {}",
         child.id()
     );
 
     // Save daemon PID to file for tracking
-    let daemon_pid_file = get_daemon_pid_file();
+    let daemon_pid_file = get_daemon_pid_file(config.profile.as_deref());
     if let Err(e) = std::fs::write(&daemon_pid_file, child.id().to_string()) {
         warn!("Failed to write daemon PID file: {}", e);
     }
@@ -335,6 +938,77 @@ fn spawn_reconnection_manager_daemon(
     Ok(())
 }
 
+/// Where the daemon's stderr goes: `AKON_DAEMON_LOG`, else the policy's `daemon_log`
+fn daemon_log_path(policy: &ReconnectionPolicy) -> Option<PathBuf> {
+    std::env::var_os(DAEMON_LOG_ENV)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| policy.daemon_log.clone())
+}
+
+/// Command starting the detached daemon
+///
+/// Its output is discarded unless `log` is given: then stderr is appended to
+/// it and the daemon runs in its directory, so a failure before logging is
+/// set up still leaves a trace. A log that can't be opened is only warned about.
+fn daemon_command(program: &Path, args: Vec<String>, log: Option<&Path>) -> std::process::Command {
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::process::Stdio;
+
+    let mut command = std::process::Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    let Some(log) = log else {
+        return command;
+    };
+    match fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(log)
+    {
+        Ok(file) => {
+            command.stderr(file);
+            if let Some(dir) = log.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                command.current_dir(dir);
+            }
+        }
+        Err(e) => warn!(
+            "Failed to open daemon log {}, discarding its output: {}",
+            log.display(),
+            e
+        ),
+    }
+    command
+}
+
+/// Arguments for the daemon process: the marker, then policy and config as JSON
+fn daemon_args(policy: &ReconnectionPolicy, config: &VpnConfig) -> Result<Vec<String>, AkonError> {
+    let policy_json = serde_json::to_string(policy).map_err(|e| {
+        error!("Failed to serialize reconnection policy: {}", e);
+        AkonError::Vpn(VpnError::ConnectionFailed {
+            reason: format!("Failed to serialize policy: {}", e),
+        })
+    })?;
+
+    let config_json = serde_json::to_string(config).map_err(|e| {
+        error!("Failed to serialize VPN config: {}", e);
+        AkonError::Vpn(VpnError::ConnectionFailed {
+            reason: format!("Failed to serialize config: {}", e),
+        })
+    })?;
+
+    Ok(vec![
+        "__internal_reconnection_daemon".to_string(),
+        policy_json,
+        config_json,
+    ])
+}
+
 /// Internal function to run the reconnection manager daemon
 /// This is called by the daemon process itself, not by user commands
 #[doc(hidden)]
@@ -346,10 +1020,26 @@ pub async fn run_reconnection_manager_daemon(
 
     info!("Reconnection manager daemon starting");
 
+    // Resolve "@server" to the VPN gateway itself
+    let health_check_endpoint = policy
+        .resolve_health_check_endpoint(&config.server)
+        .map_err(|e| {
+            error!("Invalid health check endpoint: {}", e);
+            AkonError::Vpn(VpnError::ConnectionFailed {
+                reason: format!("Failed to initialize health checker: {}", e),
+            })
+        })?;
+
     // Create HealthChecker for periodic connectivity verification
-    let health_checker = HealthChecker::new(
-        policy.health_check_endpoint.clone(),
+    let health_checker = HealthChecker::with_options(
+        health_check_endpoint.clone(),
         Duration::from_secs(5), // 5 second timeout per health check
+        HealthCheckOptions {
+            accept_invalid_certs: config.insecure_skip_tls_verify,
+            address_family: policy.health_check_address_family,
+            // Skip the request once openconnect's device is gone
+            tunnel_state_file: Some(state_file_path(config.profile.as_deref())),
+        },
     )
     .map_err(|e| {
         error!("Failed to create HealthChecker: {}", e);
@@ -359,13 +1049,27 @@ pub async fn run_reconnection_manager_daemon(
     })?;
     info!(
         "HealthChecker initialized with endpoint: {}, interval: {}s",
-        policy.health_check_endpoint, policy.health_check_interval_secs
+        health_check_endpoint, policy.health_check_interval_secs
     );
 
-    // Create ReconnectionManager
-    let reconnection_manager = ReconnectionManager::new(policy.clone());
+    // Create ReconnectionManager; it gives up once retries are exhausted
+    // and no openconnect process is left
+    let mut reconnection_manager =
+        ReconnectionManager::new(policy.clone()).with_process_check(any_openconnect_running);
+    if policy.pause_while_locked {
+        match watch_session_state(Duration::from_secs(5)).await {
+            Some(session_state) => {
+                info!("Reconnection pauses while the session is locked");
+                reconnection_manager = reconnection_manager.with_session_state(session_state);
+            }
+            None => warn!(
+                "pause_while_locked is set, but the session state is unavailable; reconnecting regardless"
+            ),
+        }
+    }
     let command_tx = reconnection_manager.command_sender();
     let mut state_rx = reconnection_manager.state_receiver();
+    let final_state_rx = reconnection_manager.state_receiver();
     info!(
         "ReconnectionManager created with max_attempts={}, base_interval={}s, backoff={}x",
         policy.max_attempts, policy.base_interval_secs, policy.backoff_multiplier
@@ -381,14 +1085,49 @@ pub async fn run_reconnection_manager_daemon(
         .ok();
     info!("Set reconnection manager state to Connected");
 
+    if let Some(interval) = notify::watchdog_interval() {
+        info!("Pinging the systemd watchdog every {:?}", interval);
+        tokio::spawn(async move {
+            loop {
+                notify::ping_watchdog();
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    // 'akon vpn off' stops the daemon with SIGTERM: record the deliberate
+    // disconnect so no reconnection starts while shutting down
+    let shutdown_tx = command_tx.clone();
+    tokio::spawn(async move {
+        use akon_core::vpn::DisconnectReason;
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let Ok(mut sigterm) = signal(SignalKind::terminate()) else {
+            warn!("Failed to install SIGTERM handler");
+            return;
+        };
+        sigterm.recv().await;
+        info!("Received SIGTERM, stopping reconnection manager");
+        let _ = shutdown_tx.send(ReconnectionCommand::Disconnected(
+            DisconnectReason::UserRequested,
+        ));
+        let _ = shutdown_tx.send(ReconnectionCommand::Shutdown);
+    });
+
     // Spawn a task to watch for reconnection state changes and trigger actual reconnection
     let config_for_watcher = config.clone();
     let policy_for_watcher = policy.clone();
+    let hooks = ReconnectionHooks::from_policy(&policy);
 
     // Track if reconnection is in progress and last attempt number to prevent duplicate attempts
     let reconnection_state = Arc::new(tokio::sync::Mutex::new((false, 0u32))); // (in_progress, last_attempt)
     let reconnection_state_clone = reconnection_state.clone();
 
+    // openconnect of the connection being watched, for cleanup before reconnecting
+    let mut openconnect_pid = VpnStateFile::load(config.profile.as_deref())
+        .ok()
+        .and_then(|state| state.pid);
+
     tokio::spawn(async move {
         use akon_core::vpn::reconnection::ReconnectionCommand;
         use akon_core::vpn::state::ConnectionState;
@@ -420,16 +1159,13 @@ pub async fn run_reconnection_manager_daemon(
                             "Reconnection already in progress, skipping attempt {}",
                             attempt
                         );
-                        let state_json = serde_json::json!({
-                            "state": "Reconnecting",
-                            "attempt": attempt,
-                            "next_retry_at": next_retry_at,
-                            "max_attempts": max_attempts,
-                            "updated_at": chrono::Utc::now().to_rfc3339(),
-                        });
-                        if let Ok(json) = serde_json::to_string_pretty(&state_json) {
-                            let _ = fs::write(state_file_path(), json);
-                        }
+                        let _ = reconnecting_state(
+                            &config_for_watcher,
+                            *attempt,
+                            *max_attempts,
+                            *next_retry_at,
+                        )
+                        .store(config_for_watcher.profile.as_deref());
                         continue;
                     }
 
@@ -442,27 +1178,38 @@ pub async fn run_reconnection_manager_daemon(
                     *reconnection_info = (true, *attempt);
                     drop(reconnection_info); // Release lock before async work
 
-                    info!("Starting reconnection attempt {}", attempt);
+                    info!(
+                        name = config_for_watcher.display_name(),
+                        "Starting reconnection attempt {}", attempt
+                    );
 
                     // Write reconnecting state to file
-                    let state_json = serde_json::json!({
-                        "state": "Reconnecting",
-                        "attempt": attempt,
-                        "next_retry_at": next_retry_at,
-                        "max_attempts": max_attempts,
-                        "updated_at": chrono::Utc::now().to_rfc3339(),
+                    let _ = reconnecting_state(
+                        &config_for_watcher,
+                        *attempt,
+                        *max_attempts,
+                        *next_retry_at,
+                    )
+                    .store(config_for_watcher.profile.as_deref());
+
+                    notify::notify(ServiceState::Reconnecting {
+                        server: &config_for_watcher.server,
+                        attempt: *attempt,
+                        max_attempts: *max_attempts,
                     });
-                    if let Ok(json) = serde_json::to_string_pretty(&state_json) {
-                        let _ = fs::write(state_file_path(), json);
-                    }
+                    hooks
+                        .attempt_started(*attempt, &config_for_watcher.server)
+                        .await;
 
                     // Perform the actual reconnection
-                    match perform_reconnection(config_for_watcher.clone()).await {
-                        Ok(_) => {
+                    match perform_reconnection(config_for_watcher.clone(), openconnect_pid).await {
+                        Ok(pid) => {
+                            openconnect_pid = pid;
                             info!(
                                 "Reconnection attempt {} successful, transitioning to Connected",
                                 attempt
                             );
+                            hooks.succeeded(*attempt, &config_for_watcher.server).await;
                             // Set state to Connected to stop the retry loop
                             let _ = command_tx.send(ReconnectionCommand::SetConnected {
                                 server: config_for_watcher.server.clone(),
@@ -477,6 +1224,14 @@ pub async fn run_reconnection_manager_daemon(
                             reconnection_info.1 = u32::MAX; // Reject all future attempts until reset
                             info!("Set last_attempt=MAX to reject any queued retry attempts");
                         }
+                        Err(AkonError::Vpn(VpnError::RateLimited { retry_after_secs })) => {
+                            warn!("Reconnection attempt {} rate limited", attempt);
+                            // Retries restart from attempt 1 once the budget refills
+                            *reconnection_state_clone.lock().await = (false, 0);
+                            let _ = command_tx.send(ReconnectionCommand::RateLimited {
+                                retry_after: Duration::from_secs(retry_after_secs),
+                            });
+                        }
                         Err(e) => {
                             warn!("Reconnection attempt {} failed: {}", attempt, e);
                             // Mark reconnection as complete so next attempt can proceed
@@ -498,25 +1253,20 @@ pub async fn run_reconnection_manager_daemon(
                 ConnectionState::Error(error_msg) => {
                     // T053: Write Error state to file so 'akon vpn status' can detect it
                     warn!("Reconnection manager in Error state: {}", error_msg);
-                    let state_json = serde_json::json!({
-                        "state": "Error",
-                        "error": error_msg,
-                        "max_attempts": policy_for_watcher.max_attempts,
-                        "updated_at": chrono::Utc::now().to_rfc3339(),
+                    notify::notify(ServiceState::Failed {
+                        reason: error_msg.clone(),
                     });
-                    if let Ok(json) = serde_json::to_string_pretty(&state_json) {
-                        let _ = fs::write(state_file_path(), json);
-                    }
+                    write_error_state(
+                        &config_for_watcher,
+                        policy_for_watcher.max_attempts,
+                        error_msg,
+                    );
                 }
                 ConnectionState::Disconnected => {
                     info!("Reconnection manager in Disconnected state");
-                    let state_json = serde_json::json!({
-                        "state": "Disconnected",
-                        "updated_at": chrono::Utc::now().to_rfc3339(),
-                    });
-                    if let Ok(json) = serde_json::to_string_pretty(&state_json) {
-                        let _ = fs::write(state_file_path(), json);
-                    }
+                    notify::notify(ServiceState::Disconnected);
+                    let _ = VpnStateFile::disconnected(config_for_watcher.display_name())
+                        .store(config_for_watcher.profile.as_deref());
                 }
                 _ => {
                     // Other states (Connected, Connecting, Disconnecting) are handled elsewhere
@@ -529,18 +1279,87 @@ pub async fn run_reconnection_manager_daemon(
     info!("Starting reconnection manager event loop (health check mode)");
     reconnection_manager.run(Some(health_checker)).await;
 
+    // The manager gave up: record the final state before the watcher task
+    // is dropped, so status doesn't keep showing a stale Reconnecting
+    let final_state = final_state_rx.borrow().clone();
+    match final_state {
+        akon_core::vpn::state::ConnectionState::Error(error_msg) => {
+            write_error_state(&config, policy.max_attempts, &error_msg);
+        }
+        akon_core::vpn::state::ConnectionState::Disconnecting => {
+            // Scheduled disconnect: what 'akon vpn off' does. With the PID
+            // file gone first, it doesn't try to stop this daemon.
+            info!("Disconnecting as scheduled");
+            remove_own_daemon_pid_file(config.profile.as_deref());
+            if let Err(e) = run_vpn_off(false, config.profile.as_deref()).await {
+                error!("Scheduled disconnect failed: {}", e);
+            }
+            notify::notify(ServiceState::Disconnected);
+        }
+        _ => {}
+    }
+    remove_own_daemon_pid_file(config.profile.as_deref());
+    info!("Reconnection manager daemon exiting");
+
     Ok(())
 }
 
-/// Get the path to the daemon PID file
-fn get_daemon_pid_file() -> PathBuf {
-    // Use /tmp for the daemon PID file
-    PathBuf::from("/tmp/akon-reconnection-daemon.pid")
+/// State file contents while reconnecting
+///
+/// Keeps the IP of the connection being replaced, for `vpn status`.
+fn reconnecting_state(
+    config: &VpnConfig,
+    attempt: u32,
+    max_attempts: u32,
+    next_retry_at: Option<u64>,
+) -> VpnStateFile {
+    let last_ip = VpnStateFile::load(config.profile.as_deref())
+        .ok()
+        .and_then(|state| state.known_ip().map(str::to_string));
+    VpnStateFile::reconnecting(
+        config.display_name(),
+        attempt,
+        max_attempts,
+        next_retry_at,
+        last_ip,
+    )
+}
+
+/// Write the reconnection manager's Error state to the state file
+fn write_error_state(config: &VpnConfig, max_attempts: u32, error_msg: &str) {
+    let state = VpnStateFile {
+        max_attempts: Some(max_attempts),
+        ..VpnStateFile::error(config.display_name(), error_msg)
+    };
+    let _ = state.store(config.profile.as_deref());
+}
+
+/// Remove the daemon PID file of `profile` if it records this process
+fn remove_own_daemon_pid_file(profile: Option<&str>) {
+    let daemon_pid_file = get_daemon_pid_file(profile);
+    let recorded_pid = fs::read_to_string(&daemon_pid_file)
+        .ok()
+        .and_then(|pid| pid.trim().parse::<u32>().ok());
+    if recorded_pid != Some(std::process::id()) {
+        return;
+    }
+    if let Err(e) = fs::remove_file(&daemon_pid_file) {
+        warn!("Failed to remove daemon PID file: {}", e);
+    }
+}
+
+/// Get the path to the daemon PID file of `profile`
+pub fn get_daemon_pid_file(profile: Option<&str>) -> PathBuf {
+    // Use /tmp for the daemon PID file, one per profile
+    PathBuf::from(format!(
+        "/tmp/akon-reconnection-daemon{}.pid",
+        profile_file_suffix(profile)
+    ))
 }
 
-/// Stop the reconnection manager daemon
-fn stop_reconnection_manager_daemon() {
-    let daemon_pid_file = get_daemon_pid_file();
+/// Stop the reconnection manager daemon of `profile`
+fn stop_reconnection_manager_daemon(profile: Option<&str>) {
+    let daemon_pid_file = get_daemon_pid_file(profile);
 
     if !daemon_pid_file.exists() {
         debug!("No reconnection manager daemon running");
@@ -588,15 +1407,240 @@ fn stop_reconnection_manager_daemon() {
     }
 }
 
+/// Present the offered authentication groups as a numbered menu
+///
+/// Re-prompts on invalid input; returns `None` if input ends without a choice.
+fn select_auth_group<R: BufRead, W: Write>(
+    groups: &[String],
+    input: &mut R,
+    output: &mut W,
+) -> Option<String> {
+    let _ = writeln!(output, "\nThe server offers these authentication groups:");
+    for (index, group) in groups.iter().enumerate() {
+        let _ = writeln!(output, "  {}. {}", index + 1, group);
+    }
+
+    loop {
+        let _ = write!(output, "Select group (1-{}): ", groups.len());
+        let _ = output.flush();
+
+        let mut line = String::new();
+        match input.read_line(&mut line) {
+            Ok(0) | Err(_) => return None,
+            Ok(_) => {}
+        }
+
+        match line.trim().parse::<usize>() {
+            Ok(choice) if (1..=groups.len()).contains(&choice) => {
+                return Some(groups[choice - 1].clone());
+            }
+            _ => {
+                let _ = writeln!(
+                    output,
+                    "Please enter a number between 1 and {}.",
+                    groups.len()
+                );
+            }
+        }
+    }
+}
+
+/// Connect, moving on to the server's next address while its gateways fail
+///
+/// Only for a server reached directly: the egress route and the jump forward
+/// both lead to a single address.
+async fn retry_alternate_addresses<C, F, Fut>(
+    config: VpnConfig,
+    mut connect: F,
+) -> Result<C, AkonError>
+where
+    F: FnMut(VpnConfig) -> Fut,
+    Fut: Future<Output = Result<C, AkonError>>,
+{
+    if config.egress_interface.is_some() || config.jump_host.is_some() {
+        return connect(config).await;
+    }
+
+    let addresses = alternate_addresses(&config.server, resolve_gateways(&config.server).await);
+    if !addresses.is_empty() {
+        debug!("{} resolves to {} gateways", config.server, addresses.len());
+    }
+    connect_across(&addresses, |address| {
+        let mut config = config.clone();
+        config.server_address = address;
+        if let Some(address) = address {
+            info!("Connecting to {} at {}", config.server, address);
+        }
+        connect(config)
+    })
+    .await
+}
+
+/// Connect, letting the user pick an authentication group if the server asks for one
+///
+/// The first attempt uses the configured group, if any. When the server
+/// answers with its group list instead, `choose_group` picks one and the
+/// connection is retried with it; the choice is left in `config`.
+async fn connect_with_auth_group<C, F, Fut, G>(
+    config: &mut VpnConfig,
+    mut connect: F,
+    mut choose_group: G,
+) -> Result<C, AkonError>
+where
+    F: FnMut(VpnConfig) -> Fut,
+    Fut: Future<Output = Result<C, AkonError>>,
+    G: FnMut(&[String]) -> Option<String>,
+{
+    match connect(config.clone()).await {
+        Err(AkonError::Vpn(VpnError::AuthGroupRequired { groups }))
+            if config.auth_group.is_none() =>
+        {
+            let Some(group) = choose_group(&groups) else {
+                return Err(AkonError::Vpn(VpnError::AuthGroupRequired { groups }));
+            };
+            info!("Retrying connection with auth group '{}'", group);
+            config.auth_group = Some(group);
+            connect(config.clone()).await
+        }
+        result => result,
+    }
+}
+
+/// Connect, retrying while the server name fails to resolve temporarily
+///
+/// Covers the network still coming up (e.g. right after resume). Retries
+/// follow `dns_retries` and `dns_retry_interval_secs`; a name that doesn't
+/// exist fails straight away. `connect` gets the number of the retry, 0 for
+/// the first try.
+async fn retry_transient_dns<C, F, Fut>(config: VpnConfig, mut connect: F) -> Result<C, AkonError>
+where
+    F: FnMut(VpnConfig, u32) -> Fut,
+    Fut: Future<Output = Result<C, AkonError>>,
+{
+    let mut retry = 0;
+    loop {
+        match connect(config.clone(), retry).await {
+            Err(AkonError::Vpn(VpnError::DnsFailure { transient: true })) => {
+                retry += 1;
+                let Some(delay) = config.dns_retry_delay(retry) else {
+                    return Err(AkonError::Vpn(VpnError::DnsFailure { transient: true }));
+                };
+                warn!(
+                    "Temporary DNS failure resolving {}, retrying in {}s",
+                    config.server,
+                    delay.as_secs()
+                );
+                println!(
+                    "  {} Could not resolve {} yet, retrying in {}s",
+                    ui::symbol(Symbol::Hint),
+                    config.server.bright_yellow(),
+                    delay.as_secs()
+                );
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Options for `akon vpn on`
+#[derive(Debug, Clone, Default)]
+pub struct VpnOnOptions {
+    /// Disconnect any existing connection and reset state first
+    pub force: bool,
+    /// Skip the reconnection daemon even if a policy is configured
+    pub no_reconnect: bool,
+    /// Health check interval for this session, overriding the policy
+    pub health_interval: Option<u64>,
+    /// Append OpenConnect's raw output to this file while connecting
+    pub capture_raw: Option<PathBuf>,
+    /// Write the connection summary as JSON to this file once connected
+    pub output: Option<PathBuf>,
+    /// Authenticate with this session cookie instead of keyring credentials
+    pub cookie: Option<SessionCookie>,
+    /// Profile to connect; the config's `default_profile` without one
+    pub profile: Option<String>,
+}
+
+/// Session cookie given as `--cookie` or read from `--cookie-file`
+///
+/// Surrounding whitespace (such as the file's trailing newline) is dropped.
+pub fn session_cookie(
+    value: Option<String>,
+    file: Option<&Path>,
+) -> Result<Option<SessionCookie>, AkonError> {
+    let cookie = match (value, file) {
+        (Some(value), _) => value,
+        (None, Some(path)) => fs::read_to_string(path).map_err(|e| {
+            AkonError::Config(ConfigError::IoError {
+                message: format!("Failed to read cookie file {}: {}", path.display(), e),
+            })
+        })?,
+        (None, None) => return Ok(None),
+    };
+
+    let cookie = cookie.trim();
+    if cookie.is_empty() {
+        return Err(AkonError::Config(ConfigError::ValidationError {
+            message: "Session cookie is empty".to_string(),
+        }));
+    }
+    Ok(Some(SessionCookie::new(cookie.to_string())))
+}
+
+/// Reconnection policy the daemon should be started with, if any
+///
+/// Applies the `--health-interval` override, which must be within the
+/// policy's valid range.
+fn reconnection_policy_for(
+    configured: Option<ReconnectionPolicy>,
+    options: &VpnOnOptions,
+) -> Result<Option<ReconnectionPolicy>, AkonError> {
+    if options.no_reconnect {
+        return Ok(None);
+    }
+    let Some(mut policy) = configured else {
+        if options.health_interval.is_some() {
+            warn!("Ignoring --health-interval: no reconnection policy configured");
+        }
+        return Ok(None);
+    };
+
+    if let Some(secs) = options.health_interval {
+        policy.health_check_interval_secs = secs;
+        policy.validate().map_err(|e| {
+            AkonError::Config(ConfigError::ValidationError {
+                message: format!("Invalid --health-interval: {}", e),
+            })
+        })?;
+        info!(
+            "Health check interval overridden to {}s for this session",
+            secs
+        );
+    }
+    Ok(Some(policy))
+}
+
 /// Run the VPN on command using CLI process delegation
-pub async fn run_vpn_on(force: bool) -> Result<(), AkonError> {
+pub async fn run_vpn_on(options: VpnOnOptions) -> Result<(), AkonError> {
+    let force = options.force;
+    let profile = options.profile.as_deref();
+    // Only one invocation connects at a time; released when this returns
+    let _connect_lock = ConnectLock::acquire(&connect_lock_path(profile))?;
+
     // Check for existing connection first
-    let state_path = state_file_path();
+    let state_path = state_file_path(profile);
     if state_path.exists() {
         // Try to read existing state
-        if let Ok(state_content) = fs::read_to_string(&state_path) {
-            if let Ok(state) = serde_json::from_str::<serde_json::Value>(&state_content) {
-                if let Some(pid) = state.get("pid").and_then(|p| p.as_u64()) {
+        match VpnStateFile::load_from(&state_path) {
+            Err(e @ StateFileError::NewerSchema { .. }) => {
+                return Err(AkonError::Vpn(VpnError::ConnectionFailed {
+                    reason: e.to_string(),
+                }));
+            }
+            Err(_) => {}
+            Ok(state) => {
+                if let Some(pid) = state.pid {
                     // Check if process is still running
                     let process_running = std::process::Command::new("ps")
                         .args(["-p", &pid.to_string()])
@@ -615,15 +1659,14 @@ pub async fn run_vpn_on(force: bool) -> Result<(), AkonError> {
                             );
                             println!(
                                 "{} {}",
-                                "🔄".bright_yellow(),
+                                ui::symbol(Symbol::Reconnect),
                                 "Force reconnection requested - disconnecting and resetting..."
                                     .bright_yellow()
                             );
 
                             // Disconnect the existing connection
-                            let _ = std::process::Command::new("sudo")
-                                .args(["kill", "-TERM", &pid.to_string()])
-                                .status();
+                            let mut signaler = PrivilegedSignaler::configured(profile);
+                            let _ = signaler.signal(pid as i32, "TERM");
 
                             // Wait a moment for graceful shutdown
                             std::thread::sleep(std::time::Duration::from_secs(1));
@@ -638,27 +1681,25 @@ pub async fn run_vpn_on(force: bool) -> Result<(), AkonError> {
                                 .unwrap_or(false);
 
                             if still_running {
-                                let _ = std::process::Command::new("sudo")
-                                    .args(["kill", "-KILL", &pid.to_string()])
-                                    .status();
+                                let _ = signaler.signal(pid as i32, "KILL");
                             }
 
                             // Clean up state file (reset functionality)
                             let _ = fs::remove_file(&state_path);
-                            println!("  {} Cleared connection state", "✓".bright_green());
+                            println!("  {} Cleared connection state", ui::symbol(Symbol::Success));
                             info!("Force flag cleared state file (reset)");
                         } else {
                             // Connection is already active - return early
                             println!(
                                 "{} {}",
-                                "✓".bright_green().bold(),
-                                "VPN is already connected".bright_green()
+                                ui::symbol(Symbol::Success),
+                                already_connected_message(&state).bright_green()
                             );
-                            if let Some(ip) = state.get("ip") {
+                            if let Some(ip) = &state.ip {
                                 println!(
                                     "  {} {}",
                                     "IP address:".bright_white(),
-                                    ip.as_str().unwrap_or("unknown").bright_cyan().bold()
+                                    ip.bright_cyan().bold()
                                 );
                             }
                             println!(
@@ -673,7 +1714,7 @@ pub async fn run_vpn_on(force: bool) -> Result<(), AkonError> {
                         info!("Found stale connection state (PID: {}), cleaning up", pid);
                         println!(
                             "{} {}",
-                            "⚠".bright_yellow(),
+                            ui::symbol(Symbol::Warning),
                             "Cleaning up stale connection...".dimmed()
                         );
                         let _ = fs::remove_file(&state_path);
@@ -686,39 +1727,185 @@ pub async fn run_vpn_on(force: bool) -> Result<(), AkonError> {
     // Load configuration
     let config_path = get_config_path()?;
     let toml_config = TomlConfig::from_file(&config_path)?;
-    let config = toml_config.vpn_config;
+    let mut config = toml_config.profile(profile)?;
+    let configured_auth_group = config.auth_group.clone();
     info!("Loaded configuration for server: {}", config.server);
-
-    // Generate complete VPN password (PIN + OTP) from user's keyring
-    let password = generate_password(&config.username)?;
-    info!("Generated VPN password from keyring credentials");
+    let reconnection_policy = reconnection_policy_for(
+        toml_config.reconnection_policy_for(profile).cloned(),
+        &options,
+    )?;
+
+    if let Some(warning) = config.insecure_tls_warning() {
+        warn!("{}", warning);
+        eprintln!(
+            "{} {} {}",
+            ui::symbol(Symbol::Warning),
+            "WARNING:".bright_red().bold(),
+            warning.bright_red().bold()
+        );
+    }
 
     // Check if OpenConnect is installed
-    if let Err(e) = which::which("openconnect") {
-        error!("OpenConnect not found in PATH: {}", e);
-        eprintln!("Error: OpenConnect is not installed or not in PATH");
-        eprintln!("Install it with: sudo apt install openconnect");
-        return Err(AkonError::Vpn(VpnError::ProcessSpawnError {
-            reason: "openconnect command not found".to_string(),
-        }));
+    if let Err(e) = locate_openconnect(config.openconnect_path.as_deref()) {
+        error!("{}", e);
+        eprintln!(
+            "{} {}",
+            ui::symbol(Symbol::Failure),
+            format!("Error: {}", e).bright_red().bold()
+        );
+        print_error_suggestions(&e, config.privilege_escalation);
+        return Err(AkonError::Vpn(e));
     }
 
-    // Create CLI connector
-    let mut connector = CliConnector::new(config.clone())?;
-    info!("Created CLI connector");
-
     // Start connection
     println!(
         "{} {} {}",
-        "🔌".bright_cyan(),
+        ui::symbol(Symbol::Connect),
         "Connecting to VPN server:".bright_white().bold(),
         config.server.bright_yellow()
     );
-    connector.connect(password.expose().to_string()).await?;
+    notify::notify(ServiceState::Connecting {
+        server: &config.server,
+    });
+
+    if let Some(route) = install_egress_route(&config)? {
+        println!(
+            "  {} Reaching {} via {}",
+            ui::symbol(Symbol::Route),
+            route.server_ip.to_string().bright_yellow(),
+            route.interface.bright_cyan()
+        );
+    }
+
+    if let Some(forward) = open_jump_forward(&config)? {
+        println!(
+            "  {} Reaching {} through {} (local port {})",
+            ui::symbol(Symbol::Route),
+            forward.server.bright_yellow(),
+            forward.jump_host.bright_cyan(),
+            forward.local_port
+        );
+    }
+
+    warn_about_clock_skew(&config).await;
+    warn_about_damaged_secret(&config).await;
+
+    // Only offer the group menu when someone is there to answer it
+    let interactive = std::io::stdin().is_terminal();
+    let connect_once = |config: VpnConfig, dns_retry: u32| {
+        let capture_raw = options.capture_raw.clone();
+        let cookie = options.cookie.clone();
+        async move {
+            // A DNS failure never got to authenticate: its retries are
+            // counted by `dns_retries`, not the attempt budget
+            if dns_retry == 0 {
+                spend_attempt_budget(&config)?;
+            }
+
+            let mut connector =
+                CliConnector::new(config.clone())?.with_approval_notice(|message| {
+                    notify::notify(ServiceState::AwaitingApproval);
+                    println!("{} {}", ui::symbol(Symbol::Auth), message.bright_white());
+                    println!(
+                        "  {} Check your phone to approve the sign-in",
+                        ui::symbol(Symbol::Hint)
+                    );
+                });
+            if let Some(path) = capture_raw {
+                info!("Capturing raw OpenConnect output to {}", path.display());
+                connector = connector.with_raw_capture(path);
+            }
+            info!("Created CLI connector");
+
+            if let Some(cookie) = cookie {
+                // Authentication already happened elsewhere (e.g. browser SSO)
+                info!("Connecting with a supplied session cookie");
+                connector = connector.with_cookie_auth();
+                connector.connect(cookie.expose().to_string()).await?;
+                return Ok(connector);
+            }
+
+            // Generate complete VPN password (PIN + OTP) from user's keyring.
+            // Done per attempt, as the OTP may expire while a group is chosen.
+            let password = generate_password_async_with(
+                &config.keyring_account(),
+                StoredCredentials::for_config(&config),
+                TerminalPrompt::new(),
+                &OffsetClock::for_config(&config),
+                TotpSettings::for_config(&config),
+            )
+            .await?;
+            info!("Generated VPN password from keyring credentials");
+            connector.connect(password.expose().to_string()).await?;
+            Ok(connector)
+        }
+    };
+    let connect_once = &connect_once;
+    let connected = connect_with_auth_group(
+        &mut config,
+        |config| {
+            retry_transient_dns(config, |config, dns_retry| {
+                retry_alternate_addresses(config, move |config| connect_once(config, dns_retry))
+            })
+        },
+        |groups| {
+            if !interactive {
+                return None;
+            }
+            select_auth_group(groups, &mut std::io::stdin().lock(), &mut std::io::stdout())
+        },
+    )
+    .await;
+
+    if let Err(e) = &connected {
+        remove_egress_route(config.profile.as_deref());
+        close_jump_forward(config.profile.as_deref());
+        notify::notify(ServiceState::Failed {
+            reason: e.to_string(),
+        });
+    }
+
+    let mut connector = match connected {
+        Ok(connector) => connector,
+        Err(AkonError::Vpn(e)) => {
+            eprintln!(
+                "{} {}",
+                ui::symbol(Symbol::Failure),
+                format!("Error: {}", e).bright_red().bold()
+            );
+            print_error_suggestions(&e, config.privilege_escalation);
+            write_connect_error_state(&config, &e);
+            return Err(AkonError::Vpn(e));
+        }
+        Err(e) => return Err(e),
+    };
+
+    // Offer to remember a group picked from the menu
+    if let Some(group) = config
+        .auth_group
+        .as_ref()
+        .filter(|_| configured_auth_group.is_none())
+    {
+        print!("Save auth group '{}' to config? [y/N]: ", group);
+        let _ = std::io::stdout().flush();
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer).is_ok()
+            && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+        {
+            match save_auth_group_to_path(group, config.profile.as_deref(), &config_path) {
+                Ok(()) => println!(
+                    "  {} Saved auth group to config",
+                    ui::symbol(Symbol::Success)
+                ),
+                Err(e) => warn!("Failed to save auth group: {}", e),
+            }
+        }
+    }
 
     // Monitor events
     // Note: We don't use a timeout wrapper here when reconnection is enabled,
     // as the reconnection manager needs to run indefinitely
+    let mut established = false;
     let process_result = async {
         while let Some(event) = connector.next_event().await {
             // Log all events with structured metadata (T047)
@@ -730,7 +1917,7 @@ pub async fn run_vpn_on(force: bool) -> Result<(), AkonError> {
                     info!(pid = pid, "VPN process spawned");
                 }
                 ConnectionEvent::Authenticating { message } => {
-                    println!("{} {}", "🔐".bright_magenta(), message.bright_white());
+                    println!("{} {}", ui::symbol(Symbol::Auth), message.bright_white());
                     info!(phase = "authentication", message = %message, "Authentication in progress");
                 }
                 ConnectionEvent::F5SessionEstablished { .. } => {
@@ -739,35 +1926,75 @@ pub async fn run_vpn_on(force: bool) -> Result<(), AkonError> {
                 }
                 ConnectionEvent::TunConfigured { device, ip } => {
                     // Silent - not shown to user during connection
-                    info!(device = %device, ip = %ip, "TUN device configured");
+                    info!(device = ?device, ip = %ip, "TUN device configured");
                 }
                 ConnectionEvent::Connected { ip, device } => {
-                    println!("{} {}", "✓".bright_green().bold(), "VPN connection established".bright_green().bold());
-                    info!(ip = %ip, device = %device, "VPN connection fully established");
+                    if let Some(e) = default_route_problem(&config, device.as_deref()) {
+                        if config.verify_default_route == RouteCheck::Enforce {
+                            eprintln!("{} {}", ui::symbol(Symbol::Failure), format!("Error: {}", e).bright_red().bold());
+                            print_error_suggestions(&e, config.privilege_escalation);
+                            if let Err(disconnect_error) = connector.disconnect().await {
+                                warn!("Failed to disconnect after route check: {}", disconnect_error);
+                            }
+                            return Err(AkonError::Vpn(e));
+                        }
+                        warn!("Default route check failed: {}", e);
+                        println!("{} {}", ui::symbol(Symbol::Warning), format!("Traffic may bypass the VPN: {}", e).bright_yellow());
+                    }
+                    if let Some(e) = required_route_problem(&config, device.as_deref()) {
+                        eprintln!("{} {}", ui::symbol(Symbol::Failure), format!("Error: {}", e).bright_red().bold());
+                        if let Err(disconnect_error) = connector.disconnect().await {
+                            warn!("Failed to disconnect after route check: {}", disconnect_error);
+                        }
+                        return Err(AkonError::Vpn(e));
+                    }
+                    match install_kill_switch(&config, device.as_deref()) {
+                        Ok(Some(kill_switch)) => println!("{} {}", ui::symbol(Symbol::Success), format!("Kill switch on: traffic only leaves through {}", kill_switch.device).bright_green()),
+                        Ok(None) => {}
+                        Err(e) => {
+                            eprintln!("{} {}", ui::symbol(Symbol::Failure), format!("Error: {}", e).bright_red().bold());
+                            if let Err(disconnect_error) = connector.disconnect().await {
+                                warn!("Failed to disconnect after kill switch failure: {}", disconnect_error);
+                            }
+                            return Err(AkonError::Vpn(e));
+                        }
+                    }
+                    println!("{} {}", ui::symbol(Symbol::Success), "VPN connection established".bright_green().bold());
+                    info!(ip = %ip, device = ?device, "VPN connection fully established");
+                    notify::notify(ServiceState::Connected { server: &config.server, ip });
 
                     // Get PID from connector for state persistence
                     let pid = connector.get_pid();
 
                     // Save state for status command
-                    let state = serde_json::json!({
-                        "ip": ip.to_string(),
-                        "device": device,
-                        "connected_at": chrono::Utc::now().to_rfc3339(),
-                        "pid": pid,
-                    });
-
-                    let state_json = serde_json::to_string_pretty(&state).map_err(|e| {
-                        AkonError::Vpn(VpnError::ConnectionFailed {
-                            reason: format!("Failed to serialize state: {}", e),
-                        })
-                    })?;
+                    let ip_unusual = record_ip_history(&config, ip);
+                    if ip_unusual == Some(true) {
+                        println!(
+                            "{} {}",
+                            ui::symbol(Symbol::Warning),
+                            format!("Assigned IP {} is outside the usual range for this server", ip).bright_yellow()
+                        );
+                    }
+                    let state = connected_state(&config, ip, device.as_deref(), pid, ip_unusual);
 
-                    if let Err(e) = fs::write(state_file_path(), state_json) {
+                    if let Err(e) = state.store(config.profile.as_deref()) {
                         error!("Failed to write state file: {}", e);
                     }
 
+                    if let Some(path) = &options.output {
+                        let transport = connector.transport().unwrap_or_default();
+                        let summary = ConnectionSummary::new(ip, device.as_deref(), pid, transport);
+                        match summary.write_to(path) {
+                            Ok(()) => info!("Wrote connection summary to {}", path.display()),
+                            Err(e) => {
+                                error!("{}", e);
+                                println!("{} {}", ui::symbol(Symbol::Warning), e.to_string().bright_yellow());
+                            }
+                        }
+                    }
+
                     // Start reconnection manager daemon if reconnection policy is configured
-                    if let Some(reconnection_policy) = toml_config.reconnection.clone() {
+                    if let Some(reconnection_policy) = reconnection_policy.clone() {
                         // Only start if we have a valid PID
                         if let Some(pid_value) = pid {
                             info!("Starting reconnection manager daemon with policy: max_attempts={}, health_endpoint={}",
@@ -784,37 +2011,53 @@ pub async fn run_vpn_on(force: bool) -> Result<(), AkonError> {
                                 error!("Failed to spawn reconnection manager daemon: {}", e);
                                 warn!("Continuing without reconnection manager");
                             } else {
-                                println!("{} {}", "🔄".bright_cyan(), "Reconnection manager started in background".dimmed());
+                                println!("{} {}", ui::symbol(Symbol::Reconnect), "Reconnection manager started in background".dimmed());
                             }
                         } else {
                             warn!("Cannot start reconnection manager: no PID available");
                         }
+                    } else if options.no_reconnect {
+                        info!("Reconnection disabled for this connection (--no-reconnect)");
                     } else {
                         debug!("No reconnection policy configured, skipping reconnection manager");
                     }
 
+                    established = true;
                     return Ok::<(), AkonError>(());
                 }
                 ConnectionEvent::Error { kind, raw_output } => {
                     error!("VPN error: {} - {}", kind, raw_output);
-                    eprintln!("{} {}", "❌".bright_red(), format!("Error: {}", kind).bright_red().bold());
+                    eprintln!("{} {}", ui::symbol(Symbol::Failure), format!("Error: {}", kind).bright_red().bold());
                     if !raw_output.is_empty() {
                         eprintln!("   {} {}", "Details:".bright_yellow(), raw_output.dimmed());
                     }
 
                     // Provide actionable suggestions based on error type
-                    print_error_suggestions(&kind);
+                    print_error_suggestions(&kind, config.privilege_escalation);
 
                     return Err(AkonError::Vpn(kind));
                 }
                 ConnectionEvent::Disconnected { reason } => {
                     info!("VPN disconnected: {:?}", reason);
-                    println!("{} VPN disconnected: {:?}", "⚠".bright_yellow(), reason);
+                    println!("{} VPN disconnected: {:?}", ui::symbol(Symbol::Warning), reason);
                     return Ok(());
                 }
                 ConnectionEvent::UnknownOutput { line } => {
                     debug!("Unparsed output: {}", line);
                 }
+                ConnectionEvent::AuthGroupsAvailable { groups } => {
+                    debug!("Server offered auth groups: {:?}", groups);
+                }
+                ConnectionEvent::AwaitingApproval { message } => {
+                    // Already shown while connecting
+                    debug!("Push approval requested: {}", message);
+                }
+                ConnectionEvent::CstpConnected => {
+                    info!(phase = "session", "CSTP tunnel up, configuring TUN device");
+                }
+                ConnectionEvent::Progress { percent, phase } => {
+                    debug!(percent = percent, phase = phase.as_str(), "Connection progress");
+                }
             }
         }
 
@@ -824,153 +2067,209 @@ pub async fn run_vpn_on(force: bool) -> Result<(), AkonError> {
         }))
     }.await;
 
+    if !established {
+        remove_egress_route(config.profile.as_deref());
+        close_jump_forward(config.profile.as_deref());
+    }
+
+    if let Err(AkonError::Vpn(e)) = &process_result {
+        write_connect_error_state(&config, e);
+    }
+
     process_result
 }
 
+/// Sends signals to the OpenConnect process
+///
+/// OpenConnect runs as root, so signals go through the privileged path.
+/// Abstracted so the termination sequence can be tested without sudo.
+trait ProcessSignaler {
+    /// Send `signal` (e.g. "TERM", "KILL") to `pid`
+    fn signal(&mut self, pid: i32, signal: &str) -> std::io::Result<()>;
+
+    /// Check whether `pid` is still running
+    fn is_running(&mut self, pid: i32) -> bool;
+}
+
+/// Signals processes with `kill` through the configured privilege
+/// escalation and checks them with `ps`
+struct PrivilegedSignaler(PrivilegeEscalation);
+
+impl PrivilegedSignaler {
+    /// Signaler using `privilege_escalation` of `profile` from the config
+    /// (sudo without one)
+    fn configured(profile: Option<&str>) -> Self {
+        Self(
+            load_config(profile)
+                .map(|config| config.privilege_escalation)
+                .unwrap_or_default(),
+        )
+    }
+}
+
+impl ProcessSignaler for PrivilegedSignaler {
+    fn signal(&mut self, pid: i32, signal: &str) -> std::io::Result<()> {
+        self.0
+            .command("kill")
+            .args([&format!("-{}", signal), &pid.to_string()])
+            .status()
+            .map(|_| ())
+    }
+
+    fn is_running(&mut self, pid: i32) -> bool {
+        std::process::Command::new("ps")
+            .args(["-p", &pid.to_string()])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    }
+}
+
+/// How the OpenConnect process was stopped
+#[derive(Debug, PartialEq, Eq)]
+enum Termination {
+    /// Process exited after SIGTERM
+    Graceful,
+    /// Process was killed with SIGKILL
+    Forced,
+}
+
+/// Terminate the OpenConnect process
+///
+/// Sends SIGTERM and waits up to 5 seconds before escalating to SIGKILL.
+/// With `force`, SIGKILL is sent immediately and the grace period is skipped.
+async fn terminate_openconnect(
+    signaler: &mut impl ProcessSignaler,
+    pid: i32,
+    force: bool,
+) -> Result<Termination, AkonError> {
+    if !force {
+        info!(pid, "Sending SIGTERM to OpenConnect process");
+
+        if let Err(e) = signaler.signal(pid, "TERM") {
+            error!("Failed to send SIGTERM: {}", e);
+            return Err(AkonError::Vpn(VpnError::TerminationError));
+        }
+
+        // Wait up to 5 seconds for graceful shutdown (500ms * 10)
+        for _ in 0..10 {
+            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+            if !signaler.is_running(pid) {
+                return Ok(Termination::Graceful);
+            }
+        }
+
+        warn!("Graceful shutdown timeout, force killing process");
+        println!(
+            "{} {}",
+            ui::symbol(Symbol::Warning),
+            "Process not responding, force killing...".bright_yellow()
+        );
+    } else {
+        info!(
+            pid,
+            "Force flag set, sending SIGKILL to OpenConnect process"
+        );
+    }
+
+    if let Err(e) = signaler.signal(pid, "KILL") {
+        error!("Failed to send SIGKILL: {}", e);
+        return Err(AkonError::Vpn(VpnError::TerminationError));
+    }
+
+    // Wait a bit for SIGKILL to take effect
+    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+    Ok(Termination::Forced)
+}
+
 /// Run the VPN off command
 ///
 /// Disconnects from VPN by terminating the tracked OpenConnect process and
-/// cleaning up any orphaned OpenConnect processes from previous sessions.
-pub async fn run_vpn_off() -> Result<(), AkonError> {
+/// any processes it left behind. Other profiles' connections stay up.
+/// With `force`, the process is killed immediately without a grace period.
+pub async fn run_vpn_off(force: bool, profile: Option<&str>) -> Result<(), AkonError> {
     use nix::unistd::Pid;
 
     // Load state file
-    let state_path = state_file_path();
+    let state_path = state_file_path(profile);
 
     if !state_path.exists() {
         println!("No active VPN connection found");
 
-        // Still check for and clean up any orphaned OpenConnect processes
-        println!(
-            "{} {}",
-            "🧹".bright_yellow(),
-            "Checking for orphaned OpenConnect processes...".bright_white()
-        );
-
-        info!("No active connection, scanning for orphaned processes");
-
-        let result = cleanup_orphaned_processes();
-        handle_cleanup_result(result, "run_vpn_off (no state)");
+        // A kill switch outlives the connection it was installed for
+        remove_kill_switch(profile);
 
         return Ok(());
     }
 
     // Read state to get PID
-    let state_content = fs::read_to_string(&state_path).map_err(|e| {
+    let state = VpnStateFile::load_from(&state_path).map_err(|e| {
         AkonError::Vpn(VpnError::ConnectionFailed {
-            reason: format!("Failed to read state file: {}", e),
+            reason: e.to_string(),
         })
     })?;
 
-    let state: serde_json::Value = serde_json::from_str(&state_content).map_err(|e| {
-        AkonError::Vpn(VpnError::ConnectionFailed {
-            reason: format!("Failed to parse state file: {}", e),
-        })
-    })?;
-
-    // Extract PID
-    let pid = state.get("pid").and_then(|p| p.as_u64()).ok_or_else(|| {
-        AkonError::Vpn(VpnError::ConnectionFailed {
-            reason: "PID not found in state file".to_string(),
-        })
-    })? as i32;
-
-    let pid = Pid::from_raw(pid);
+    // Extract PID; reconnecting and error states have none, but may still
+    // hold a kill switch and a daemon to clean up
+    let pid = state.pid.map(|pid| Pid::from_raw(pid as i32));
 
     // Check if process is still running (Step 2 from vpn-off-command.md)
-    // Note: openconnect runs as root, so we check via ps and kill with sudo
-    let process_running = std::process::Command::new("ps")
-        .args(["-p", &pid.as_raw().to_string()])
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null())
-        .status()
-        .map(|s| s.success())
-        .unwrap_or(false);
+    // Note: openconnect runs as root, so we check via ps and kill through
+    // the configured privilege escalation
+    let running_pid = pid.filter(|pid| {
+        std::process::Command::new("ps")
+            .args(["-p", &pid.as_raw().to_string()])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    });
 
-    if process_running {
-        // Process exists, try graceful termination
+    if let Some(pid) = running_pid {
         println!(
             "{} {} (PID: {})...",
-            "🔌".bright_cyan(),
+            ui::symbol(Symbol::Connect),
             "Disconnecting VPN".bright_white().bold(),
             pid.to_string().bright_yellow()
         );
-        info!(pid = pid.as_raw(), "Sending SIGTERM to OpenConnect process");
-
-        // Send SIGTERM via sudo (Step 3)
-        let kill_result = std::process::Command::new("sudo")
-            .args(["kill", "-TERM", &pid.as_raw().to_string()])
-            .status();
-
-        if let Err(e) = kill_result {
-            error!("Failed to send SIGTERM: {}", e);
-            return Err(AkonError::Vpn(VpnError::TerminationError));
-        }
-
-        // Wait up to 5 seconds for graceful shutdown
-        let mut attempts = 0;
-        let max_attempts = 10; // 5 seconds (500ms * 10)
-
-        loop {
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-            attempts += 1;
 
-            // Check if process still exists
-            let still_running = std::process::Command::new("ps")
-                .args(["-p", &pid.as_raw().to_string()])
-                .stdout(std::process::Stdio::null())
-                .stderr(std::process::Stdio::null())
-                .status()
-                .map(|s| s.success())
-                .unwrap_or(false);
-
-            if !still_running {
-                // Process no longer exists
+        match terminate_openconnect(
+            &mut PrivilegedSignaler::configured(profile),
+            pid.as_raw(),
+            force,
+        )
+        .await?
+        {
+            Termination::Graceful => {
                 println!(
                     "{} {}",
-                    "✓".bright_green().bold(),
+                    ui::symbol(Symbol::Success),
                     "VPN disconnected gracefully".bright_green()
                 );
                 info!("OpenConnect process terminated gracefully");
-                break;
-            } else if attempts >= max_attempts {
-                // Timeout, force kill (Step 4)
-                warn!("Graceful shutdown timeout, force killing process");
-                println!(
-                    "{} {}",
-                    "⚠".bright_yellow(),
-                    "Process not responding, force killing...".bright_yellow()
-                );
-
-                let kill_result = std::process::Command::new("sudo")
-                    .args(["kill", "-KILL", &pid.as_raw().to_string()])
-                    .status();
-
-                if let Err(e) = kill_result {
-                    error!("Failed to send SIGKILL: {}", e);
-                    return Err(AkonError::Vpn(VpnError::TerminationError));
-                }
-
-                // Wait a bit for SIGKILL to take effect
-                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+            }
+            Termination::Forced => {
                 println!(
                     "{} {}",
-                    "✓".bright_green().bold(),
+                    ui::symbol(Symbol::Success),
                     "VPN disconnected (forced)".bright_green()
                 );
                 info!("OpenConnect process force-killed");
-                break;
             }
-            // Still running, continue waiting
         }
     } else {
         // Process not running, stale state (edge case from vpn-off-command.md)
         println!(
             "{} {}",
-            "⚠".bright_yellow(),
+            ui::symbol(Symbol::Warning),
             "VPN process no longer running (stale state)".dimmed()
         );
-        info!(pid = pid.as_raw(), "Cleaning up stale connection state");
+        info!(pid = ?pid.map(|pid| pid.as_raw()), "Cleaning up stale connection state");
     }
 
     // Clean up state file (Step 5)
@@ -984,255 +2283,1103 @@ pub async fn run_vpn_off() -> Result<(), AkonError> {
     info!("State file cleaned up");
     debug!("Removed state file at {:?}", state_path);
 
-    // Stop reconnection manager daemon if running
-    stop_reconnection_manager_daemon();
+    for step in TEARDOWN_ORDER {
+        run_teardown_step(step, profile, state.pid);
+    }
 
-    // Comprehensive cleanup: Terminate any orphaned OpenConnect processes
     println!(
         "{} {}",
-        "🧹".bright_yellow(),
-        "Cleaning up any orphaned OpenConnect processes...".bright_white()
+        ui::symbol(Symbol::Success),
+        "Disconnect complete".bright_green().bold()
     );
 
-    info!("Starting comprehensive cleanup of orphaned processes");
+    Ok(())
+}
 
-    let result = cleanup_orphaned_processes();
-    handle_cleanup_result(result, "run_vpn_off (after disconnect)");
+/// Fully disconnect, then connect fresh
+///
+/// If `disconnect` fails, `connect` is not attempted, so a restart never
+/// starts a second openconnect next to one it couldn't stop.
+async fn restart_with<D, DFut, C, CFut>(disconnect: D, connect: C) -> Result<(), AkonError>
+where
+    D: FnOnce() -> DFut,
+    DFut: Future<Output = Result<(), AkonError>>,
+    C: FnOnce() -> CFut,
+    CFut: Future<Output = Result<(), AkonError>>,
+{
+    println!(
+        "{} {}",
+        ui::symbol(Symbol::Reconnect),
+        "Restarting VPN: disconnecting...".bright_white().bold()
+    );
+    if let Err(e) = disconnect().await {
+        error!("Restart aborted, disconnect failed: {}", e);
+        eprintln!(
+            "{} {}",
+            ui::symbol(Symbol::Failure),
+            "Restart aborted: could not disconnect; the connection was left as is"
+                .bright_red()
+                .bold()
+        );
+        return Err(e);
+    }
 
     println!(
         "{} {}",
-        "✓".bright_green(),
-        "Disconnect complete".bright_green().bold()
+        ui::symbol(Symbol::Reconnect),
+        "Restarting VPN: connecting...".bright_white().bold()
     );
+    connect().await
+}
 
-    Ok(())
+/// Run the VPN restart command
+///
+/// Unlike `vpn on --force`, this goes through the whole `vpn off` path
+/// (graceful termination, reconnection daemon stop, orphan cleanup) before
+/// connecting, so nothing from the old session survives.
+pub async fn run_vpn_restart(force: bool, profile: Option<&str>) -> Result<(), AkonError> {
+    restart_with(
+        || async move {
+            run_vpn_off(force, profile).await?;
+            // vpn off only knows about a daemon through the state file
+            stop_reconnection_manager_daemon(profile);
+            Ok(())
+        },
+        || {
+            run_vpn_on(VpnOnOptions {
+                profile: profile.map(str::to_string),
+                ..VpnOnOptions::default()
+            })
+        },
+    )
+    .await
 }
 
-/// Run the VPN status command
-pub fn run_vpn_status() -> Result<(), AkonError> {
-    use chrono::{DateTime, Utc};
+/// Whether the state file records a running openconnect process
+fn connection_is_up(state_path: &Path, signaler: &mut impl ProcessSignaler) -> bool {
+    VpnStateFile::load_from(state_path)
+        .ok()
+        .and_then(|state| state.pid)
+        .is_some_and(|pid| signaler.is_running(pid as i32))
+}
 
-    let state_path = state_file_path();
+/// Whether `akon` without arguments should connect rather than show the status
+fn lazy_connects(
+    action: LazyAction,
+    state_path: &Path,
+    signaler: &mut impl ProcessSignaler,
+) -> bool {
+    action.connects(connection_is_up(state_path, signaler))
+}
 
-    if !state_path.exists() {
-        println!(
-            "{} {}",
-            "●".bright_red(),
-            "Status: Not connected".bright_white().bold()
-        );
-        std::process::exit(1);
+/// Run `akon` without arguments in lazy mode
+pub async fn run_lazy(action: LazyAction, profile: Option<&str>) -> Result<(), AkonError> {
+    if lazy_connects(
+        action,
+        &state_file_path(profile),
+        &mut PrivilegedSignaler::configured(profile),
+    ) {
+        run_vpn_on(VpnOnOptions {
+            profile: profile.map(str::to_string),
+            ..VpnOnOptions::default()
+        })
+        .await
+    } else {
+        run_vpn_status(false, false, profile)
     }
+}
 
-    // Read state file
-    let state_content = fs::read_to_string(&state_path).map_err(|e| {
-        AkonError::Vpn(VpnError::ConnectionFailed {
-            reason: format!("Failed to read state file: {}", e),
-        })
-    })?;
+/// Exit codes of `akon vpn status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusExit {
+    Connected = 0,
+    /// Also used while reconnecting
+    NotConnected = 1,
+    /// The state file names a process that has exited
+    Stale = 2,
+    /// The last `vpn on` failed, or reconnection gave up
+    Error = 3,
+}
 
-    let state: serde_json::Value = serde_json::from_str(&state_content).map_err(|e| {
-        AkonError::Vpn(VpnError::ConnectionFailed {
-            reason: format!("Failed to parse state file: {}", e),
-        })
-    })?;
+impl StatusExit {
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+}
 
-    // Check state from the state file
-    let state_str = state.get("state").and_then(|s| s.as_str()).unwrap_or("");
-    let is_reconnecting = state_str.contains("reconnecting") || state_str.contains("Reconnecting");
-    let is_error = state_str.contains("Error") || state_str.contains("error");
+/// State of the connection `vpn status` reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionStatus {
+    Connected,
+    Reconnecting,
+    /// The state file names a process that has exited
+    Stale,
+    Error,
+    Disconnected,
+}
 
-    // T053: Check for Error state and suggest manual intervention
-    if is_error {
-        println!(
-            "{} {}",
-            "●".bright_red(),
-            "Status: Error - Max reconnection attempts exceeded"
-                .bright_red()
-                .bold()
-        );
+impl ConnectionStatus {
+    /// Exit code of `akon vpn status` in this state
+    pub fn exit(self) -> StatusExit {
+        match self {
+            Self::Connected => StatusExit::Connected,
+            Self::Reconnecting | Self::Disconnected => StatusExit::NotConnected,
+            Self::Stale => StatusExit::Stale,
+            Self::Error => StatusExit::Error,
+        }
+    }
+}
 
-        if let Some(error_msg) = state.get("error").and_then(|e| e.as_str()) {
-            println!(
-                "  {} {}",
-                "Last error:".bright_white(),
-                error_msg.bright_yellow()
-            );
+/// What `akon vpn status` reports, read from the state file
+///
+/// Shared by the text output and `--json`; the serialized field names are
+/// part of the JSON output and kept stable.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VpnStatus {
+    pub state: ConnectionStatus,
+    /// Assigned IP, or the last known one while reconnecting
+    pub ip: Option<String>,
+    pub device: Option<String>,
+    pub pid: Option<u64>,
+    #[serde(serialize_with = "serialize_rfc3339")]
+    pub connected_at: Option<DateTime<Utc>>,
+    /// Seconds since `connected_at`, while connected
+    pub duration_secs: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attempt: Option<u64>,
+    /// Attempts the reconnection daemon makes, or made before giving up
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u64>,
+    /// Unix time of the next reconnection attempt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_retry_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Connection label
+    #[serde(skip)]
+    pub name: Option<String>,
+    #[serde(skip)]
+    pub error_kind: Option<String>,
+    #[serde(skip)]
+    pub suggestions: Vec<String>,
+    /// The IP is outside the usual range for the server
+    #[serde(skip)]
+    pub ip_unusual: bool,
+}
+
+fn serialize_rfc3339<S: serde::Serializer>(
+    time: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match time {
+        Some(time) => serializer.serialize_some(&time.to_rfc3339()),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl VpnStatus {
+    /// Status without a state file
+    fn disconnected() -> Self {
+        Self {
+            state: ConnectionStatus::Disconnected,
+            ip: None,
+            device: None,
+            pid: None,
+            connected_at: None,
+            duration_secs: None,
+            attempt: None,
+            max_attempts: None,
+            next_retry_at: None,
+            error: None,
+            name: None,
+            error_kind: None,
+            suggestions: Vec::new(),
+            ip_unusual: false,
         }
+    }
 
-        if let Some(attempts) = state.get("max_attempts").and_then(|a| a.as_u64()) {
-            println!(
-                "  {} Failed after {} reconnection attempts",
-                "❌".bright_red(),
-                attempts.to_string().bright_yellow()
-            );
+    /// Status a state file records, checking that its process still runs
+    fn from_state(
+        state: &VpnStateFile,
+        signaler: &mut impl ProcessSignaler,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let mut status = Self::disconnected();
+        status.name = state.name.clone();
+        match state.state {
+            RecordedState::Error => {
+                status.state = ConnectionStatus::Error;
+                status.error = state.error.clone();
+                status.error_kind = state.error_kind.clone();
+                status.max_attempts = state.max_attempts.map(u64::from);
+                status.suggestions = state.suggestions.clone();
+            }
+            RecordedState::Reconnecting => {
+                status.state = ConnectionStatus::Reconnecting;
+                status.ip = state.known_ip().map(str::to_string);
+                status.attempt = Some(state.attempt.unwrap_or(1).into());
+                status.max_attempts = Some(state.max_attempts.unwrap_or(5).into());
+                status.next_retry_at = state.next_retry_at;
+            }
+            RecordedState::Disconnected => {}
+            RecordedState::Connected => {
+                status.ip = state.ip.clone();
+                status.device = state.device.clone();
+                status.pid = state.pid.map(u64::from);
+                status.ip_unusual = state.ip_unusual == Some(true);
+                status.connected_at = state.connected_at.as_deref().and_then(|at| at.parse().ok());
+                if status
+                    .pid
+                    .is_some_and(|pid| signaler.is_running(pid as i32))
+                {
+                    status.state = ConnectionStatus::Connected;
+                    status.duration_secs = status
+                        .connected_at
+                        .map(|at| now.signed_duration_since(at).num_seconds());
+                } else {
+                    status.state = ConnectionStatus::Stale;
+                }
+            }
         }
+        status
+    }
+}
 
-        println!(
+/// Read the connection status from the state file at `state_path`
+///
+/// No state file means disconnected.
+fn read_status(
+    state_path: &Path,
+    signaler: &mut impl ProcessSignaler,
+) -> Result<VpnStatus, AkonError> {
+    if !state_path.exists() {
+        return Ok(VpnStatus::disconnected());
+    }
+    let state = VpnStateFile::load_from(state_path).map_err(|e| {
+        AkonError::Vpn(VpnError::ConnectionFailed {
+            reason: e.to_string(),
+        })
+    })?;
+    Ok(VpnStatus::from_state(&state, signaler, Utc::now()))
+}
+
+/// Status of the state file at `state_path`, with nothing printed
+///
+/// A state file that can't be read counts as not connected.
+fn quiet_status(state_path: &Path, signaler: &mut impl ProcessSignaler) -> StatusExit {
+    read_status(state_path, signaler).map_or(StatusExit::NotConnected, |status| status.state.exit())
+}
+
+/// Lines `vpn status` prints for an Error state
+///
+/// The reconnection daemon records how many attempts it made; a failed
+/// `vpn on` records the error kind and its suggestions instead.
+fn error_status_lines(status: &VpnStatus) -> Vec<String> {
+    let headline = if status.max_attempts.is_some() {
+        "Status: Error - Max reconnection attempts exceeded"
+    } else {
+        "Status: Error - Connection failed"
+    };
+    let mut lines = vec![format!(
+        "{} {}",
+        ui::symbol(Symbol::Down),
+        headline.bright_red().bold()
+    )];
+    lines.extend(status.name.as_deref().map(connection_name_line));
+
+    if let Some(error_msg) = &status.error {
+        lines.push(format!(
+            "  {} {}",
+            "Last error:".bright_white(),
+            error_msg.bright_yellow()
+        ));
+    }
+    if let Some(kind) = &status.error_kind {
+        lines.push(format!("  {} {}", "Error kind:".dimmed(), kind.dimmed()));
+    }
+
+    if let Some(attempts) = status.max_attempts {
+        lines.push(format!(
+            "  {} Failed after {} reconnection attempts",
+            ui::symbol(Symbol::Failure),
+            attempts.to_string().bright_yellow()
+        ));
+        lines.push(format!(
             "\n{} {}",
-            "⚠".bright_yellow(),
+            ui::symbol(Symbol::Warning),
             "Manual intervention required:".bright_white().bold()
-        );
-        println!(
+        ));
+        lines.push(format!(
             "  {} Run {} to disconnect",
             "1.".bright_yellow(),
             "akon vpn off".bright_cyan()
-        );
-        println!(
+        ));
+        lines.push(format!(
             "  {} Run {} to reconnect with reset",
             "2.".bright_yellow(),
             "akon vpn on --force".bright_cyan()
-        );
+        ));
+    }
 
-        std::process::exit(3);
+    if !status.suggestions.is_empty() {
+        lines.extend(format_suggestions(&status.suggestions));
     }
 
-    if is_reconnecting {
-        // Display reconnecting status with attempt details
-        let attempt = state.get("attempt").and_then(|a| a.as_u64()).unwrap_or(1);
-        let max_attempts = state
-            .get("max_attempts")
-            .and_then(|m| m.as_u64())
-            .unwrap_or(5);
-        let next_retry_at = state.get("next_retry_at").and_then(|n| n.as_u64());
+    lines
+}
 
+/// Bytes received and sent through the tunnel device, or "unavailable" once
+/// the device is gone
+fn traffic_line(device: &str) -> String {
+    match read_interface_stats(device) {
+        Ok(stats) => format!(
+            "{} received, {} sent",
+            format_bytes(stats.rx_bytes).bright_cyan(),
+            format_bytes(stats.tx_bytes).bright_cyan()
+        ),
+        Err(e) => {
+            debug!("{}", e);
+            "unavailable".dimmed().to_string()
+        }
+    }
+}
+
+/// Run the VPN status command
+///
+/// With `exit_code_only` nothing is printed, and with `json` the status is
+/// printed as JSON; the [`StatusExit`] code tells the status either way.
+pub fn run_vpn_status(
+    exit_code_only: bool,
+    json: bool,
+    profile: Option<&str>,
+) -> Result<(), AkonError> {
+    let state_path = state_file_path(profile);
+    let mut signaler = PrivilegedSignaler::configured(profile);
+
+    if exit_code_only {
+        std::process::exit(quiet_status(&state_path, &mut signaler).code());
+    }
+
+    let status = read_status(&state_path, &mut signaler)?;
+    if json {
         println!(
-            "{} {}",
-            "●".bright_yellow(),
-            "Status: Reconnecting".bright_yellow().bold()
-        );
-        println!(
-            "  {} Attempt {} of {}",
-            "🔄".bright_yellow(),
-            attempt.to_string().bright_cyan(),
-            max_attempts.to_string().bright_cyan()
+            "{}",
+            serde_json::to_string_pretty(&status).expect("status serializes")
         );
+    } else {
+        print_status(&status);
+    }
+
+    match status.state.exit() {
+        StatusExit::Connected => Ok(()),
+        exit => std::process::exit(exit.code()),
+    }
+}
 
-        if let Some(next_retry) = next_retry_at {
-            let retry_time = DateTime::from_timestamp(next_retry as i64, 0)
-                .map(|dt: DateTime<Utc>| dt.with_timezone(&chrono::Local))
-                .map(|dt| dt.format("%H:%M:%S").to_string())
-                .unwrap_or_else(|| "unknown".to_string());
+/// Print the status as text
+fn print_status(status: &VpnStatus) {
+    let name_line = status.name.as_deref().map(connection_name_line);
 
+    match status.state {
+        ConnectionStatus::Disconnected => {
             println!(
-                "  {} Next retry at {}",
-                "⏱".dimmed(),
-                retry_time.bright_cyan()
+                "{} {}",
+                ui::symbol(Symbol::Down),
+                "Status: Not connected".bright_white().bold()
             );
         }
+        // T053: Suggest manual intervention for errors
+        ConnectionStatus::Error => {
+            for line in error_status_lines(status) {
+                println!("{}", line);
+            }
+        }
+        ConnectionStatus::Reconnecting => {
+            println!(
+                "{} {}",
+                ui::symbol(Symbol::Degraded),
+                "Status: Reconnecting".bright_yellow().bold()
+            );
+            if let Some(line) = name_line {
+                println!("{}", line);
+            }
+            println!(
+                "  {} Attempt {} of {}",
+                ui::symbol(Symbol::Reconnect),
+                status.attempt.unwrap_or(1).to_string().bright_cyan(),
+                status.max_attempts.unwrap_or(5).to_string().bright_cyan()
+            );
 
-        if let Some(ip) = state.get("last_ip") {
+            if let Some(next_retry) = status.next_retry_at {
+                let retry_time = DateTime::from_timestamp(next_retry as i64, 0)
+                    .map(|dt: DateTime<Utc>| dt.with_timezone(&chrono::Local))
+                    .map(|dt| dt.format("%H:%M:%S").to_string())
+                    .unwrap_or_else(|| "unknown".to_string());
+
+                println!(
+                    "  {} Next retry at {}",
+                    ui::symbol(Symbol::Timer),
+                    retry_time.bright_cyan()
+                );
+            }
+
+            if let Some(ip) = &status.ip {
+                println!("  {} {}", "Last known IP:".dimmed(), ip.bright_cyan());
+            }
+        }
+        ConnectionStatus::Stale => {
+            println!(
+                "{} {}",
+                ui::symbol(Symbol::Degraded),
+                "Status: Stale connection state".bright_yellow().bold()
+            );
+            if let Some(line) = name_line {
+                println!("{}", line);
+            }
             println!(
                 "  {} {}",
-                "Last known IP:".dimmed(),
-                ip.as_str().unwrap_or("unknown").bright_cyan()
+                ui::symbol(Symbol::Warning),
+                "Process no longer running".dimmed()
+            );
+            if let Some(ip) = &status.ip {
+                println!("  {} {}", "Last known IP:".dimmed(), ip.bright_cyan());
+            }
+            println!(
+                "\n{} {} to clean up the stale state",
+                "Run".dimmed(),
+                "akon vpn off".bright_white().bold()
             );
         }
+        ConnectionStatus::Connected => {
+            println!(
+                "{} {}",
+                ui::symbol(Symbol::Up),
+                "Status: Connected".bright_green().bold()
+            );
+            if let Some(line) = name_line {
+                println!("{}", line);
+            }
+            if let Some(ip) = &status.ip {
+                println!(
+                    "  {} {}",
+                    "IP address:".bright_white(),
+                    ip.bright_cyan().bold()
+                );
+            }
+            if status.ip_unusual {
+                println!(
+                    "  {} {}",
+                    ui::symbol(Symbol::Warning),
+                    "IP is outside the usual range for this server (check realm/config)"
+                        .bright_yellow()
+                );
+            }
+            if let Some(device) = &status.device {
+                println!("  {} {}", "Device:".bright_white(), device.bright_cyan());
+                println!("  {} {}", "Traffic:".bright_white(), traffic_line(device));
+            }
+            if let Some(pid) = status.pid {
+                println!(
+                    "  {} {}",
+                    "Process ID:".bright_white(),
+                    pid.to_string().bright_yellow()
+                );
+            }
 
-        std::process::exit(1);
+            if let (Some(connected_at), Some(secs)) = (status.connected_at, status.duration_secs) {
+                let duration = chrono::Duration::seconds(secs);
+                let duration_str = if duration.num_days() > 0 {
+                    format!("{} days", duration.num_days())
+                } else if duration.num_hours() > 0 {
+                    format!("{} hours", duration.num_hours())
+                } else if duration.num_minutes() > 0 {
+                    format!("{} minutes", duration.num_minutes())
+                } else {
+                    format!("{} seconds", duration.num_seconds())
+                };
+
+                println!(
+                    "  {} {}",
+                    "Duration:".bright_white(),
+                    duration_str.bright_magenta()
+                );
+                println!(
+                    "  {} {}",
+                    "Connected at:".bright_white(),
+                    connected_at
+                        .format("%Y-%m-%d %H:%M:%S UTC")
+                        .to_string()
+                        .dimmed()
+                );
+            }
+        }
     }
+}
 
-    // Verify process is still running (Step 2 from vpn-status-command.md)
-    // Note: openconnect runs as root, so we need to check via ps instead of kill signal
-    let pid = state.get("pid").and_then(|p| p.as_u64());
-    let process_running = if let Some(pid_num) = pid {
-        // Use ps to check if process exists (works for processes owned by other users)
-        std::process::Command::new("ps")
-            .args(["-p", &pid_num.to_string()])
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .status()
-            .map(|s| s.success())
-            .unwrap_or(false)
-    } else {
-        false
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if !process_running {
-        // Stale state
-        println!(
-            "{} {}",
-            "●".bright_yellow(),
-            "Status: Stale connection state".bright_yellow().bold()
+    /// Records signals instead of running sudo
+    struct RecordingSignaler {
+        signals: Vec<String>,
+        exits_on_term: bool,
+    }
+
+    impl ProcessSignaler for RecordingSignaler {
+        fn signal(&mut self, _pid: i32, signal: &str) -> std::io::Result<()> {
+            self.signals.push(signal.to_string());
+            Ok(())
+        }
+
+        fn is_running(&mut self, _pid: i32) -> bool {
+            !(self.exits_on_term && self.signals.iter().any(|s| s == "TERM"))
+        }
+    }
+
+    /// Signaler for which every process is running
+    fn running() -> RecordingSignaler {
+        RecordingSignaler {
+            signals: Vec::new(),
+            exits_on_term: false,
+        }
+    }
+
+    #[test]
+    fn test_status_json_has_stable_fields() {
+        let now: DateTime<Utc> = "2026-01-01T12:00:00Z".parse().unwrap();
+        let state: VpnStateFile = serde_json::from_value(serde_json::json!({
+            "name": "work",
+            "ip": "10.0.0.5",
+            "device": "tun0",
+            "pid": 42,
+            "connected_at": "2026-01-01T11:00:00+00:00",
+        }))
+        .unwrap();
+
+        let status = VpnStatus::from_state(&state, &mut running(), now);
+        assert_eq!(status.state.exit(), StatusExit::Connected);
+        assert_eq!(
+            serde_json::to_value(&status).unwrap(),
+            serde_json::json!({
+                "state": "connected",
+                "ip": "10.0.0.5",
+                "device": "tun0",
+                "pid": 42,
+                "connected_at": "2026-01-01T11:00:00+00:00",
+                "duration_secs": 3600,
+            })
         );
-        println!(
-            "  {} {}",
-            "⚠".bright_yellow(),
-            "Process no longer running".dimmed()
+
+        // The same file once openconnect has exited
+        let mut exited = RecordingSignaler {
+            signals: vec!["TERM".to_string()],
+            exits_on_term: true,
+        };
+        let status = VpnStatus::from_state(&state, &mut exited, now);
+        assert_eq!(status.state.exit(), StatusExit::Stale);
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["state"], "stale");
+        assert_eq!(json["duration_secs"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_status_json_while_reconnecting_or_disconnected() {
+        let state = VpnStateFile::reconnecting(
+            "work",
+            2,
+            5,
+            Some(1767268800),
+            Some("10.0.0.5".to_string()),
         );
-        if let Some(ip) = state.get("ip") {
-            println!(
-                "  {} {}",
-                "Last known IP:".dimmed(),
-                ip.as_str().unwrap_or("unknown").bright_cyan()
+
+        let status = VpnStatus::from_state(&state, &mut running(), Utc::now());
+        assert_eq!(status.state.exit(), StatusExit::NotConnected);
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["state"], "reconnecting");
+        assert_eq!(json["ip"], "10.0.0.5");
+        assert_eq!(json["attempt"], 2);
+        assert_eq!(json["max_attempts"], 5);
+        assert_eq!(json["next_retry_at"], 1767268800u64);
+        assert_eq!(json["pid"], serde_json::Value::Null);
+
+        let json = serde_json::to_value(VpnStatus::disconnected()).unwrap();
+        assert_eq!(json["state"], "disconnected");
+        assert!(json.get("attempt").is_none());
+    }
+
+    #[test]
+    fn test_privilege_suggestions_name_the_configured_tool() {
+        let error = VpnError::RoutingNotConfigured {
+            reason: "no routes".to_string(),
+        };
+
+        let sudo = error_suggestions(&error, PrivilegeEscalation::Sudo).join("\n");
+        assert!(sudo.contains("sudo -n openconnect --version"));
+
+        let pkexec = error_suggestions(&error, PrivilegeEscalation::Pkexec).join("\n");
+        assert!(pkexec.contains("pkexec openconnect --version"));
+        assert!(!pkexec.contains("sudo"));
+
+        let none = error_suggestions(&error, PrivilegeEscalation::None).join("\n");
+        assert!(none.contains("CAP_NET_ADMIN"));
+        assert!(!none.contains("sudo"));
+    }
+
+    #[test]
+    fn test_persisted_auth_failure_shows_auth_suggestions_in_status() {
+        let config = VpnConfig::new("vpn.example.com".to_string(), "alice".to_string());
+        let state = connect_error_state(&config, &VpnError::AuthenticationFailed);
+        let persisted: VpnStateFile =
+            serde_json::from_str(&serde_json::to_string_pretty(&state).unwrap()).unwrap();
+
+        let status = VpnStatus::from_state(&persisted, &mut running(), Utc::now());
+        let output = error_status_lines(&status).join("\n");
+
+        assert!(output.contains("Status: Error - Connection failed"));
+        assert!(output.contains("vpn.authentication_failed"));
+        for suggestion in
+            error_suggestions(&VpnError::AuthenticationFailed, config.privilege_escalation)
+        {
+            assert!(output.contains(&suggestion), "missing: {}", suggestion);
+        }
+        assert!(output.contains("Verify your PIN is correct"));
+        assert!(!output.contains("Manual intervention required"));
+    }
+
+    #[test]
+    fn test_reconnection_error_state_keeps_manual_intervention() {
+        let mut state = VpnStateFile::error("work", "Max reconnection attempts (5) exceeded");
+        state.max_attempts = Some(5);
+
+        let status = VpnStatus::from_state(&state, &mut running(), Utc::now());
+        let output = error_status_lines(&status).join("\n");
+
+        assert!(output.contains("Max reconnection attempts exceeded"));
+        assert!(output.contains("Failed after 5 reconnection attempts"));
+        assert!(output.contains("Manual intervention required"));
+        assert!(!output.contains("Suggestions:"));
+    }
+
+    #[test]
+    fn test_kill_switch_is_lifted_last_after_daemon_stops() {
+        let position = |step| TEARDOWN_ORDER.iter().position(|s| *s == step).unwrap();
+
+        assert_eq!(TEARDOWN_ORDER.last(), Some(&TeardownStep::KillSwitch));
+        assert!(position(TeardownStep::ReconnectionDaemon) < position(TeardownStep::KillSwitch));
+        assert!(position(TeardownStep::OrphanedProcesses) < position(TeardownStep::KillSwitch));
+    }
+
+    #[tokio::test]
+    async fn test_restart_disconnects_fully_then_connects() {
+        let calls = std::cell::RefCell::new(Vec::new());
+
+        restart_with(
+            || async {
+                let mut signaler = RecordingSignaler {
+                    signals: Vec::new(),
+                    exits_on_term: true,
+                };
+                let result = terminate_openconnect(&mut signaler, 4242, false).await;
+                calls.borrow_mut().extend(signaler.signals);
+                calls.borrow_mut().push("off".to_string());
+                result.map(|_| ())
+            },
+            || async {
+                calls.borrow_mut().push("connect".to_string());
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(calls.into_inner(), vec!["TERM", "off", "connect"]);
+    }
+
+    #[tokio::test]
+    async fn test_restart_does_not_connect_when_disconnect_fails() {
+        let connected = std::cell::Cell::new(false);
+
+        let result = restart_with(
+            || async { Err(AkonError::Vpn(VpnError::TerminationError)) },
+            || async {
+                connected.set(true);
+                Ok(())
+            },
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(AkonError::Vpn(VpnError::TerminationError))
+        ));
+        assert!(!connected.get());
+    }
+
+    #[test]
+    fn test_lazy_action_against_connected_and_disconnected_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let connected = dir.path().join("connected.json");
+        fs::write(&connected, r#"{"ip": "10.0.0.5", "pid": 4242}"#).unwrap();
+        let disconnected = dir.path().join("missing.json");
+        let mut signaler = RecordingSignaler {
+            signals: Vec::new(),
+            exits_on_term: false,
+        };
+
+        let cases = [
+            (LazyAction::Connect, &connected, true),
+            (LazyAction::Connect, &disconnected, true),
+            (LazyAction::Status, &connected, false),
+            (LazyAction::Status, &disconnected, false),
+            (LazyAction::Toggle, &connected, false),
+            (LazyAction::Toggle, &disconnected, true),
+        ];
+        for (action, state_path, connects) in cases {
+            assert_eq!(
+                lazy_connects(action, state_path, &mut signaler),
+                connects,
+                "{:?} with {}",
+                action,
+                state_path.display()
             );
         }
-        println!(
-            "\n{} {} to clean up the stale state",
-            "Run".dimmed(),
-            "akon vpn off".bright_white().bold()
-        );
-        std::process::exit(2);
     }
 
-    // Connected and process running
-    println!(
-        "{} {}",
-        "●".bright_green(),
-        "Status: Connected".bright_green().bold()
-    );
-    if let Some(ip) = state.get("ip") {
-        println!(
-            "  {} {}",
-            "IP address:".bright_white(),
-            ip.as_str().unwrap_or("unknown").bright_cyan().bold()
+    #[test]
+    fn test_state_with_exited_process_is_not_connected() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("state.json");
+        fs::write(&state_path, r#"{"ip": "10.0.0.5", "pid": 4242}"#).unwrap();
+        let mut signaler = RecordingSignaler {
+            signals: vec!["TERM".to_string()],
+            exits_on_term: true,
+        };
+
+        assert!(!connection_is_up(&state_path, &mut signaler));
+        assert!(lazy_connects(
+            LazyAction::Toggle,
+            &state_path,
+            &mut signaler
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_force_sends_sigkill_without_sigterm() {
+        let mut signaler = RecordingSignaler {
+            signals: Vec::new(),
+            exits_on_term: true,
+        };
+
+        let started = std::time::Instant::now();
+        let result = terminate_openconnect(&mut signaler, 4242, true).await;
+
+        assert_eq!(result.unwrap(), Termination::Forced);
+        assert_eq!(signaler.signals, vec!["KILL"]);
+        // No grace loop: only the short post-kill settle delay
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_default_terminates_gracefully_with_sigterm() {
+        let mut signaler = RecordingSignaler {
+            signals: Vec::new(),
+            exits_on_term: true,
+        };
+
+        let result = terminate_openconnect(&mut signaler, 4242, false).await;
+
+        assert_eq!(result.unwrap(), Termination::Graceful);
+        assert_eq!(signaler.signals, vec!["TERM"]);
+    }
+
+    #[tokio::test]
+    async fn test_group_menu_applies_selected_group() {
+        let mut config = VpnConfig::new("vpn.example.com".to_string(), "alice".to_string());
+        let mut attempts: Vec<Option<String>> = Vec::new();
+
+        // Mock connector: the gateway demands a group until one is passed
+        let connect = |config: VpnConfig| {
+            attempts.push(config.auth_group.clone());
+            async move {
+                match config.auth_group {
+                    Some(group) => Ok(group),
+                    None => Err(AkonError::Vpn(VpnError::AuthGroupRequired {
+                        groups: vec!["Employees".to_string(), "Contractors".to_string()],
+                    })),
+                }
+            }
+        };
+
+        let mut input = std::io::Cursor::new("7\n2\n");
+        let mut output = Vec::new();
+        let result = connect_with_auth_group(&mut config, connect, |groups| {
+            select_auth_group(groups, &mut input, &mut output)
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), "Contractors");
+        assert_eq!(config.auth_group.as_deref(), Some("Contractors"));
+        assert_eq!(attempts, vec![None, Some("Contractors".to_string())]);
+
+        let menu = String::from_utf8(output).unwrap();
+        assert!(menu.contains("  1. Employees\n  2. Contractors"));
+        assert!(menu.contains("Please enter a number between 1 and 2."));
+    }
+
+    #[tokio::test]
+    async fn test_group_required_without_choice_is_reported() {
+        let mut config = VpnConfig::new("vpn.example.com".to_string(), "alice".to_string());
+        let groups = vec!["Employees".to_string()];
+
+        let result: Result<(), AkonError> = connect_with_auth_group(
+            &mut config,
+            |_| {
+                let groups = groups.clone();
+                async move { Err(AkonError::Vpn(VpnError::AuthGroupRequired { groups })) }
+            },
+            |_| None,
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(AkonError::Vpn(VpnError::AuthGroupRequired { .. }))
+        ));
+        assert_eq!(config.auth_group, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_transient_dns_failure_is_retried_until_resolved() {
+        let config = VpnConfig::new("vpn.example.com".to_string(), "alice".to_string());
+        let mut attempts = 0;
+        let started = tokio::time::Instant::now();
+
+        // Mock connector: the name resolves on the third try
+        let mut retries = Vec::new();
+        let result = retry_transient_dns(config, |_, retry| {
+            attempts += 1;
+            retries.push(retry);
+            let resolved = attempts == 3;
+            async move {
+                if resolved {
+                    Ok("connected")
+                } else {
+                    Err(AkonError::Vpn(VpnError::DnsFailure { transient: true }))
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), "connected");
+        assert_eq!(attempts, 3);
+        assert_eq!(retries, [0, 1, 2]);
+        // Waited 2s, then 4s
+        assert_eq!(started.elapsed(), Duration::from_secs(6));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_dns_retries_stop_for_unknown_names_and_when_used_up() {
+        let mut config = VpnConfig::new("vpn.example.com".to_string(), "alice".to_string());
+        let mut attempts = 0;
+        let result: Result<(), AkonError> = retry_transient_dns(config.clone(), |_, _| {
+            attempts += 1;
+            async { Err(AkonError::Vpn(VpnError::DnsFailure { transient: false })) }
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(AkonError::Vpn(VpnError::DnsFailure { transient: false }))
+        ));
+        assert_eq!(attempts, 1);
+
+        config.dns_retries = Some(1);
+        let mut attempts = 0;
+        let result: Result<(), AkonError> = retry_transient_dns(config, |_, _| {
+            attempts += 1;
+            async { Err(AkonError::Vpn(VpnError::DnsFailure { transient: true })) }
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(AkonError::Vpn(VpnError::DnsFailure { transient: true }))
+        ));
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn test_each_profile_records_its_own_daemon() {
+        let default = get_daemon_pid_file(None);
+        assert_eq!(
+            get_daemon_pid_file(Some(akon_core::config::DEFAULT_PROFILE)),
+            default
+        );
+        assert_ne!(get_daemon_pid_file(Some("work")), default);
+        assert_ne!(
+            get_daemon_pid_file(Some("work")),
+            get_daemon_pid_file(Some("lab"))
         );
     }
-    if let Some(device) = state.get("device") {
-        println!(
-            "  {} {}",
-            "Device:".bright_white(),
-            device.as_str().unwrap_or("unknown").bright_cyan()
+
+    fn test_policy() -> ReconnectionPolicy {
+        ReconnectionPolicy {
+            health_check_endpoint: "https://intranet.example.com/".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_daemon_stderr_goes_to_capture_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("daemon.log");
+        std::fs::write(&log, "earlier run\n").unwrap();
+
+        let status = daemon_command(
+            Path::new("sh"),
+            vec![
+                "-c".to_string(),
+                "echo 'Daemon: Failed to parse VPN config' >&2; pwd >&2; echo ignored".to_string(),
+            ],
+            Some(&log),
+        )
+        .status()
+        .unwrap();
+        assert!(status.success());
+
+        let captured = std::fs::read_to_string(&log).unwrap();
+        let workdir = dir.path().canonicalize().unwrap();
+        assert_eq!(
+            captured,
+            format!(
+                "earlier run\nDaemon: Failed to parse VPN config\n{}\n",
+                workdir.display()
+            )
         );
     }
-    if let Some(pid_num) = pid {
-        println!(
-            "  {} {}",
-            "Process ID:".bright_white(),
-            pid_num.to_string().bright_yellow()
+
+    #[test]
+    fn test_no_reconnect_skips_daemon_even_with_policy() {
+        let options = VpnOnOptions {
+            no_reconnect: true,
+            ..Default::default()
+        };
+        assert!(reconnection_policy_for(Some(test_policy()), &options)
+            .unwrap()
+            .is_none());
+
+        let options = VpnOnOptions::default();
+        assert!(reconnection_policy_for(Some(test_policy()), &options)
+            .unwrap()
+            .is_some());
+        assert!(reconnection_policy_for(None, &options).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_cookie_session_keeps_state_and_reconnection() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cookie");
+        fs::write(&path, "webvpn=0123456789ABCDEF\n").unwrap();
+        let cookie = session_cookie(None, Some(&path)).unwrap().unwrap();
+        assert_eq!(cookie.expose(), "webvpn=0123456789ABCDEF");
+
+        let options = VpnOnOptions {
+            cookie: Some(cookie),
+            ..Default::default()
+        };
+        assert!(!format!("{:?}", options).contains("0123456789ABCDEF"));
+
+        // Everything after authentication runs as for a keyring login
+        let policy = reconnection_policy_for(Some(test_policy()), &options)
+            .unwrap()
+            .expect("reconnection daemon should still be started");
+        let config = VpnConfig::new("vpn.example.com".to_string(), "user".to_string());
+        let state = connected_state(
+            &config,
+            "10.0.0.5".parse().unwrap(),
+            Some("tun0"),
+            Some(4242),
+            None,
+        );
+        assert_eq!(state.ip.as_deref(), Some("10.0.0.5"));
+        assert_eq!(state.pid, Some(4242));
+
+        let persisted = format!(
+            "{} {:?}",
+            serde_json::to_string(&state).unwrap(),
+            daemon_args(&policy, &config).unwrap()
         );
+        assert!(!persisted.contains("0123456789ABCDEF"));
     }
 
-    // Calculate and display duration
-    if let Some(connected_at_str) = state.get("connected_at").and_then(|v| v.as_str()) {
-        if let Ok(connected_at) = connected_at_str.parse::<DateTime<Utc>>() {
-            let now = Utc::now();
-            let duration = now.signed_duration_since(connected_at);
+    #[test]
+    fn test_empty_cookie_is_rejected() {
+        assert!(session_cookie(Some("  \n".to_string()), None).is_err());
+        assert!(session_cookie(None, None).unwrap().is_none());
+    }
 
-            let duration_str = if duration.num_days() > 0 {
-                format!("{} days", duration.num_days())
-            } else if duration.num_hours() > 0 {
-                format!("{} hours", duration.num_hours())
-            } else if duration.num_minutes() > 0 {
-                format!("{} minutes", duration.num_minutes())
-            } else {
-                format!("{} seconds", duration.num_seconds())
-            };
+    #[test]
+    fn test_health_interval_override_reaches_daemon() {
+        let options = VpnOnOptions {
+            health_interval: Some(15),
+            ..Default::default()
+        };
+        let policy = reconnection_policy_for(Some(test_policy()), &options)
+            .unwrap()
+            .unwrap();
+        let config = VpnConfig::new("vpn.example.com".to_string(), "user".to_string());
+
+        let args = daemon_args(&policy, &config).unwrap();
+        assert_eq!(args[0], "__internal_reconnection_daemon");
+        let received: ReconnectionPolicy = serde_json::from_str(&args[1]).unwrap();
+        assert_eq!(received.health_check_interval_secs, 15);
+        assert_eq!(received.health_check_interval(), Duration::from_secs(15));
+
+        let out_of_range = VpnOnOptions {
+            health_interval: Some(5),
+            ..Default::default()
+        };
+        let error = reconnection_policy_for(Some(test_policy()), &out_of_range).unwrap_err();
+        assert!(error.to_string().contains("--health-interval"), "{}", error);
+    }
 
-            println!(
-                "  {} {}",
-                "Duration:".bright_white(),
-                duration_str.bright_magenta()
-            );
-            println!(
-                "  {} {}",
-                "Connected at:".bright_white(),
-                connected_at
-                    .format("%Y-%m-%d %H:%M:%S UTC")
-                    .to_string()
-                    .dimmed()
-            );
-        }
+    #[test]
+    fn test_connection_name_in_state_and_status() {
+        let ip: IpAddr = "10.0.0.5".parse().unwrap();
+        let mut config = VpnConfig::new("vpn.example.com".to_string(), "user".to_string());
+
+        // Falls back to the server when unset
+        let state = connected_state(&config, ip, Some("tun0"), Some(42), None);
+        assert_eq!(state.name.as_deref(), Some("vpn.example.com"));
+        let status = VpnStatus::from_state(&state, &mut running(), Utc::now());
+        assert!(connection_name_line(status.name.as_deref().unwrap()).contains("vpn.example.com"));
+
+        config.name = Some("work".to_string());
+        let state = connected_state(&config, ip, Some("tun0"), Some(42), None);
+        assert_eq!(state.name.as_deref(), Some("work"));
+        assert_eq!(state.device.as_deref(), Some("tun0"));
+        let status = VpnStatus::from_state(&state, &mut running(), Utc::now());
+        assert_eq!(status.name.as_deref(), Some("work"));
+
+        // State files written before names existed have no name
+        let old: VpnStateFile = serde_json::from_str(r#"{ "ip": "10.0.0.5" }"#).unwrap();
+        assert!(VpnStatus::from_state(&old, &mut running(), Utc::now())
+            .name
+            .is_none());
     }
 
-    Ok(())
+    #[test]
+    fn test_already_connected_message_names_the_connection() {
+        let state: VpnStateFile = serde_json::from_str(r#"{ "name": "work", "pid": 42 }"#).unwrap();
+        assert_eq!(
+            already_connected_message(&state),
+            "work is already connected"
+        );
+
+        let state: VpnStateFile = serde_json::from_str(r#"{ "pid": 42 }"#).unwrap();
+        assert_eq!(
+            already_connected_message(&state),
+            "VPN is already connected"
+        );
+    }
 }
@@ -3,4 +3,5 @@
 //! This module handles spawning and managing daemon processes for VPN connections,
 //! including PID file management and process lifecycle.
 
+pub mod notify;
 pub mod process;
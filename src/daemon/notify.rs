@@ -0,0 +1,215 @@
+//! systemd service notifications
+//!
+//! When akon runs as a `Type=notify` service, it reports its progress with
+//! sd_notify: `STATUS=` lines for `systemctl status`, `READY=1` once the
+//! tunnel is up, and `WATCHDOG=1` pings from the reconnection daemon. The
+//! daemon is a child process, so the unit needs `NotifyAccess=all`. Outside
+//! systemd (no `NOTIFY_SOCKET`) nothing is sent.
+
+use std::io;
+use std::net::IpAddr;
+use std::time::Duration;
+use tracing::debug;
+
+/// Connection state reported to systemd
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServiceState<'a> {
+    Connecting {
+        server: &'a str,
+    },
+    AwaitingApproval,
+    Connected {
+        server: &'a str,
+        ip: IpAddr,
+    },
+    Reconnecting {
+        server: &'a str,
+        attempt: u32,
+        max_attempts: u32,
+    },
+    Failed {
+        reason: String,
+    },
+    Disconnected,
+}
+
+impl ServiceState<'_> {
+    /// Text shown by `systemctl status`
+    pub fn status(&self) -> String {
+        match self {
+            Self::Connecting { server } => format!("Connecting to {}", server),
+            Self::AwaitingApproval => "Waiting for the sign-in to be approved".to_string(),
+            Self::Connected { server, ip } => format!("Connected to {} as {}", server, ip),
+            Self::Reconnecting {
+                server,
+                attempt,
+                max_attempts,
+            } => format!(
+                "Reconnecting to {} (attempt {}/{})",
+                server, attempt, max_attempts
+            ),
+            Self::Failed { reason } => format!("Connection failed: {}", reason),
+            Self::Disconnected => "Disconnected".to_string(),
+        }
+    }
+
+    /// sd_notify assignments for this state
+    ///
+    /// The service only becomes ready once the tunnel is up.
+    pub fn assignments(&self) -> Vec<String> {
+        let mut assignments = vec![format!("STATUS={}", self.status())];
+        if let Self::Connected { .. } = self {
+            assignments.push("READY=1".to_string());
+        }
+        assignments
+    }
+}
+
+/// Delivers sd_notify assignments to the service manager
+pub trait Notifier {
+    fn notify(&self, assignments: &[String]) -> io::Result<()>;
+}
+
+/// Notifier for the socket in `NOTIFY_SOCKET`
+pub struct SystemdNotifier;
+
+impl Notifier for SystemdNotifier {
+    #[cfg(feature = "systemd-notify")]
+    fn notify(&self, assignments: &[String]) -> io::Result<()> {
+        let states: Vec<sd_notify::NotifyState> = assignments
+            .iter()
+            .map(|assignment| sd_notify::NotifyState::Custom(assignment))
+            .collect();
+        sd_notify::notify(false, &states)
+    }
+
+    #[cfg(not(feature = "systemd-notify"))]
+    fn notify(&self, _assignments: &[String]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Whether a service manager listens for notifications
+fn under_systemd() -> bool {
+    cfg!(target_os = "linux") && std::env::var_os("NOTIFY_SOCKET").is_some()
+}
+
+/// Send `assignments`, logging rather than failing on errors
+fn send<N: Notifier>(notifier: &N, assignments: &[String]) {
+    if let Err(e) = notifier.notify(assignments) {
+        debug!("Failed to notify systemd: {}", e);
+    }
+}
+
+/// Report `state` to systemd when running as a notify service
+pub fn notify(state: ServiceState) {
+    if under_systemd() {
+        send(&SystemdNotifier, &state.assignments());
+    }
+}
+
+/// How often to ping the watchdog: half the unit's `WatchdogSec`, if set
+///
+/// `WATCHDOG_PID` names `akon vpn on`, not the daemon, so it isn't checked.
+pub fn watchdog_interval() -> Option<Duration> {
+    if !under_systemd() {
+        return None;
+    }
+    let usec: u64 = std::env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    (usec > 0).then(|| Duration::from_micros(usec / 2))
+}
+
+/// Tell the watchdog akon is still alive
+pub fn ping_watchdog() {
+    send(&SystemdNotifier, &["WATCHDOG=1".to_string()]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Records what would have been sent
+    #[derive(Default)]
+    struct RecordingNotifier {
+        sent: RefCell<Vec<Vec<String>>>,
+    }
+
+    impl Notifier for RecordingNotifier {
+        fn notify(&self, assignments: &[String]) -> io::Result<()> {
+            self.sent.borrow_mut().push(assignments.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_status_for_each_state() {
+        let server = "vpn.example.com";
+        let cases = [
+            (
+                ServiceState::Connecting { server },
+                "Connecting to vpn.example.com",
+            ),
+            (
+                ServiceState::AwaitingApproval,
+                "Waiting for the sign-in to be approved",
+            ),
+            (
+                ServiceState::Connected {
+                    server,
+                    ip: "10.0.0.5".parse().unwrap(),
+                },
+                "Connected to vpn.example.com as 10.0.0.5",
+            ),
+            (
+                ServiceState::Reconnecting {
+                    server,
+                    attempt: 2,
+                    max_attempts: 5,
+                },
+                "Reconnecting to vpn.example.com (attempt 2/5)",
+            ),
+            (
+                ServiceState::Failed {
+                    reason: "Authentication failed".to_string(),
+                },
+                "Connection failed: Authentication failed",
+            ),
+            (ServiceState::Disconnected, "Disconnected"),
+        ];
+
+        for (state, status) in cases {
+            assert_eq!(state.status(), status);
+            assert_eq!(state.assignments()[0], format!("STATUS={}", status));
+        }
+    }
+
+    #[test]
+    fn test_ready_only_once_connected() {
+        let notifier = RecordingNotifier::default();
+        let server = "vpn.example.com";
+
+        send(
+            &notifier,
+            &ServiceState::Connecting { server }.assignments(),
+        );
+        send(
+            &notifier,
+            &ServiceState::Connected {
+                server,
+                ip: "10.0.0.5".parse().unwrap(),
+            }
+            .assignments(),
+        );
+
+        let sent = notifier.sent.borrow();
+        assert!(!sent[0].contains(&"READY=1".to_string()));
+        assert_eq!(
+            sent[1],
+            vec![
+                "STATUS=Connected to vpn.example.com as 10.0.0.5".to_string(),
+                "READY=1".to_string(),
+            ]
+        );
+    }
+}
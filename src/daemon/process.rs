@@ -2,211 +2,191 @@
 //!
 //! Handles spawning daemon processes, PID file management, and daemon lifecycle.
 
+use akon_core::config::{PrivilegeEscalation, VpnConfig};
 use akon_core::error::{AkonError, VpnError};
+use nix::errno::Errno;
+use nix::sys::signal::{kill, Signal};
+use nix::unistd::Pid;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
-/// Cleanup orphaned OpenConnect processes (T049)
-/// Cleanup orphaned OpenConnect processes (T049)
-///
-/// Finds all OpenConnect processes and terminates them gracefully (SIGTERM),
-/// then forcefully (SIGKILL) if they don't respond within 5 seconds.
-///
-/// Returns the number of processes successfully terminated.
-///
-/// # Errors
-///
-/// Returns an error if:
-/// - Unable to list running processes
-/// - All termination attempts fail (but logs individual failures)
-///
-/// # Example
-///
-/// ```no_run
-/// use akon::daemon::process::cleanup_orphaned_processes;
-///
-/// match cleanup_orphaned_processes() {
-///     Ok(count) => println!("Terminated {} orphaned processes", count),
-///     Err(e) => eprintln!("Cleanup failed: {}", e),
-/// }
-/// ```
-pub fn cleanup_orphaned_processes() -> Result<usize, AkonError> {
-    use nix::errno::Errno;
-    use nix::sys::signal::{kill, Signal};
-    use nix::unistd::Pid;
-    use std::process::{Command, Stdio};
-    use tracing::{debug, warn};
-
-    enum SignalResult {
-        Delivered,
-        AlreadyExited,
-        NotPermitted,
-        Failed,
-    }
-
-    fn attempt_privileged_kill(pid: i32, signal: Signal) -> bool {
-        let signal_arg = match signal {
-            Signal::SIGTERM => "-TERM",
-            Signal::SIGKILL => "-KILL",
-            _ => return false,
-        };
+/// Default time processes get to exit after SIGTERM
+pub const DEFAULT_CLEANUP_GRACE_SECS: u64 = 5;
 
-        match Command::new("sudo")
-            .arg("-n")
-            .arg("kill")
-            .arg(signal_arg)
-            .arg(pid.to_string())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-        {
-            Ok(status) if status.success() => {
-                debug!(
-                    "Elevated kill succeeded for process {} with {:?}",
-                    pid, signal
-                );
-                true
-            }
-            Ok(status) => {
-                warn!(
-                    "sudo kill exited with status {:?} when sending {:?} to process {}",
-                    status.code(),
-                    signal,
-                    pid
-                );
-                false
-            }
-            Err(e) => {
-                warn!(
-                    "Failed to invoke sudo when sending {:?} to process {}: {}",
-                    signal, pid, e
-                );
-                false
-            }
+/// How long to wait for processes to disappear after SIGKILL
+const KILL_WAIT: Duration = Duration::from_millis(500);
+
+/// How often to check whether signalled processes have exited
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How orphaned processes are terminated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CleanupOptions {
+    /// Time processes get to exit after SIGTERM
+    pub grace_period: Duration,
+    /// Send SIGKILL to processes still running after the grace period
+    pub escalate: bool,
+    /// How to signal processes owned by root
+    pub privilege_escalation: PrivilegeEscalation,
+}
+
+impl Default for CleanupOptions {
+    fn default() -> Self {
+        Self {
+            grace_period: Duration::from_secs(DEFAULT_CLEANUP_GRACE_SECS),
+            escalate: true,
+            privilege_escalation: PrivilegeEscalation::default(),
         }
     }
+}
 
-    fn is_process_running(pid: i32) -> bool {
-        Command::new("ps")
-            .args(["-p", &pid.to_string()])
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-            .map(|status| status.success())
-            .unwrap_or(false)
-    }
-
-    fn send_signal(pid: i32, signal: Signal) -> SignalResult {
-        let pid_obj = Pid::from_raw(pid);
-
-        match kill(pid_obj, signal) {
-            Ok(_) => SignalResult::Delivered,
-            Err(Errno::ESRCH) => SignalResult::AlreadyExited,
-            Err(Errno::EPERM) => {
-                if attempt_privileged_kill(pid, signal) {
-                    SignalResult::Delivered
-                } else if !is_process_running(pid) {
-                    SignalResult::AlreadyExited
-                } else {
-                    SignalResult::NotPermitted
-                }
-            }
-            Err(err) => {
-                warn!("Failed to send {:?} to process {}: {}", signal, pid, err);
-                SignalResult::Failed
-            }
+impl CleanupOptions {
+    /// Cleanup options set in the `[vpn]` section
+    pub fn for_config(config: &VpnConfig) -> Self {
+        Self {
+            grace_period: Duration::from_secs(
+                config
+                    .cleanup_grace_secs
+                    .unwrap_or(DEFAULT_CLEANUP_GRACE_SECS),
+            ),
+            escalate: !config.no_kill_escalation,
+            privilege_escalation: config.privilege_escalation,
         }
     }
+}
 
-    // Find all openconnect processes
-    let output = Command::new("pgrep")
-        .arg("-x") // Exact match
-        .arg("openconnect")
-        .output()
-        .map_err(|e| {
-            AkonError::Vpn(VpnError::ConnectionFailed {
-                reason: format!("Failed to search for openconnect processes: {}", e),
-            })
-        })?;
+/// Outcome of terminating a set of processes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    /// Number of processes that are gone, however they ended
+    pub terminated: usize,
+    /// Processes that ignored SIGTERM and were ended with SIGKILL
+    pub killed: Vec<i32>,
+}
 
-    if !output.status.success() {
-        // No processes found (pgrep returns non-zero when no matches)
-        debug!("No openconnect processes found");
-        return Ok(0);
-    }
+enum SignalResult {
+    Delivered,
+    AlreadyExited,
+    NotPermitted,
+    Failed,
+}
 
-    let pids_str = String::from_utf8_lossy(&output.stdout);
-    let pids: Vec<i32> = pids_str
-        .lines()
-        .filter_map(|line| line.trim().parse().ok())
-        .collect();
+fn attempt_privileged_kill(pid: i32, signal: Signal, escalation: PrivilegeEscalation) -> bool {
+    let signal_arg = match signal {
+        Signal::SIGTERM => "-TERM",
+        Signal::SIGKILL => "-KILL",
+        _ => return false,
+    };
+    // Without a tool the plain kill that just failed is all there is
+    let Some(tool) = escalation.tool() else {
+        return false;
+    };
 
-    if pids.is_empty() {
-        debug!("No openconnect processes to cleanup");
-        return Ok(0);
+    let mut command = Command::new(tool);
+    if escalation == PrivilegeEscalation::Sudo {
+        // Never wait for a password prompt
+        command.arg("-n");
+    }
+    match command
+        .arg("kill")
+        .arg(signal_arg)
+        .arg(pid.to_string())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+    {
+        Ok(status) if status.success() => {
+            debug!(
+                "Elevated kill succeeded for process {} with {:?}",
+                pid, signal
+            );
+            true
+        }
+        Ok(status) => {
+            warn!(
+                "{} kill exited with status {:?} when sending {:?} to process {}",
+                tool,
+                status.code(),
+                signal,
+                pid
+            );
+            false
+        }
+        Err(e) => {
+            warn!(
+                "Failed to invoke {} when sending {:?} to process {}: {}",
+                tool, signal, pid, e
+            );
+            false
+        }
     }
+}
 
-    let total_pids = pids.len();
-    info!(
-        "Found {} openconnect process(es) to cleanup: {:?}",
-        total_pids, pids
-    );
+fn is_process_running(pid: i32) -> bool {
+    Command::new("ps")
+        .args(["-p", &pid.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
 
-    let mut terminated_count = 0;
-
-    for pid in pids {
-        debug!("Sending SIGTERM to process {}", pid);
-
-        match send_signal(pid, Signal::SIGTERM) {
-            SignalResult::Delivered => {
-                // Wait for graceful shutdown
-                std::thread::sleep(std::time::Duration::from_secs(5));
-
-                if is_process_running(pid) {
-                    warn!(
-                        "Process {} did not respond to SIGTERM, sending SIGKILL",
-                        pid
-                    );
-
-                    match send_signal(pid, Signal::SIGKILL) {
-                        SignalResult::Delivered => {
-                            std::thread::sleep(std::time::Duration::from_millis(500));
-                            if is_process_running(pid) {
-                                warn!(
-                                    "Process {} still running after SIGKILL; manual intervention required",
-                                    pid
-                                );
-                            } else {
-                                info!("Successfully terminated process {} with SIGKILL", pid);
-                                terminated_count += 1;
-                            }
-                        }
-                        SignalResult::AlreadyExited => {
-                            debug!("Process {} exited while escalating to SIGKILL", pid);
-                            terminated_count += 1;
-                        }
-                        SignalResult::NotPermitted => {
-                            warn!(
-                                "Insufficient privileges to forcefully terminate process {}. Run akon with sudo or configure passwordless sudo for kill/openconnect.",
-                                pid
-                            );
-                        }
-                        SignalResult::Failed => {
-                            // Error already logged inside send_signal
-                        }
-                    }
-                } else {
-                    info!("Process {} terminated gracefully", pid);
-                    terminated_count += 1;
-                }
+fn send_signal(pid: i32, signal: Signal, escalation: PrivilegeEscalation) -> SignalResult {
+    match kill(Pid::from_raw(pid), signal) {
+        Ok(_) => SignalResult::Delivered,
+        Err(Errno::ESRCH) => SignalResult::AlreadyExited,
+        Err(Errno::EPERM) => {
+            if attempt_privileged_kill(pid, signal, escalation) {
+                SignalResult::Delivered
+            } else if !is_process_running(pid) {
+                SignalResult::AlreadyExited
+            } else {
+                SignalResult::NotPermitted
             }
+        }
+        Err(err) => {
+            warn!("Failed to send {:?} to process {}: {}", signal, pid, err);
+            SignalResult::Failed
+        }
+    }
+}
+
+/// Wait up to `timeout` for `pids` to exit, returning those still running
+fn wait_for_exit(mut pids: Vec<i32>, timeout: Duration) -> Vec<i32> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        pids.retain(|&pid| is_process_running(pid));
+        if pids.is_empty() || Instant::now() >= deadline {
+            return pids;
+        }
+        std::thread::sleep(EXIT_POLL_INTERVAL);
+    }
+}
+
+/// Send `signal` to each process, returning those it was delivered to
+///
+/// Processes that had already exited are counted in `report`.
+fn signal_all(
+    pids: &[i32],
+    signal: Signal,
+    escalation: PrivilegeEscalation,
+    report: &mut CleanupReport,
+) -> Vec<i32> {
+    let mut signalled = Vec::new();
+    for &pid in pids {
+        debug!("Sending {:?} to process {}", signal, pid);
+        match send_signal(pid, signal, escalation) {
+            SignalResult::Delivered => signalled.push(pid),
             SignalResult::AlreadyExited => {
                 debug!("Process {} already terminated", pid);
-                terminated_count += 1;
+                report.terminated += 1;
             }
             SignalResult::NotPermitted => {
                 warn!(
-                    "Insufficient privileges to terminate process {}. Run akon with sudo or configure passwordless sudo for kill/openconnect.",
-                    pid
+                    "Insufficient privileges to send {:?} to process {} (privilege_escalation = \"{}\"). Run akon as root or allow kill/openconnect through sudo or pkexec.",
+                    signal, pid, escalation.as_str()
                 );
             }
             SignalResult::Failed => {
@@ -214,10 +194,261 @@ pub fn cleanup_orphaned_processes() -> Result<usize, AkonError> {
             }
         }
     }
+    signalled
+}
+
+/// Terminate processes with SIGTERM, escalating to SIGKILL for survivors
+///
+/// All processes are signalled at once and share one grace period, so a
+/// stubborn process doesn't delay the others.
+pub fn terminate_processes(pids: &[i32], options: &CleanupOptions) -> CleanupReport {
+    let mut report = CleanupReport::default();
+
+    let signalled = signal_all(
+        pids,
+        Signal::SIGTERM,
+        options.privilege_escalation,
+        &mut report,
+    );
+    let survivors = wait_for_exit(signalled.clone(), options.grace_period);
+    report.terminated += signalled.len() - survivors.len();
+    if survivors.is_empty() {
+        return report;
+    }
+
+    if !options.escalate {
+        warn!(
+            "Process(es) {:?} did not respond to SIGTERM; leaving them running",
+            survivors
+        );
+        return report;
+    }
+
+    warn!(
+        "Process(es) {:?} did not respond to SIGTERM, sending SIGKILL",
+        survivors
+    );
+    let killed = signal_all(
+        &survivors,
+        Signal::SIGKILL,
+        options.privilege_escalation,
+        &mut report,
+    );
+    let remaining = wait_for_exit(killed.clone(), KILL_WAIT);
+    for pid in killed {
+        if remaining.contains(&pid) {
+            warn!(
+                "Process {} still running after SIGKILL; manual intervention required",
+                pid
+            );
+        } else {
+            info!("Terminated process {} with SIGKILL", pid);
+            report.terminated += 1;
+            report.killed.push(pid);
+        }
+    }
+
+    report
+}
+
+/// Processes started by `root`, directly or through their children
+fn descendants(root: i32) -> Result<Vec<i32>, AkonError> {
+    let mut found = Vec::new();
+    let mut parents = vec![root];
+    while let Some(parent) = parents.pop() {
+        let output = Command::new("pgrep")
+            .arg("-P")
+            .arg(parent.to_string())
+            .output()
+            .map_err(|e| {
+                AkonError::Vpn(VpnError::ConnectionFailed {
+                    reason: format!("Failed to search for openconnect processes: {}", e),
+                })
+            })?;
+        // pgrep exits non-zero when the process has no children
+        let children: Vec<i32> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.trim().parse().ok())
+            .collect();
+        parents.extend(&children);
+        found.extend(children);
+    }
+    Ok(found)
+}
+
+/// Cleanup orphaned OpenConnect processes of one connection (T049)
+///
+/// Terminates the openconnect process recorded for a profile's connection,
+/// along with the processes it started (such as vpnc-script), gracefully
+/// (SIGTERM), then forcefully (SIGKILL) if they don't exit within the grace
+/// period and escalation is enabled. Connections of other profiles are left
+/// running.
+///
+/// # Errors
+///
+/// Returns an error if unable to list running processes. Failures to
+/// terminate individual processes are logged and left out of the report.
+///
+/// # Example
+///
+/// ```no_run
+/// use akon::daemon::process::{cleanup_orphaned_processes, CleanupOptions};
+///
+/// match cleanup_orphaned_processes(Some(4242), &CleanupOptions::default()) {
+///     Ok(report) => println!("Terminated {} orphaned processes", report.terminated),
+///     Err(e) => eprintln!("Cleanup failed: {}", e),
+/// }
+/// ```
+pub fn cleanup_orphaned_processes(
+    openconnect_pid: Option<u32>,
+    options: &CleanupOptions,
+) -> Result<CleanupReport, AkonError> {
+    let Some(root) = openconnect_pid.map(|pid| pid as i32) else {
+        debug!("No openconnect process recorded");
+        return Ok(CleanupReport::default());
+    };
+    if !is_process_running(root) {
+        debug!("Recorded openconnect process {} already exited", root);
+        return Ok(CleanupReport::default());
+    }
+
+    // Once the root exits its children are reparented and can't be found
+    let mut pids = descendants(root)?;
+    pids.insert(0, root);
+
+    info!(
+        "Found {} openconnect process(es) to cleanup: {:?}",
+        pids.len(),
+        pids
+    );
+
+    let report = terminate_processes(&pids, options);
 
     info!(
-        "Cleanup complete: terminated {}/{} processes",
-        terminated_count, total_pids
+        "Cleanup complete: terminated {}/{} processes ({} with SIGKILL)",
+        report.terminated,
+        pids.len(),
+        report.killed.len()
     );
-    Ok(terminated_count)
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+
+    /// Spawn a shell running `script`, reaped in the background once it exits
+    ///
+    /// The script must print a line once it is ready to receive signals.
+    fn spawn_dummy(script: &str) -> i32 {
+        let mut child = Command::new("sh")
+            .args(["-c", script])
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("failed to spawn dummy process");
+        let mut ready = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut ready)
+            .unwrap();
+
+        let pid = child.id() as i32;
+        std::thread::spawn(move || child.wait());
+        pid
+    }
+
+    fn spawn_stubborn() -> i32 {
+        spawn_dummy("trap '' TERM; echo ready; while :; do sleep 0.1; done")
+    }
+
+    fn spawn_obedient() -> i32 {
+        spawn_dummy("echo ready; exec sleep 30")
+    }
+
+    #[test]
+    fn test_only_the_process_ignoring_sigterm_is_killed() {
+        let stubborn = spawn_stubborn();
+        let obedient = spawn_obedient();
+        let options = CleanupOptions {
+            grace_period: Duration::from_millis(500),
+            escalate: true,
+            ..CleanupOptions::default()
+        };
+
+        let report = terminate_processes(&[stubborn, obedient], &options);
+
+        assert_eq!(report.terminated, 2);
+        assert_eq!(report.killed, vec![stubborn]);
+        assert!(!is_process_running(stubborn));
+        assert!(!is_process_running(obedient));
+    }
+
+    #[test]
+    fn test_without_escalation_stubborn_process_survives() {
+        let stubborn = spawn_stubborn();
+        let obedient = spawn_obedient();
+        let options = CleanupOptions {
+            grace_period: Duration::from_millis(500),
+            escalate: false,
+            ..CleanupOptions::default()
+        };
+
+        let report = terminate_processes(&[stubborn, obedient], &options);
+        let survived = is_process_running(stubborn);
+        let _ = kill(Pid::from_raw(stubborn), Signal::SIGKILL);
+
+        assert_eq!(report.terminated, 1);
+        assert!(report.killed.is_empty());
+        assert!(survived);
+        assert!(!is_process_running(obedient));
+    }
+
+    #[test]
+    fn test_cleanup_leaves_another_profiles_processes_running() {
+        // The root reaps its child on the way out, as openconnect does
+        let root = spawn_dummy("sleep 30 & trap 'wait; exit' TERM; echo ready; wait");
+        let child = descendants(root).unwrap();
+        let other = spawn_obedient();
+        let options = CleanupOptions {
+            grace_period: Duration::from_millis(500),
+            ..CleanupOptions::default()
+        };
+
+        let report = cleanup_orphaned_processes(Some(root as u32), &options).unwrap();
+        let other_survived = is_process_running(other);
+        let _ = kill(Pid::from_raw(other), Signal::SIGKILL);
+
+        assert_eq!(child.len(), 1);
+        assert_eq!(report.terminated, 2);
+        assert!(!is_process_running(root));
+        assert!(!is_process_running(child[0]));
+        assert!(other_survived);
+    }
+
+    #[test]
+    fn test_cleanup_without_recorded_process_does_nothing() {
+        let report = cleanup_orphaned_processes(None, &CleanupOptions::default()).unwrap();
+        assert_eq!(report, CleanupReport::default());
+    }
+
+    #[test]
+    fn test_cleanup_options_from_config() {
+        let mut config = VpnConfig::new("vpn.example.com".to_string(), "user".to_string());
+        assert_eq!(
+            CleanupOptions::for_config(&config),
+            CleanupOptions::default()
+        );
+
+        config.cleanup_grace_secs = Some(2);
+        config.no_kill_escalation = true;
+        config.privilege_escalation = PrivilegeEscalation::Pkexec;
+        assert_eq!(
+            CleanupOptions::for_config(&config),
+            CleanupOptions {
+                grace_period: Duration::from_secs(2),
+                escalate: false,
+                privilege_escalation: PrivilegeEscalation::Pkexec,
+            }
+        );
+    }
 }
@@ -3,11 +3,12 @@
 //! A secure command-line tool for managing VPN connections with
 //! automatic TOTP authentication using GNOME Keyring storage.
 
-use akon_core::{error::AkonError, init_logging};
+use akon_core::init_logging;
 use clap::{Parser, Subcommand};
 
 mod cli;
 mod daemon;
+mod ui;
 
 #[derive(Parser)]
 #[command(name = "akon")]
@@ -17,6 +18,30 @@ mod daemon;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Disable colored output (also set by the NO_COLOR environment variable)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Plain ASCII output without color or emoji, for logs and screen readers
+    #[arg(long, global = true)]
+    plain: bool,
+
+    /// Abort the command if it hasn't finished after this many seconds (exit code 124)
+    #[arg(long, global = true, value_name = "SECS")]
+    deadline: Option<u64>,
+
+    /// Use the named profile from config.toml instead of the default one
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Print the config file path (honoring AKON_CONFIG_DIR) and exit
+    #[arg(long, conflicts_with = "print_state_path")]
+    print_config_path: bool,
+
+    /// Print the state file path (honoring AKON_STATE_FILE) and exit
+    #[arg(long)]
+    print_state_path: bool,
 }
 
 #[derive(Subcommand)]
@@ -73,6 +98,77 @@ enum Commands {
     },
     /// Generate OTP token for manual use
     GetPassword,
+    /// Configuration file helpers
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
+    /// Connect to VPN automatically on login
+    Autostart {
+        #[command(subcommand)]
+        action: AutostartCommands,
+    },
+    /// Inspect and migrate stored credentials
+    Credentials {
+        #[command(subcommand)]
+        action: CredentialsCommands,
+    },
+    /// Check that the stored OTP secret is intact
+    ///
+    /// Warns about a secret that is unusually short, which often means it was
+    /// truncated when pasted. Exits with status 1 if anything looks wrong.
+    Doctor,
+    /// Check codes from your token against the stored secret
+    Otp {
+        #[command(subcommand)]
+        action: OtpCommands,
+    },
+    /// List the profiles in config.toml or change the default one
+    Profile {
+        #[command(subcommand)]
+        action: ProfileCommands,
+    },
+    /// Show akon's entries in the systemd journal
+    Logs {
+        /// Keep printing new entries as they are logged
+        #[arg(short, long)]
+        follow: bool,
+        /// Number of most recent entries to show
+        #[arg(short = 'n', long, value_name = "N")]
+        lines: Option<usize>,
+        /// Only show entries from this long ago onwards (e.g. 30m, 2h, 1d)
+        #[arg(long, value_name = "DURATION")]
+        since: Option<String>,
+    },
+    /// Export config and credentials to a passphrase-encrypted bundle
+    ///
+    /// For moving to a new machine; restore it there with 'akon import'.
+    /// The passphrase is read from the terminal, or from stdin when piped.
+    Export {
+        /// File to write the bundle to
+        #[arg(long, value_name = "FILE")]
+        out: std::path::PathBuf,
+        /// Replace an existing file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Restore config and credentials from a bundle made by 'akon export'
+    Import {
+        /// Bundle file
+        bundle: std::path::PathBuf,
+        /// Replace an existing config.toml
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print the version; with --verbose, build details for bug reports
+    Version {
+        /// Also show the akon-core and openconnect versions, TLS backend and features
+        #[arg(short, long)]
+        verbose: bool,
+        /// Print every detail as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -82,11 +178,132 @@ enum VpnCommands {
         /// Force reconnection (disconnects existing connection and resets state)
         #[arg(short, long)]
         force: bool,
+        /// Connect once without starting the reconnection daemon
+        #[arg(long)]
+        no_reconnect: bool,
+        /// Seconds between health checks for this session (10-3600),
+        /// overriding health_check_interval_secs
+        #[arg(long, value_name = "SECS")]
+        health_interval: Option<u64>,
+        /// Append OpenConnect's raw output to this file, for debugging
+        #[arg(long, value_name = "PATH")]
+        capture_raw: Option<std::path::PathBuf>,
+        /// Write the connection summary (ip, device, pid, connected_at,
+        /// transport) as JSON to this file once connected
+        #[arg(long, value_name = "PATH")]
+        output: Option<std::path::PathBuf>,
+        /// Skip akon's authentication and connect with this pre-authenticated
+        /// session cookie (e.g. from a browser SSO login). Visible to other
+        /// local users in the process list; prefer --cookie-file
+        #[arg(long, value_name = "COOKIE", conflicts_with = "cookie_file")]
+        cookie: Option<String>,
+        /// Like --cookie, reading the cookie from this file
+        #[arg(long, value_name = "PATH")]
+        cookie_file: Option<std::path::PathBuf>,
     },
     /// Disconnect from VPN
-    Off,
+    Off {
+        /// Kill OpenConnect immediately instead of waiting for graceful shutdown
+        #[arg(short, long)]
+        force: bool,
+    },
     /// Show VPN connection status
-    Status,
+    Status {
+        /// Print nothing; exit 0 if connected, 1 if not, 2 if stale, 3 on error
+        #[arg(short = 'q', long, visible_alias = "quiet")]
+        exit_code_only: bool,
+        /// Print the status as JSON (state, ip, device, pid, connected_at,
+        /// duration_secs; attempt, max_attempts and next_retry_at while
+        /// reconnecting), with the same exit codes
+        #[arg(long, conflicts_with = "exit_code_only")]
+        json: bool,
+    },
+    /// Disconnect completely (including the reconnection daemon), then connect fresh
+    Restart {
+        /// Kill OpenConnect immediately instead of waiting for graceful shutdown
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum AutostartCommands {
+    /// Add an XDG autostart entry that runs 'akon vpn on' on login
+    Enable,
+    /// Remove the autostart entry
+    Disable,
+}
+
+#[derive(Subcommand)]
+enum CredentialsCommands {
+    /// Check that the stored OTP secret produces the given code
+    ///
+    /// Compares against the code for the given (or current) time and the
+    /// neighboring time windows, to detect clock skew. Never prints the secret.
+    #[command(hide = true)]
+    Verify {
+        /// Code shown by your authenticator app
+        code: String,
+        /// Unix timestamp to check the code at (default: now)
+        #[arg(long)]
+        at: Option<u64>,
+    },
+    /// Move the stored PIN and OTP secret to a new username
+    ///
+    /// Use after your VPN username changes. Also updates the username in
+    /// config.toml.
+    Migrate {
+        /// Username the credentials are stored under
+        #[arg(long)]
+        from: String,
+        /// New username
+        #[arg(long)]
+        to: String,
+        /// Replace credentials already stored for the new username
+        #[arg(long)]
+        overwrite: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum OtpCommands {
+    /// Check that a code from your token is valid right now
+    ///
+    /// Accepts the code for the current time window and the ones before and
+    /// after it, reporting which one matched. Exits with status 1 otherwise.
+    Verify {
+        /// Code shown by your token or authenticator app
+        code: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommands {
+    /// List each profile with its server and protocol, marking the default
+    List,
+    /// Make a profile the one used without --profile
+    ///
+    /// Rewrites default_profile in config.toml.
+    Switch {
+        /// Name of a configured profile
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print a commented example config.toml
+    ///
+    /// Redirect the output to ~/.config/akon/config.toml and edit it.
+    Template {
+        /// Include the [reconnection] section
+        #[arg(long)]
+        with_reconnection: bool,
+    },
+    /// Print where akon keeps its config, state and daemon PID files
+    ///
+    /// Paths reflect the AKON_CONFIG_DIR and AKON_STATE_FILE overrides.
+    Paths,
 }
 
 #[tokio::main]
@@ -106,71 +323,159 @@ async fn main() {
     }
 
     let cli = Cli::parse();
+    ui::init(cli.no_color, cli.plain);
+    let deadline = cli.deadline;
+    let profile = resolve_profile(cli.profile.as_deref());
+    let profile = profile.as_deref();
 
-    let result = match cli.command {
-        Some(Commands::Setup) => cli::setup::run_setup(),
-        Some(Commands::Vpn { action }) => match action {
-            VpnCommands::On { force } => cli::vpn::run_vpn_on(force).await,
-            VpnCommands::Off => cli::vpn::run_vpn_off().await,
-            VpnCommands::Status => cli::vpn::run_vpn_status(),
-        },
-        Some(Commands::GetPassword) => cli::get_password::run_get_password(),
-        None => {
-            // No command provided - check for lazy mode
-            use akon_core::config::toml_config::load_config;
-            match load_config() {
-                Ok(config) if config.lazy_mode => {
-                    // Lazy mode enabled - run vpn on
-                    cli::vpn::run_vpn_on(false).await
+    let command = async move {
+        if cli.print_config_path {
+            return cli::config::print_config_path();
+        }
+        if cli.print_state_path {
+            cli::config::print_state_path(profile);
+            return Ok(());
+        }
+        match cli.command {
+            Some(Commands::Setup) => cli::setup::run_setup(profile).await,
+            Some(Commands::Vpn { action }) => match action {
+                VpnCommands::On {
+                    force,
+                    no_reconnect,
+                    health_interval,
+                    capture_raw,
+                    output,
+                    cookie,
+                    cookie_file,
+                } => {
+                    let cookie = cli::vpn::session_cookie(cookie, cookie_file.as_deref())?;
+                    cli::vpn::run_vpn_on(cli::vpn::VpnOnOptions {
+                        force,
+                        no_reconnect,
+                        health_interval,
+                        capture_raw,
+                        output,
+                        cookie,
+                        profile: profile.map(str::to_string),
+                    })
+                    .await
+                }
+                VpnCommands::Off { force } => cli::vpn::run_vpn_off(force, profile).await,
+                VpnCommands::Status {
+                    exit_code_only,
+                    json,
+                } => cli::vpn::run_vpn_status(exit_code_only, json, profile),
+                VpnCommands::Restart { force } => cli::vpn::run_vpn_restart(force, profile).await,
+            },
+            Some(Commands::GetPassword) => cli::get_password::run_get_password(profile).await,
+            Some(Commands::Config { action }) => match action {
+                ConfigCommands::Template { with_reconnection } => {
+                    cli::config::run_config_template(with_reconnection)
                 }
-                Ok(_) => {
-                    // Config exists but lazy mode disabled - show help
-                    use clap::CommandFactory;
-                    Cli::command().print_help().unwrap();
-                    std::process::exit(2);
+                ConfigCommands::Paths => cli::config::run_config_paths(profile),
+            },
+            Some(Commands::Autostart { action }) => match action {
+                AutostartCommands::Enable => cli::autostart::run_autostart_enable(profile),
+                AutostartCommands::Disable => cli::autostart::run_autostart_disable(),
+            },
+            Some(Commands::Credentials { action }) => match action {
+                CredentialsCommands::Verify { code, at } => {
+                    cli::credentials::run_credentials_verify(&code, at, profile).await
                 }
-                Err(_) => {
-                    // No config - show help
-                    use clap::CommandFactory;
-                    Cli::command().print_help().unwrap();
-                    std::process::exit(2);
+                CredentialsCommands::Migrate {
+                    from,
+                    to,
+                    overwrite,
+                } => {
+                    cli::credentials::run_credentials_migrate(&from, &to, overwrite, profile).await
+                }
+            },
+            Some(Commands::Doctor) => cli::doctor::run_doctor(profile).await,
+            Some(Commands::Otp { action }) => match action {
+                OtpCommands::Verify { code } => cli::otp::run_otp_verify(&code, profile).await,
+            },
+            Some(Commands::Profile { action }) => match action {
+                ProfileCommands::List => cli::profile::run_profile_list(),
+                ProfileCommands::Switch { name } => cli::profile::run_profile_switch(&name),
+            },
+            Some(Commands::Export { out, force }) => {
+                cli::bundle::run_export(&out, force, profile).await
+            }
+            Some(Commands::Import { bundle, force }) => {
+                cli::bundle::run_import(&bundle, force).await
+            }
+            Some(Commands::Version { verbose, json }) => {
+                cli::version::run_version(verbose, json, profile)
+            }
+            Some(Commands::Logs {
+                follow,
+                lines,
+                since,
+            }) => cli::logs::run_logs(cli::logs::LogsOptions {
+                follow,
+                lines,
+                since,
+            }),
+            None => {
+                // No command provided - check for lazy mode
+                use akon_core::config::toml_config::{load_behavior, load_config};
+                match load_config(profile) {
+                    Ok(config) if config.lazy_mode => {
+                        // Lazy mode enabled - connect, show status, or toggle
+                        let behavior = load_behavior()?;
+                        cli::vpn::run_lazy(behavior.lazy_action, profile).await
+                    }
+                    Ok(_) => {
+                        // Config exists but lazy mode disabled - show help
+                        use clap::CommandFactory;
+                        Cli::command().print_help().unwrap();
+                        std::process::exit(2);
+                    }
+                    Err(_) => {
+                        // No config - show help
+                        use clap::CommandFactory;
+                        Cli::command().print_help().unwrap();
+                        std::process::exit(2);
+                    }
                 }
             }
         }
     };
+    let result = cli::with_deadline(deadline, command).await;
 
     match result {
         Ok(()) => std::process::exit(0),
         Err(e) => {
-            let exit_code = match e {
-                // Configuration errors (exit code 2)
-                AkonError::Config(_) | AkonError::Toml(_) | AkonError::TomlSerialize(_) => 2,
-                // Keyring errors (exit code 2 for configuration/setup issues)
-                AkonError::Keyring(_) => 2,
-                // VPN errors - distinguish between auth/network vs config
-                AkonError::Vpn(ref vpn_error) => match vpn_error {
-                    akon_core::error::VpnError::ConnectionFailed { .. } => 1,
-                    akon_core::error::VpnError::AuthenticationFailed => 1,
-                    akon_core::error::VpnError::NetworkError { .. } => 1,
-                    akon_core::error::VpnError::InvalidStateTransition => 1,
-                    akon_core::error::VpnError::OpenConnectError { .. } => 1,
-                    akon_core::error::VpnError::ProcessSpawnError { .. } => 1,
-                    akon_core::error::VpnError::ConnectionTimeout { .. } => 1,
-                    akon_core::error::VpnError::TerminationError => 1,
-                    akon_core::error::VpnError::ParseError { .. } => 1,
-                },
-                // OTP errors (exit code 2 - configuration/setup)
-                AkonError::Otp(_) => 2,
-                // IO errors (exit code 1 - runtime)
-                AkonError::Io(_) => 1,
-            };
+            let exit_code = e.exit_code();
 
             eprintln!("{}", e);
+            if let akon_core::error::AkonError::Otp(otp_error) = &e {
+                cli::print_otp_suggestions(otp_error);
+            }
             std::process::exit(exit_code);
         }
     }
 }
 
+/// Profile this invocation works with
+///
+/// `--profile` wins over AKON_PROFILE, which wins over the config's
+/// `default_profile`.
+fn resolve_profile(flag: Option<&str>) -> Option<String> {
+    use akon_core::config::{selected_profile, validate_profile_name};
+
+    match flag {
+        Some(name) => {
+            if let Err(message) = validate_profile_name(name) {
+                eprintln!("{}", message);
+                std::process::exit(2);
+            }
+            Some(name.to_string())
+        }
+        None => selected_profile().or_else(akon_core::config::toml_config::load_default_profile),
+    }
+}
+
 /// Handle internal daemon invocation
 /// This function is called when the process is spawned as a daemon
 async fn handle_daemon_invocation(args: Vec<String>) {
@@ -193,7 +498,7 @@ async fn handle_daemon_invocation(args: Vec<String>) {
             }
         };
 
-    let config: akon_core::config::VpnConfig = match serde_json::from_str(config_json) {
+    let mut config: akon_core::config::VpnConfig = match serde_json::from_str(config_json) {
         Ok(c) => c,
         Err(e) => {
             eprintln!("Daemon: Failed to parse VPN config: {}", e);
@@ -201,6 +506,9 @@ async fn handle_daemon_invocation(args: Vec<String>) {
         }
     };
 
+    // The profile isn't serialized; it comes with the environment
+    config.profile = akon_core::config::selected_profile();
+
     // Run the reconnection manager
     if let Err(e) = cli::vpn::run_reconnection_manager_daemon(policy, config).await {
         eprintln!("Daemon: Reconnection manager error: {}", e);
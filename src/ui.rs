@@ -0,0 +1,199 @@
+//! Terminal output styling
+//!
+//! Status lines start with a symbol (✓, ⚠, 🔌, ...). This module decides how
+//! those symbols are drawn: colored emoji by default, uncolored when
+//! `NO_COLOR` is set or `--no-color` is passed, and as ASCII tags with
+//! `--plain`, which suits logs, CI and screen readers. Plain mode implies no
+//! color.
+
+use colored::{Color, Colorize};
+use std::sync::OnceLock;
+
+/// Symbol leading a line of status output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symbol {
+    Success,
+    Failure,
+    Warning,
+    Hint,
+    Info,
+    Bullet,
+    Connect,
+    Route,
+    Auth,
+    Reconnect,
+    Cleanup,
+    Timer,
+    /// Status indicator: connected
+    Up,
+    /// Status indicator: reconnecting or stale
+    Degraded,
+    /// Status indicator: not connected or failed
+    Down,
+}
+
+impl Symbol {
+    fn emoji(self) -> &'static str {
+        match self {
+            Self::Success => "✓",
+            Self::Failure => "❌",
+            Self::Warning => "⚠",
+            Self::Hint => "💡",
+            Self::Info => "ℹ",
+            Self::Bullet => "•",
+            Self::Connect => "🔌",
+            Self::Route => "↪",
+            Self::Auth => "🔐",
+            Self::Reconnect => "🔄",
+            Self::Cleanup => "🧹",
+            Self::Timer => "⏱",
+            Self::Up | Self::Degraded | Self::Down => "●",
+        }
+    }
+
+    fn ascii(self) -> &'static str {
+        match self {
+            Self::Success => "[ok]",
+            Self::Failure => "[error]",
+            Self::Warning => "[warn]",
+            Self::Hint => "[hint]",
+            Self::Info => "[info]",
+            Self::Bullet => "-",
+            Self::Connect => "[vpn]",
+            Self::Route => "->",
+            Self::Auth => "[auth]",
+            Self::Reconnect => "[retry]",
+            Self::Cleanup => "[cleanup]",
+            Self::Timer => "[wait]",
+            Self::Up => "[up]",
+            Self::Degraded => "[degraded]",
+            Self::Down => "[down]",
+        }
+    }
+
+    /// Color of the symbol, or `None` to dim it
+    fn color(self) -> Option<Color> {
+        match self {
+            Self::Success | Self::Up => Some(Color::BrightGreen),
+            Self::Failure | Self::Down => Some(Color::BrightRed),
+            Self::Warning | Self::Hint | Self::Reconnect | Self::Cleanup | Self::Degraded => {
+                Some(Color::BrightYellow)
+            }
+            Self::Info | Self::Bullet => Some(Color::BrightBlue),
+            Self::Connect | Self::Route => Some(Color::BrightCyan),
+            Self::Auth => Some(Color::BrightMagenta),
+            Self::Timer => None,
+        }
+    }
+}
+
+/// How status output is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Renderer {
+    color: bool,
+    plain: bool,
+}
+
+impl Renderer {
+    fn new(no_color: bool, plain: bool) -> Self {
+        let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        Self {
+            color: !(no_color || plain || no_color_env),
+            plain,
+        }
+    }
+
+    fn symbol(&self, symbol: Symbol) -> String {
+        let text = if self.plain {
+            symbol.ascii()
+        } else {
+            symbol.emoji()
+        };
+        if !self.color {
+            return text.to_string();
+        }
+
+        match symbol.color() {
+            Some(color) => text.color(color).to_string(),
+            None => text.dimmed().to_string(),
+        }
+    }
+}
+
+static RENDERER: OnceLock<Renderer> = OnceLock::new();
+
+/// Configure output styling from the command-line flags and `NO_COLOR`
+///
+/// Disabling color also turns off the coloring of message text elsewhere.
+pub fn init(no_color: bool, plain: bool) {
+    let renderer = Renderer::new(no_color, plain);
+    if !renderer.color {
+        colored::control::set_override(false);
+    }
+    let _ = RENDERER.set(renderer);
+}
+
+/// Render a status symbol in the configured style
+pub fn symbol(symbol: Symbol) -> String {
+    RENDERER
+        .get()
+        .copied()
+        .unwrap_or(Renderer {
+            color: true,
+            plain: false,
+        })
+        .symbol(symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_SYMBOLS: [Symbol; 15] = [
+        Symbol::Success,
+        Symbol::Failure,
+        Symbol::Warning,
+        Symbol::Hint,
+        Symbol::Info,
+        Symbol::Bullet,
+        Symbol::Connect,
+        Symbol::Route,
+        Symbol::Auth,
+        Symbol::Reconnect,
+        Symbol::Cleanup,
+        Symbol::Timer,
+        Symbol::Up,
+        Symbol::Degraded,
+        Symbol::Down,
+    ];
+
+    #[test]
+    fn test_plain_status_is_ascii_without_escape_codes() {
+        let renderer = Renderer::new(false, true);
+
+        let line = format!(
+            "{} VPN connection established",
+            renderer.symbol(Symbol::Success)
+        );
+        assert_eq!(line, "[ok] VPN connection established");
+
+        for symbol in ALL_SYMBOLS {
+            let rendered = renderer.symbol(symbol);
+            assert!(
+                rendered.is_ascii(),
+                "{:?} renders as {:?}",
+                symbol,
+                rendered
+            );
+            assert!(!rendered.contains('\x1b'));
+        }
+    }
+
+    #[test]
+    fn test_no_color_keeps_symbols_uncolored() {
+        let renderer = Renderer::new(true, false);
+
+        assert_eq!(renderer.symbol(Symbol::Warning), "⚠");
+        assert_eq!(renderer.symbol(Symbol::Down), "●");
+    }
+}
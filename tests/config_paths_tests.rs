@@ -0,0 +1,41 @@
+//! Integration tests for the path introspection commands
+//!
+//! Checks that the printed paths follow the environment overrides.
+
+use std::process::Command;
+
+const AKON_BINARY: &str = "target/debug/akon";
+
+fn akon_with_overrides(args: &[&str]) -> String {
+    let output = Command::new(AKON_BINARY)
+        .args(args)
+        .env("AKON_CONFIG_DIR", "/tmp/akon-paths-test/config")
+        .env("AKON_STATE_FILE", "/tmp/akon-paths-test/state.json")
+        .output()
+        .expect("Failed to run akon");
+
+    assert!(output.status.success(), "{:?} failed", args);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn test_print_path_flags_honor_overrides() {
+    assert_eq!(
+        akon_with_overrides(&["--print-config-path"]),
+        "/tmp/akon-paths-test/config/config.toml\n"
+    );
+    assert_eq!(
+        akon_with_overrides(&["--print-state-path"]),
+        "/tmp/akon-paths-test/state.json\n"
+    );
+}
+
+#[test]
+fn test_config_paths_lists_every_file() {
+    let stdout = akon_with_overrides(&["config", "paths"]);
+
+    assert!(stdout.contains("Config:     /tmp/akon-paths-test/config/config.toml"));
+    assert!(stdout.contains("State:      /tmp/akon-paths-test/state.json"));
+    assert!(stdout.contains("Daemon PID: /tmp/akon-reconnection-daemon.pid"));
+    assert!(stdout.contains("Log:"));
+}
@@ -6,7 +6,9 @@
 use akon_core::config::VpnConfig;
 use akon_core::vpn::health_check::HealthChecker;
 use akon_core::vpn::reconnection::{ReconnectionCommand, ReconnectionManager, ReconnectionPolicy};
+use akon_core::vpn::session::SessionState;
 use akon_core::vpn::state::ConnectionState;
+use akon_core::vpn::DisconnectReason;
 use std::time::Duration;
 use tokio::time::timeout;
 
@@ -20,6 +22,7 @@ fn create_test_policy(health_endpoint: String) -> ReconnectionPolicy {
         consecutive_failures_threshold: 3,
         health_check_interval_secs: 2, // Check every 2 seconds for faster testing
         health_check_endpoint: health_endpoint,
+        ..Default::default()
     }
 }
 
@@ -32,6 +35,7 @@ fn create_test_vpn_config() -> VpnConfig {
         timeout: Some(30),
         no_dtls: true,
         lazy_mode: false,
+        ..Default::default()
     }
 }
 
@@ -887,3 +891,303 @@ async fn test_reconnection_success_resets_failure_counter() {
 
     println!("✓ Test passed: Successful reconnection resets failure counter");
 }
+
+#[tokio::test]
+async fn test_rate_limited_pauses_retries_until_budget_refills() {
+    let policy = create_test_policy("https://test.example.com/health".to_string());
+
+    let manager = ReconnectionManager::new(policy);
+    let command_tx = manager.command_sender();
+    let mut state_rx = manager.state_receiver();
+
+    command_tx
+        .send(ReconnectionCommand::SetConnected {
+            server: "test.example.com".to_string(),
+            username: "testuser".to_string(),
+        })
+        .expect("Failed to send SetConnected");
+    command_tx
+        .send(ReconnectionCommand::RateLimited {
+            retry_after: Duration::from_secs(1),
+        })
+        .expect("Failed to send RateLimited");
+
+    let manager_handle = tokio::spawn(async move {
+        manager.run(None).await;
+    });
+
+    // The budget running out surfaces as an Error state
+    let error = timeout(Duration::from_secs(2), async {
+        loop {
+            if state_rx.changed().await.is_ok() {
+                if let ConnectionState::Error(message) = state_rx.borrow().clone() {
+                    return message;
+                }
+            }
+        }
+    })
+    .await
+    .expect("Timeout waiting for Error state");
+    assert!(error.contains("Rate limited by akon"), "{}", error);
+
+    // Once the pause is over, reconnection resumes on the next retry tick
+    let resumed = timeout(Duration::from_secs(12), async {
+        loop {
+            if state_rx.changed().await.is_ok()
+                && matches!(
+                    *state_rx.borrow(),
+                    ConnectionState::Reconnecting { attempt: 1, .. }
+                )
+            {
+                return true;
+            }
+        }
+    })
+    .await;
+
+    command_tx
+        .send(ReconnectionCommand::Shutdown)
+        .expect("Failed to send shutdown");
+    let _ = timeout(Duration::from_secs(2), manager_handle).await;
+
+    assert!(
+        resumed.unwrap_or(false),
+        "Expected reconnection to resume after the rate limit pause"
+    );
+}
+
+/// Report a disconnect to a manager with `policy`, then watch for a reconnection attempt
+async fn reconnects_after(policy: ReconnectionPolicy, reason: DisconnectReason) -> bool {
+    let manager = ReconnectionManager::new(policy);
+    let command_tx = manager.command_sender();
+    let mut state_rx = manager.state_receiver();
+
+    command_tx
+        .send(ReconnectionCommand::SetConnected {
+            server: "test.example.com".to_string(),
+            username: "testuser".to_string(),
+        })
+        .expect("Failed to send SetConnected");
+    command_tx
+        .send(ReconnectionCommand::Disconnected(reason))
+        .expect("Failed to send Disconnected");
+
+    let manager_handle = tokio::spawn(async move {
+        manager.run(None).await;
+    });
+
+    // Retries run on a 5s tick
+    let reconnecting = timeout(Duration::from_secs(7), async {
+        loop {
+            if state_rx.changed().await.is_ok()
+                && matches!(*state_rx.borrow(), ConnectionState::Reconnecting { .. })
+            {
+                return;
+            }
+        }
+    })
+    .await
+    .is_ok();
+
+    command_tx
+        .send(ReconnectionCommand::Shutdown)
+        .expect("Failed to send shutdown");
+    let _ = timeout(Duration::from_secs(2), manager_handle).await;
+    reconnecting
+}
+
+#[tokio::test]
+async fn test_only_unrequested_disconnects_trigger_reconnection() {
+    let policy = || create_test_policy("https://test.example.com/health".to_string());
+    let (user_requested, server_disconnect) = tokio::join!(
+        reconnects_after(policy(), DisconnectReason::UserRequested),
+        reconnects_after(policy(), DisconnectReason::ServerDisconnect)
+    );
+
+    assert!(
+        !user_requested,
+        "A requested disconnect must not trigger reconnection"
+    );
+    assert!(
+        server_disconnect,
+        "A server disconnect should trigger reconnection"
+    );
+}
+
+#[tokio::test]
+async fn test_reconnect_on_selects_the_reasons_that_reconnect() {
+    let policy = || create_test_policy("https://test.example.com/health".to_string());
+    let revocations_ignored = || ReconnectionPolicy {
+        reconnect_on: vec![DisconnectReason::NetworkDown],
+        ..policy()
+    };
+    let (user_requested, network_down, server_disconnect) = tokio::join!(
+        reconnects_after(policy(), DisconnectReason::UserRequested),
+        reconnects_after(revocations_ignored(), DisconnectReason::NetworkDown),
+        reconnects_after(revocations_ignored(), DisconnectReason::ServerDisconnect)
+    );
+
+    assert!(
+        !user_requested,
+        "A requested disconnect must not trigger reconnection"
+    );
+    assert!(network_down, "A network outage should trigger reconnection");
+    assert!(
+        !server_disconnect,
+        "A reason left out of reconnect_on must not trigger reconnection"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_scheduled_disconnect_fires_at_the_configured_time() {
+    // Two hours from now, to the minute
+    let fire_at = chrono::Local::now() + chrono::Duration::hours(2);
+    let policy = ReconnectionPolicy {
+        disconnect_at: Some(fire_at.format("%H:%M").to_string()),
+        ..create_test_policy("https://test.example.com/health".to_string())
+    };
+    assert!(policy.disconnect_schedule().unwrap().is_some());
+
+    let manager = ReconnectionManager::new(policy);
+    let command_tx = manager.command_sender();
+    let state_rx = manager.state_receiver();
+    command_tx
+        .send(ReconnectionCommand::SetConnected {
+            server: "test.example.com".to_string(),
+            username: "testuser".to_string(),
+        })
+        .expect("Failed to send SetConnected");
+
+    let started = tokio::time::Instant::now();
+    let mut manager_handle = tokio::spawn(async move {
+        manager.run(None).await;
+    });
+
+    // Still connected a minute before the scheduled time
+    tokio::time::sleep(Duration::from_secs(118 * 60)).await;
+    assert!(!manager_handle.is_finished());
+    assert!(matches!(*state_rx.borrow(), ConnectionState::Connected(_)));
+
+    // The clock only moves while the runtime is idle, so this returns once it fires
+    timeout(Duration::from_secs(3 * 60), &mut manager_handle)
+        .await
+        .expect("Scheduled disconnect did not fire")
+        .unwrap();
+    let elapsed = started.elapsed();
+    assert!(
+        elapsed > Duration::from_secs(118 * 60),
+        "fired after {:?}",
+        elapsed
+    );
+    assert!(
+        elapsed <= Duration::from_secs(120 * 60),
+        "fired after {:?}",
+        elapsed
+    );
+    assert_eq!(*state_rx.borrow(), ConnectionState::Disconnecting);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_reconnection_waits_while_session_is_locked() {
+    let (session_tx, session_rx) = tokio::sync::watch::channel(SessionState::Locked);
+    let manager = ReconnectionManager::new(create_test_policy(
+        "https://test.example.com/health".to_string(),
+    ))
+    .with_session_state(session_rx);
+    let command_tx = manager.command_sender();
+    let mut state_rx = manager.state_receiver();
+
+    command_tx
+        .send(ReconnectionCommand::SetConnected {
+            server: "test.example.com".to_string(),
+            username: "testuser".to_string(),
+        })
+        .expect("Failed to send SetConnected");
+    command_tx
+        .send(ReconnectionCommand::Disconnected(
+            DisconnectReason::ServerDisconnect,
+        ))
+        .expect("Failed to send Disconnected");
+
+    let manager_handle = tokio::spawn(async move {
+        manager.run(None).await;
+    });
+
+    // Retries run on a 5s tick; none start while the session is locked
+    let reconnecting =
+        |state: &ConnectionState| matches!(state, ConnectionState::Reconnecting { .. });
+    assert!(
+        timeout(Duration::from_secs(60), state_rx.wait_for(reconnecting))
+            .await
+            .is_err(),
+        "Reconnected while the session was locked"
+    );
+    assert_eq!(*state_rx.borrow(), ConnectionState::Disconnected);
+
+    // Unlocking resumes with the first attempt
+    session_tx.send(SessionState::Active).unwrap();
+    timeout(Duration::from_secs(7), state_rx.wait_for(reconnecting))
+        .await
+        .expect("Reconnection did not resume once unlocked")
+        .unwrap();
+    assert!(matches!(
+        *state_rx.borrow(),
+        ConnectionState::Reconnecting { attempt: 1, .. }
+    ));
+
+    command_tx
+        .send(ReconnectionCommand::Shutdown)
+        .expect("Failed to send shutdown");
+    let _ = timeout(Duration::from_secs(2), manager_handle).await;
+}
+
+#[tokio::test]
+async fn test_exhausted_manager_exits_once_vpn_process_is_gone() {
+    /// Exhaust a single retry, then report whether the manager returned
+    async fn exits_with(process_alive: bool) -> (bool, ConnectionState) {
+        let mut policy = create_test_policy("https://test.example.com/health".to_string());
+        policy.max_attempts = 1;
+        let manager = ReconnectionManager::new(policy).with_process_check(move || process_alive);
+        let command_tx = manager.command_sender();
+        let state_rx = manager.state_receiver();
+
+        command_tx
+            .send(ReconnectionCommand::Disconnected(
+                DisconnectReason::ServerDisconnect,
+            ))
+            .expect("Failed to send Disconnected");
+
+        // Attempt 1 on the first 5s tick, exhaustion on the second
+        let mut manager_handle = tokio::spawn(async move {
+            manager.run(None).await;
+        });
+        let exited = timeout(Duration::from_secs(12), &mut manager_handle)
+            .await
+            .is_ok();
+        let final_state = state_rx.borrow().clone();
+
+        if !exited {
+            let _ = command_tx.send(ReconnectionCommand::Shutdown);
+            let _ = timeout(Duration::from_secs(2), manager_handle).await;
+        }
+        (exited, final_state)
+    }
+
+    let ((dead_exited, dead_state), (alive_exited, _)) =
+        tokio::join!(exits_with(false), exits_with(true));
+
+    assert!(
+        dead_exited,
+        "Manager should exit once the VPN process is gone"
+    );
+    match dead_state {
+        ConnectionState::Error(message) => {
+            assert!(message.contains("VPN process has exited"), "{}", message)
+        }
+        state => panic!("Expected a final Error state, got {:?}", state),
+    }
+    assert!(
+        !alive_exited,
+        "Manager should keep running while the VPN process is alive"
+    );
+}
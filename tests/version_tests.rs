@@ -0,0 +1,39 @@
+//! Integration tests for `akon version`
+
+use std::process::Command;
+
+const AKON_BINARY: &str = "target/debug/akon";
+
+fn akon(args: &[&str]) -> String {
+    let output = Command::new(AKON_BINARY)
+        .args(args)
+        .output()
+        .expect("Failed to run akon");
+
+    assert!(output.status.success(), "{:?} failed", args);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn test_version_reports_package_version() {
+    let version = env!("CARGO_PKG_VERSION");
+
+    assert_eq!(akon(&["version"]), format!("akon {}\n", version));
+    assert_eq!(akon(&["--version"]), format!("akon {}\n", version));
+
+    let verbose = akon(&["version", "--verbose"]);
+    assert!(verbose.starts_with(&format!("akon {}\n", version)));
+    assert!(verbose.contains("TLS backend: rustls"));
+    assert!(verbose.contains("openconnect: "));
+}
+
+#[test]
+fn test_version_json_has_every_detail() {
+    let json: serde_json::Value = serde_json::from_str(&akon(&["version", "--json"])).unwrap();
+
+    assert_eq!(json["akon"], env!("CARGO_PKG_VERSION"));
+    assert!(json["akon_core"].is_string());
+    assert_eq!(json["tls_backend"], "rustls");
+    assert!(json["features"].is_array());
+    assert!(json.get("openconnect").is_some());
+}
@@ -0,0 +1,153 @@
+//! Integration tests for overlapping `akon vpn on` invocations
+//!
+//! While one invocation is still connecting, a second must stop instead of
+//! starting another openconnect. Only invocations for the same profile
+//! conflict.
+
+use std::process::Command;
+use tempfile::TempDir;
+
+const AKON_BINARY: &str = "target/debug/akon";
+
+#[test]
+fn test_second_vpn_on_is_rejected_while_first_connects() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let state_path = temp_dir.path().join("state.json");
+    let lock_path = temp_dir.path().join("state.lock");
+
+    // The first invocation, still connecting: its lock names a live process
+    let first_pid = std::process::id().to_string();
+    std::fs::write(&lock_path, format!("{}\n", first_pid)).unwrap();
+
+    let output = Command::new(AKON_BINARY)
+        .args(["vpn", "on"])
+        .env("AKON_CONFIG_DIR", temp_dir.path())
+        .env("AKON_STATE_FILE", &state_path)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("failed to run akon binary");
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Connection already in progress"),
+        "unexpected stderr: {}",
+        stderr
+    );
+    assert!(stderr.contains(&first_pid));
+
+    // The first invocation's lock is left alone
+    assert_eq!(
+        std::fs::read_to_string(&lock_path).unwrap().trim(),
+        first_pid
+    );
+}
+
+#[test]
+fn test_stale_lock_from_crashed_attempt_does_not_block() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let state_path = temp_dir.path().join("state.json");
+    let lock_path = temp_dir.path().join("state.lock");
+
+    let mut crashed = Command::new("true").spawn().unwrap();
+    let crashed_pid = crashed.id();
+    crashed.wait().unwrap();
+    std::fs::write(&lock_path, format!("{}\n", crashed_pid)).unwrap();
+
+    // No config: the attempt gets past the lock and fails on that instead
+    let output = Command::new(AKON_BINARY)
+        .args(["vpn", "on"])
+        .env("AKON_CONFIG_DIR", temp_dir.path())
+        .env("AKON_STATE_FILE", &state_path)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("failed to run akon binary");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(!stderr.contains("already in progress"), "{}", stderr);
+    assert!(!lock_path.exists(), "lock should be released on error");
+}
+
+#[test]
+fn test_other_profile_is_not_blocked_while_one_connects() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let state_path = temp_dir.path().join("state.json");
+    let lock_path = temp_dir.path().join("state.lock");
+
+    // The default profile is still connecting
+    let first_pid = std::process::id().to_string();
+    std::fs::write(&lock_path, format!("{}\n", first_pid)).unwrap();
+
+    // No config: the attempt gets past the lock and fails on that instead
+    let output = Command::new(AKON_BINARY)
+        .args(["--profile", "work", "vpn", "on"])
+        .env("AKON_CONFIG_DIR", temp_dir.path())
+        .env("AKON_STATE_FILE", &state_path)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("failed to run akon binary");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(!stderr.contains("already in progress"), "{}", stderr);
+    assert!(!temp_dir.path().join("state.work.lock").exists());
+    assert_eq!(
+        std::fs::read_to_string(&lock_path).unwrap().trim(),
+        first_pid
+    );
+}
+
+/// State file of a connection whose openconnect is this test process
+fn write_connected_state(path: &std::path::Path) {
+    let state = format!(
+        r#"{{"schema_version":1,"state":"Connected","name":"office","ip":"10.0.0.5","pid":{}}}"#,
+        std::process::id()
+    );
+    std::fs::write(path, state).unwrap();
+}
+
+#[test]
+fn test_connected_profile_is_not_connected_again() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let state_path = temp_dir.path().join("state.json");
+    write_connected_state(&state_path);
+
+    let output = Command::new(AKON_BINARY)
+        .args(["vpn", "on"])
+        .env("AKON_CONFIG_DIR", temp_dir.path())
+        .env("AKON_STATE_FILE", &state_path)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("failed to run akon binary");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("office is already connected"),
+        "unexpected stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_other_profile_connects_while_one_is_connected() {
+    let temp_dir = TempDir::new().expect("failed to create temp dir");
+    let state_path = temp_dir.path().join("state.json");
+    write_connected_state(&state_path);
+
+    // No config: the attempt gets past the connected check and fails on that
+    let output = Command::new(AKON_BINARY)
+        .args(["--profile", "work", "vpn", "on"])
+        .env("AKON_CONFIG_DIR", temp_dir.path())
+        .env("AKON_STATE_FILE", &state_path)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("failed to run akon binary");
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("already connected"), "{}", stdout);
+    // The default profile's connection is left alone
+    assert!(state_path.exists());
+}
@@ -18,3 +18,55 @@ fn test_vpn_status_command_exists() {
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(stdout.contains("status"));
 }
+
+#[test]
+fn test_exit_code_only_reports_each_state_silently() {
+    let temp_dir = tempfile::TempDir::new().expect("failed to create temp dir");
+
+    let mut exited = Command::new("true").spawn().unwrap();
+    let exited_pid = exited.id();
+    exited.wait().unwrap();
+
+    let cases = [
+        (None, 1),
+        (
+            Some(format!(
+                r#"{{"ip": "10.0.0.5", "pid": {}}}"#,
+                std::process::id()
+            )),
+            0,
+        ),
+        (
+            Some(r#"{"state": "Reconnecting", "attempt": 2}"#.to_string()),
+            1,
+        ),
+        (
+            Some(format!(r#"{{"ip": "10.0.0.5", "pid": {}}}"#, exited_pid)),
+            2,
+        ),
+        (
+            Some(r#"{"state": "Error", "error": "Authentication failed"}"#.to_string()),
+            3,
+        ),
+        (Some("not json".to_string()), 1),
+    ];
+
+    for (index, (state, expected)) in cases.into_iter().enumerate() {
+        let state_path = temp_dir.path().join(format!("state-{}.json", index));
+        if let Some(state) = &state {
+            std::fs::write(&state_path, state).unwrap();
+        }
+
+        for flag in ["--exit-code-only", "-q", "--quiet"] {
+            let output = Command::new(AKON_BINARY)
+                .args(["vpn", "status", flag])
+                .env("AKON_STATE_FILE", &state_path)
+                .output()
+                .expect("Failed to run vpn status");
+
+            assert_eq!(output.status.code(), Some(expected), "{:?} {}", state, flag);
+            assert!(output.stdout.is_empty(), "{:?} printed to stdout", state);
+            assert!(output.stderr.is_empty(), "{:?} printed to stderr", state);
+        }
+    }
+}